@@ -0,0 +1,47 @@
+//! The overhead of throttling a function, compared to calling it directly.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use throttle_my_fn::throttle;
+
+fn plain(x: u64) -> u64 {
+  x
+}
+
+// With plenty of quota, calls are charged lock-free.
+#[throttle(1_000_000, "1ms")]
+fn plenty(x: u64) -> u64 {
+  x
+}
+
+// Every call but the first is skipped, which is lock-free too.
+#[throttle(1, "1h")]
+fn exhausted(x: u64) -> u64 {
+  x
+}
+
+// Collecting stats looks into the calls, so they are charged under a lock.
+#[throttle(1_000_000, "1ms", stats = true)]
+fn locked(x: u64) -> u64 {
+  x
+}
+
+// Sharded quotas are charged under the lock of a shard.
+#[throttle(1_000_000, "1ms", sharded = true)]
+fn sharded(x: u64) -> u64 {
+  x
+}
+
+fn overhead(c: &mut Criterion) {
+  let mut group = c.benchmark_group("overhead");
+  group.bench_function("plain", |b| b.iter(|| plain(black_box(1))));
+  group.bench_function("plenty", |b| b.iter(|| plenty(black_box(1))));
+  group.bench_function("exhausted", |b| b.iter(|| exhausted(black_box(1))));
+  group.bench_function("locked", |b| b.iter(|| locked(black_box(1))));
+  group.bench_function("sharded", |b| b.iter(|| sharded(black_box(1))));
+  group.finish();
+}
+
+criterion_group!(benches, overhead);
+criterion_main!(benches);
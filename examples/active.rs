@@ -0,0 +1,20 @@
+use throttle_my_fn::throttle;
+use throttle_my_fn_runtime::ThrottleResult;
+
+// Only runs during office hours, and at most twice per second then.
+#[throttle(2, "1s", active = "08:00-20:00 UTC", output = detailed)]
+pub(crate) fn sync_batch(batch: u32) -> String {
+  format!("synced batch {}", batch)
+}
+
+fn main() {
+  // Outside of office hours, every call is skipped and told when the window opens.
+  for batch in [1, 2, 3] {
+    match sync_batch(batch) {
+      ThrottleResult::Executed(message) => eprintln!("{}", message),
+      ThrottleResult::Skipped { retry_after, .. } => {
+        eprintln!("batch {} skipped, retry after {:?}", batch, retry_after)
+      }
+    }
+  }
+}
@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+use throttle_my_fn::throttle;
+
+// The first word of a text, borrowed from it rather than from the separator.
+#[throttle(2, "1s")]
+fn head<'a>(text: &'a str, separator: &str) -> &'a str {
+  text.split(separator).next().unwrap_or_default()
+}
+
+// The last word of a text, or the fallback, borrowed from either.
+#[throttle(2, "1s", output = detailed)]
+fn last<'a>(text: &'a str, fallback: &'a str) -> &'a str {
+  text.split_whitespace().last().unwrap_or(fallback)
+}
+
+// Callers with a deadline wait for a slot, and get the borrowed value all the same.
+#[throttle(1, Duration::from_millis(100), mode = wait_with_deadline)]
+fn tail<'a>(bytes: &'a [u8], fallback: &'a u8) -> &'a u8 {
+  bytes.last().unwrap_or(fallback)
+}
+
+struct Parser<'a> {
+  text: &'a str,
+}
+
+impl<'a> Parser<'a> {
+  // The returned text borrows from the text being parsed rather than from the parser.
+  #[throttle(1, "1s")]
+  fn rest(&self) -> &'a str {
+    self.text
+  }
+}
+
+fn main() {
+  let text = String::from("hello borrowed world");
+
+  assert_eq!(head(&text, " "), Some("hello"));
+  assert_eq!(head(&text, " "), Some("hello"));
+  assert_eq!(head(&text, " "), None);
+
+  println!("{:?}", last(&text, "nothing"));
+
+  let deadline = Instant::now() + Duration::from_secs(1);
+  assert_eq!(tail(b"ab", &b'-', deadline), Some(&b'b'));
+  assert_eq!(tail(b"", &b'-', deadline), Some(&b'-'));
+
+  let rest = {
+    let parser = Parser { text: &text };
+    parser.rest()
+  };
+  assert_eq!(rest, Some("hello borrowed world"));
+}
@@ -0,0 +1,18 @@
+use throttle_my_fn::throttle;
+
+// At most 3 calls per clock minute, with the quota resetting at the top of every minute
+// rather than a minute after each call.
+#[throttle(3, per = "minute", align = calendar, queries = true)]
+pub(crate) fn query_vendor(query: &str) -> String {
+  format!("results for {}", query)
+}
+
+fn main() {
+  // The first three calls are executed, the fourth one is skipped.
+  for query in ["a", "b", "c", "d"] {
+    eprintln!("{:?}", query_vendor(query));
+  }
+
+  // The quota frees up at the start of the next minute.
+  eprintln!("retry after {:?}", query_vendor_retry_after());
+}
@@ -0,0 +1,21 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use throttle_my_fn::throttle;
+
+// The slot of an export only starts counting down once it has finished.
+#[throttle(1, "200ms", charge = end)]
+fn export(report: u32) -> String {
+  thread::sleep(Duration::from_millis(300));
+  format!("exported report {}", report)
+}
+
+fn main() {
+  let start = Instant::now();
+  eprintln!("{:?} after {:?}", export(1), start.elapsed());
+
+  // Charged when it started, this call would have been let through.
+  eprintln!("{:?} after {:?}", export(2), start.elapsed());
+
+  thread::sleep(Duration::from_millis(250));
+  eprintln!("{:?} after {:?}", export(3), start.elapsed());
+}
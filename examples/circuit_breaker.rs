@@ -0,0 +1,25 @@
+use std::time::Duration;
+use throttle_my_fn::circuit_breaker;
+
+#[circuit_breaker(failures = 3, reset = "200ms")]
+pub(crate) fn fetch(attempt: usize) -> Result<usize, String> {
+  eprintln!("Fetching, attempt {}", attempt);
+  if attempt < 8 {
+    Err(String::from("service unavailable"))
+  } else {
+    Ok(attempt)
+  }
+}
+
+fn main() {
+  for attempt in 0..6 {
+    eprintln!("Result: {:?}", fetch(attempt));
+  }
+
+  std::thread::sleep(Duration::from_millis(200));
+
+  for attempt in 6..12 {
+    eprintln!("Result: {:?}", fetch(attempt));
+    std::thread::sleep(Duration::from_millis(120));
+  }
+}
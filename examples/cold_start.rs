@@ -0,0 +1,22 @@
+use std::thread;
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+// Up to 10 connections every 200 milliseconds, but only 3 in the first window, for a
+// fleet restarting at once not to overwhelm the upstream.
+#[throttle(10, Duration::from_millis(200), cold_start_cap = 3)]
+fn connect(id: usize) -> usize {
+  id
+}
+
+fn main() {
+  let connected = (0..10).filter(|&id| connect(id).is_some()).count();
+  println!("{} connections in the first window", connected);
+  assert_eq!(connected, 3);
+
+  // Once the first window is over, the configured rate applies.
+  thread::sleep(Duration::from_millis(250));
+  let connected = (0..10).filter(|&id| connect(id).is_some()).count();
+  println!("{} connections in the next one", connected);
+  assert_eq!(connected, 10);
+}
@@ -0,0 +1,14 @@
+use throttle_my_fn::throttle;
+
+// The limit is read from examples/throttles.toml when the example is compiled.
+#[throttle(config = "examples/throttles.toml", name = "github.fetch")]
+pub(crate) fn fetch(path: &str) -> String {
+  format!("https://api.github.com{}", path)
+}
+
+fn main() {
+  // The first two calls are executed, the third one is skipped.
+  for path in ["/users/fredmorcos", "/repos/fredmorcos/throttle_my_fn", "/rate_limit"] {
+    eprintln!("{:?}", fetch(path));
+  }
+}
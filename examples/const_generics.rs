@@ -0,0 +1,18 @@
+use throttle_my_fn::throttle;
+
+#[throttle(2, "1s")]
+pub(crate) fn chunked<const N: usize>(data: &[u8]) -> Vec<&[u8]> {
+  data.chunks(N).collect()
+}
+
+#[throttle(2, "1s")]
+pub(crate) fn repeat<T: Copy, const N: usize>(value: T) -> [T; N] {
+  [value; N]
+}
+
+fn main() {
+  for _ in 0..3 {
+    eprintln!("{:?}", chunked::<2>(b"abcde"));
+    eprintln!("{:?}", repeat::<_, 3>('x'));
+  }
+}
@@ -0,0 +1,33 @@
+use throttle_my_fn::throttle;
+
+#[throttle(5, "1s", consts = true)]
+pub(crate) fn upload(batch: &[u32]) -> usize {
+  batch.len()
+}
+
+struct Client;
+
+impl Client {
+  #[throttle(2, "100ms", consts = true)]
+  fn ping(&self) -> &'static str {
+    "pong"
+  }
+}
+
+fn main() {
+  // Size batches after the limit instead of duplicating its numbers.
+  let items: Vec<u32> = (0..12).collect();
+  let batch_size = items.len().div_ceil(UPLOAD_THROTTLE_TIMES);
+  eprintln!("Uploading in batches of {} per {:?}", batch_size, UPLOAD_THROTTLE_PERIOD);
+
+  for batch in items.chunks(batch_size) {
+    eprintln!("{:?}", upload(batch));
+  }
+
+  eprintln!(
+    "Pinging at most {} times per {:?}: {:?}",
+    Client::PING_THROTTLE_TIMES,
+    Client::PING_THROTTLE_PERIOD,
+    Client.ping()
+  );
+}
@@ -0,0 +1,19 @@
+use std::time::Duration;
+use throttle_my_fn::cooldown;
+
+#[cooldown(Duration::from_millis(100))]
+pub(crate) fn refresh_cache(attempt: usize) -> usize {
+  eprintln!("{}: Refreshing cache", attempt);
+  attempt
+}
+
+fn main() {
+  for _ in 0..3 {
+    for i in 0..20 {
+      let _ = refresh_cache(i);
+    }
+
+    eprintln!();
+    std::thread::sleep(Duration::from_millis(100));
+  }
+}
@@ -0,0 +1,21 @@
+use std::time::{Duration, Instant};
+use throttle_my_fn::throttle;
+
+// Calls wait for a slot, but only for as long as their deadline allows.
+#[throttle(2, "200ms", mode = wait_with_deadline)]
+pub(crate) fn query_backend(query: &str) -> String {
+  format!("results for {}", query)
+}
+
+fn main() {
+  let start = Instant::now();
+
+  // The first two calls go through right away, the third one would have to wait longer
+  // than its deadline allows, while the fourth one can wait for a slot.
+  let deadlines = [50, 50, 50, 500];
+  for (i, deadline) in deadlines.into_iter().enumerate() {
+    let deadline = Instant::now() + Duration::from_millis(deadline);
+    let response = query_backend(&format!("query {}", i), deadline);
+    eprintln!("{:?} after {:?}", response, start.elapsed());
+  }
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+use throttle_my_fn::debounce;
+
+#[debounce(Duration::from_millis(300))]
+pub(crate) fn search(query: String) -> usize {
+  eprintln!("Searching for {:?}", query);
+  query.len()
+}
+
+#[debounce(Duration::from_millis(300), edge = leading)]
+pub(crate) fn on_click(click: usize) {
+  eprintln!("Handling click {}", click);
+}
+
+#[debounce(Duration::from_millis(300), edge = both)]
+pub(crate) fn resize(width: usize) {
+  eprintln!("Resizing to {}", width);
+}
+
+fn main() {
+  // Simulate someone typing, only the last query should be searched for.
+  let mut handles = Vec::new();
+  for query in ["t", "th", "thr", "thro", "throttle"] {
+    handles.push(search(String::from(query)));
+    std::thread::sleep(Duration::from_millis(100));
+  }
+
+  for handle in handles {
+    eprintln!("Result: {:?}", handle.wait());
+  }
+
+  // Only the first click of a burst is handled.
+  for click in 0..5 {
+    drop(on_click(click));
+    std::thread::sleep(Duration::from_millis(100));
+  }
+
+  // The first and last resizes of a burst are handled.
+  let handles = (0..5).map(resize).collect::<Vec<_>>();
+  for handle in handles {
+    let _ = handle.wait();
+  }
+}
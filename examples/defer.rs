@@ -0,0 +1,84 @@
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+#[throttle(3, Duration::from_millis(500), mode = defer)]
+pub(crate) fn flush_analytics(batch: usize) -> usize {
+  eprintln!("Flushing analytics batch {}", batch);
+  batch * 10
+}
+
+#[throttle(
+  1,
+  Duration::from_millis(200),
+  mode = defer,
+  queue = 2,
+  overflow = drop_oldest
+)]
+pub(crate) fn save_settings(version: usize) -> usize {
+  eprintln!("Saving settings version {}", version);
+  version
+}
+
+#[throttle(1, Duration::from_millis(200), mode = defer, queue = 2, overflow = block)]
+pub(crate) fn upload_chunk(chunk: usize) {
+  eprintln!("Uploading chunk {}", chunk);
+}
+
+#[throttle(1, Duration::from_millis(200), mode = coalesce)]
+pub(crate) fn redraw(frame: usize) -> usize {
+  eprintln!("Redrawing frame {}", frame);
+  frame
+}
+
+#[throttle(1, Duration::from_millis(200), edge = trailing)]
+pub(crate) fn sync_state(version: usize) -> usize {
+  eprintln!("Syncing state version {}", version);
+  version
+}
+
+fn main() {
+  let mut handles = Vec::new();
+
+  for batch in 0..10 {
+    let handle = flush_analytics(batch);
+    if handle.is_finished() {
+      eprintln!("Batch {} flushed immediately", batch);
+    } else {
+      eprintln!("Batch {} deferred", batch);
+    }
+    handles.push(handle);
+  }
+
+  // Cancel the last batch while it is still waiting in the queue.
+  if let Some(handle) = handles.last() {
+    eprintln!("Cancelled last batch: {}", handle.cancel());
+  }
+
+  for handle in handles {
+    eprintln!("Result: {:?}", handle.wait());
+  }
+
+  // Only the first call and the two newest pending calls survive.
+  let handles = (0..6).map(save_settings).collect::<Vec<_>>();
+  for handle in handles {
+    eprintln!("Saved: {:?}", handle.wait());
+  }
+
+  // Callers block once two chunks are pending.
+  let handles = (0..5).map(upload_chunk).collect::<Vec<_>>();
+  for handle in handles {
+    let _ = handle.wait();
+  }
+
+  // Only the first and the last redraws happen.
+  let handles = (0..5).map(redraw).collect::<Vec<_>>();
+  for handle in handles {
+    eprintln!("Redrawn: {:?}", handle.wait());
+  }
+
+  // Only the last call of the window is executed, at the end of the window.
+  let handles = (0..5).map(sync_state).collect::<Vec<_>>();
+  for handle in handles {
+    eprintln!("Synced: {:?}", handle.wait());
+  }
+}
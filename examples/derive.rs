@@ -0,0 +1,47 @@
+use throttle_my_fn::Throttled;
+use throttle_my_fn_runtime::Throttler;
+
+/// A session with its own limits, rather than limits shared by all sessions.
+#[derive(Throttled)]
+pub(crate) struct Session {
+  user: String,
+
+  #[throttled(3, "1s")]
+  requests: Throttler,
+
+  #[throttled(1, "1m")]
+  password_resets: Throttler,
+}
+
+impl Session {
+  fn new(user: &str) -> Self {
+    Session {
+      user: String::from(user),
+      requests: Session::requests_throttler(),
+      password_resets: Session::password_resets_throttler(),
+    }
+  }
+
+  fn request(&self, path: &str) -> Option<String> {
+    self.try_requests(|| format!("{} requested {}", self.user, path))
+  }
+
+  fn reset_password(&self) -> Option<String> {
+    self.try_password_resets(|| format!("Sent a password reset email to {}", self.user))
+  }
+}
+
+fn main() {
+  let alice = Session::new("alice");
+  let bob = Session::new("bob");
+
+  for i in 0..4 {
+    eprintln!("{:?}", alice.request(&format!("/page/{}", i)));
+  }
+
+  // Bob's quota is his own.
+  eprintln!("{:?}", bob.request("/page/0"));
+
+  eprintln!("{:?}", alice.reset_password());
+  eprintln!("{:?}", alice.reset_password());
+}
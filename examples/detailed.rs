@@ -0,0 +1,28 @@
+use std::thread;
+use throttle_my_fn::throttle;
+use throttle_my_fn_runtime::ThrottleResult;
+
+#[throttle(2, "100ms", output = detailed)]
+pub(crate) fn send_request(id: u32) -> String {
+  format!("Response to request {}", id)
+}
+
+fn main() {
+  let mut id = 0;
+  while id < 5 {
+    match send_request(id) {
+      ThrottleResult::Executed(response) => {
+        eprintln!("{}", response);
+        id += 1;
+      }
+      // Sleep exactly until the next slot frees up instead of polling.
+      ThrottleResult::Skipped { retry_after, window_remaining } => {
+        eprintln!(
+          "Skipped ({} slots left), retrying after {:?}",
+          window_remaining, retry_after
+        );
+        thread::sleep(retry_after);
+      }
+    }
+  }
+}
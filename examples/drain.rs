@@ -0,0 +1,27 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+static FLUSHED: AtomicUsize = AtomicUsize::new(0);
+
+// Up to 2 flushes every 100 milliseconds, the others being deferred.
+#[throttle(2, Duration::from_millis(100), mode = defer, drain = true)]
+fn flush(batch: usize) {
+  println!("flushing batch {}", batch);
+  let _ = FLUSHED.fetch_add(1, Ordering::Relaxed);
+}
+
+fn main() {
+  // The handles of the flushes aren't waited on, which doesn't cancel them.
+  for batch in 0..6 {
+    drop(flush(batch));
+  }
+
+  // Not all of the deferred flushes can be executed in such a short time.
+  assert!(!flush_throttle_drain(Duration::from_millis(10)));
+
+  // Before shutting down, wait for the deferred flushes to be executed.
+  assert!(flush_throttle_drain(Duration::from_secs(1)));
+  assert_eq!(FLUSHED.load(Ordering::Relaxed), 6);
+  println!("all batches flushed");
+}
@@ -0,0 +1,26 @@
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+// The limiter is generated into the `fetch_throttle` module, for tooling to reflect
+// over at predictable paths.
+#[throttle(2, Duration::from_secs(60), emit = module, stats = true)]
+fn fetch(url: &str) -> usize {
+  url.len()
+}
+
+fn main() {
+  for url in ["a", "bb", "ccc"] {
+    println!("{}: {:?}", url, fetch(url));
+  }
+
+  println!("{} calls in the window", fetch_throttle::in_window());
+  println!("retry after {:?}", fetch_throttle::retry_after());
+  println!("{:?}", fetch_throttle::stats());
+  assert_eq!(fetch_throttle::in_window(), 2);
+
+  // Resetting the limiter lets the next calls through right away.
+  fetch_throttle::reset();
+  assert_eq!(fetch("dddd"), Some(4));
+  assert!(fetch_throttle::STATE.try_acquire());
+  assert!(!fetch_throttle::STATE.try_acquire());
+}
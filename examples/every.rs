@@ -0,0 +1,19 @@
+use std::thread;
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+// At least 250 milliseconds between two executions.
+#[throttle(every = Duration::from_millis(250))]
+pub(crate) fn poll_sensor(sensor: u32) -> String {
+  format!("polled sensor {}", sensor)
+}
+
+fn main() {
+  // The first call is executed, the second one is skipped.
+  eprintln!("{:?}", poll_sensor(1));
+  eprintln!("{:?}", poll_sensor(2));
+
+  // Once the interval has passed, the next call is executed.
+  thread::sleep(Duration::from_millis(300));
+  eprintln!("{:?}", poll_sensor(3));
+}
@@ -0,0 +1,21 @@
+use throttle_my_fn::throttle;
+
+// The limiter is exposed as `send_sms_throttle::STATE`.
+#[throttle(3, "1m", expose = true)]
+pub(crate) fn send_sms(to: &str) -> String {
+  format!("sent an sms to {}", to)
+}
+
+fn main() {
+  eprintln!("{:?}", send_sms("alice"));
+
+  // A batch sent through another code path draws from the same quota.
+  let batch = ["bob", "carol", "dave"];
+  for to in batch {
+    let sent = send_sms_throttle::STATE.try_acquire();
+    eprintln!("batch item for {}: {}", to, if sent { "sent" } else { "skipped" });
+  }
+
+  eprintln!("{:?}", send_sms("erin"));
+  eprintln!("{} calls in the window", send_sms_throttle::STATE.in_window());
+}
@@ -0,0 +1,31 @@
+use throttle_my_fn::throttle;
+
+/// Fetch a page from a remote server, politely.
+#[throttle(1, "1s", expose_inner = "fetch_page_raw")]
+pub(crate) fn fetch_page(url: &str) -> String {
+  format!("Contents of {}", url)
+}
+
+struct Crawler;
+
+impl Crawler {
+  #[throttle(1, "1s", expose_inner = "visit_unthrottled")]
+  fn visit(&self, url: &str) -> usize {
+    fetch_page_raw(url).len()
+  }
+}
+
+fn main() {
+  // Callers go through the throttle...
+  eprintln!("{:?}", fetch_page("https://example.com/1"));
+  eprintln!("{:?}", fetch_page("https://example.com/2"));
+
+  // ...while trusted callers, tests and benchmarks can skip it.
+  eprintln!("{:?}", fetch_page_raw("https://example.com/3"));
+  eprintln!("{:?}", fetch_page_raw("https://example.com/4"));
+
+  let crawler = Crawler;
+  eprintln!("{:?}", crawler.visit("https://example.com/5"));
+  eprintln!("{:?}", crawler.visit("https://example.com/6"));
+  eprintln!("{:?}", crawler.visit_unthrottled("https://example.com/7"));
+}
@@ -0,0 +1,20 @@
+use throttle_my_fn::throttle;
+
+// Each tenant may send 4 queries per minute, but only their share of the 4 queries
+// per minute of the whole function.
+#[throttle(global = (4, "1m"), per_key = (4, "1m"), key = tenant, fair = true)]
+fn query(tenant: &str, sql: &str) -> String {
+  format!("{} ran {}", tenant, sql)
+}
+
+fn main() {
+  eprintln!("{:?}", query("noisy", "SELECT 1"));
+  eprintln!("{:?}", query("quiet", "SELECT 2"));
+
+  // The noisy tenant is held to half of the limit while the quiet one is active.
+  for _ in 0..3 {
+    eprintln!("{:?}", query("noisy", "SELECT 3"));
+  }
+
+  eprintln!("{:?}", query("quiet", "SELECT 4"));
+}
@@ -0,0 +1,25 @@
+use throttle_my_fn::throttle;
+
+// C callers cannot handle an Option, so skipped calls return -1 instead.
+#[no_mangle]
+#[throttle(2, "1s", skipped = -1)]
+pub extern "C" fn throttled_add(a: i32, b: i32) -> i32 {
+  a + b
+}
+
+#[throttle(1, "1s", skipped = -1)]
+#[no_mangle]
+pub unsafe extern "C" fn throttled_read(value: *const i32) -> i32 {
+  *value
+}
+
+fn main() {
+  for i in 0..3 {
+    eprintln!("{}", throttled_add(i, 1));
+  }
+
+  let value = 42;
+  for _ in 0..2 {
+    eprintln!("{}", unsafe { throttled_read(&value) });
+  }
+}
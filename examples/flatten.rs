@@ -0,0 +1,17 @@
+use std::collections::HashMap;
+use throttle_my_fn::throttle;
+
+// Without `flatten = true`, this would return an Option<Option<String>>.
+#[throttle(2, "1s", flatten = true)]
+pub(crate) fn lookup(users: &HashMap<u32, String>, id: u32) -> Option<String> {
+  users.get(&id).cloned()
+}
+
+fn main() {
+  let users = HashMap::from([(1, String::from("alice")), (2, String::from("bob"))]);
+
+  // Found, not found, and then throttled: all the same to the caller.
+  for id in [1, 3, 2] {
+    eprintln!("{}: {:?}", id, lookup(&users, id));
+  }
+}
@@ -0,0 +1,48 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use throttle_my_fn::throttle;
+
+// Charged when called: futures that are never awaited still use up the quota.
+#[throttle(2, "1s")]
+pub(crate) fn fetch_on_call(id: u32) -> impl Future<Output = String> + Send {
+  std::future::ready(format!("item {}", id))
+}
+
+// Charged when first polled: futures that are never awaited are not charged.
+#[throttle(2, "1s", future = poll)]
+pub(crate) fn fetch_on_poll(id: u32) -> impl Future<Output = String> + Send {
+  std::future::ready(format!("item {}", id))
+}
+
+/// A minimal executor, to keep this example free of an async runtime dependency.
+fn block_on<F: Future>(future: F) -> F::Output {
+  struct Noop;
+
+  impl Wake for Noop {
+    fn wake(self: Arc<Self>) {}
+  }
+
+  let waker = Arc::new(Noop).into();
+  let mut cx = Context::from_waker(&waker);
+  let mut future = pin!(future);
+  loop {
+    if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+      return output;
+    }
+  }
+}
+
+fn main() {
+  // Create futures without ever awaiting them, e.g. the losing branches of a select.
+  for i in 0..2 {
+    drop(fetch_on_call(i));
+    drop(fetch_on_poll(i));
+  }
+
+  for i in 2..4 {
+    eprintln!("on call: {:?}", fetch_on_call(i).map(block_on));
+    eprintln!("on poll: {:?}", block_on(fetch_on_poll(i)));
+  }
+}
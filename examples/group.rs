@@ -0,0 +1,36 @@
+use throttle_my_fn::throttle;
+
+// The modules stand for crates of a workspace calling the same upstream service.
+mod accounts {
+  use throttle_my_fn::throttle;
+
+  #[throttle(3, "1m", group = "github")]
+  pub fn fetch_repos(user: &str) -> String {
+    format!("repos of {}", user)
+  }
+}
+
+mod billing {
+  use throttle_my_fn::throttle;
+
+  #[throttle(3, "1m", group = "github")]
+  pub fn fetch_sponsors(user: &str) -> String {
+    format!("sponsors of {}", user)
+  }
+}
+
+// Functions of other groups have quotas of their own.
+#[throttle(3, "1m", group = "gitlab")]
+fn fetch_projects(user: &str) -> String {
+  format!("projects of {}", user)
+}
+
+fn main() {
+  eprintln!("{:?}", accounts::fetch_repos("alice"));
+  eprintln!("{:?}", accounts::fetch_repos("bob"));
+  eprintln!("{:?}", billing::fetch_sponsors("alice"));
+
+  // The quota of the group is used up by now.
+  eprintln!("{:?}", billing::fetch_sponsors("bob"));
+  eprintln!("{:?}", fetch_projects("alice"));
+}
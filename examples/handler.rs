@@ -0,0 +1,59 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use throttle_my_fn::throttle;
+
+/// A stand-in for a web framework's response type.
+#[derive(Debug)]
+pub(crate) struct Response {
+  status: u16,
+  body: String,
+}
+
+pub(crate) struct State(u32);
+
+pub(crate) struct Json<T>(T);
+
+#[throttle(2, "1s", skipped = Response { status: 429, body: String::from("Slow down") })]
+pub(crate) async fn create_item(State(id): State, Json(name): Json<String>) -> Response {
+  Response { status: 201, body: format!("Created item {} named {}", id, name) }
+}
+
+#[throttle(1, "1s")]
+pub(crate) async fn greet<T: std::fmt::Display + Send>(name: T) -> impl std::fmt::Debug {
+  format!("Hello {}", name)
+}
+
+/// A minimal executor, to keep this example free of an async runtime dependency.
+fn block_on<F: Future>(future: F) -> F::Output {
+  struct Noop;
+
+  impl Wake for Noop {
+    fn wake(self: Arc<Self>) {}
+  }
+
+  let waker = Arc::new(Noop).into();
+  let mut cx = Context::from_waker(&waker);
+  let mut future = pin!(future);
+  loop {
+    if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+      return output;
+    }
+  }
+}
+
+fn is_send<T: Send>(_: T) {}
+
+fn main() {
+  // Web frameworks need the futures of handlers to be Send.
+  is_send(greet("world"));
+
+  for i in 0..3 {
+    let response = block_on(create_item(State(i), Json(format!("item-{}", i))));
+    eprintln!("{} {}", response.status, response.body);
+  }
+
+  eprintln!("{:?}", block_on(greet("world")));
+  eprintln!("{:?}", block_on(greet(42)));
+}
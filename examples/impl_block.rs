@@ -0,0 +1,62 @@
+use throttle_my_fn::throttle;
+
+pub(crate) struct Client {
+  name: String,
+  requests: u32,
+}
+
+// All methods share a quota of 3 calls per second.
+#[throttle(3, "1s")]
+impl Client {
+  pub(crate) fn new(name: &str) -> Self {
+    Client { name: String::from(name), requests: 0 }
+  }
+
+  pub(crate) fn get(&mut self, path: &str) -> String {
+    self.requests += 1;
+    format!("{}: GET {} (request {})", self.name, path, self.requests)
+  }
+
+  pub(crate) fn post(&mut self, path: &str, body: &str) -> String {
+    self.requests += 1;
+    format!("{}: POST {} {} (request {})", self.name, path, body, self.requests)
+  }
+}
+
+pub(crate) struct Counter(u32);
+
+// Every method gets its own quota of 1 call per second.
+#[throttle(1, "1s", share = false)]
+impl Counter {
+  pub(crate) fn increment(&mut self) -> u32 {
+    self.0 += 1;
+    self.0
+  }
+
+  pub(crate) fn get(&self) -> u32 {
+    self.0
+  }
+
+  // Methods can also be throttled individually, and have their own quota.
+  #[throttle(2, "1s")]
+  pub(crate) fn reset(&mut self) -> Result<(), String> {
+    if self.0 == 0 {
+      return Err(String::from("already reset"));
+    }
+    self.0 = 0;
+    Ok(())
+  }
+}
+
+fn main() {
+  let mut client = Client::new("api");
+  eprintln!("{:?}", client.get("/users"));
+  eprintln!("{:?}", client.post("/users", "{}"));
+  eprintln!("{:?}", client.get("/users/1"));
+  eprintln!("{:?}", client.post("/users/1", "{}"));
+
+  let mut counter = Counter(0);
+  eprintln!("{:?} {:?}", counter.increment(), counter.increment());
+  eprintln!("{:?} {:?}", counter.get(), counter.get());
+  eprintln!("{:?} {:?} {:?}", counter.reset(), counter.reset(), counter.reset());
+}
@@ -0,0 +1,11 @@
+use std::time::{Duration, Instant};
+use throttle_my_fn_runtime::ThrottleExt;
+
+fn main() {
+  let start = Instant::now();
+
+  // Process at most 3 jobs every 100ms.
+  for job in (0..10).throttle(3, Duration::from_millis(100)) {
+    eprintln!("{:>4}ms: Running job {}", start.elapsed().as_millis(), job);
+  }
+}
@@ -0,0 +1,37 @@
+use std::thread;
+use std::time::Duration;
+use throttle_my_fn::{max_concurrent, throttle};
+
+#[max_concurrent(2)]
+pub(crate) fn resize_image(image: usize) -> usize {
+  eprintln!("Resizing image {}", image);
+  thread::sleep(Duration::from_millis(100));
+  image
+}
+
+#[throttle(3, "1s", concurrency = 1)]
+pub(crate) fn fetch_page(page: usize) -> usize {
+  eprintln!("Fetching page {}", page);
+  thread::sleep(Duration::from_millis(100));
+  page
+}
+
+fn main() {
+  let threads =
+    (0..6).map(|i| thread::spawn(move || resize_image(i))).collect::<Vec<_>>();
+
+  for thread in threads {
+    eprintln!("Resized: {:?}", thread.join().unwrap());
+  }
+
+  // Only one fetch can be in flight at a time, and at most 3 can start per second.
+  for round in 0..3 {
+    let threads = (0..2)
+      .map(|i| thread::spawn(move || fetch_page(round * 2 + i)))
+      .collect::<Vec<_>>();
+
+    for thread in threads {
+      eprintln!("Fetched: {:?}", thread.join().unwrap());
+    }
+  }
+}
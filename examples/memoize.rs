@@ -0,0 +1,45 @@
+use std::thread::sleep;
+use std::time::Duration;
+use throttle_my_fn::{memoize, throttle};
+
+#[memoize(ttl = "200ms")]
+pub(crate) fn lookup(host: &str, port: u16) -> String {
+  eprintln!("Resolving {}:{}", host, port);
+  format!("{}:{} -> 10.0.0.1", host, port)
+}
+
+// At most two users are cached at a time.
+#[memoize(ttl = "10s", max_keys = 2)]
+pub(crate) fn user_name(id: u32) -> String {
+  eprintln!("Loading user {}", id);
+  format!("user-{}", id)
+}
+
+#[memoize(ttl = "1s")]
+#[throttle(1, "1s")]
+pub(crate) fn exchange_rate(currency: String) -> f64 {
+  eprintln!("Fetching exchange rate for {}", currency);
+  1.1
+}
+
+fn main() {
+  // Only the first call for every host and port executes.
+  eprintln!("{}", lookup("example.com", 80));
+  eprintln!("{}", lookup("example.com", 80));
+  eprintln!("{}", lookup("example.com", 443));
+
+  // The cached value has expired.
+  sleep(Duration::from_millis(250));
+  eprintln!("{}", lookup("example.com", 80));
+
+  // Caching user 3 evicts user 1, which was cached first.
+  for id in [1, 2, 3, 2, 1] {
+    eprintln!("{}", user_name(id));
+    sleep(Duration::from_millis(1));
+  }
+
+  // Cache hits don't count against the rate limit.
+  eprintln!("EUR: {:?}", exchange_rate(String::from("EUR")));
+  eprintln!("EUR: {:?}", exchange_rate(String::from("EUR")));
+  eprintln!("USD: {:?}", exchange_rate(String::from("USD")));
+}
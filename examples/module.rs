@@ -0,0 +1,19 @@
+use throttle_my_fn::throttle;
+
+// All functions share a quota of 2 calls per second.
+#[throttle(2, "1s")]
+mod github {
+  pub(crate) fn repos(user: &str) -> String {
+    format!("GET /users/{}/repos", user)
+  }
+
+  pub(crate) fn issues(repo: &str) -> String {
+    format!("GET /repos/{}/issues", repo)
+  }
+}
+
+fn main() {
+  eprintln!("{:?}", github::repos("fredmorcos"));
+  eprintln!("{:?}", github::issues("fredmorcos/throttle_my_fn"));
+  eprintln!("{:?}", github::repos("rust-lang"));
+}
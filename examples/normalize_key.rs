@@ -0,0 +1,29 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::BuildHasherDefault;
+use throttle_my_fn::throttle;
+
+// Hostnames differing only in case are the same host.
+fn lowercase(host: String) -> String {
+  host.to_lowercase()
+}
+
+// Up to 2 fetches a minute per host, with hosts looked up in a map of a fixed hasher.
+#[throttle(
+  global = (100, "1m"),
+  per_key = (2, "1m"),
+  key = host,
+  normalize_key = lowercase,
+  hasher = BuildHasherDefault::<DefaultHasher>
+)]
+fn fetch(host: &str, path: &str) -> String {
+  format!("https://{}{}", host, path)
+}
+
+fn main() {
+  assert!(fetch("example.com", "/a").is_some());
+  assert!(fetch("EXAMPLE.com", "/b").is_some());
+
+  // The third fetch of the same host is skipped, however its name is written.
+  assert_eq!(fetch("Example.Com", "/c"), None);
+  assert!(fetch("example.org", "/a").is_some());
+}
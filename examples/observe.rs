@@ -0,0 +1,19 @@
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+// A proposed limit, checked against the traffic before it is enforced.
+#[throttle(3, "1s", mode = observe)]
+fn handle_request(id: u32) -> String {
+  format!("handled request {}", id)
+}
+
+fn main() {
+  for id in 0..5 {
+    eprintln!("{:?}", handle_request(id));
+    std::thread::sleep(Duration::from_millis(100));
+  }
+
+  let stats = handle_request_stats();
+  eprintln!("{} requests would have been skipped", stats.skipped);
+  eprintln!("a request would have waited up to {:?}", stats.max_wait);
+}
@@ -0,0 +1,30 @@
+use std::time::{Duration, Instant};
+use throttle_my_fn::throttle;
+
+// At most two pages are fetched at once, the other calls wait for their turn.
+#[throttle(10, "1s", concurrency = 2, on_busy = wait)]
+pub(crate) async fn fetch_page(page: u32) -> String {
+  tokio::time::sleep(Duration::from_millis(100)).await;
+  format!("page {}", page)
+}
+
+fn main() {
+  let runtime = tokio::runtime::Runtime::new().unwrap();
+  runtime.block_on(async {
+    let start = Instant::now();
+
+    let tasks: Vec<_> = (1..=5).map(|page| tokio::spawn(fetch_page(page))).collect();
+    for task in tasks {
+      eprintln!("{:?} after {:?}", task.await.unwrap(), start.elapsed());
+    }
+
+    // A call given up on while waiting for a permit leaves the queue.
+    let busy: Vec<_> = (6..=7).map(|page| tokio::spawn(fetch_page(page))).collect();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let cancelled = tokio::time::timeout(Duration::from_millis(10), fetch_page(8)).await;
+    eprintln!("cancelled: {}", cancelled.is_err());
+    for task in busy {
+      eprintln!("{:?}", task.await.unwrap());
+    }
+  });
+}
@@ -0,0 +1,18 @@
+use throttle_my_fn::throttle;
+
+fn audit(function: &'static str, occupancy: usize) {
+  eprintln!("audit: executing {} ({} calls in the window)", function, occupancy);
+}
+
+// Every call that is let through is reported to `audit` right before it is executed.
+#[throttle(2, "1s", on_execute = audit)]
+pub(crate) fn delete_account(id: u32) -> String {
+  format!("deleted account {}", id)
+}
+
+fn main() {
+  // The first two calls are executed and audited, the third one is skipped.
+  for id in [1, 2, 3] {
+    eprintln!("{:?}", delete_account(id));
+  }
+}
@@ -0,0 +1,37 @@
+use std::thread;
+use throttle_my_fn::once;
+
+#[once]
+pub(crate) fn print_banner(version: &str) {
+  eprintln!("Starting version {}", version);
+}
+
+#[once(skipped = false)]
+pub(crate) fn init_logger() -> bool {
+  eprintln!("Initializing the logger");
+  true
+}
+
+#[once(cache_last)]
+pub(crate) fn load_config(path: &str) -> String {
+  eprintln!("Loading config from {}", path);
+  format!("config from {}", path)
+}
+
+fn main() {
+  for _ in 0..3 {
+    eprintln!("Printed banner: {}", print_banner("1.0").is_some());
+  }
+
+  for _ in 0..3 {
+    eprintln!("Initialized logger: {}", init_logger());
+  }
+
+  let threads = (0..4)
+    .map(|i| thread::spawn(move || load_config(&format!("config-{}.toml", i))))
+    .collect::<Vec<_>>();
+
+  for thread in threads {
+    eprintln!("Loaded: {:?}", thread.join().unwrap());
+  }
+}
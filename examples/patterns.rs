@@ -0,0 +1,24 @@
+use throttle_my_fn::throttle;
+
+pub(crate) struct Point {
+  x: i32,
+  y: i32,
+}
+
+#[throttle(2, "1s")]
+pub(crate) fn distance((x1, y1): (i32, i32), Point { x: x2, y: y2 }: Point) -> i32 {
+  (x2 - x1).abs() + (y2 - y1).abs()
+}
+
+#[throttle(2, "1s")]
+pub(crate) fn total(mut sum: u32, [a, b, c]: [u32; 3]) -> u32 {
+  sum += a + b + c;
+  sum
+}
+
+fn main() {
+  for i in 0..3 {
+    eprintln!("{:?}", distance((0, 0), Point { x: i, y: 2 }));
+    eprintln!("{:?}", total(10, [1, 2, 3]));
+  }
+}
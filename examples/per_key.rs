@@ -0,0 +1,18 @@
+use std::net::{IpAddr, Ipv4Addr};
+use throttle_my_fn::throttle;
+
+// At most 3 requests per second overall, and 2 per second from the same client.
+#[throttle(global = (3, "1s"), per_key = (2, "1s"), key = client_ip)]
+pub(crate) fn handle_request(client_ip: IpAddr, path: &str) -> String {
+  format!("{} -> {}", client_ip, path)
+}
+
+fn main() {
+  let alice = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+  let bob = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+  // Alice's third request exceeds her own budget, and Bob's second one the global one.
+  for client_ip in [alice, alice, alice, bob, bob] {
+    eprintln!("{:?}", handle_request(client_ip, "/index.html"));
+  }
+}
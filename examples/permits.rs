@@ -0,0 +1,44 @@
+use throttle_my_fn::throttle;
+
+#[throttle(2, "1s", permits = true)]
+pub(crate) fn send_message(to: &str, text: &str) -> String {
+  format!("Sent {:?} to {}", text, to)
+}
+
+/// Validation that may fail after a slot has been reserved.
+fn validate(text: &str) -> Option<&str> {
+  Some(text).filter(|text| !text.is_empty())
+}
+
+fn main() {
+  for text in ["hello", "", "world", "again"] {
+    // Reserve a slot before validating, so that a valid message is never throttled
+    // after the work of validating it.
+    let permit = match send_message_acquire() {
+      Some(permit) => permit,
+      None => {
+        eprintln!("Throttled {:?}", text);
+        continue;
+      }
+    };
+
+    // Invalid messages drop their permit, returning its slot for the next message.
+    match validate(text) {
+      Some(text) => eprintln!("{}", permit.run("alice", text)),
+      None => eprintln!("Invalid message {:?}", text),
+    }
+  }
+
+  // A batch is only sent if there is a slot left for each of its messages.
+  std::thread::sleep(std::time::Duration::from_secs(1));
+  for batch in [["bob", "carol"], ["dave", "erin"]] {
+    match send_message_acquire_n(batch.len()) {
+      Some(permits) => {
+        for (permit, to) in permits.into_iter().zip(batch) {
+          eprintln!("{}", permit.run(to, "batched"));
+        }
+      }
+      None => eprintln!("Throttled the batch for {:?}", batch),
+    }
+  }
+}
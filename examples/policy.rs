@@ -0,0 +1,36 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use throttle_my_fn::throttle;
+use throttle_my_fn_runtime::{AdmissionPolicy, Decision};
+
+static NIGHT: AtomicBool = AtomicBool::new(false);
+
+// Twice the budget during the night.
+struct Nightly;
+
+impl AdmissionPolicy for Nightly {
+  fn decide(&self, _now: Instant, history: &VecDeque<Instant>, cost: usize) -> Decision {
+    let budget = if NIGHT.load(Ordering::Relaxed) { 4 } else { 2 };
+    match history.len() + cost <= budget {
+      true => Decision::Admit,
+      false => Decision::Skip,
+    }
+  }
+}
+
+#[throttle(2, "1h", policy = Nightly)]
+fn backup(file: &str) -> String {
+  format!("backed up {}", file)
+}
+
+fn main() {
+  for file in ["a", "b", "c"] {
+    eprintln!("{:?}", backup(file));
+  }
+
+  NIGHT.store(true, Ordering::Relaxed);
+  for file in ["c", "d", "e"] {
+    eprintln!("{:?}", backup(file));
+  }
+}
@@ -0,0 +1,37 @@
+use std::thread;
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+// Up to 10 requests every half a second, of which high-priority requests can only take
+// 7 while low-priority ones keep coming, and all 10 otherwise.
+#[throttle(
+  10,
+  Duration::from_millis(500),
+  priorities = (high = 70, low = 30),
+  priority = class
+)]
+fn request(class: &str, id: usize) -> String {
+  format!("{} request {}", class, id)
+}
+
+fn main() {
+  // Both classes keep making requests, high-priority ones the most.
+  let mut high = 0;
+  let mut low = 0;
+  for id in 0..40 {
+    if request("high", id).is_some() {
+      high += 1;
+    }
+    if id % 4 == 0 && request("low", id).is_some() {
+      low += 1;
+    }
+  }
+  println!("{} high-priority and {} low-priority requests went through", high, low);
+  assert_eq!((high, low), (7, 3));
+
+  // Once the window is over, a class that is the only one active can use the whole limit.
+  thread::sleep(Duration::from_millis(600));
+  let high = (0..20).filter(|&id| request("high", id).is_some()).count();
+  println!("{} high-priority requests went through on their own", high);
+  assert_eq!(high, 10);
+}
@@ -0,0 +1,29 @@
+use std::thread;
+use std::time::Instant;
+use throttle_my_fn::throttle;
+
+#[throttle(2, "100ms", queries = true)]
+pub(crate) fn sync_inbox(round: u32) -> String {
+  format!("Synced inbox (round {})", round)
+}
+
+fn main() {
+  // Probing doesn't use up the quota.
+  eprintln!("Available: {}", sync_inbox_is_available());
+  eprintln!("Available: {}", sync_inbox_is_available());
+
+  for round in 0..5 {
+    // Sleep exactly as long as needed instead of polling the function.
+    if let Some(delay) = sync_inbox_retry_after() {
+      eprintln!("Waiting {:?}", delay);
+      thread::sleep(delay);
+    }
+
+    eprintln!("{:?}", sync_inbox(round));
+  }
+
+  // Executors scheduling their own wake-ups can get the exact time instead.
+  if let Some(next_slot) = sync_inbox_next_slot() {
+    eprintln!("Next slot in {:?}", next_slot.saturating_duration_since(Instant::now()));
+  }
+}
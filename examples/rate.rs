@@ -0,0 +1,17 @@
+use std::thread;
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+// At most 30 frames per second, evenly spaced.
+#[throttle(rate = 30.0)]
+pub(crate) fn render_frame(frame: u32) -> String {
+  format!("rendered frame {}", frame)
+}
+
+fn main() {
+  // Only every other frame is rendered when they come in at 60 frames per second.
+  for frame in 0..6 {
+    eprintln!("{:?}", render_frame(frame));
+    thread::sleep(Duration::from_millis(17));
+  }
+}
@@ -0,0 +1,29 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+// Stands for a live configuration system, where the limit can change at any time.
+static LIMIT: AtomicUsize = AtomicUsize::new(2);
+
+fn current_limit() -> (usize, Duration) {
+  (LIMIT.load(Ordering::Relaxed), Duration::from_secs(1))
+}
+
+// The provider is called at most once every 100 milliseconds.
+#[throttle(rate_from = current_limit, rate_refresh = "100ms")]
+pub(crate) fn send(message: &str) -> String {
+  format!("sent {}", message)
+}
+
+fn main() {
+  // The first two calls are executed, the third one is skipped.
+  for message in ["a", "b", "c"] {
+    eprintln!("{:?}", send(message));
+  }
+
+  // Once the limit is raised and the cached one is stale, the next call is executed.
+  LIMIT.store(3, Ordering::Relaxed);
+  thread::sleep(Duration::from_millis(150));
+  eprintln!("{:?}", send("d"));
+}
@@ -0,0 +1,17 @@
+use throttle_my_fn::throttle;
+
+// Each top-level call is charged once, however deep the recursion goes.
+#[throttle(2, "1s", recursion = bypass)]
+pub(crate) fn factorial(n: u64) -> u64 {
+  match n {
+    0 => 1,
+    n => n * factorial(n - 1),
+  }
+}
+
+fn main() {
+  // The first two calls are executed, the third one is skipped.
+  for n in [5, 10, 15] {
+    eprintln!("{}! = {:?}", n, factorial(n));
+  }
+}
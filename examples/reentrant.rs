@@ -0,0 +1,21 @@
+use std::thread;
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+// Rebuilding the index is rate-limited, and must not overlap with itself.
+#[throttle(10, "1s", reentrant = deny)]
+pub(crate) fn rebuild_index(who: &str) {
+  eprintln!("{} is rebuilding the index", who);
+  thread::sleep(Duration::from_millis(100));
+}
+
+fn main() {
+  let other = thread::spawn(|| rebuild_index("other thread"));
+  thread::sleep(Duration::from_millis(10));
+
+  // Skipped, the other thread is still rebuilding the index.
+  eprintln!("main thread: {:?}", rebuild_index("main thread"));
+
+  other.join().unwrap();
+  eprintln!("main thread: {:?}", rebuild_index("main thread"));
+}
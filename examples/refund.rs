@@ -0,0 +1,21 @@
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+// A search given up on doesn't count against the quota.
+#[throttle(1, "1m", refund = on_cancel)]
+async fn search(query: &str) -> String {
+  tokio::time::sleep(Duration::from_millis(50)).await;
+  format!("results for {}", query)
+}
+
+fn main() {
+  let runtime = tokio::runtime::Runtime::new().unwrap();
+  runtime.block_on(async {
+    let timeout = Duration::from_millis(10);
+    eprintln!("{:?}", tokio::time::timeout(timeout, search("rust")).await);
+
+    // The cancelled search gave its slot back.
+    eprintln!("{:?}", search("rust").await);
+    eprintln!("{:?}", search("tokio").await);
+  });
+}
@@ -0,0 +1,20 @@
+use std::thread::sleep;
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+#[throttle(2, "1m", reset_after_idle = "200ms")]
+fn sync(account: &str) -> String {
+  format!("synced {}", account)
+}
+
+fn main() {
+  for account in ["alice", "bob", "carol"] {
+    eprintln!("{:?}", sync(account));
+  }
+
+  // After a quiet moment, the calls above are forgotten.
+  sleep(Duration::from_millis(300));
+  for account in ["dave", "erin"] {
+    eprintln!("{:?}", sync(account));
+  }
+}
@@ -0,0 +1,19 @@
+use std::thread;
+use std::time::Duration;
+use throttle_my_fn::throttle;
+
+// The window only keeps a count per slot of 10ms, whatever the limit.
+#[throttle(50_000, "100ms", resolution = "10ms")]
+fn ingest(event: u32) -> u32 {
+  event
+}
+
+fn main() {
+  let ingested = (0..60_000).filter_map(ingest).count();
+  eprintln!("Ingested {} events", ingested);
+
+  // Calls are counted as if made at the end of their slot, so they stay in the window
+  // for up to 10ms longer.
+  thread::sleep(Duration::from_millis(120));
+  eprintln!("{:?}", ingest(60_000));
+}
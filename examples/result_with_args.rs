@@ -0,0 +1,27 @@
+use throttle_my_fn::throttle;
+
+/// An upload that isn't `Clone`, like a file handle.
+pub(crate) struct Upload {
+  name: String,
+  data: Vec<u8>,
+}
+
+// Skipped calls hand their upload back instead of dropping it.
+#[throttle(2, "1s", output = result_with_args)]
+pub(crate) fn send(upload: Upload) -> String {
+  format!("sent {} ({} bytes)", upload.name, upload.data.len())
+}
+
+fn main() {
+  let mut retry = Vec::new();
+  for i in 0..4 {
+    let upload = Upload { name: format!("file{}", i), data: vec![0; i * 10] };
+    match send(upload) {
+      Ok(sent) => eprintln!("{}", sent),
+      Err((upload,)) => retry.push(upload),
+    }
+  }
+
+  let names: Vec<_> = retry.iter().map(|upload| upload.name.as_str()).collect();
+  eprintln!("to retry later: {:?}", names);
+}
@@ -0,0 +1,30 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use throttle_my_fn::{retry, throttle};
+
+static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+
+#[retry(3, backoff = "10ms")]
+pub(crate) fn flaky(msg: &str) -> Result<usize, String> {
+  let attempt = ATTEMPTS.fetch_add(1, Ordering::Relaxed);
+  eprintln!("{}: attempt {}", msg, attempt);
+  if attempt % 3 == 2 {
+    Ok(attempt)
+  } else {
+    Err(format!("attempt {} failed", attempt))
+  }
+}
+
+#[retry(5, backoff = "10ms")]
+#[throttle(2, "500ms")]
+pub(crate) fn fetch(url: String) -> Result<String, String> {
+  eprintln!("Fetching {}", url);
+  Err(String::from("service unavailable"))
+}
+
+fn main() {
+  eprintln!("Result: {:?}", flaky("first"));
+  eprintln!("Result: {:?}", flaky("second"));
+
+  // Every attempt is throttled to 2 per 500ms: the 6 attempts take about a second.
+  eprintln!("Result: {:?}", fetch(String::from("https://example.com")));
+}
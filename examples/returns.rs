@@ -0,0 +1,27 @@
+use throttle_my_fn::{throttle, timeout};
+
+#[throttle(2, "1s")]
+pub(crate) fn words(text: &str) -> impl Iterator<Item = &str> {
+  text.split_whitespace()
+}
+
+#[throttle(2, "1s")]
+pub(crate) fn first_line(text: &str) -> &str {
+  text.lines().next().unwrap_or_default()
+}
+
+#[timeout("1s")]
+pub(crate) fn countdown(from: u32) -> impl Iterator<Item = u32> + Send {
+  (0..=from).rev()
+}
+
+fn main() {
+  let text = "hello throttled\nworld";
+
+  for _ in 0..3 {
+    eprintln!("{:?}", words(text).map(|words| words.collect::<Vec<_>>()));
+    eprintln!("{:?}", first_line(text));
+  }
+
+  eprintln!("{:?}", countdown(3).map(|countdown| countdown.collect::<Vec<_>>()));
+}
@@ -0,0 +1,20 @@
+use throttle_my_fn::sample;
+
+#[sample(5)]
+pub(crate) fn log_request(request: usize) -> usize {
+  eprintln!("Handling request {}", request);
+  request
+}
+
+// Callers get an empty sample instead of None.
+#[sample(5, skipped = Vec::new())]
+pub(crate) fn sample_metrics(request: usize) -> Vec<String> {
+  vec![format!("latency of request {}", request)]
+}
+
+fn main() {
+  for request in 0..20 {
+    let _ = log_request(request);
+    eprintln!("{:?}", sample_metrics(request));
+  }
+}
@@ -0,0 +1,16 @@
+use std::time::Duration;
+use throttle_my_fn_runtime::{set_scale, Throttler};
+
+fn main() {
+  let outbound = Throttler::new(4, Duration::from_secs(60));
+
+  // During an incident, only half of the usual traffic goes out.
+  set_scale(0.5);
+  for request in 0..4 {
+    let sent = outbound.try_acquire();
+    eprintln!("request {}: {}", request, if sent { "sent" } else { "shed" });
+  }
+
+  set_scale(1.0);
+  eprintln!("request 4: {}", if outbound.try_acquire() { "sent" } else { "shed" });
+}
@@ -0,0 +1,16 @@
+use std::fmt::Debug;
+use throttle_my_fn::throttle;
+
+// Logging at most one message per second of each type, rather than of any type.
+#[throttle(1, "1s", scope = per_type)]
+pub(crate) fn log<M: Debug>(message: M) {
+  eprintln!("message: {:?}", message);
+}
+
+fn main() {
+  // The second call of each type is skipped.
+  eprintln!("{:?}", log("connection lost"));
+  eprintln!("{:?}", log(503));
+  eprintln!("{:?}", log("connection restored"));
+  eprintln!("{:?}", log(504));
+}
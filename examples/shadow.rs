@@ -0,0 +1,17 @@
+use throttle_my_fn::throttle;
+
+// Would halving the limit skip many more quotes?
+#[throttle(4, "1m", shadow = (2, "1m"))]
+fn fetch_quote(symbol: &str) -> String {
+  format!("quote for {}", symbol)
+}
+
+fn main() {
+  for symbol in ["AAPL", "MSFT", "GOOG", "AMZN", "NVDA"] {
+    eprintln!("{:?}", fetch_quote(symbol));
+  }
+
+  let stats = fetch_quote_stats();
+  let (skipped, shadow_skipped) = (stats.skipped, stats.shadow_skipped);
+  eprintln!("skipped {} calls, the candidate limit {}", skipped, shadow_skipped);
+}
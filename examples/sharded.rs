@@ -0,0 +1,30 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use throttle_my_fn::throttle;
+
+static SENT: AtomicUsize = AtomicUsize::new(0);
+
+// Calls from different threads mostly lock different shards of the quota.
+#[throttle(1000, "1m", sharded = true)]
+pub(crate) fn send_metric(value: u64) {
+  let _ = value;
+  SENT.fetch_add(1, Ordering::Relaxed);
+}
+
+fn main() {
+  let threads: Vec<_> = (0..32)
+    .map(|thread| {
+      std::thread::spawn(move || {
+        for value in 0..100 {
+          send_metric(thread * 100 + value);
+        }
+      })
+    })
+    .collect();
+
+  for thread in threads {
+    thread.join().unwrap();
+  }
+
+  // At most 1000 of the 3200 calls were sent.
+  eprintln!("sent {} metrics", SENT.load(Ordering::Relaxed));
+}
@@ -0,0 +1,29 @@
+use throttle_my_fn::throttle;
+
+enum Response {
+  Created(String),
+  TooManyRequests,
+}
+
+struct RateLimited;
+
+impl From<RateLimited> for Response {
+  fn from(_: RateLimited) -> Self {
+    Response::TooManyRequests
+  }
+}
+
+// The handler keeps its return type, so it can still be used as a route.
+#[throttle(2, "1m", skip_into = RateLimited)]
+fn create_item(name: &str) -> Response {
+  Response::Created(String::from(name))
+}
+
+fn main() {
+  for name in ["chair", "table", "lamp"] {
+    match create_item(name) {
+      Response::Created(item) => eprintln!("201 Created: {}", item),
+      Response::TooManyRequests => eprintln!("429 Too Many Requests"),
+    }
+  }
+}
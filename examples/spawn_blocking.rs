@@ -0,0 +1,24 @@
+use std::time::Instant;
+use throttle_my_fn::throttle;
+
+// A blocking function, which async code calls through its `_async` variant.
+#[throttle(2, "100ms", variants(try, async))]
+pub(crate) fn compress(data: Vec<u8>) -> usize {
+  data.iter().filter(|&&byte| byte != 0).count()
+}
+
+fn main() {
+  let runtime = tokio::runtime::Runtime::new().unwrap();
+  runtime.block_on(async {
+    let start = Instant::now();
+
+    // The calls beyond the quota wait on the tokio timer, without blocking the executor,
+    // and then run on its blocking thread pool.
+    let tasks: Vec<_> =
+      (1..=4).map(|len| tokio::spawn(compress_async(vec![1; len]))).collect();
+    for task in tasks {
+      let compressed = task.await.unwrap();
+      eprintln!("compressed {} bytes after {:?}", compressed, start.elapsed());
+    }
+  });
+}
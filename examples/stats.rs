@@ -0,0 +1,18 @@
+use throttle_my_fn::throttle;
+
+#[throttle(2, "100ms", stats = true, variants(try, wait))]
+pub(crate) fn send_heartbeat(id: u32) {
+  eprintln!("Heartbeat {}", id);
+}
+
+fn main() {
+  // Two heartbeats are sent, the third one is skipped.
+  for id in 0..3 {
+    let _ = send_heartbeat(id);
+  }
+  eprintln!("{:?}", send_heartbeat_stats());
+
+  // This one waits for the window to free up.
+  send_heartbeat_wait(3);
+  eprintln!("{:?}", send_heartbeat_stats());
+}
@@ -0,0 +1,24 @@
+use std::time::Duration;
+use throttle_my_fn::throttle;
+use throttle_my_fn_runtime::assert_throttled;
+use throttle_my_fn_runtime::testing::{advance, drive};
+
+#[throttle(10, "1s")]
+fn fetch(page: u32) -> u32 {
+  page
+}
+
+#[throttle(1, "1m")]
+fn notify() {}
+
+fn main() {
+  // Run with `--features testing`: the windows move on along with the virtual clock,
+  // without sleeping.
+  assert_throttled!(fetch(1), within = "1s", allows = 10);
+
+  let executed = drive(3, Duration::from_secs(30), notify);
+  assert_eq!(executed, [true, false, true]);
+
+  advance(Duration::from_secs(60));
+  eprintln!("{:?}", notify());
+}
@@ -0,0 +1,20 @@
+use std::thread::sleep;
+use std::time::Duration;
+use throttle_my_fn::throttle_block;
+
+fn main() {
+  for i in 0..20 {
+    // Report progress at most 2 times per 100ms.
+    throttle_block!(2, "100ms", {
+      eprintln!("Progress: {}/20", i);
+    });
+
+    // Every call site has its own quota.
+    let report = throttle_block!(1, Duration::from_secs(1), format!("Started at {}", i));
+    if let Some(report) = report {
+      eprintln!("{}", report);
+    }
+
+    sleep(Duration::from_millis(20));
+  }
+}
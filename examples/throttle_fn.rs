@@ -0,0 +1,18 @@
+use std::thread::sleep;
+use std::time::Duration;
+use throttle_my_fn::throttle_fn;
+
+fn main() {
+  let double = throttle_fn!(2, "100ms", |x: u32| x * 2);
+
+  // Every closure has its own quota.
+  let prefix = String::from("Message");
+  let greet = throttle_fn!(1, "100ms", move |name: &str, (a, b): (u32, u32)| {
+    format!("{}: Hello {}, {} + {} = {}", prefix, name, a, b, a + b)
+  });
+
+  for i in 0..10 {
+    eprintln!("{}: {:?} {:?}", i, double(i), greet("world", (i, 1)));
+    sleep(Duration::from_millis(20));
+  }
+}
@@ -0,0 +1,36 @@
+use throttle_my_fn::throttle_group;
+
+// Every client gets a quota of its own, shared by its marked methods.
+#[throttle_group(2, "1s")]
+#[derive(Debug)]
+pub(crate) struct Client {
+  host: String,
+}
+
+impl Client {
+  pub(crate) fn new(host: &str) -> Self {
+    Client { host: String::from(host), throttle_group: Client::new_throttle_group() }
+  }
+
+  #[throttle_group]
+  pub(crate) fn get(&self, path: &str) -> String {
+    format!("GET {}{}", self.host, path)
+  }
+
+  #[throttle_group]
+  pub(crate) fn post(&self, path: &str) -> String {
+    format!("POST {}{}", self.host, path)
+  }
+}
+
+fn main() {
+  let github = Client::new("github.com");
+  let gitlab = Client::new("gitlab.com");
+
+  // The third call to `github` is skipped, while `gitlab` has its own quota.
+  eprintln!("{:?}", github.get("/repos"));
+  eprintln!("{:?}", github.post("/issues"));
+  eprintln!("{:?}", github.get("/users"));
+  eprintln!("{:?}", gitlab.get("/projects"));
+  eprintln!("{:?}", gitlab);
+}
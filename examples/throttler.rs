@@ -0,0 +1,43 @@
+use std::thread;
+use std::time::Duration;
+use throttle_my_fn_runtime::Throttler;
+
+/// A connection with its own limit, configured when it is created.
+struct Connection {
+  name: String,
+  throttler: Throttler,
+}
+
+impl Connection {
+  fn new(name: &str, messages_per_second: usize) -> Self {
+    Connection {
+      name: String::from(name),
+      throttler: Throttler::new(messages_per_second, Duration::from_secs(1)),
+    }
+  }
+
+  fn send(&self, message: &str) -> Option<String> {
+    self.throttler.try_acquire().then(|| format!("{} <- {}", self.name, message))
+  }
+}
+
+fn main() {
+  let slow = Connection::new("slow", 1);
+  let fast = Connection::new("fast", 3);
+
+  for i in 0..3 {
+    eprintln!("{:?}", slow.send(&format!("message {}", i)));
+    eprintln!("{:?}", fast.send(&format!("message {}", i)));
+  }
+
+  eprintln!("slow: {:?}", slow.throttler.stats());
+  eprintln!("fast: {:?}", fast.throttler.stats());
+
+  // Raise the limit of the slow connection and wait for a slot.
+  slow.throttler.set_times(2);
+  slow.throttler.acquire_blocking();
+  eprintln!("slow: {:?}", slow.throttler.stats());
+
+  thread::sleep(Duration::from_millis(10));
+  eprintln!("slow retry after: {:?}", slow.throttler.retry_after());
+}
@@ -0,0 +1,25 @@
+use std::thread::sleep;
+use std::time::Duration;
+use throttle_my_fn::{throttle, timeout};
+
+#[timeout(Duration::from_millis(100))]
+pub(crate) fn query(delay: u64) -> u64 {
+  sleep(Duration::from_millis(delay));
+  delay
+}
+
+#[timeout(Duration::from_millis(100))]
+#[throttle(1, "1s")]
+pub(crate) fn refresh() -> u64 {
+  sleep(Duration::from_millis(500));
+  1
+}
+
+fn main() {
+  eprintln!("Fast query: {:?}", query(10));
+  eprintln!("Slow query: {:?}", query(500));
+
+  // Times out, then gets throttled.
+  eprintln!("First refresh: {:?}", refresh());
+  eprintln!("Second refresh: {:?}", refresh());
+}
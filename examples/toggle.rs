@@ -0,0 +1,21 @@
+use throttle_my_fn::throttle;
+
+#[throttle(1, "1m", toggle = true, stats = true)]
+fn notify(user: &str) -> String {
+  format!("notified {}", user)
+}
+
+fn main() {
+  eprintln!("{:?}", notify("alice"));
+  eprintln!("{:?}", notify("bob"));
+
+  // Everyone is notified while throttling is off.
+  notify_throttle_enabled(false);
+  for user in ["carol", "dave"] {
+    eprintln!("{:?}", notify(user));
+  }
+
+  notify_throttle_enabled(true);
+  eprintln!("{:?}", notify("erin"));
+  eprintln!("{:?}", notify_stats());
+}
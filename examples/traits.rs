@@ -0,0 +1,67 @@
+use throttle_my_fn::throttle;
+
+pub(crate) trait Notifier {
+  fn name(&self) -> String;
+
+  // Default methods share a single quota across all implementors.
+  #[throttle(2, "1s", skipped = false)]
+  fn notify(&self, message: &str) -> bool {
+    eprintln!("[{}] {}", self.name(), message);
+    true
+  }
+}
+
+pub(crate) struct Email;
+pub(crate) struct Sms;
+
+impl Notifier for Email {
+  fn name(&self) -> String {
+    String::from("email")
+  }
+}
+
+impl Notifier for Sms {
+  fn name(&self) -> String {
+    String::from("sms")
+  }
+
+  // Methods of trait impls have a quota per impl, and need the `skipped` option to keep
+  // their return type.
+  #[throttle(1, "1s", skipped = false)]
+  fn notify(&self, message: &str) -> bool {
+    eprintln!("[{}] {}", self.name(), message);
+    true
+  }
+}
+
+pub(crate) trait Storage {
+  fn load(&self, key: &str) -> Option<String>;
+  fn store(&mut self, key: &str, value: String) -> Result<(), String>;
+}
+
+pub(crate) struct Remote;
+
+// All methods of the impl share a single quota.
+#[throttle(2, "1s", skipped = Err(String::from("throttled")))]
+impl Storage for Remote {
+  #[throttle(1, "1s", skipped = None)]
+  fn load(&self, key: &str) -> Option<String> {
+    Some(format!("value of {}", key))
+  }
+
+  fn store(&mut self, key: &str, value: String) -> Result<(), String> {
+    eprintln!("Storing {} = {}", key, value);
+    Ok(())
+  }
+}
+
+fn main() {
+  eprintln!("{} {} {}", Email.notify("a"), Email.notify("b"), Email.notify("c"));
+  eprintln!("{} {}", Sms.notify("a"), Sms.notify("b"));
+
+  let mut remote = Remote;
+  eprintln!("{:?} {:?}", remote.load("a"), remote.load("b"));
+  eprintln!("{:?}", remote.store("a", String::from("1")));
+  eprintln!("{:?}", remote.store("b", String::from("2")));
+  eprintln!("{:?}", remote.store("c", String::from("3")));
+}
@@ -0,0 +1,31 @@
+use std::sync::{Mutex, MutexGuard};
+use throttle_my_fn::throttle;
+
+/// A token proving that the caller is allowed to flush, not needed otherwise.
+pub(crate) struct FlushToken;
+
+#[throttle(2, "1s")]
+pub(crate) fn flush(_: FlushToken, buffer: &mut Vec<u8>) -> usize {
+  let flushed = buffer.len();
+  buffer.clear();
+  flushed
+}
+
+// The guard is held while the call runs, and released right away for skipped calls.
+#[throttle(1, "1s")]
+pub(crate) fn report(_guard: MutexGuard<'_, ()>, message: &str) -> String {
+  format!("Reported: {}", message)
+}
+
+fn main() {
+  let mut buffer = Vec::new();
+  for i in 0..3 {
+    buffer.extend_from_slice(&[i; 4]);
+    eprintln!("{:?}", flush(FlushToken, &mut buffer));
+  }
+
+  let lock = Mutex::new(());
+  for message in ["first", "second"] {
+    eprintln!("{:?}", report(lock.lock().unwrap(), message));
+  }
+}
@@ -0,0 +1,24 @@
+use std::thread;
+use throttle_my_fn::throttle;
+
+#[throttle(2, "200ms", variants(try, wait))]
+pub(crate) fn send_email(to: &str) -> String {
+  format!("Sent an email to {}", to)
+}
+
+fn main() {
+  let worker = thread::spawn(|| {
+    // Background jobs can afford to wait for their turn...
+    for i in 0..3 {
+      eprintln!("worker: {}", send_email_wait(&format!("user{}@example.com", i)));
+    }
+  });
+
+  // ...while interactive callers would rather give up right away.
+  for _ in 0..3 {
+    eprintln!("main: {:?}", send_email("admin@example.com"));
+    thread::sleep(std::time::Duration::from_millis(50));
+  }
+
+  worker.join().unwrap();
+}
@@ -0,0 +1,25 @@
+use std::time::Instant;
+use throttle_my_fn::throttle;
+
+// A tight pacing loop busy-waits for its slots, rather than oversleeping them.
+#[throttle(1, "1ms", variants(try, wait), wait = spin)]
+pub(crate) fn step_motor() {}
+
+// Long windows sleep for most of the wait, and only yield for the end of it.
+#[throttle(2, "100ms", variants(try, wait), wait = adaptive)]
+pub(crate) fn poll_api() -> &'static str {
+  "polled"
+}
+
+fn main() {
+  let start = Instant::now();
+  for _ in 0..20 {
+    step_motor_wait();
+  }
+  eprintln!("20 steps in {:?}", start.elapsed());
+
+  let start = Instant::now();
+  for _ in 0..3 {
+    eprintln!("{} after {:?}", poll_api_wait(), start.elapsed());
+  }
+}
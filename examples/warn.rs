@@ -0,0 +1,20 @@
+use std::panic::Location;
+use throttle_my_fn::throttle;
+
+fn over_budget(function: &'static str, caller: &'static Location<'static>) {
+  eprintln!("{} called over budget at {}", function, caller);
+}
+
+// Observe how often the limit would be hit before enforcing it.
+#[throttle(2, "1m", mode = warn, on_over_budget = over_budget, stats = true)]
+fn charge_card(amount: u32) -> String {
+  format!("charged {}", amount)
+}
+
+fn main() {
+  for amount in [10, 20, 30, 40] {
+    eprintln!("{:?}", charge_card(amount));
+  }
+
+  eprintln!("{:?}", charge_card_stats());
+}
@@ -0,0 +1,19 @@
+use throttle_my_fn::throttle;
+use throttle_my_fn_runtime::ThrottleOverride;
+
+// Up to 4 exports a minute, with an extra parameter to override the limit per call.
+#[throttle(4, "1m", with_override = true)]
+fn export(report: &str) -> String {
+  format!("exported {}", report)
+}
+
+fn main() {
+  // A full export takes 3 slots, leaving a single one.
+  assert!(export("full", ThrottleOverride::CostMultiplier(3)).is_some());
+  assert!(export("daily", ThrottleOverride::Default).is_some());
+  assert_eq!(export("weekly", ThrottleOverride::Default), None);
+
+  // The admin console is never throttled, and doesn't take up any slot either.
+  println!("{:?}", export("audit", ThrottleOverride::Bypass));
+  assert!(export("audit", ThrottleOverride::Bypass).is_some());
+}
@@ -0,0 +1,35 @@
+//! Support for the `async` variant of throttled functions, which waits for a slot on the
+//! timer of the tokio runtime and then runs the function on its blocking thread pool, and
+//! for the timeouts of async functions.
+
+use crate::time::Instant;
+use std::future::Future;
+use std::time::Duration;
+
+/// Wait until `deadline` on the timer of the current tokio runtime.
+pub async fn sleep_until(deadline: Instant) {
+  tokio::time::sleep_until(deadline.into()).await
+}
+
+/// Await `future` for at most `duration` on the timer of the current tokio runtime, or
+/// return `None` once it elapses, dropping the future.
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+  tokio::time::timeout(duration, future).await.ok()
+}
+
+/// Run the blocking `call` on the blocking thread pool of the current tokio runtime. A
+/// panic of the call is resumed in the calling task.
+pub async fn spawn_blocking<F, T>(call: F) -> T
+where
+  F: FnOnce() -> T + Send + 'static,
+  T: Send + 'static,
+{
+  match tokio::task::spawn_blocking(call).await {
+    Ok(value) => value,
+    Err(error) => match error.try_into_panic() {
+      Ok(panic) => std::panic::resume_unwind(panic),
+      // Blocking tasks are only cancelled when the runtime is shutting down.
+      Err(error) => panic!("{}", error),
+    },
+  }
+}
@@ -0,0 +1,86 @@
+//! The bucketed window of throttles with a `resolution`, see [BucketWindow].
+
+use crate::sync::Mutex;
+use crate::time::Instant;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The slots of a [BucketWindow].
+struct Buckets {
+  /// The time each slot starts at along with the number of calls charged to it, the
+  /// oldest first.
+  slots: VecDeque<(Instant, usize)>,
+
+  /// The number of calls charged to all the slots.
+  calls: usize,
+}
+
+/// A sliding window counting calls in slots of `resolution` each, starting at the first
+/// call of the slot, which the `#[throttle(...)]` attribute uses with a `resolution`.
+///
+/// Rather than the time of every call in the window, only the slots are kept, so the
+/// memory taken by the window is proportional to `duration / resolution` instead of to
+/// `times`. A call is only known to have been made within its slot, so it is counted as
+/// if it had been made at the end of the slot, for the limit to never be exceeded: calls
+/// stay in the window for up to `resolution` longer than they would otherwise.
+#[doc(hidden)]
+pub struct BucketWindow {
+  buckets: Mutex<Buckets>,
+}
+
+impl BucketWindow {
+  /// Create an empty window.
+  pub fn new() -> Self {
+    BucketWindow { buckets: Mutex::new(Buckets { slots: VecDeque::new(), calls: 0 }) }
+  }
+
+  /// Charge a call made at `now` to a slot of `resolution`, if fewer than `times` calls
+  /// were let through over the last `duration`.
+  ///
+  /// # Returns
+  ///
+  /// Whether the call can go ahead.
+  pub fn try_charge(
+    &self,
+    now: Instant,
+    times: usize,
+    duration: Duration,
+    resolution: Duration,
+  ) -> bool {
+    let mut buckets = self.buckets.lock();
+
+    // Cleanup the slots whose end fell out of the window.
+    while let Some(&(slot_start, slot_calls)) = buckets.slots.front() {
+      let since_end = now.saturating_duration_since(slot_start + resolution);
+      if since_end > duration {
+        let _ = buckets.slots.pop_front();
+        buckets.calls -= slot_calls;
+      } else {
+        break;
+      }
+    }
+
+    if buckets.calls >= times {
+      return false;
+    }
+
+    // Open a new slot once the last one is over.
+    buckets.calls += 1;
+    match buckets.slots.back_mut() {
+      Some((slot_start, slot_calls))
+        if now.saturating_duration_since(*slot_start) < resolution =>
+      {
+        *slot_calls += 1;
+      }
+      _ => buckets.slots.push_back((now, 1)),
+    }
+
+    true
+  }
+}
+
+impl Default for BucketWindow {
+  fn default() -> Self {
+    Self::new()
+  }
+}
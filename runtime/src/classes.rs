@@ -0,0 +1,201 @@
+//! The weighted classes of calls of throttles with priorities, see [WeightedClasses].
+
+use crate::sync::{Condvar, Mutex, MutexGuard};
+use crate::time::Instant;
+use crate::window;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The book-keeping of every class, by index.
+struct Classes {
+  /// The time of the last call of each class, whether it was let through or not.
+  last_call: Box<[Option<Instant>]>,
+
+  /// The calls of each class let through in the window.
+  calls: Box<[VecDeque<Instant>]>,
+
+  /// The number of callers of each class waiting for a free slot.
+  waiting: Box<[usize]>,
+
+  /// The number of free slots each class can still take in its turn.
+  deficit: Box<[usize]>,
+
+  /// The class whose turn it is among the waiting callers.
+  turn: usize,
+}
+
+/// The classes of calls of a function sharing its limit by their weights, which the
+/// `#[throttle(...)]` attribute uses with `priorities = (...), priority = class`.
+///
+/// Calls that are skipped over the limit are charged against the share of their class,
+/// see [WeightedClasses::try_charge]. Calls with a deadline are admitted by deficit
+/// round-robin instead, see [WeightedClasses::enter]: while callers of several classes
+/// are waiting, each free slot goes to the class whose turn it is, which takes as many
+/// slots in a row as its weight before the turn moves on to the next class with waiting
+/// callers. A class alone in waiting takes every free slot.
+#[doc(hidden)]
+pub struct WeightedClasses {
+  /// The weights of the classes, divided by their greatest common divisor so that turns
+  /// are as short as they can be.
+  weights: Box<[usize]>,
+  classes: Mutex<Classes>,
+
+  /// Notified whenever the turn moves on to another class or a caller stops waiting.
+  turned: Condvar,
+}
+
+impl WeightedClasses {
+  /// Create the classes with the given `weights`, none of which is zero.
+  pub fn new(weights: &[usize]) -> Self {
+    let gcd = weights.iter().fold(0, |gcd, &weight| {
+      let (mut a, mut b) = (gcd, weight);
+      while b != 0 {
+        (a, b) = (b, a % b);
+      }
+      a
+    });
+    let weights: Box<[usize]> =
+      weights.iter().map(|weight| weight / gcd.max(1)).collect();
+
+    let count = weights.len();
+    let classes = Classes {
+      last_call: vec![None; count].into(),
+      calls: (0..count).map(|_| VecDeque::new()).collect(),
+      waiting: vec![0; count].into(),
+      deficit: vec![0; count].into(),
+      turn: 0,
+    };
+
+    WeightedClasses { weights, classes: Mutex::new(classes), turned: Condvar::new() }
+  }
+
+  /// Mark `class` as active with a call made at `now`, before the call is checked against
+  /// the limit of the function.
+  pub fn mark_active(&self, class: usize, now: Instant) {
+    self.classes.lock().last_call[class] = Some(now);
+  }
+
+  /// Charge a call of `class` made at `now` against the share of its class of `times`
+  /// calls over `duration`: the limit split between the classes with calls in the window
+  /// by their weights, rounded up.
+  ///
+  /// # Returns
+  ///
+  /// Whether the call can go ahead.
+  pub fn try_charge(
+    &self,
+    class: usize,
+    now: Instant,
+    times: usize,
+    duration: Duration,
+  ) -> bool {
+    let mut classes = self.classes.lock();
+    let classes = &mut *classes;
+
+    let active_weight: usize = classes
+      .last_call
+      .iter()
+      .zip(&*self.weights)
+      .filter(|(last_call, _)| {
+        last_call
+          .is_some_and(|last_call| now.saturating_duration_since(last_call) <= duration)
+      })
+      .map(|(_, weight)| weight)
+      .sum();
+    let share = usize::div_ceil(times * self.weights[class], active_weight.max(1));
+
+    let class_calls = &mut classes.calls[class];
+    window::cleanup(class_calls, now, share, duration);
+
+    let free = class_calls.len() < share;
+    if free {
+      class_calls.push_back(now);
+    }
+    free
+  }
+
+  /// Count a caller of `class` as waiting for a free slot until the returned guard is
+  /// dropped.
+  pub fn enter(&self, class: usize) -> Waiting<'_> {
+    self.classes.lock().waiting[class] += 1;
+    Waiting { classes: self, class }
+  }
+}
+
+/// A caller waiting for a free slot, see [WeightedClasses::enter].
+#[doc(hidden)]
+pub struct Waiting<'a> {
+  classes: &'a WeightedClasses,
+  class: usize,
+}
+
+/// The turn of another class, which a caller waits for once it found a free slot it
+/// cannot take, see [Waiting::admit].
+#[doc(hidden)]
+pub struct Turn<'a> {
+  classes: MutexGuard<'a, Classes>,
+  turned: &'a Condvar,
+}
+
+impl Turn<'_> {
+  /// Wait for up to `timeout` for the turn to move on, or for a caller to stop waiting.
+  /// The classes stay locked until then, so that none of that is missed.
+  pub fn wait(mut self, timeout: Duration) {
+    let _ = self.turned.wait_for(&mut self.classes, timeout);
+  }
+}
+
+impl<'a> Waiting<'a> {
+  /// Decide whether the caller takes the free slot it found, which it does if it is the
+  /// turn of its class or no other class has waiting callers.
+  ///
+  /// # Returns
+  ///
+  /// The turn of the other class the caller has to wait for otherwise.
+  pub fn admit(&self) -> Result<(), Turn<'a>> {
+    let WeightedClasses { weights, classes, turned } = self.classes;
+    let mut guard = classes.lock();
+    let classes = &mut *guard;
+
+    let others_waiting = (classes.waiting.iter().enumerate())
+      .any(|(class, &waiting)| class != self.class && waiting > 0);
+    if !others_waiting {
+      return Ok(());
+    }
+
+    // Move the turn on to the next class with waiting callers once the current one has
+    // none or took all of its slots. The class of the caller is waiting, so there is one.
+    let mut turned_over = false;
+    while classes.waiting[classes.turn] == 0 || classes.deficit[classes.turn] == 0 {
+      classes.deficit[classes.turn] = 0;
+      classes.turn = (classes.turn + 1) % weights.len();
+      if classes.waiting[classes.turn] > 0 {
+        classes.deficit[classes.turn] = weights[classes.turn];
+      }
+      turned_over = true;
+    }
+
+    // The callers of the class whose turn it is now may be waiting for it.
+    if turned_over {
+      turned.notify_all();
+    }
+
+    if classes.turn != self.class {
+      return Err(Turn { classes: guard, turned });
+    }
+    classes.deficit[self.class] -= 1;
+    Ok(())
+  }
+}
+
+impl Drop for Waiting<'_> {
+  fn drop(&mut self) {
+    let mut classes = self.classes.classes.lock();
+    classes.waiting[self.class] -= 1;
+    if classes.waiting[self.class] == 0 {
+      classes.deficit[self.class] = 0;
+    }
+    drop(classes);
+    self.classes.turned.notify_all();
+  }
+}
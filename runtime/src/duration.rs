@@ -0,0 +1,48 @@
+//! Parsing of human-readable durations such as `"1s"` or `"1h30m"`, shared by the
+//! duration literals of the `#[throttle(...)]` attribute and by
+//! [assert_throttled](crate::assert_throttled).
+
+/// Nanoseconds per supported unit suffix.
+const UNITS: &[(&str, u128)] = &[
+  ("ns", 1),
+  ("us", 1_000),
+  ("ms", 1_000_000),
+  ("s", 1_000_000_000),
+  ("m", 60 * 1_000_000_000),
+  ("min", 60 * 1_000_000_000),
+  ("h", 60 * 60 * 1_000_000_000),
+  ("d", 24 * 60 * 60 * 1_000_000_000),
+];
+
+/// Parse a duration made of one or more amounts followed by a unit, such as `"500ms"`,
+/// `"30s"` or `"1h30m"`, with units ns, us, ms, s, m (or min), h and d.
+///
+/// # Returns
+///
+/// The number of nanoseconds, or `None` if the text is not a duration or the number
+/// overflows.
+pub fn parse_nanos(text: &str) -> Option<u128> {
+  let mut rest = text.trim();
+  let mut total: u128 = 0;
+
+  if rest.is_empty() {
+    return None;
+  }
+
+  while !rest.is_empty() {
+    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    if digits == 0 {
+      return None;
+    }
+    let value: u128 = rest[..digits].parse().ok()?;
+    rest = &rest[digits..];
+
+    let unit = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+    let (_, nanos) = UNITS.iter().find(|(name, _)| *name == rest[..unit].trim())?;
+    rest = &rest[unit..];
+
+    total = total.checked_add(value.checked_mul(*nanos)?)?;
+  }
+
+  Some(total)
+}
@@ -0,0 +1,33 @@
+//! The calls deques of functions sharing a quota by name, see [group_calls].
+
+use crate::sync::Mutex;
+use crate::time::Instant;
+use std::collections::{HashMap, VecDeque};
+
+/// The calls deque of a group of functions.
+type Calls = Mutex<Option<VecDeque<Instant>>>;
+
+/// The calls deques of the groups, by name.
+type Groups = Mutex<Option<HashMap<&'static str, &'static Calls>>>;
+
+/// The deques of the groups. They are leaked for them to be borrowed for as long as a
+/// static is, and there are only as many of them as groups.
+#[cfg(not(loom))]
+static GROUPS: Groups = crate::sync::const_mutex(None);
+
+#[cfg(loom)]
+loom::lazy_static! {
+  static ref GROUPS: Groups = Mutex::new(None);
+}
+
+/// The calls deque of the functions throttled with `group = name`.
+///
+/// The deque lives in this crate rather than in the expansion of the attribute, for the
+/// functions of every crate linking it to share it. The first call for a name creates its
+/// deque, which every call after it gets, whichever thread it is made from.
+#[doc(hidden)]
+pub fn group_calls(name: &'static str) -> &'static Calls {
+  let mut groups = GROUPS.lock();
+  let groups = groups.get_or_insert_with(HashMap::new);
+  groups.entry(name).or_insert_with(|| Box::leak(Box::new(Mutex::new(None))))
+}
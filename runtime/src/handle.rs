@@ -0,0 +1,211 @@
+//! Handles to the eventual results of deferred calls.
+
+use crate::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+/// The state of a call as seen by its [ThrottleHandle].
+enum State<T> {
+  /// The call is waiting in the queue, it can still be cancelled.
+  Pending,
+
+  /// The call is being executed, it is too late to cancel it.
+  Running,
+
+  /// The call has executed and its result has not been taken yet.
+  Done(T),
+
+  /// The result of the call has been taken by polling the handle.
+  Taken,
+
+  /// The call was cancelled, or was dropped before it ran (e.g. because it panicked).
+  Cancelled,
+}
+
+struct Inner<T> {
+  state: State<T>,
+  waker: Option<Waker>,
+}
+
+struct Shared<T> {
+  inner: Mutex<Inner<T>>,
+  condvar: Condvar,
+}
+
+impl<T> Shared<T> {
+  fn new(state: State<T>) -> Arc<Self> {
+    Arc::new(Shared {
+      inner: Mutex::new(Inner { state, waker: None }),
+      condvar: Condvar::new(),
+    })
+  }
+
+  /// Transition to `state` and wake up whoever is waiting on the handle.
+  fn finish(&self, mut inner: MutexGuard<Inner<T>>, state: State<T>) {
+    inner.state = state;
+    if let Some(waker) = inner.waker.take() {
+      waker.wake();
+    }
+    drop(inner);
+    self.condvar.notify_all();
+  }
+}
+
+/// A handle to the eventual result of a throttled call.
+///
+/// Returned by functions annotated with `#[throttle(..., mode = defer)]`. Calls that can
+/// execute immediately return a handle that is already finished, while calls that have
+/// been deferred return a handle that finishes once the background worker has executed
+/// them.
+///
+/// The result can be obtained by blocking on [ThrottleHandle::wait] or by `.await`ing the
+/// handle, which implements [std::future::Future]. A call that is still pending can be
+/// cancelled with [ThrottleHandle::cancel], while dropping the handle lets the call
+/// execute regardless.
+pub struct ThrottleHandle<T> {
+  shared: Arc<Shared<T>>,
+}
+
+impl<T> ThrottleHandle<T> {
+  /// Create a handle for a call that has already executed and returned `value`.
+  pub fn ready(value: T) -> Self {
+    ThrottleHandle { shared: Shared::new(State::Done(value)) }
+  }
+
+  /// Create a handle for a call that has been skipped and will never execute.
+  pub fn cancelled() -> Self {
+    ThrottleHandle { shared: Shared::new(State::Cancelled) }
+  }
+
+  /// Block the current thread until the call has executed.
+  ///
+  /// # Returns
+  ///
+  /// The value returned by the call, or `None` if the call was cancelled or was dropped
+  /// before it could execute.
+  pub fn wait(self) -> Option<T> {
+    let mut inner = self.shared.inner.lock();
+    loop {
+      match std::mem::replace(&mut inner.state, State::Taken) {
+        State::Done(value) => return Some(value),
+        State::Cancelled => return None,
+        State::Taken => unreachable!("the result of a call can only be taken once"),
+        state @ (State::Pending | State::Running) => {
+          inner.state = state;
+          self.shared.condvar.wait(&mut inner);
+        }
+      }
+    }
+  }
+
+  /// Cancel the call if it has not started executing yet.
+  ///
+  /// # Returns
+  ///
+  /// Whether the call was cancelled. Calls that are already running or have already
+  /// executed cannot be cancelled.
+  pub fn cancel(&self) -> bool {
+    let inner = self.shared.inner.lock();
+    if !matches!(inner.state, State::Pending) {
+      return false;
+    }
+    self.shared.finish(inner, State::Cancelled);
+    true
+  }
+
+  /// Whether the call has executed or has been cancelled.
+  pub fn is_finished(&self) -> bool {
+    !matches!(self.shared.inner.lock().state, State::Pending | State::Running)
+  }
+}
+
+impl<T> Future for ThrottleHandle<T> {
+  type Output = Option<T>;
+
+  fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    let mut inner = self.shared.inner.lock();
+    match std::mem::replace(&mut inner.state, State::Taken) {
+      State::Done(value) => Poll::Ready(Some(value)),
+      State::Cancelled => Poll::Ready(None),
+      State::Taken => panic!("ThrottleHandle polled after completion"),
+      state @ (State::Pending | State::Running) => {
+        inner.state = state;
+        inner.waker = Some(cx.waker().clone());
+        Poll::Pending
+      }
+    }
+  }
+}
+
+/// A queued call, as seen by the deferred execution worker.
+#[doc(hidden)]
+pub trait Deferred: Send {
+  /// Whether the call has been cancelled through its [ThrottleHandle], in which case it
+  /// should not be charged against the quota.
+  fn is_cancelled(&self) -> bool;
+
+  /// Execute the call and hand its result over to its [ThrottleHandle].
+  fn run(self: Box<Self>);
+}
+
+/// The worker's side of a [ThrottleHandle].
+///
+/// If dropped without completing (e.g. the call panicked or the queue was dropped), the
+/// call is considered cancelled so that waiting on the handle does not block forever.
+struct Completer<T> {
+  shared: Arc<Shared<T>>,
+}
+
+impl<T> Drop for Completer<T> {
+  fn drop(&mut self) {
+    let inner = self.shared.inner.lock();
+    if matches!(inner.state, State::Pending | State::Running) {
+      self.shared.finish(inner, State::Cancelled);
+    }
+  }
+}
+
+/// A deferred call along with the [ThrottleHandle] waiting for its result.
+#[doc(hidden)]
+pub struct DeferredCall<T, F> {
+  completer: Completer<T>,
+  call: F,
+}
+
+impl<T, F> DeferredCall<T, F>
+where
+  F: FnOnce() -> T,
+{
+  /// Wrap `call` for deferred execution, returning it along with its handle.
+  pub fn new(call: F) -> (Self, ThrottleHandle<T>) {
+    let shared = Shared::new(State::Pending);
+    let handle = ThrottleHandle { shared: shared.clone() };
+    (DeferredCall { completer: Completer { shared }, call }, handle)
+  }
+}
+
+impl<T, F> Deferred for DeferredCall<T, F>
+where
+  T: Send,
+  F: FnOnce() -> T + Send,
+{
+  fn is_cancelled(&self) -> bool {
+    // A cancelled call whose handle was waited on was taken already.
+    !matches!(self.completer.shared.inner.lock().state, State::Pending)
+  }
+
+  fn run(self: Box<Self>) {
+    let DeferredCall { completer, call } = *self;
+
+    let mut inner = completer.shared.inner.lock();
+    if !matches!(inner.state, State::Pending) {
+      return;
+    }
+    inner.state = State::Running;
+    drop(inner);
+
+    let value = call();
+    completer.shared.finish(completer.shared.inner.lock(), State::Done(value));
+  }
+}
@@ -0,0 +1,66 @@
+//! Pacing of iterators.
+
+use crate::time::Instant;
+use crate::Pacer;
+use std::time::Duration;
+
+/// An iterator yielding at most `times` items per `duration`, blocking the current thread
+/// in between items when needed.
+///
+/// Created by [ThrottleExt::throttle].
+pub struct ThrottledIterator<I> {
+  iter: I,
+  pacer: Pacer,
+}
+
+impl<I: Iterator> Iterator for ThrottledIterator<I> {
+  type Item = I::Item;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    // Wait for the oldest item in the window to fall out of it.
+    let delay = self.pacer.delay(Instant::now())?;
+    std::thread::sleep(delay);
+
+    let item = self.iter.next();
+
+    // Exhausting the inner iterator doesn't count against the quota.
+    if item.is_some() {
+      self.pacer.record(Instant::now());
+    }
+
+    item
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    match self.pacer.delay(Instant::now()) {
+      Some(_) => self.iter.size_hint(),
+      None => (0, Some(0)),
+    }
+  }
+}
+
+/// An extension trait to throttle any iterator.
+pub trait ThrottleExt: Iterator + Sized {
+  /// Throttle the iterator to `times` items per `duration`.
+  ///
+  /// Once `times` items have been pulled within `duration`, pulling the next one blocks
+  /// the current thread until the oldest of them is older than `duration`. The pace is
+  /// set by pulling items, so work done on each item before pulling the next one counts
+  /// towards the wait.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::time::Duration;
+  /// use throttle_my_fn_runtime::ThrottleExt;
+  ///
+  /// for job in (0..3).throttle(2, Duration::from_millis(10)) {
+  ///   println!("Running job {}", job);
+  /// }
+  /// ```
+  fn throttle(self, times: usize, duration: Duration) -> ThrottledIterator<Self> {
+    ThrottledIterator { iter: self, pacer: Pacer::new(times, duration) }
+  }
+}
+
+impl<I: Iterator> ThrottleExt for I {}
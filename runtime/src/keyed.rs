@@ -0,0 +1,189 @@
+//! The windows of throttles with a limit per key, see [KeyedWindow].
+
+use crate::sync::Mutex;
+use crate::time::Instant;
+use crate::window;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::{BuildHasher, Hash};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// When a key was last let through, or last called with `fair`, made unique by a count of
+/// the stamps handed out.
+type Stamp = (Instant, u64);
+
+/// The calls of every key along with the stamp of the last one, and the keys in the
+/// order of their stamps, least recent first.
+struct Keys<K, S> {
+  calls: HashMap<Arc<K>, (Stamp, VecDeque<Instant>), S>,
+  recency: BTreeMap<Stamp, Arc<K>>,
+  count: u64,
+}
+
+impl<K: Hash + Eq, S: BuildHasher> Keys<K, S> {
+  /// Hand out the next stamp, for a key used at `now`.
+  fn stamp(&mut self, now: Instant) -> Stamp {
+    self.count += 1;
+    (now, self.count)
+  }
+
+  /// Move a key from its `last_stamp` to its new `stamp` in the order of recency.
+  fn restamp(&mut self, last_stamp: Stamp, stamp: Stamp) {
+    if let Some(key) = self.recency.remove(&last_stamp) {
+      let _ = self.recency.insert(stamp, key);
+    }
+  }
+
+  /// Add a new `key` with its `stamp` and `calls`, forgetting about the least recent keys
+  /// to make room for it with a maximum number of keys.
+  fn insert(
+    &mut self,
+    key: Arc<K>,
+    stamp: Stamp,
+    calls: VecDeque<Instant>,
+    max_keys: Option<usize>,
+  ) {
+    if let Some(max_keys) = max_keys {
+      while self.calls.len() >= max_keys {
+        match self.recency.pop_first() {
+          Some((_, least_recent)) => {
+            let _ = self.calls.remove(&*least_recent);
+          }
+          None => break,
+        }
+      }
+    }
+
+    let _ = self.recency.insert(stamp, Arc::clone(&key));
+    let _ = self.calls.insert(key, (stamp, calls));
+  }
+}
+
+/// A sliding window per key, which the `#[throttle(...)]` attribute uses with
+/// `per_key = (times, duration), key = ARG`, with keys hashed with `S`.
+///
+/// The keys are kept in the order they were last let through in as well, for those whose
+/// calls all fell out of the window to be forgotten without going through every key, so
+/// that the map doesn't keep growing with keys that aren't used anymore. With a maximum
+/// number of keys, the least recently let through key is forgotten to make room for a
+/// new one. Keys marked as active count as let through for both.
+#[doc(hidden)]
+pub struct KeyedWindow<K, S = RandomState> {
+  keys: Mutex<Keys<K, S>>,
+  max_keys: Option<usize>,
+}
+
+impl<K: Hash + Eq, S: BuildHasher + Default> KeyedWindow<K, S> {
+  /// Create a window without any keys, keeping at most `max_keys` of them if given.
+  pub fn new(max_keys: Option<usize>) -> Self {
+    let keys = Keys { calls: HashMap::default(), recency: BTreeMap::new(), count: 0 };
+    KeyedWindow { keys: Mutex::new(keys), max_keys }
+  }
+
+  /// Mark `key` as active with a call made at `now`, before the call is checked against
+  /// the limit of the function, for keys whose calls are all skipped by that limit to
+  /// count in the shares of `fair` all the same, see [KeyedWindow::try_charge].
+  pub fn mark_active(&self, key: &K, now: Instant)
+  where
+    K: Clone,
+  {
+    let mut keys = self.keys.lock();
+    let stamp = keys.stamp(now);
+    match keys.calls.get_mut(key) {
+      Some((last_stamp, _)) => {
+        let last_stamp = std::mem::replace(last_stamp, stamp);
+        keys.restamp(last_stamp, stamp);
+      }
+      None => keys.insert(Arc::new(key.clone()), stamp, VecDeque::new(), self.max_keys),
+    }
+  }
+
+  /// Charge a call of `key` made at `now`, if fewer than `times` calls of the key were
+  /// let through over the last `duration`.
+  ///
+  /// With `fair`, the `(times, duration)` limit of the function as a whole, the key can
+  /// also only use its share of that limit: split evenly between the keys called in its
+  /// window, including this one, rounded down but at least one call. The calls of a key
+  /// are then kept for as long as they count in either window, and keys are expected to
+  /// be marked as active before the limit of the function is checked, see
+  /// [KeyedWindow::mark_active].
+  ///
+  /// # Returns
+  ///
+  /// Whether the call can go ahead.
+  pub fn try_charge(
+    &self,
+    key: K,
+    now: Instant,
+    times: usize,
+    duration: Duration,
+    fair: Option<(usize, Duration)>,
+  ) -> bool {
+    let mut keys = self.keys.lock();
+    let keys = &mut *keys;
+    let retention = match fair {
+      Some((_, global_duration)) => duration.max(global_duration),
+      None => duration,
+    };
+
+    // Forget about the keys whose calls all fell out of the window, which are the least
+    // recently let through.
+    while let Some(least_recent) = keys.recency.first_entry() {
+      let (last_call, _) = *least_recent.key();
+      if now.saturating_duration_since(last_call) <= retention {
+        break;
+      }
+      let least_recent = least_recent.remove();
+      let _ = keys.calls.remove(&*least_recent);
+    }
+
+    if let Some((_, key_calls)) = keys.calls.get_mut(&key) {
+      window::cleanup(key_calls, now, 0, retention);
+    }
+    let no_calls = VecDeque::new();
+    let key_calls = keys.calls.get(&key).map_or(&no_calls, |(_, key_calls)| key_calls);
+
+    let in_window = |duration: Duration| {
+      let calls = key_calls.iter().rev();
+      calls.take_while(|&&call_time| now.duration_since(call_time) <= duration).count()
+    };
+
+    // The quota of the key is full as well.
+    if in_window(duration) >= times {
+      return false;
+    }
+
+    // The key used up its share.
+    if let Some((global_times, global_duration)) = fair {
+      let active = keys.calls.iter().filter(|&(other, (last_stamp, _))| {
+        **other != key && now.saturating_duration_since(last_stamp.0) <= global_duration
+      });
+      let share = (global_times / (active.count() + 1)).max(1);
+
+      if in_window(global_duration) >= share {
+        return false;
+      }
+    }
+
+    // The key is now the most recently let through.
+    let stamp = keys.stamp(now);
+    match keys.calls.get_mut(&key) {
+      Some((last_stamp, key_calls)) => {
+        key_calls.push_back(now);
+        let last_stamp = std::mem::replace(last_stamp, stamp);
+        keys.restamp(last_stamp, stamp);
+      }
+      None => keys.insert(Arc::new(key), stamp, VecDeque::from([now]), self.max_keys),
+    }
+
+    true
+  }
+
+  /// Forget about the calls of every key.
+  pub fn clear(&self) {
+    let mut keys = self.keys.lock();
+    keys.calls.clear();
+    keys.recency.clear();
+  }
+}
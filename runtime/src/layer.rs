@@ -0,0 +1,171 @@
+//! Throttling of tower services, see [ThrottleLayer].
+
+use crate::Throttler;
+use pin_project_lite::pin_project;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// The error of the services wrapped by a [ThrottleLayer], boxed like in tower's own
+/// middleware, for the errors of the inner service to be returned along with
+/// [Throttled].
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// The error returned by a [ThrottleService] for requests over its limit, which are
+/// turned down without being passed on to the inner service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throttled;
+
+impl fmt::Display for Throttled {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("the request was throttled")
+  }
+}
+
+impl Error for Throttled {}
+
+/// A tower layer letting at most `times` requests through per `duration` to the services
+/// it wraps, with the `tower` feature. Requests over the limit are turned down with a
+/// [Throttled] error, like skipped calls of a throttled function.
+///
+/// The limit is kept in a [Throttler], which can be shared with code calling the same
+/// upstream directly, for both to be throttled together:
+///
+/// ```
+/// use std::convert::Infallible;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use throttle_my_fn_runtime::{ThrottleLayer, Throttled, Throttler};
+/// use tower::{service_fn, Layer, ServiceExt};
+///
+/// # let runtime = tokio::runtime::Builder::new_current_thread().build();
+/// # runtime.unwrap().block_on(async {
+/// let throttler = Arc::new(Throttler::new(2, Duration::from_secs(60)));
+/// let layer = ThrottleLayer::shared(Arc::clone(&throttler));
+/// let double = service_fn(|n: u32| async move { Ok::<_, Infallible>(n * 2) });
+/// let service = layer.layer(double);
+///
+/// // A direct call takes one of the two slots, leaving the other to the service.
+/// assert!(throttler.try_acquire());
+/// assert_eq!(service.clone().oneshot(1).await.unwrap(), 2);
+///
+/// let error = service.oneshot(2).await.unwrap_err();
+/// assert!(error.is::<Throttled>());
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ThrottleLayer {
+  limiter: Limiter,
+}
+
+/// What the requests going through a [ThrottleLayer] are charged against.
+#[derive(Debug, Clone)]
+enum Limiter {
+  /// A throttler, possibly shared, see [ThrottleLayer::shared].
+  Throttler(Arc<Throttler>),
+
+  /// A function taking a slot, see [ThrottleLayer::from_fn].
+  Fn(fn() -> bool),
+}
+
+impl Limiter {
+  /// Take a slot for a request, or `false` if there is none left.
+  fn try_acquire(&self) -> bool {
+    match self {
+      Limiter::Throttler(throttler) => throttler.try_acquire(),
+      Limiter::Fn(try_acquire) => try_acquire(),
+    }
+  }
+}
+
+impl ThrottleLayer {
+  /// Create a layer letting `times` requests through per `duration`, shared by all the
+  /// services it wraps.
+  pub fn new(times: usize, duration: Duration) -> Self {
+    Self::shared(Arc::new(Throttler::new(times, duration)))
+  }
+
+  /// Create a layer charging requests against `throttler`, which can also be charged
+  /// directly.
+  pub fn shared(throttler: Arc<Throttler>) -> Self {
+    ThrottleLayer { limiter: Limiter::Throttler(throttler) }
+  }
+
+  /// Create a layer charging requests with `try_acquire`, which takes a slot and returns
+  /// `true`, or returns `false` if there is none left. This is how the layer generated
+  /// by `#[throttle(..., layer = true)]` charges the quota of the throttled function.
+  pub fn from_fn(try_acquire: fn() -> bool) -> Self {
+    ThrottleLayer { limiter: Limiter::Fn(try_acquire) }
+  }
+}
+
+impl<S> Layer<S> for ThrottleLayer {
+  type Service = ThrottleService<S>;
+
+  fn layer(&self, inner: S) -> Self::Service {
+    ThrottleService { inner, limiter: self.limiter.clone() }
+  }
+}
+
+/// A service throttled by a [ThrottleLayer].
+#[derive(Debug, Clone)]
+pub struct ThrottleService<S> {
+  inner: S,
+  limiter: Limiter,
+}
+
+impl<S, Request> Service<Request> for ThrottleService<S>
+where
+  S: Service<Request>,
+  S::Error: Into<BoxError>,
+{
+  type Response = S::Response;
+  type Error = BoxError;
+  type Future = ThrottleResponseFuture<S::Future>;
+
+  fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.inner.poll_ready(cx).map_err(Into::into)
+  }
+
+  fn call(&mut self, request: Request) -> Self::Future {
+    // The request is charged once it is made rather than once the service is ready,
+    // since readiness can be polled for without making a request.
+    let inner = match self.limiter.try_acquire() {
+      true => Some(self.inner.call(request)),
+      false => None,
+    };
+
+    ThrottleResponseFuture { inner }
+  }
+}
+
+pin_project! {
+  /// The response of a [ThrottleService], which is a [Throttled] error right away for a
+  /// request over the limit.
+  #[derive(Debug)]
+  pub struct ThrottleResponseFuture<F> {
+    #[pin]
+    inner: Option<F>,
+  }
+}
+
+impl<F, T, E> Future for ThrottleResponseFuture<F>
+where
+  F: Future<Output = Result<T, E>>,
+  E: Into<BoxError>,
+{
+  type Output = Result<T, BoxError>;
+
+  fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    match self.project().inner.as_pin_mut() {
+      Some(inner) => inner.poll(cx).map_err(Into::into),
+      None => Poll::Ready(Err(Throttled.into())),
+    }
+  }
+}
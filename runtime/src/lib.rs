@@ -0,0 +1,107 @@
+//! Runtime support for the [`throttle_my_fn`](https://docs.rs/throttle_my_fn) attribute
+//! macro.
+//!
+//! The code generated by the `#[throttle(...)]` attribute refers to types and functions
+//! from this crate, which needs to be added as a dependency alongside `throttle_my_fn`:
+//!
+//! ```toml
+//! [dependencies]
+//! throttle_my_fn = "0.2"
+//! throttle_my_fn_runtime = "0.2"
+//! ```
+//!
+//! The types and functions documented here are the public API of this crate, which can
+//! also be used without the macro, e.g. [Throttler] for limits chosen at runtime. The
+//! hidden items are only there for the generated code to call into, and may change
+//! between versions along with the macro, so both crates are to be kept at the same
+//! version. The sliding window of throttled functions is implemented in the [window]
+//! module, which the generated code calls into.
+
+// The timer of tokio reads `std::time::Instant`, which panics in the browser.
+#[cfg(all(feature = "tokio", target_arch = "wasm32", target_os = "unknown"))]
+compile_error!(
+  "the `tokio` feature of `throttle_my_fn_runtime` is not supported on \
+   `wasm32-unknown-unknown`, where the timer of tokio cannot read the time"
+);
+
+#[cfg(feature = "tokio")]
+mod blocking;
+mod buckets;
+mod classes;
+mod duration;
+mod group;
+mod handle;
+mod iter;
+mod keyed;
+#[cfg(feature = "tower")]
+mod layer;
+mod overrides;
+mod pacer;
+mod policy;
+mod queue;
+mod result;
+mod scale;
+mod shards;
+mod slots;
+mod stats;
+#[cfg(feature = "futures")]
+mod stream;
+mod sync;
+pub mod testing;
+mod throttler;
+mod time;
+pub mod window;
+mod worker;
+
+pub use handle::ThrottleHandle;
+pub use iter::{ThrottleExt, ThrottledIterator};
+#[cfg(feature = "tower")]
+pub use layer::{ThrottleLayer, ThrottleResponseFuture, ThrottleService, Throttled};
+pub use overrides::ThrottleOverride;
+pub use pacer::Pacer;
+pub use policy::{AdmissionPolicy, Decision, SlidingWindow};
+pub use result::ThrottleResult;
+pub use scale::{scale, set_scale};
+pub use stats::ThrottleStats;
+#[cfg(feature = "futures")]
+pub use stream::{ThrottleStreamExt, ThrottledStream};
+pub use throttler::{Throttler, ThrottlerStats};
+pub use time::Instant;
+
+#[cfg(feature = "tokio")]
+#[doc(hidden)]
+pub use blocking::{sleep_until, spawn_blocking, timeout};
+#[doc(hidden)]
+pub use buckets::BucketWindow;
+#[doc(hidden)]
+pub use classes::{Turn, Waiting, WeightedClasses};
+#[doc(hidden)]
+pub use duration::parse_nanos;
+#[doc(hidden)]
+pub use group::group_calls;
+#[doc(hidden)]
+pub use handle::{Deferred, DeferredCall};
+#[doc(hidden)]
+pub use keyed::KeyedWindow;
+#[doc(hidden)]
+pub use parking_lot;
+#[doc(hidden)]
+pub use queue::{DeferredQueue, Overflow, Pending};
+#[cfg(feature = "tokio")]
+#[doc(hidden)]
+pub use scale::wait_rescaled_async;
+#[doc(hidden)]
+pub use scale::{scaled, wait_rescaled};
+#[doc(hidden)]
+pub use shards::ShardedWindow;
+#[doc(hidden)]
+pub use slots::SlotWindow;
+#[doc(hidden)]
+pub use stats::ThrottleCounters;
+#[doc(hidden)]
+pub use time::system_now;
+#[cfg(feature = "tokio")]
+#[doc(hidden)]
+pub use tokio::sync::Semaphore;
+#[doc(hidden)]
+pub use worker::Worker;
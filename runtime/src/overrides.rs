@@ -0,0 +1,44 @@
+//! Per-call control over how throttled calls are charged.
+
+/// How a single call to a function annotated with
+/// `#[throttle(..., with_override = true)]` is throttled, passed as its last argument.
+///
+/// ```
+/// use throttle_my_fn_runtime::ThrottleOverride;
+///
+/// assert_eq!(ThrottleOverride::default(), ThrottleOverride::Default);
+/// assert_eq!(ThrottleOverride::CostMultiplier(3).cost(), 3);
+/// assert_eq!(ThrottleOverride::Bypass.cost(), 0);
+/// assert!(ThrottleOverride::CostMultiplier(0).is_bypass());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ThrottleOverride {
+  /// The call is throttled like any other.
+  #[default]
+  Default,
+
+  /// The call is executed right away, without being charged against the quota, e.g. for
+  /// an admin console.
+  Bypass,
+
+  /// The call takes this many slots of the quota rather than one, and is skipped unless
+  /// they are all free. A multiplier of zero takes none, bypassing the throttle as with
+  /// [ThrottleOverride::Bypass].
+  CostMultiplier(usize),
+}
+
+impl ThrottleOverride {
+  /// The number of slots of the quota the call takes.
+  pub fn cost(&self) -> usize {
+    match *self {
+      ThrottleOverride::Default => 1,
+      ThrottleOverride::Bypass => 0,
+      ThrottleOverride::CostMultiplier(multiplier) => multiplier,
+    }
+  }
+
+  /// Whether the call is executed without being throttled.
+  pub fn is_bypass(&self) -> bool {
+    matches!(self, ThrottleOverride::Bypass | ThrottleOverride::CostMultiplier(0))
+  }
+}
@@ -0,0 +1,62 @@
+//! Runtime-agnostic pacing of items.
+
+use crate::time::Instant;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The book-keeping of a sliding window of at most `times` items per `duration`, without
+/// any waiting.
+///
+/// A pacer tells how long to wait before the next item can go through, leaving the
+/// waiting itself to the caller: [crate::ThrottledIterator] blocks the current thread,
+/// and `ThrottledStream` (with the `futures` feature) sleeps on the timer of the tokio
+/// runtime, while a pipeline on another runtime would sleep on the timer of that one:
+///
+/// ```ignore
+/// if let Some(delay) = pacer.delay(Instant::now()) {
+///   tokio::time::sleep(delay).await;
+///   let item = stream.next().await;
+///   pacer.record(Instant::now());
+/// }
+/// ```
+pub struct Pacer {
+  times: usize,
+  duration: Duration,
+
+  /// The times at which the last (at most) `times` items went through.
+  calls: VecDeque<Instant>,
+}
+
+impl Pacer {
+  /// Create a pacer letting `times` items go through per `duration`.
+  pub fn new(times: usize, duration: Duration) -> Self {
+    Pacer { times, duration, calls: VecDeque::with_capacity(times) }
+  }
+
+  /// How long to wait from `now` before the next item can go through.
+  ///
+  /// # Returns
+  ///
+  /// A zero duration if the next item can go through right away, or `None` if no item
+  /// can ever go through (`times` is zero).
+  pub fn delay(&self, now: Instant) -> Option<Duration> {
+    if self.times == 0 {
+      return None;
+    }
+
+    match self.calls.front() {
+      Some(&oldest) if self.calls.len() >= self.times => {
+        Some((oldest + self.duration).saturating_duration_since(now))
+      }
+      _ => Some(Duration::ZERO),
+    }
+  }
+
+  /// Record an item going through at `now`.
+  pub fn record(&mut self, now: Instant) {
+    while self.calls.len() >= self.times.max(1) {
+      let _ = self.calls.pop_front();
+    }
+    self.calls.push_back(now);
+  }
+}
@@ -0,0 +1,90 @@
+//! Admission policies deciding whether calls are let through, see [AdmissionPolicy].
+
+use crate::time::Instant;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Whether a call is let through, see [AdmissionPolicy::decide].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+  /// The call is let through and charged against the quota.
+  Admit,
+
+  /// The call is skipped.
+  Skip,
+}
+
+/// A policy deciding whether calls are let through, given the calls let through before
+/// them.
+///
+/// Passed to the `#[throttle(...)]` attribute with `policy = MY_POLICY`, a policy takes
+/// the place of the limit of the function when deciding about a call. The limit of the
+/// attribute still tells how long calls are kept in the history for.
+///
+/// ```
+/// use std::collections::VecDeque;
+/// use std::time::Instant;
+/// use throttle_my_fn_runtime::{AdmissionPolicy, Decision};
+///
+/// // Twice the budget during the night.
+/// struct Nightly {
+///   night: bool,
+/// }
+///
+/// impl AdmissionPolicy for Nightly {
+///   fn decide(&self, _: Instant, history: &VecDeque<Instant>, cost: usize) -> Decision {
+///     let budget = if self.night { 20 } else { 10 };
+///     match history.len() + cost <= budget {
+///       true => Decision::Admit,
+///       false => Decision::Skip,
+///     }
+///   }
+/// }
+///
+/// let now = Instant::now();
+/// let history = VecDeque::from(vec![now; 15]);
+/// assert_eq!(Nightly { night: true }.decide(now, &history, 1), Decision::Admit);
+/// assert_eq!(Nightly { night: false }.decide(now, &history, 1), Decision::Skip);
+/// ```
+pub trait AdmissionPolicy {
+  /// Decide whether a call of the given `cost` (the number of slots it takes, `1` for a
+  /// single call) made at `now` is let through, given the times at which the calls in
+  /// the `history` were let through, the oldest first.
+  fn decide(&self, now: Instant, history: &VecDeque<Instant>, cost: usize) -> Decision;
+}
+
+/// The sliding window letting at most `times` calls through per `duration`, which is the
+/// policy of the `#[throttle(...)]` attribute and of [Throttler](crate::Throttler) unless
+/// told otherwise.
+///
+/// ```
+/// use std::collections::VecDeque;
+/// use std::time::{Duration, Instant};
+/// use throttle_my_fn_runtime::{AdmissionPolicy, Decision, SlidingWindow};
+///
+/// let window = SlidingWindow { times: 2, duration: Duration::from_secs(1) };
+/// let history = VecDeque::from(vec![Instant::now()]);
+/// assert_eq!(window.decide(Instant::now(), &history, 1), Decision::Admit);
+/// assert_eq!(window.decide(Instant::now(), &history, 2), Decision::Skip);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlidingWindow {
+  /// The number of calls let through per `duration`.
+  pub times: usize,
+
+  /// The duration of the window.
+  pub duration: Duration,
+}
+
+impl AdmissionPolicy for SlidingWindow {
+  fn decide(&self, now: Instant, history: &VecDeque<Instant>, cost: usize) -> Decision {
+    let in_window = history.iter().rev().take_while(|&&call_time| {
+      now.saturating_duration_since(call_time) <= self.duration
+    });
+
+    match in_window.count().saturating_add(cost) <= self.times {
+      true => Decision::Admit,
+      false => Decision::Skip,
+    }
+  }
+}
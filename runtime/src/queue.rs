@@ -0,0 +1,211 @@
+//! The queue of the deferred calls of a throttled function, see [DeferredQueue].
+
+use crate::handle::Deferred;
+#[cfg(not(loom))]
+use crate::sync::const_mutex;
+use crate::sync::{Condvar, Mutex, MutexGuard};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use crate::time::Instant;
+use crate::worker::Worker;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The calls of a [DeferredQueue] that could not be executed immediately, in the order
+/// they came in.
+#[doc(hidden)]
+pub type Pending = VecDeque<Box<dyn Deferred>>;
+
+/// What happens to a call deferred while its [DeferredQueue] is full.
+#[doc(hidden)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+  /// The call is dropped, cancelling it.
+  DropNewest,
+
+  /// The oldest pending call is dropped to make room for it, cancelling that one.
+  DropOldest,
+
+  /// The caller waits for the worker to take a pending call off the queue.
+  Block,
+}
+
+/// The queue of the calls of a function throttled with `mode = defer` or
+/// `mode = coalesce`, along with the [Worker] executing them once they get a free slot.
+///
+/// The generated code checks whether a call is to be deferred with the queue locked, see
+/// [DeferredQueue::lock], and the worker waits on the queue for a pending call before
+/// waiting for a free slot, see [DeferredQueue::wait_for_call], and only takes the call
+/// off the queue once the slot is free, see [DeferredQueue::take]. Calls coming in while
+/// the worker waits can then still be queued behind the pending ones, or replace them
+/// when coalescing. Calls cancelled through their handles are discarded without being
+/// charged.
+///
+/// Whether the worker is idle is tracked for `drain = true`, see [DeferredQueue::drain]:
+/// it is only ever changed with the queue locked, cleared whenever a call is queued, and
+/// set by the worker once it finds the queue empty, after having executed the calls
+/// before.
+#[doc(hidden)]
+pub struct DeferredQueue {
+  pending: Mutex<Pending>,
+
+  /// Notified whenever a call is queued.
+  queued: Condvar,
+
+  /// Notified whenever a call is taken off the queue, for callers blocked on a full one.
+  space: Condvar,
+
+  /// The maximum number of pending calls, unbounded if `None`.
+  bound: Option<usize>,
+  overflow: Overflow,
+
+  /// Whether the worker is idle, with the `drained` condition variable notified when it
+  /// becomes idle. There is nothing to drain in the browser, where callers cannot block.
+  idle: Mutex<bool>,
+  #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+  drained: Condvar,
+
+  worker: Worker,
+}
+
+impl DeferredQueue {
+  /// Create an empty queue of at most `bound` calls, for use in a static.
+  #[cfg(not(loom))]
+  pub const fn new(bound: Option<usize>, overflow: Overflow) -> Self {
+    DeferredQueue {
+      pending: const_mutex(VecDeque::new()),
+      queued: Condvar::new(),
+      space: Condvar::new(),
+      bound,
+      overflow,
+      idle: const_mutex(true),
+      #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+      drained: Condvar::new(),
+      worker: Worker::new(),
+    }
+  }
+
+  /// Create an empty queue of at most `bound` calls. Locks cannot be created in a const
+  /// context under `loom`.
+  #[cfg(loom)]
+  pub fn new(bound: Option<usize>, overflow: Overflow) -> Self {
+    DeferredQueue {
+      pending: Mutex::new(VecDeque::new()),
+      queued: Condvar::new(),
+      space: Condvar::new(),
+      bound,
+      overflow,
+      idle: Mutex::new(true),
+      #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+      drained: Condvar::new(),
+      worker: Worker::new(),
+    }
+  }
+
+  /// Lock the queue, for the caller to decide whether its call is to be deferred, e.g.
+  /// because there are pending calls to be executed before it.
+  pub fn lock(&self) -> MutexGuard<'_, Pending> {
+    self.pending.lock()
+  }
+
+  /// Queue `call` into the locked `pending` calls, dropping a call or waiting for room
+  /// on a full queue depending on its [Overflow], and notify the worker.
+  pub fn push(&self, mut pending: MutexGuard<'_, Pending>, call: Box<dyn Deferred>) {
+    if let Some(bound) = self.bound {
+      match self.overflow {
+        Overflow::DropNewest if pending.len() >= bound => {
+          drop(pending);
+          drop(call);
+          return;
+        }
+        Overflow::DropNewest => {}
+        Overflow::DropOldest => {
+          while pending.len() >= bound && pending.pop_front().is_some() {}
+        }
+        Overflow::Block => {
+          while pending.len() >= bound {
+            self.space.wait(&mut pending);
+          }
+        }
+      }
+    }
+
+    pending.push_back(call);
+    *self.idle.lock() = false;
+    drop(pending);
+    self.queued.notify_one();
+  }
+
+  /// Take the next pending call off the queue, discarding the cancelled ones.
+  pub fn take(&self) -> Option<Box<dyn Deferred>> {
+    let mut pending = self.pending.lock();
+    loop {
+      let job = pending.pop_front()?;
+      self.space.notify_one();
+      if !job.is_cancelled() {
+        return Some(job);
+      }
+    }
+  }
+
+  /// Whether every pending call was cancelled, or there are none.
+  pub fn all_cancelled(&self) -> bool {
+    self.pending.lock().iter().all(|job| job.is_cancelled())
+  }
+
+  /// Wait for a pending call that wasn't cancelled, marking the worker as idle while
+  /// there is none. With a `window`, e.g. with only a trailing edge, the worker waits for
+  /// that long after finding a pending call as well, for the calls of a burst to come in,
+  /// with the time read from `now`.
+  #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+  pub fn wait_for_call(&self, now: fn() -> Instant, window: Option<Duration>) {
+    let mut pending = self.pending.lock();
+    loop {
+      if let (Some(window), false) = (window, pending.is_empty()) {
+        let window_end = now() + window;
+        while now() < window_end {
+          self.queued.wait_until(&mut pending, window_end);
+        }
+      }
+
+      while pending.front().is_some_and(|job| job.is_cancelled()) {
+        let _ = pending.pop_front();
+        self.space.notify_one();
+      }
+      if !pending.is_empty() {
+        return;
+      }
+
+      *self.idle.lock() = true;
+      self.drained.notify_all();
+      self.queued.wait(&mut pending);
+    }
+  }
+
+  /// Wait until the worker is idle for up to `timeout`, with the time read from `now`.
+  ///
+  /// # Returns
+  ///
+  /// Whether the worker became idle, rather than the timeout expiring.
+  #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+  pub fn drain(&self, now: fn() -> Instant, timeout: Duration) -> bool {
+    let deadline = now() + timeout;
+    let mut idle = self.idle.lock();
+    while !*idle && now() < deadline {
+      self.drained.wait_until(&mut idle, deadline);
+    }
+    *idle
+  }
+
+  /// Spawn the thread named `name` of the worker calling `step`, unless it is running,
+  /// see [Worker::ensure_running].
+  pub fn ensure_running(&'static self, name: &str, step: fn()) {
+    self.worker.ensure_running(name, step);
+  }
+
+  /// Call `poll` from the timers of the browser, unless it is being called already, see
+  /// [Worker::ensure_polling].
+  #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+  pub fn ensure_polling(&'static self, poll: fn() -> Option<Duration>) {
+    self.worker.ensure_polling(poll);
+  }
+}
@@ -0,0 +1,59 @@
+//! Detailed results of throttled calls.
+
+use std::time::Duration;
+
+/// The result of a call to a function annotated with
+/// `#[throttle(..., output = detailed)]`.
+///
+/// Unlike an [Option], a skipped call tells when it is worth trying again, for callers
+/// implementing their own backoff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThrottleResult<T> {
+  /// The call was executed and returned this value.
+  Executed(T),
+
+  /// The call was skipped.
+  Skipped {
+    /// How long until the oldest call in the window falls out of it and frees up a slot.
+    /// Zero if the window has free slots, e.g. when the call was skipped for lack of a
+    /// concurrency permit.
+    retry_after: Duration,
+
+    /// The number of free slots left in the window.
+    window_remaining: usize,
+  },
+}
+
+impl<T> ThrottleResult<T> {
+  /// Whether the call was executed.
+  pub fn is_executed(&self) -> bool {
+    matches!(self, ThrottleResult::Executed(_))
+  }
+
+  /// Whether the call was skipped.
+  pub fn is_skipped(&self) -> bool {
+    !self.is_executed()
+  }
+
+  /// The returned value of an executed call, discarding the details of skipped calls.
+  pub fn executed(self) -> Option<T> {
+    match self {
+      ThrottleResult::Executed(value) => Some(value),
+      ThrottleResult::Skipped { .. } => None,
+    }
+  }
+
+  /// How long to wait before trying again, if the call was skipped.
+  pub fn retry_after(&self) -> Option<Duration> {
+    match self {
+      ThrottleResult::Executed(_) => None,
+      ThrottleResult::Skipped { retry_after, .. } => Some(*retry_after),
+    }
+  }
+}
+
+impl<T> From<ThrottleResult<T>> for Option<T> {
+  fn from(result: ThrottleResult<T>) -> Self {
+    result.executed()
+  }
+}
@@ -0,0 +1,82 @@
+//! The process-wide scale of the limits of throttles, see [set_scale].
+
+use crate::time::Instant;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use parking_lot::{const_mutex, Condvar, Mutex};
+
+/// The bits of the current scale, `1.0` to begin with.
+static SCALE: AtomicU32 = AtomicU32::new(0x3f80_0000);
+
+/// Notified whenever the scale changes, for the throttles left without any slots at all
+/// to wait for it to change, see [wait_rescaled].
+static RESCALED: (Mutex<()>, Condvar) = (const_mutex(()), Condvar::new());
+
+/// Notified like [RESCALED], for the throttles waiting asynchronously.
+#[cfg(feature = "tokio")]
+static RESCALED_ASYNC: tokio::sync::Notify = tokio::sync::Notify::const_new();
+
+/// Scale the number of calls every throttle lets through per window by `scale`, e.g. to
+/// shed load during an incident: `0.5` lets half as many calls through, `0.0` lets none
+/// through at all, and `1.0` goes back to the limits as they were written. Negative and
+/// NaN scales are taken as `0.0`.
+///
+/// This applies to every [Throttler](crate::Throttler), and to the functions throttled
+/// with the `#[throttle(...)]` attribute when the `scale` feature of `throttle_my_fn` is
+/// enabled. Calls already in a window are kept and count against the scaled limit.
+///
+/// ```
+/// use std::time::Duration;
+/// use throttle_my_fn_runtime::{set_scale, Throttler};
+///
+/// let throttler = Throttler::new(4, Duration::from_secs(1));
+/// set_scale(0.5);
+/// assert!(throttler.try_acquire());
+/// assert!(throttler.try_acquire());
+/// assert!(!throttler.try_acquire());
+/// set_scale(1.0);
+/// assert!(throttler.try_acquire());
+/// ```
+pub fn set_scale(scale: f32) {
+  let scale = if scale > 0.0 { scale } else { 0.0 };
+  SCALE.store(scale.to_bits(), Ordering::Relaxed);
+
+  let (lock, rescaled) = &RESCALED;
+  let _guard = lock.lock();
+  rescaled.notify_all();
+  #[cfg(feature = "tokio")]
+  RESCALED_ASYNC.notify_waiters();
+}
+
+/// The scale set with [set_scale].
+pub fn scale() -> f32 {
+  f32::from_bits(SCALE.load(Ordering::Relaxed))
+}
+
+/// Scale `times` by the current scale, rounding down.
+#[doc(hidden)]
+pub fn scaled(times: usize) -> usize {
+  let scale = scale();
+  if scale == 1.0 {
+    return times;
+  }
+
+  (times as f64 * f64::from(scale)) as usize
+}
+
+/// Block the current thread until the scale changes, or until `until` at the latest. A
+/// throttle whose limit is zero has nothing to wait for but its limit to change, which it
+/// checks again once woken up.
+#[doc(hidden)]
+pub fn wait_rescaled(until: Instant) {
+  let (lock, rescaled) = &RESCALED;
+  let mut guard = lock.lock();
+  let _ = rescaled.wait_for(&mut guard, until.saturating_duration_since(Instant::now()));
+}
+
+/// Wait like [wait_rescaled], on the timer of the current tokio runtime.
+#[cfg(feature = "tokio")]
+#[doc(hidden)]
+pub async fn wait_rescaled_async(until: Instant) {
+  let _ = tokio::time::timeout_at(until.into(), RESCALED_ASYNC.notified()).await;
+}
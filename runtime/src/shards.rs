@@ -0,0 +1,84 @@
+//! The sharded window of throttles called from many threads at once, see
+//! [ShardedWindow].
+
+use crate::sync::Mutex;
+use crate::time::Instant;
+use crate::window;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// The next shard to assign a thread to, counted across all the sharded windows.
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+std::thread_local! {
+  /// The shard of the current thread, modulo the number of shards of a window.
+  static SHARD: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A shard of a [ShardedWindow], aligned to keep threads locking neighbouring shards
+/// from contending for the same cache line.
+#[repr(align(128))]
+struct Shard(Mutex<VecDeque<Instant>>);
+
+/// A sliding window whose quota is split across one calls deque per available core,
+/// which the `#[throttle(...)]` attribute uses with `sharded = true`.
+///
+/// Each shard is behind a lock of its own on a cache line of its own. Threads are
+/// assigned to shards round-robin, so threads calling at the same time mostly lock
+/// different shards. A thread whose shard is full borrows from the other shards that
+/// aren't locked at the moment, so a call is only skipped with quota left in shards that
+/// are busy.
+#[doc(hidden)]
+pub struct ShardedWindow {
+  shards: Box<[Shard]>,
+}
+
+impl ShardedWindow {
+  /// Create a window with one shard per available core, and at most `times` shards.
+  pub fn new(times: usize) -> Self {
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+    let shards = (0..cores.min(times).max(1)).map(|_| Shard(Mutex::new(VecDeque::new())));
+    ShardedWindow { shards: shards.collect() }
+  }
+
+  /// Charge a call made at `now` to a shard at `charged_at`, if fewer than its share of
+  /// `times` calls were let through over the last `duration`. The own shard of the
+  /// thread is waited for, the others are only borrowed from when free.
+  ///
+  /// # Returns
+  ///
+  /// Whether the call can go ahead.
+  pub fn try_charge(
+    &self,
+    now: Instant,
+    charged_at: Instant,
+    times: usize,
+    duration: Duration,
+  ) -> bool {
+    let shards = &self.shards;
+    let own = SHARD.with(|shard| *shard) % shards.len();
+
+    (0..shards.len()).any(|offset| {
+      let index = (own + offset) % shards.len();
+      let mut calls = match offset {
+        0 => shards[index].0.lock(),
+        _ => match shards[index].0.try_lock() {
+          Some(calls) => calls,
+          None => return false,
+        },
+      };
+
+      // The remainder of the quota goes to the first shards.
+      let share = times / shards.len() + usize::from(index < times % shards.len());
+
+      window::cleanup(&mut calls, now, share, duration);
+
+      let free = calls.len() < share;
+      if free {
+        calls.push_back(charged_at);
+      }
+      free
+    })
+  }
+}
@@ -0,0 +1,111 @@
+//! The lock-free window of throttles that only skip calls, see [SlotWindow].
+
+use crate::sync::{spin_loop, yield_now, AtomicU64, AtomicUsize, Ordering};
+use crate::time::Instant;
+use std::time::Duration;
+
+/// A sliding window keeping the times at which the last `times` calls were let through
+/// in a ring of slots, which the `#[throttle(...)]` attribute uses instead of a locked
+/// calls deque when calls are only ever skipped and nothing else looks into the deque.
+///
+/// The call charged at position `p` (the number of calls let through before it) takes
+/// slot `p % times`, which holds the time of the call charged at position `p - times`:
+/// the call can go ahead once that one fell out of the window. A call let through with
+/// plenty of quota is then a few atomic loads, a compare-exchange of the position and two
+/// stores, instead of locking a mutex and cleaning up a deque.
+#[doc(hidden)]
+pub struct SlotWindow {
+  /// The time the window was created at, which the times in `stamps` count from.
+  epoch: Instant,
+
+  /// The position of the next call to be let through.
+  head: AtomicUsize,
+
+  /// The time each slot was last charged at, in nanoseconds since `epoch` plus one, or
+  /// zero if it was never charged.
+  stamps: Box<[AtomicU64]>,
+
+  /// The position each slot can be charged at next, once the time of its previous charge
+  /// is stored.
+  ready: Box<[AtomicUsize]>,
+}
+
+impl SlotWindow {
+  /// Create a window of `times` slots, counting time from `epoch`.
+  pub fn new(times: usize, epoch: Instant) -> Self {
+    SlotWindow {
+      epoch,
+      head: AtomicUsize::new(0),
+      stamps: (0..times).map(|_| AtomicU64::new(0)).collect(),
+      ready: (0..times).map(AtomicUsize::new).collect(),
+    }
+  }
+
+  /// Charge a call made at `now` against the window, if fewer than `times` calls were
+  /// let through over the last `duration`.
+  ///
+  /// # Returns
+  ///
+  /// Whether the call can go ahead.
+  pub fn try_charge(&self, now: Instant, duration: Duration) -> bool {
+    let times = self.stamps.len();
+
+    // Without any quota at all, there is no slot to charge either.
+    if times == 0 {
+      return false;
+    }
+
+    let elapsed = now.saturating_duration_since(self.epoch).as_nanos();
+    let now = u64::try_from(elapsed).unwrap_or(u64::MAX - 1) + 1;
+    let duration = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+
+    let mut spins = 0_u32;
+    loop {
+      let position = self.head.load(Ordering::Acquire);
+      let slot = position % times;
+
+      // Another call took the position, or is still storing the time of the previous
+      // charge of the slot, which only takes a moment.
+      if self.ready[slot].load(Ordering::Acquire) != position {
+        spins += 1;
+        if spins < 64 {
+          spin_loop();
+        } else {
+          yield_now();
+        }
+        continue;
+      }
+
+      // The quota is full for the duration, unless the position is stale: the stamp may
+      // then be that of the charge of the position read, whose call was let through
+      // already. Reading the position again by modifying it tells for sure.
+      let oldest = self.stamps[slot].load(Ordering::Relaxed);
+      if oldest != 0 && now.saturating_sub(oldest) <= duration {
+        let current = self.head.compare_exchange(
+          position,
+          position,
+          Ordering::AcqRel,
+          Ordering::Acquire,
+        );
+        match current {
+          Ok(_) => return false,
+          Err(_) => continue,
+        }
+      }
+
+      let next = position.wrapping_add(1);
+      let claimed = self.head.compare_exchange_weak(
+        position,
+        next,
+        Ordering::AcqRel,
+        Ordering::Relaxed,
+      );
+
+      if claimed.is_ok() {
+        self.stamps[slot].store(now, Ordering::Relaxed);
+        self.ready[slot].store(position.wrapping_add(times), Ordering::Release);
+        return true;
+      }
+    }
+  }
+}
@@ -0,0 +1,158 @@
+//! Counters of the calls made to a function throttled with `stats = true`.
+//!
+//! The times calls waited for are counted in a histogram of a few buckets per power of
+//! two nanoseconds, which the percentiles of [ThrottleStats] are read from. The
+//! percentiles are the upper bounds of their buckets, which are at most a quarter wider
+//! than the waits they hold.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The number of buckets per power of two of the histogram of waits.
+const SUB_BUCKETS: u32 = 4;
+
+/// The number of buckets of the histogram of waits, enough for any number of
+/// nanoseconds in a `u64`.
+const BUCKETS: usize = ((65 - SUB_BUCKETS.trailing_zeros()) * SUB_BUCKETS) as usize;
+
+/// The bucket of the histogram of waits counting a wait of `nanos`.
+fn bucket(nanos: u64) -> usize {
+  let bits = SUB_BUCKETS.trailing_zeros();
+  if nanos < u64::from(SUB_BUCKETS) {
+    return nanos as usize;
+  }
+
+  // The highest bit set tells the power of two, the bits after it the bucket within.
+  let exponent = 63 - nanos.leading_zeros();
+  let within = (nanos >> (exponent - bits)) as u32 - SUB_BUCKETS;
+  ((exponent - bits + 1) * SUB_BUCKETS + within) as usize
+}
+
+/// The longest wait counted in `bucket` of the histogram of waits, in nanoseconds.
+fn bucket_end(bucket: usize) -> u64 {
+  let (power, within) = (bucket as u32 / SUB_BUCKETS, bucket as u32 % SUB_BUCKETS);
+  if power == 0 {
+    return u64::from(within);
+  }
+
+  let start = u64::from(SUB_BUCKETS + within) << (power - 1);
+  start.saturating_add((1 << (power - 1)) - 1)
+}
+
+/// Statistics about the calls made to a function throttled with
+/// `#[throttle(..., stats = true)]`, see the `NAME_stats()` function generated alongside
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ThrottleStats {
+  /// The number of calls let through since the program started.
+  pub executed: u64,
+
+  /// The number of calls skipped since the program started.
+  pub skipped: u64,
+
+  /// The longest a call has waited for a free slot, e.g. through the `NAME_wait` variant
+  /// of the function.
+  pub max_wait: Duration,
+
+  /// The median of the times calls waited for a free slot, calls let through right away
+  /// included, or zero if no call waited.
+  pub p50_wait: Duration,
+
+  /// The 95th percentile of the times calls waited for a free slot, calls let through
+  /// right away included, or zero if no call waited.
+  pub p95_wait: Duration,
+
+  /// The number of calls in the current window.
+  pub in_window: usize,
+
+  /// The number of calls that the candidate limit of the `shadow` option would have
+  /// skipped since the program started, or zero without one.
+  pub shadow_skipped: u64,
+}
+
+/// The counters behind [ThrottleStats], kept in a static by the generated code. They are
+/// only ever incremented, so relaxed atomics are enough.
+#[doc(hidden)]
+pub struct ThrottleCounters {
+  executed: AtomicU64,
+  skipped: AtomicU64,
+  max_wait_nanos: AtomicU64,
+  waits: [AtomicU64; BUCKETS],
+  shadow_skipped: AtomicU64,
+}
+
+impl ThrottleCounters {
+  /// Create counters starting at zero, for use in a static.
+  pub const fn new() -> Self {
+    ThrottleCounters {
+      executed: AtomicU64::new(0),
+      skipped: AtomicU64::new(0),
+      max_wait_nanos: AtomicU64::new(0),
+      waits: [const { AtomicU64::new(0) }; BUCKETS],
+      shadow_skipped: AtomicU64::new(0),
+    }
+  }
+
+  /// Count a call let through.
+  pub fn record_executed(&self) {
+    let _ = self.executed.fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// Count a skipped call.
+  pub fn record_skipped(&self) {
+    let _ = self.skipped.fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// Count a call that the shadow limit would have skipped.
+  pub fn record_shadow_skipped(&self) {
+    let _ = self.shadow_skipped.fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// Record that a call waited for `wait` before being let through.
+  pub fn record_wait(&self, wait: Duration) {
+    let nanos = u64::try_from(wait.as_nanos()).unwrap_or(u64::MAX);
+    let _ = self.max_wait_nanos.fetch_max(nanos, Ordering::Relaxed);
+    let _ = self.waits[bucket(nanos)].fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// The `percentile` of the waits recorded so far, capped by the longest one.
+  fn wait_percentile(&self, percentile: u64, max_wait_nanos: u64) -> Duration {
+    let waits: Vec<u64> =
+      self.waits.iter().map(|waits| waits.load(Ordering::Relaxed)).collect();
+    let rank = (waits.iter().sum::<u64>() * percentile).div_ceil(100);
+    if rank == 0 {
+      return Duration::ZERO;
+    }
+
+    let mut counted = 0;
+    for (bucket, waits) in waits.into_iter().enumerate() {
+      counted += waits;
+      if counted >= rank {
+        return Duration::from_nanos(bucket_end(bucket).min(max_wait_nanos));
+      }
+    }
+
+    Duration::from_nanos(max_wait_nanos)
+  }
+
+  /// A snapshot of the counters, with `in_window` calls in the current window.
+  pub fn stats(&self, in_window: usize) -> ThrottleStats {
+    let max_wait_nanos = self.max_wait_nanos.load(Ordering::Relaxed);
+    ThrottleStats {
+      executed: self.executed.load(Ordering::Relaxed),
+      skipped: self.skipped.load(Ordering::Relaxed),
+      max_wait: Duration::from_nanos(max_wait_nanos),
+      p50_wait: self.wait_percentile(50, max_wait_nanos),
+      p95_wait: self.wait_percentile(95, max_wait_nanos),
+      in_window,
+      shadow_skipped: self.shadow_skipped.load(Ordering::Relaxed),
+    }
+  }
+}
+
+impl Default for ThrottleCounters {
+  fn default() -> Self {
+    ThrottleCounters::new()
+  }
+}
@@ -0,0 +1,94 @@
+//! Pacing of streams, on the timer of the tokio runtime.
+
+use crate::time::Instant;
+use crate::Pacer;
+use futures_core::Stream;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+use tokio::time::Sleep;
+
+/// A stream yielding at most `times` items per `duration`, sleeping on the timer of the
+/// tokio runtime in between items when needed.
+///
+/// Created by [ThrottleStreamExt::throttle_items].
+pub struct ThrottledStream<S> {
+  stream: S,
+  pacer: Pacer,
+
+  /// The sleep until the oldest item in the window falls out of it, if it hasn't yet.
+  sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S: Stream + Unpin> Stream for ThrottledStream<S> {
+  type Item = S::Item;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let this = self.get_mut();
+
+    // Wait for the oldest item in the window to fall out of it.
+    loop {
+      if let Some(sleep) = &mut this.sleep {
+        ready!(sleep.as_mut().poll(cx));
+        this.sleep = None;
+      }
+
+      match this.pacer.delay(Instant::now()) {
+        Some(delay) if delay.is_zero() => break,
+        Some(delay) => this.sleep = Some(Box::pin(tokio::time::sleep(delay))),
+        None => return Poll::Ready(None),
+      }
+    }
+
+    let item = ready!(Pin::new(&mut this.stream).poll_next(cx));
+
+    // Exhausting the inner stream doesn't count against the quota.
+    if item.is_some() {
+      this.pacer.record(Instant::now());
+    }
+
+    Poll::Ready(item)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    match self.pacer.delay(Instant::now()) {
+      Some(_) => self.stream.size_hint(),
+      None => (0, Some(0)),
+    }
+  }
+}
+
+/// An extension trait to throttle any stream, with the `futures` feature.
+pub trait ThrottleStreamExt: Stream + Sized {
+  /// Throttle the stream to `times` items per `duration`, like
+  /// [ThrottleExt::throttle](crate::ThrottleExt::throttle) throttles an iterator.
+  ///
+  /// Once `times` items have been polled within `duration`, polling the next one sleeps
+  /// on the timer of the current tokio runtime until the oldest of them is older than
+  /// `duration`, without blocking the thread. Streams that aren't `Unpin` can be pinned
+  /// with `Box::pin` first.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use futures_util::{stream, StreamExt};
+  /// use std::time::{Duration, Instant};
+  /// use throttle_my_fn_runtime::ThrottleStreamExt;
+  ///
+  /// # let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build();
+  /// # runtime.unwrap().block_on(async {
+  /// let start = Instant::now();
+  /// let mut jobs = stream::iter(0..3).throttle_items(2, Duration::from_millis(10));
+  /// while let Some(job) = jobs.next().await {
+  ///   println!("Running job {}", job);
+  /// }
+  /// assert!(start.elapsed() >= Duration::from_millis(10));
+  /// # });
+  /// ```
+  fn throttle_items(self, times: usize, duration: Duration) -> ThrottledStream<Self> {
+    ThrottledStream { stream: self, pacer: Pacer::new(times, duration), sleep: None }
+  }
+}
+
+impl<S: Stream> ThrottleStreamExt for S {}
@@ -0,0 +1,113 @@
+//! The synchronization primitives used by the runtime, swapped for those of `loom` when
+//! model checking them (`RUSTFLAGS="--cfg loom"`, see `tests/loom.rs`).
+//!
+//! The shim around `loom` has the same interface as `parking_lot`, which is used
+//! otherwise: locks cannot be poisoned, and condition variables wait on a guard in place.
+//! The atomics, spinning and yielding come from `loom` as well, while statics, which
+//! cannot be initialized in a const context under `loom`, are declared with
+//! `loom::lazy_static!` instead.
+
+#[cfg(not(loom))]
+pub(crate) use parking_lot::{const_mutex, Condvar, Mutex, MutexGuard};
+#[cfg(not(loom))]
+pub(crate) use std::hint::spin_loop;
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+#[cfg(not(loom))]
+pub(crate) use std::sync::Arc;
+#[cfg(not(loom))]
+pub(crate) use std::thread::yield_now;
+
+#[cfg(loom)]
+pub(crate) use loom::hint::spin_loop;
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+#[cfg(loom)]
+pub(crate) use loom::sync::Arc;
+#[cfg(loom)]
+pub(crate) use loom::thread::yield_now;
+#[cfg(loom)]
+pub(crate) use shim::{Condvar, Mutex, MutexGuard};
+
+#[cfg(loom)]
+mod shim {
+  use crate::time::Instant;
+  use std::ops::{Deref, DerefMut};
+  use std::time::Duration;
+
+  /// A `loom` mutex, locked like a `parking_lot` one. It is public for the signatures of
+  /// the hidden items handing out locks, e.g. [group_calls](crate::group_calls).
+  pub struct Mutex<T>(loom::sync::Mutex<T>);
+
+  impl<T> Mutex<T> {
+    pub(crate) fn new(value: T) -> Self {
+      Mutex(loom::sync::Mutex::new(value))
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+      MutexGuard(Some(self.0.lock().unwrap()))
+    }
+
+    pub(crate) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+      self.0.try_lock().ok().map(|guard| MutexGuard(Some(guard)))
+    }
+  }
+
+  /// The guard of a locked [Mutex]. It is only ever empty while waiting on a [Condvar].
+  pub struct MutexGuard<'a, T>(Option<loom::sync::MutexGuard<'a, T>>);
+
+  impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+      self.0.as_ref().unwrap()
+    }
+  }
+
+  impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+      self.0.as_mut().unwrap()
+    }
+  }
+
+  /// A `loom` condition variable, waited on like a `parking_lot` one.
+  pub(crate) struct Condvar(loom::sync::Condvar);
+
+  impl Condvar {
+    pub(crate) fn new() -> Self {
+      Condvar(loom::sync::Condvar::new())
+    }
+
+    pub(crate) fn wait<T>(&self, guard: &mut MutexGuard<'_, T>) {
+      let locked = guard.0.take().unwrap();
+      guard.0 = Some(self.0.wait(locked).unwrap());
+    }
+
+    /// Wait on `guard` until notified. `loom` doesn't model time, so the `deadline` only
+    /// lets the wait end spuriously, which callers have to handle all the same.
+    pub(crate) fn wait_until<T>(
+      &self,
+      guard: &mut MutexGuard<'_, T>,
+      _deadline: Instant,
+    ) {
+      let locked = guard.0.take().unwrap();
+      let (locked, _) = self.0.wait_timeout(locked, Duration::ZERO).unwrap();
+      guard.0 = Some(locked);
+    }
+
+    /// Wait on `guard` until notified or for up to `timeout`.
+    pub(crate) fn wait_for<T>(&self, guard: &mut MutexGuard<'_, T>, timeout: Duration) {
+      let locked = guard.0.take().unwrap();
+      let (locked, _) = self.0.wait_timeout(locked, timeout).unwrap();
+      guard.0 = Some(locked);
+    }
+
+    pub(crate) fn notify_one(&self) {
+      self.0.notify_one();
+    }
+
+    pub(crate) fn notify_all(&self) {
+      self.0.notify_all();
+    }
+  }
+}
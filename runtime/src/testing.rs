@@ -0,0 +1,180 @@
+//! Helpers for testing throttled code without sleeping, see [advance] and
+//! [assert_throttled](crate::assert_throttled).
+//!
+//! Throttles read the time from a virtual clock running alongside the monotonic clock of
+//! the system, which tests can move forward to let the calls in a window fall out of it
+//! right away: [Throttler](crate::Throttler) always does, and the functions generated by
+//! the attributes of `throttle_my_fn` reading the time do with its `testing` feature,
+//! which is meant for dev-dependencies:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! throttle_my_fn = { version = "0.2", features = ["testing"] }
+//! ```
+//!
+//! The virtual clock is shared by the whole process, so moving it forward lets the
+//! windows of every throttle move on at once, including those of tests running in
+//! parallel.
+
+use crate::time::Instant;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// How far the virtual clock is ahead of the monotonic clock, in nanoseconds.
+static OFFSET: AtomicU64 = AtomicU64::new(0);
+
+/// The current time on the virtual clock, which is the monotonic clock moved forward by
+/// every call to [advance].
+pub fn now() -> Instant {
+  Instant::now() + Duration::from_nanos(OFFSET.load(Ordering::Relaxed))
+}
+
+/// Move the virtual clock forward by `by`, as if that long had passed.
+pub fn advance(by: Duration) {
+  let by = u64::try_from(by.as_nanos()).unwrap_or(u64::MAX);
+  let _ = OFFSET.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |offset| {
+    Some(offset.saturating_add(by))
+  });
+}
+
+/// Call `call` `times` times, moving the virtual clock forward by `every` after each
+/// call, without sleeping.
+///
+/// # Returns
+///
+/// Whether each call was executed, in order.
+///
+/// ```
+/// use std::time::Duration;
+/// use throttle_my_fn_runtime::testing::drive;
+/// use throttle_my_fn_runtime::Throttler;
+///
+/// let throttler = Throttler::new(1, Duration::from_secs(1));
+/// let executed = drive(4, Duration::from_millis(600), || throttler.try_acquire());
+/// assert_eq!(executed, [true, false, true, false]);
+/// ```
+pub fn drive<T: Outcome>(
+  times: usize,
+  every: Duration,
+  mut call: impl FnMut() -> T,
+) -> Vec<bool> {
+  (0..times)
+    .map(|_| {
+      let executed = call().executed();
+      advance(every);
+      executed
+    })
+    .collect()
+}
+
+/// The outcome of a call to a throttled function, telling whether the call was executed.
+pub trait Outcome {
+  /// Whether the call was executed rather than skipped.
+  fn executed(&self) -> bool;
+}
+
+impl Outcome for bool {
+  fn executed(&self) -> bool {
+    *self
+  }
+}
+
+impl<T> Outcome for Option<T> {
+  fn executed(&self) -> bool {
+    self.is_some()
+  }
+}
+
+impl<T> Outcome for crate::ThrottleResult<T> {
+  fn executed(&self) -> bool {
+    self.is_executed()
+  }
+}
+
+/// A duration given to [assert_throttled](crate::assert_throttled), as a
+/// [Duration] or a string such as `"1s"`, `"500ms"` or `"1h30m"`, written like the
+/// duration literals of the `#[throttle(...)]` attribute.
+///
+/// ```
+/// use std::time::Duration;
+/// use throttle_my_fn_runtime::testing::Within;
+///
+/// assert_eq!("1h30m".duration(), Duration::from_secs(90 * 60));
+/// assert_eq!("2min".duration(), Duration::from_secs(120));
+/// ```
+pub trait Within {
+  /// The duration, panicking if it can't be parsed.
+  fn duration(self) -> Duration;
+}
+
+impl Within for Duration {
+  fn duration(self) -> Duration {
+    self
+  }
+}
+
+impl Within for &str {
+  fn duration(self) -> Duration {
+    match crate::duration::parse_nanos(self).map(u64::try_from) {
+      Some(Ok(nanos)) => Duration::from_nanos(nanos),
+      _ => panic!(
+        "expecting a duration like \"500ms\", \"30s\" or \"1h30m\", with units ns, us, \
+         ms, s, m (or min), h and d, found {:?}",
+        self
+      ),
+    }
+  }
+}
+
+/// Assert that a throttled call lets `allows` calls through `within` a window: the first
+/// `allows` evaluations of the call are executed and the next one is skipped, then once
+/// the virtual clock moved forward by `within`, the call is executed again.
+///
+/// The window must be empty to begin with, e.g. at the start of a test. The call is an
+/// expression returning an [Option], a [ThrottleResult](crate::ThrottleResult) or a
+/// `bool`, and `within` is a [Duration] or a string such as `"1s"`, see
+/// [testing](crate::testing).
+///
+/// ```
+/// use std::time::Duration;
+/// use throttle_my_fn_runtime::{assert_throttled, Throttler};
+///
+/// let throttler = Throttler::new(10, Duration::from_secs(1));
+/// assert_throttled!(throttler.try_acquire(), within = "1s", allows = 10);
+/// ```
+#[macro_export]
+macro_rules! assert_throttled {
+  ($call:expr, within = $within:expr, allows = $allows:expr $(,)?) => {{
+    use $crate::testing::{Outcome, Within};
+
+    let within = Within::duration($within);
+    let allows: usize = $allows;
+    for call in 0..allows {
+      assert!(
+        Outcome::executed(&$call),
+        "call {} of `{}` was skipped, expecting {} calls within {:?}",
+        call + 1,
+        stringify!($call),
+        allows,
+        within,
+      );
+    }
+
+    assert!(
+      !Outcome::executed(&$call),
+      "call {} of `{}` was executed, expecting {} calls within {:?}",
+      allows + 1,
+      stringify!($call),
+      allows,
+      within,
+    );
+
+    $crate::testing::advance(within);
+    assert!(
+      Outcome::executed(&$call),
+      "`{}` was still skipped after {:?}",
+      stringify!($call),
+      within,
+    );
+  }};
+}
@@ -0,0 +1,197 @@
+//! A throttle as a value, for use without the attribute macro.
+
+use crate::policy::{AdmissionPolicy, Decision, SlidingWindow};
+use crate::scale::scaled;
+use crate::sync::Mutex;
+use crate::testing::now;
+use crate::time::Instant;
+use crate::window;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+/// A throttle letting at most `times` calls through per `duration`, as a value that can
+/// be stored in a struct, e.g. for per-connection limits.
+///
+/// This is the same sliding window that the `#[throttle(...)]` attribute keeps in a
+/// static, behind a lock so that it can be shared between threads:
+///
+/// ```
+/// use std::time::Duration;
+/// use throttle_my_fn_runtime::Throttler;
+///
+/// let throttler = Throttler::new(2, Duration::from_secs(1));
+/// assert!(throttler.try_acquire());
+/// assert!(throttler.try_acquire());
+/// assert!(!throttler.try_acquire());
+///
+/// let stats = throttler.stats();
+/// assert_eq!((stats.executed, stats.skipped, stats.in_window), (2, 1, 2));
+/// ```
+pub struct Throttler {
+  state: Mutex<State>,
+}
+
+/// The book-keeping of a [Throttler].
+struct State {
+  times: usize,
+  duration: Duration,
+
+  /// The times at which the calls in the window were let through.
+  calls: VecDeque<Instant>,
+
+  executed: u64,
+  skipped: u64,
+}
+
+/// Statistics about the calls made through a [Throttler], see [Throttler::stats].
+///
+/// With the `serde` feature, statistics can be serialized, e.g. to be reported by a
+/// health endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ThrottlerStats {
+  /// The number of calls let through since the throttler was created.
+  pub executed: u64,
+
+  /// The number of calls turned down by [Throttler::try_acquire] since the throttler was
+  /// created.
+  pub skipped: u64,
+
+  /// The number of calls in the current window.
+  pub in_window: usize,
+}
+
+impl State {
+  /// Remove the calls that fell out of the window at `now`.
+  fn cleanup(&mut self, now: Instant) {
+    window::cleanup(&mut self.calls, now, 0, self.duration);
+  }
+
+  /// Let a call through at `now` if there is a free slot in the window, otherwise tell
+  /// how long until one frees up.
+  fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+    self.cleanup(now);
+
+    let times = scaled(self.times);
+    let window = SlidingWindow { times, duration: self.duration };
+    if window.decide(now, &self.calls, 1) == Decision::Admit {
+      self.calls.push_back(now);
+      self.executed += 1;
+      return Ok(());
+    }
+
+    // Without any slots at all, there is nothing to wait for but a reconfiguration.
+    let retry_after = window::retry_after(&self.calls, now, times, self.duration);
+    Err(retry_after.unwrap_or(self.duration))
+  }
+}
+
+impl Throttler {
+  /// Create a throttler letting `times` calls through per `duration`.
+  pub fn new(times: usize, duration: Duration) -> Self {
+    Throttler {
+      state: Mutex::new(State {
+        times,
+        duration,
+        calls: VecDeque::with_capacity(times),
+        executed: 0,
+        skipped: 0,
+      }),
+    }
+  }
+
+  /// Let a call through if there is a free slot in the window.
+  ///
+  /// # Returns
+  ///
+  /// Whether the call can go ahead, in which case it is charged against the quota.
+  pub fn try_acquire(&self) -> bool {
+    let mut state = self.state.lock();
+    match state.try_acquire(now()) {
+      Ok(()) => true,
+      Err(_) => {
+        state.skipped += 1;
+        false
+      }
+    }
+  }
+
+  /// Block the current thread until there is a free slot in the window, and charge the
+  /// call against the quota.
+  pub fn acquire_blocking(&self) {
+    loop {
+      let retry_after = match self.state.lock().try_acquire(now()) {
+        Ok(()) => return,
+        Err(retry_after) => retry_after,
+      };
+
+      // The lock is released while sleeping, for other threads to reconfigure the
+      // throttler or query it in the meantime.
+      std::thread::sleep(retry_after);
+    }
+  }
+
+  /// How long until a call would be let through, or `None` if it would go ahead right
+  /// away. Doesn't charge anything against the quota.
+  pub fn retry_after(&self) -> Option<Duration> {
+    let now = now();
+    let mut state = self.state.lock();
+    state.cleanup(now);
+
+    window::retry_after(&state.calls, now, scaled(state.times), state.duration)
+  }
+
+  /// Statistics about the calls made through the throttler.
+  pub fn stats(&self) -> ThrottlerStats {
+    let mut state = self.state.lock();
+    state.cleanup(now());
+    ThrottlerStats {
+      executed: state.executed,
+      skipped: state.skipped,
+      in_window: state.calls.len(),
+    }
+  }
+
+  /// The number of calls let through per [Throttler::duration].
+  pub fn times(&self) -> usize {
+    self.state.lock().times
+  }
+
+  /// The duration of the window.
+  pub fn duration(&self) -> Duration {
+    self.state.lock().duration
+  }
+
+  /// Change the number of calls let through per window. Calls already in the window are
+  /// kept and count against the new limit.
+  pub fn set_times(&self, times: usize) {
+    self.state.lock().times = times;
+  }
+
+  /// Change the duration of the window. Calls already in the window are kept, and fall
+  /// out of it according to the new duration.
+  pub fn set_duration(&self, duration: Duration) {
+    self.state.lock().duration = duration;
+  }
+
+  /// Forget about the calls in the window, letting the next `times` calls through right
+  /// away. Statistics are kept.
+  pub fn reset(&self) {
+    self.state.lock().calls.clear();
+  }
+}
+
+/// Shows the limit of the throttler, for structs holding one to derive `Debug`.
+impl fmt::Debug for Throttler {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let state = self.state.lock();
+    let (times, duration) = (state.times, state.duration);
+    drop(state);
+
+    f.debug_struct("Throttler")
+      .field("times", &times)
+      .field("duration", &duration)
+      .finish()
+  }
+}
@@ -0,0 +1,136 @@
+//! The monotonic clock read by throttles, see [Instant].
+//!
+//! On `wasm32-unknown-unknown`, where `std::time::Instant::now()` panics since browsers
+//! don't let the standard library read the time, the clock is read from
+//! `performance.now()` instead, and the deferred calls are executed from the timers of
+//! the browser.
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use std::time::Instant;
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) use browser::schedule;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub use browser::Instant;
+
+/// The current time, as read by the code generated by the `#[throttle(...)]` attribute.
+/// With the `testing` feature of `throttle_my_fn`, the generated calls to it are replaced
+/// by calls to [testing::now](crate::testing::now), which leaves the clock read by the
+/// bodies of the throttled functions as it is.
+#[doc(hidden)]
+pub fn system_now() -> Instant {
+  Instant::now()
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+mod browser {
+  use std::fmt;
+  use std::ops::{Add, AddAssign, Sub, SubAssign};
+  use std::time::Duration;
+  use wasm_bindgen::prelude::{wasm_bindgen, Closure, JsValue};
+
+  #[wasm_bindgen]
+  extern "C" {
+    /// `performance.now()`: the milliseconds since the time origin of the page or of
+    /// the worker.
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn performance_now() -> f64;
+
+    /// `setTimeout(handler, timeout)`, on the global object of the page or of the
+    /// worker.
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(handler: &JsValue, timeout: i32) -> JsValue;
+  }
+
+  /// A reading of the monotonic clock of the browser, with the interface of
+  /// `std::time::Instant`.
+  #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+  pub struct Instant(Duration);
+
+  impl Instant {
+    /// The current time.
+    pub fn now() -> Self {
+      Instant(Duration::from_secs_f64(performance_now().max(0.0) / 1000.0))
+    }
+
+    /// The time passed since `earlier`, or zero if `earlier` is later.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+      self.0.saturating_sub(earlier.0)
+    }
+
+    /// The time passed since `earlier`, or `None` if `earlier` is later.
+    pub fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+      self.0.checked_sub(earlier.0)
+    }
+
+    /// The time passed since `earlier`, or zero if `earlier` is later.
+    pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+      self.duration_since(earlier)
+    }
+
+    /// The time passed since this instant.
+    pub fn elapsed(&self) -> Duration {
+      Instant::now().duration_since(*self)
+    }
+
+    /// This instant moved forward by `duration`, unless that overflows.
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+      self.0.checked_add(duration).map(Instant)
+    }
+
+    /// This instant moved back by `duration`, unless that is before the time origin.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+      self.0.checked_sub(duration).map(Instant)
+    }
+  }
+
+  impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, duration: Duration) -> Instant {
+      Instant(self.0 + duration)
+    }
+  }
+
+  impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, duration: Duration) {
+      self.0 += duration;
+    }
+  }
+
+  impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, duration: Duration) -> Instant {
+      Instant(self.0 - duration)
+    }
+  }
+
+  impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, duration: Duration) {
+      self.0 -= duration;
+    }
+  }
+
+  impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, earlier: Instant) -> Duration {
+      self.duration_since(earlier)
+    }
+  }
+
+  impl fmt::Debug for Instant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      f.debug_tuple("Instant").field(&self.0).finish()
+    }
+  }
+
+  /// Call `step` from a timer of the browser once `after` has passed, rounded up to the
+  /// millisecond.
+  pub(crate) fn schedule(after: Duration, step: impl FnOnce() + 'static) {
+    let timeout = after.as_nanos().div_ceil(1_000_000);
+    let handler = Closure::once_into_js(step);
+    let _ = set_timeout(&handler, i32::try_from(timeout).unwrap_or(i32::MAX));
+  }
+}
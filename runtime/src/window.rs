@@ -0,0 +1,101 @@
+//! The sliding window of the `#[throttle(...)]` attribute, over a deque of the times at
+//! which calls were let through, oldest first.
+//!
+//! The code generated by the attribute keeps the deque in a static and calls into these
+//! functions to clean it up and to tell when a slot frees up, and so does
+//! [Throttler](crate::Throttler). They can be used for hand-rolled throttles as well:
+//!
+//! ```
+//! use std::collections::VecDeque;
+//! use std::time::{Duration, Instant};
+//! use throttle_my_fn_runtime::window;
+//!
+//! let (times, duration) = (1, Duration::from_secs(1));
+//! let now = Instant::now();
+//! let mut calls = VecDeque::from(vec![now - Duration::from_secs(2), now]);
+//!
+//! window::cleanup(&mut calls, now, times, duration);
+//! assert_eq!(calls, [now]);
+//! assert_eq!(window::free_at(&calls, now, times, duration), Some(now + duration));
+//! assert_eq!(window::retry_after(&calls, now, times, duration), Some(duration));
+//! ```
+
+use crate::time::Instant;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Remove the calls that fell out of the window of `duration` at `now`, oldest first.
+///
+/// While there are fewer than `times` calls, the deque is left as it is, since all of
+/// them fit in the window anyway: a deque cleaned up on every call never grows larger
+/// than `times`. Counting the calls in the window therefore needs to skip the calls older
+/// than `duration` still, unless `times` is zero, with which every call that fell out of
+/// the window is removed.
+pub fn cleanup(
+  calls: &mut VecDeque<Instant>,
+  now: Instant,
+  times: usize,
+  duration: Duration,
+) {
+  if calls.len() < times {
+    return;
+  }
+
+  while let Some(&call_time) = calls.front() {
+    if now.duration_since(call_time) > duration {
+      let _ = calls.pop_front();
+    } else {
+      break;
+    }
+  }
+}
+
+/// When a slot of the window frees up, for a deque cleaned up at `now` (see [cleanup]).
+///
+/// # Returns
+///
+/// `None` if there is a free slot already. Without any slots at all (a limit of zero),
+/// there is nothing to wait for but the limit to change, which is to be read again after
+/// a window, at `now + duration`.
+pub fn free_at(
+  calls: &VecDeque<Instant>,
+  now: Instant,
+  times: usize,
+  duration: Duration,
+) -> Option<Instant> {
+  if calls.len() < times {
+    return None;
+  }
+
+  match calls.get(calls.len() - times) {
+    Some(&oldest) => Some(oldest + duration),
+    None => Some(now + duration),
+  }
+}
+
+/// How long from `now` until enough calls fall out of the window for a slot to free up,
+/// like [free_at], for a deque that need not have been cleaned up.
+///
+/// # Returns
+///
+/// `None` if there is a free slot already, or `duration` without any slots at all.
+pub fn retry_after(
+  calls: &VecDeque<Instant>,
+  now: Instant,
+  times: usize,
+  duration: Duration,
+) -> Option<Duration> {
+  let mut in_window = calls
+    .iter()
+    .filter(|&&call_time| now.saturating_duration_since(call_time) <= duration);
+
+  let count = in_window.clone().count();
+  if count < times {
+    return None;
+  }
+
+  match in_window.nth(count - times) {
+    Some(&oldest) => Some((oldest + duration).saturating_duration_since(now)),
+    None => Some(duration),
+  }
+}
@@ -0,0 +1,104 @@
+//! The background threads executing deferred and debounced calls, or the timers of the
+//! browser on `wasm32-unknown-unknown`.
+
+use crate::sync::{AtomicBool, Ordering};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+use std::time::Duration;
+
+/// A background thread calling a function over and over, e.g. taking the next deferred
+/// call off a queue and executing it, which is spawned on first use.
+///
+/// A panic of the function, whether in a call it executes or while waiting for one, is
+/// caught and the thread carries on calling it. Should the thread stop all the same, e.g.
+/// because a panic payload panics when dropped, it is spawned again on the next use.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct Worker {
+  running: AtomicBool,
+}
+
+/// The claim of the thread (or of the timers) running a [Worker], see [Worker::claim].
+/// The worker is marked as stopped once the claim is dropped, e.g. when its thread
+/// returns or unwinds.
+#[doc(hidden)]
+pub struct Running<'a>(&'a AtomicBool);
+
+impl Drop for Running<'_> {
+  fn drop(&mut self) {
+    self.0.store(false, Ordering::Release);
+  }
+}
+
+impl Worker {
+  /// Create a worker whose thread isn't spawned yet, for use in a static.
+  #[cfg(not(loom))]
+  pub const fn new() -> Self {
+    Worker { running: AtomicBool::new(false) }
+  }
+
+  /// Create a worker whose thread isn't spawned yet. Atomics cannot be created in a
+  /// const context under `loom`.
+  #[cfg(loom)]
+  pub fn new() -> Self {
+    Worker { running: AtomicBool::new(false) }
+  }
+
+  /// Claim the worker for running it, unless it is running already: of the callers
+  /// racing for a stopped worker, exactly one gets the claim.
+  pub fn claim(&self) -> Option<Running<'_>> {
+    if self.running.load(Ordering::Acquire) || self.running.swap(true, Ordering::AcqRel) {
+      return None;
+    }
+
+    Some(Running(&self.running))
+  }
+
+  /// Spawn the thread named `name` calling `step` over and over, unless it is running.
+  pub fn ensure_running(&'static self, name: &str, step: fn()) {
+    let running = match self.claim() {
+      Some(running) => running,
+      None => return,
+    };
+
+    // Should the thread fail to spawn, the claim is dropped along with it.
+    let spawned = std::thread::Builder::new().name(String::from(name)).spawn(move || {
+      let _running = running;
+      loop {
+        let _ = catch_unwind(AssertUnwindSafe(step));
+      }
+    });
+
+    if let Err(error) = spawned {
+      panic!("failed to spawn the {} thread: {}", name, error);
+    }
+  }
+
+  /// Call `poll` from the timers of the browser until it has nothing left to do, unless
+  /// it is being called already. `poll` returns how long until it is to be called again,
+  /// e.g. until the next deferred call gets a free slot, or `None` once its queue is
+  /// empty.
+  ///
+  /// Panics abort in the browser, so there is nothing to catch.
+  #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+  pub fn ensure_polling(&'static self, poll: fn() -> Option<Duration>) {
+    if let Some(running) = self.claim() {
+      poll_after(running, Duration::ZERO, poll);
+    }
+  }
+}
+
+/// Call `poll` once `after` has passed, and again for as long as it asks to, holding on
+/// to the claim of the worker until it doesn't.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn poll_after(
+  running: Running<'static>,
+  after: Duration,
+  poll: fn() -> Option<Duration>,
+) {
+  crate::time::schedule(after, move || match poll() {
+    Some(after) => poll_after(running, after, poll),
+    // Nothing is left to do: the next call is to poll again.
+    None => drop(running),
+  });
+}
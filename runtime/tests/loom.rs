@@ -0,0 +1,196 @@
+//! Model checking of the synchronization of the runtime with `loom`, which runs every
+//! test under all the interleavings of its threads:
+//!
+//! ```sh
+//! RUSTFLAGS="--cfg loom" cargo test -p throttle_my_fn_runtime --test loom --release
+//! ```
+
+#![cfg(loom)]
+
+use loom::sync::Arc;
+use loom::thread;
+use std::time::{Duration, Instant};
+use throttle_my_fn_runtime::{
+  group_calls, Deferred, DeferredCall, SlotWindow, Throttler, ThrottlerStats, Worker,
+};
+
+/// A window long enough for no call to fall out of it while a model runs.
+const WINDOW: Duration = Duration::from_secs(3600);
+
+#[test]
+fn first_calls_race_for_the_quota() {
+  loom::model(|| {
+    let throttler = Arc::new(Throttler::new(1, WINDOW));
+
+    let other = {
+      let throttler = throttler.clone();
+      thread::spawn(move || throttler.try_acquire())
+    };
+    let acquired = throttler.try_acquire();
+    let other_acquired = other.join().unwrap();
+
+    // Exactly one of the calls is let through, whichever comes first.
+    assert!(acquired ^ other_acquired);
+    let ThrottlerStats { executed, skipped, in_window } = throttler.stats();
+    assert_eq!((executed, skipped, in_window), (1, 1, 1));
+  });
+}
+
+#[test]
+fn quota_is_shared_across_threads() {
+  loom::model(|| {
+    let throttler = Arc::new(Throttler::new(2, WINDOW));
+
+    let threads: Vec<_> = (0..2)
+      .map(|_| {
+        let throttler = throttler.clone();
+        thread::spawn(move || usize::from(throttler.try_acquire()))
+      })
+      .collect();
+    let acquired = usize::from(throttler.try_acquire());
+    let acquired: usize =
+      acquired + threads.into_iter().map(|thread| thread.join().unwrap()).sum::<usize>();
+
+    // No more than the quota is let through, and every call is accounted for.
+    assert_eq!(acquired, 2);
+    let ThrottlerStats { executed, skipped, in_window } = throttler.stats();
+    assert_eq!((executed, skipped, in_window), (2, 1, 2));
+  });
+}
+
+#[test]
+fn reconfiguring_races_with_calls() {
+  loom::model(|| {
+    let throttler = Arc::new(Throttler::new(1, WINDOW));
+
+    let other = {
+      let throttler = throttler.clone();
+      thread::spawn(move || throttler.set_times(2))
+    };
+    let first = throttler.try_acquire();
+    other.join().unwrap();
+    let second = throttler.try_acquire();
+
+    // The first call always fits, the second one only does under the new limit, which
+    // is in place by then.
+    assert!(first && second);
+  });
+}
+
+#[test]
+fn cancelling_races_with_running() {
+  loom::model(|| {
+    let (call, handle) = DeferredCall::new(|| 42);
+    let call: Box<dyn Deferred> = Box::new(call);
+
+    let worker = thread::spawn(move || call.run());
+    let cancelled = handle.cancel();
+    worker.join().unwrap();
+
+    // The call either runs to completion or is cancelled before it starts, never both.
+    assert!(handle.is_finished());
+    match handle.wait() {
+      Some(value) => assert!(!cancelled && value == 42),
+      None => assert!(cancelled),
+    }
+  });
+}
+
+#[test]
+fn waiting_wakes_up_when_the_call_runs() {
+  loom::model(|| {
+    let (call, handle) = DeferredCall::new(|| 42);
+    let call: Box<dyn Deferred> = Box::new(call);
+
+    let worker = thread::spawn(move || call.run());
+    assert_eq!(handle.wait(), Some(42));
+    worker.join().unwrap();
+  });
+}
+
+#[test]
+fn first_calls_race_for_the_slots() {
+  loom::model(|| {
+    let now = Instant::now();
+    let window = Arc::new(SlotWindow::new(1, now));
+
+    let other = {
+      let window = window.clone();
+      thread::spawn(move || window.try_charge(now, WINDOW))
+    };
+    let charged = window.try_charge(now, WINDOW);
+    let other_charged = other.join().unwrap();
+
+    // Exactly one of the calls takes the only slot, whichever comes first.
+    assert!(charged ^ other_charged);
+    assert!(!window.try_charge(now, WINDOW));
+  });
+}
+
+#[test]
+fn slots_are_shared_across_threads() {
+  // Three threads spinning on the slots are too many interleavings to go through all of
+  // them, but few preemptions are enough for a stale position to be read.
+  let mut model = loom::model::Builder::new();
+  model.preemption_bound = Some(3);
+  model.check(|| {
+    let now = Instant::now();
+    let window = Arc::new(SlotWindow::new(2, now));
+
+    let threads: Vec<_> = (0..2)
+      .map(|_| {
+        let window = window.clone();
+        thread::spawn(move || usize::from(window.try_charge(now, WINDOW)))
+      })
+      .collect();
+    let charged = usize::from(window.try_charge(now, WINDOW));
+    let charged: usize =
+      charged + threads.into_iter().map(|thread| thread.join().unwrap()).sum::<usize>();
+
+    // No more than the quota is let through, nor any less, and the slots free up once
+    // the calls fell out of the window, whichever thread charged them.
+    assert_eq!(charged, 2);
+    let later = now + WINDOW + Duration::from_nanos(1);
+    assert!(window.try_charge(later, WINDOW) && window.try_charge(later, WINDOW));
+    assert!(!window.try_charge(later, WINDOW));
+  });
+}
+
+#[test]
+fn workers_are_claimed_once() {
+  loom::model(|| {
+    let worker = Arc::new(Worker::new());
+
+    // The other caller keeps its claim, as if its thread were still running.
+    let other = {
+      let worker = worker.clone();
+      thread::spawn(move || {
+        let claim = worker.claim();
+        let claimed = claim.is_some();
+        std::mem::forget(claim);
+        claimed
+      })
+    };
+    let claim = worker.claim();
+    let other_claimed = other.join().unwrap();
+
+    // Exactly one of the callers spawns the worker, and once its thread stops, the next
+    // caller spawns it again.
+    assert!(claim.is_some() ^ other_claimed);
+    drop(claim);
+    if !other_claimed {
+      assert!(worker.claim().is_some());
+    }
+  });
+}
+
+#[test]
+fn groups_are_created_once() {
+  loom::model(|| {
+    let other = thread::spawn(|| group_calls("upstream") as *const _ as usize);
+    let calls = group_calls("upstream") as *const _ as usize;
+
+    // Both calls get the deque created by the first one.
+    assert_eq!(calls, other.join().unwrap());
+  });
+}
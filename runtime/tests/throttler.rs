@@ -0,0 +1,45 @@
+//! Throttlers used as values, which always read the time from the virtual clock. The
+//! clock is shared by the whole test binary, so a single test moves it forward.
+
+#![cfg(not(loom))]
+
+use std::time::Duration;
+use throttle_my_fn_runtime::testing::{advance, drive};
+use throttle_my_fn_runtime::{assert_throttled, Throttler, ThrottlerStats};
+
+#[test]
+fn calls_over_quota_turned_down() {
+  let throttler = Throttler::new(2, Duration::from_secs(1));
+
+  // The virtual clock runs alongside the monotonic one, which keeps going meanwhile.
+  let retry_after = |expected: Duration| {
+    let slack = throttler.retry_after().and_then(|after| expected.checked_sub(after));
+    assert!(slack.is_some_and(|slack| slack < Duration::from_millis(50)));
+  };
+
+  // Calls are let through while the window has free slots.
+  let acquired = drive(3, Duration::from_millis(400), || throttler.try_acquire());
+  assert_eq!(acquired, [true, true, false]);
+  assert_eq!(throttler.retry_after(), None);
+  let stats = throttler.stats();
+  assert_eq!(stats, ThrottlerStats { executed: 2, skipped: 1, in_window: 1 });
+
+  // Calls already in the window count against a new limit, and fall out of it according
+  // to a new duration.
+  throttler.set_times(1);
+  assert!(!throttler.try_acquire());
+  retry_after(Duration::from_millis(200));
+  throttler.set_duration(Duration::from_secs(2));
+  retry_after(Duration::from_millis(1200));
+  advance(Duration::from_secs(1));
+  assert!(!throttler.try_acquire());
+  advance(Duration::from_millis(600));
+  assert_throttled!(throttler.try_acquire(), within = "2s", allows = 1);
+  assert_eq!((throttler.times(), throttler.duration()), (1, Duration::from_secs(2)));
+
+  // Resetting forgets about the calls in the window, but not about the statistics.
+  throttler.reset();
+  assert!(throttler.try_acquire());
+  let stats = throttler.stats();
+  assert_eq!(stats, ThrottlerStats { executed: 5, skipped: 4, in_window: 1 });
+}
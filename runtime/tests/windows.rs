@@ -0,0 +1,157 @@
+//! The windows the generated code keeps the calls of throttled functions in, charged with
+//! made-up times.
+
+#![cfg(not(loom))]
+
+use std::thread;
+use std::time::{Duration, Instant};
+use throttle_my_fn_runtime::{
+  BucketWindow, DeferredCall, DeferredQueue, KeyedWindow, Overflow, ShardedWindow,
+  WeightedClasses,
+};
+
+#[test]
+fn sharded_window_lets_times_calls_through() {
+  let (times, duration) = (8, Duration::from_secs(1));
+  let window = ShardedWindow::new(times);
+  let now = Instant::now();
+
+  // The shards of the other threads are borrowed from once the own one is full.
+  let charged = (0..2 * times).filter(|_| window.try_charge(now, now, times, duration));
+  assert_eq!(charged.count(), times);
+
+  let later = now + duration + Duration::from_millis(1);
+  assert!(window.try_charge(later, later, times, duration));
+}
+
+#[test]
+fn sharded_window_shared_between_threads() {
+  let (times, duration) = (8, Duration::from_secs(1));
+  let window = ShardedWindow::new(times);
+  let now = Instant::now();
+
+  let charged: usize = thread::scope(|scope| {
+    let threads: Vec<_> = (0..4)
+      .map(|_| {
+        scope.spawn(|| {
+          (0..times).filter(|_| window.try_charge(now, now, times, duration)).count()
+        })
+      })
+      .collect();
+    threads.into_iter().map(|thread| thread.join().unwrap()).sum()
+  });
+  assert_eq!(charged, times);
+}
+
+#[test]
+fn bucket_window_counts_calls_until_their_slot_ends() {
+  let (times, duration, resolution) =
+    (2, Duration::from_secs(1), Duration::from_millis(100));
+  let window = BucketWindow::new();
+  let now = Instant::now();
+
+  assert!(window.try_charge(now, times, duration, resolution));
+  assert!(window.try_charge(
+    now + Duration::from_millis(50),
+    times,
+    duration,
+    resolution
+  ));
+  assert!(!window.try_charge(
+    now + Duration::from_millis(60),
+    times,
+    duration,
+    resolution
+  ));
+
+  // Both calls are counted as made at the end of their slot, 100ms in.
+  let before_end = now + duration + Duration::from_millis(90);
+  assert!(!window.try_charge(before_end, times, duration, resolution));
+  let after_end = now + duration + Duration::from_millis(110);
+  assert!(window.try_charge(after_end, times, duration, resolution));
+}
+
+#[test]
+fn keyed_window_limits_each_key() {
+  let duration = Duration::from_secs(1);
+  let window = KeyedWindow::<&str>::new(None);
+  let now = Instant::now();
+
+  assert!(window.try_charge("a", now, 1, duration, None));
+  assert!(!window.try_charge("a", now, 1, duration, None));
+  assert!(window.try_charge("b", now, 1, duration, None));
+
+  let later = now + duration + Duration::from_millis(1);
+  assert!(window.try_charge("a", later, 1, duration, None));
+}
+
+#[test]
+fn keyed_window_forgets_least_recent_key() {
+  let duration = Duration::from_secs(1);
+  let window = KeyedWindow::<&str>::new(Some(2));
+  let now = Instant::now();
+
+  assert!(window.try_charge("a", now, 1, duration, None));
+  assert!(window.try_charge("b", now, 1, duration, None));
+
+  // Making room for `c` forgets about `a`, whose next call is let through.
+  assert!(window.try_charge("c", now, 1, duration, None));
+  assert!(window.try_charge("a", now, 1, duration, None));
+  assert!(!window.try_charge("c", now, 1, duration, None));
+}
+
+#[test]
+fn deferred_queue_drops_oldest_call() {
+  static QUEUE: DeferredQueue = DeferredQueue::new(Some(1), Overflow::DropOldest);
+
+  let (first, first_handle) = DeferredCall::new(|| 1);
+  QUEUE.push(QUEUE.lock(), Box::new(first));
+  let (second, second_handle) = DeferredCall::new(|| 2);
+  QUEUE.push(QUEUE.lock(), Box::new(second));
+
+  // The first call was dropped to make room for the second one, cancelling it.
+  assert_eq!(first_handle.wait(), None);
+  QUEUE.take().unwrap().run();
+  assert_eq!(second_handle.wait(), Some(2));
+  assert!(QUEUE.take().is_none());
+}
+
+#[test]
+fn deferred_queue_discards_cancelled_calls() {
+  static QUEUE: DeferredQueue = DeferredQueue::new(None, Overflow::DropNewest);
+
+  let (first, first_handle) = DeferredCall::new(|| 1);
+  QUEUE.push(QUEUE.lock(), Box::new(first));
+  let (second, second_handle) = DeferredCall::new(|| 2);
+  QUEUE.push(QUEUE.lock(), Box::new(second));
+  assert!(!QUEUE.drain(Instant::now, Duration::ZERO));
+
+  assert!(first_handle.cancel());
+  QUEUE.take().unwrap().run();
+  assert_eq!(second_handle.wait(), Some(2));
+  assert!(QUEUE.all_cancelled());
+}
+
+#[test]
+fn weighted_classes_take_turns_by_weight() {
+  let classes = WeightedClasses::new(&[30, 10]);
+  let (high, low) = (classes.enter(0), classes.enter(1));
+
+  // Each free slot is offered to the low-priority caller first, which only takes it in
+  // the turn of its class.
+  let admitted: Vec<_> = (0..8)
+    .map(|_| match low.admit() {
+      Ok(()) => "low",
+      Err(turn) => {
+        drop(turn);
+        assert!(high.admit().is_ok());
+        "high"
+      }
+    })
+    .collect();
+  assert_eq!(admitted, ["low", "high", "high", "high", "low", "high", "high", "high"]);
+
+  // A class alone in waiting takes every free slot.
+  drop(high);
+  assert!((0..4).all(|_| low.admit().is_ok()));
+}
@@ -0,0 +1,101 @@
+//! Restricting a throttled function to the hours of the day it can be executed in, see
+//! the `active` option of [crate::throttle].
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{Ident, LitStr};
+
+/// The number of seconds in a day.
+const DAY: u32 = 24 * 60 * 60;
+
+/// The hours of the day a function can be executed in (`active = "08:00-20:00 UTC"`).
+#[derive(Clone)]
+pub(crate) struct Active {
+  /// The start of the window, in seconds since midnight UTC.
+  start: u32,
+
+  /// The end of the window, in seconds since midnight UTC, up to a whole day. Before
+  /// `start` when the window spans midnight.
+  end: u32,
+
+  /// The name of the option, for error messages.
+  pub(crate) name: Ident,
+}
+
+/// Parse a time of the day such as `08:00` into seconds since midnight, allowing `24:00`
+/// for the end of a window.
+fn time_of_day(time: &str) -> Option<u32> {
+  let (hours, minutes) = time.trim().split_once(':')?;
+  let digits = |part: &str| part.len() == 2 && part.chars().all(|c| c.is_ascii_digit());
+  if !digits(hours) || !digits(minutes) {
+    return None;
+  }
+
+  let hours: u32 = hours.parse().ok()?;
+  let minutes: u32 = minutes.parse().ok()?;
+  match (hours, minutes) {
+    (0..=23, 0..=59) | (24, 0) => Some(hours * 3600 + minutes * 60),
+    _ => None,
+  }
+}
+
+impl Active {
+  /// Parse the window of an `active = "HH:MM-HH:MM UTC"` option named `name`.
+  pub(crate) fn parse(name: Ident, value: &LitStr) -> syn::Result<Active> {
+    let text = value.value();
+    let expecting = || {
+      syn::Error::new_spanned(
+        value,
+        format!("expecting a window of the day: `{} = \"08:00-20:00 UTC\"`", name),
+      )
+    };
+
+    let (window, zone) = text.trim().split_once(' ').ok_or_else(expecting)?;
+    if zone.trim() != "UTC" {
+      return Err(syn::Error::new_spanned(
+        value,
+        format!("only `UTC` windows are supported, found `{}`", zone.trim()),
+      ));
+    }
+
+    let (start, end) = window.split_once('-').ok_or_else(expecting)?;
+    let start = time_of_day(start).filter(|&start| start < DAY).ok_or_else(expecting)?;
+    let end = time_of_day(end).ok_or_else(expecting)?;
+    if start == end {
+      return Err(syn::Error::new_spanned(
+        value,
+        "the window is empty, since it starts and ends at the same time",
+      ));
+    }
+
+    Ok(Active { start, end, name })
+  }
+
+  /// Generate the `__throttle_inactive_for()` function, returning how long until the
+  /// window opens again, or `None` while it is open.
+  pub(crate) fn inactive_for_fn(&self) -> TokenStream2 {
+    let Active { start, end, .. } = self;
+
+    // A window spanning midnight is open after its start or before its end.
+    let is_open = if start < end {
+      quote_mixed! { (#start..#end).contains(&now) }
+    } else {
+      quote_mixed! { now >= #start || now < #end }
+    };
+
+    quote_mixed! {
+      fn __throttle_inactive_for() -> Option<::std::time::Duration> {
+        let since_epoch = ::std::time::SystemTime::now()
+          .duration_since(::std::time::SystemTime::UNIX_EPOCH)
+          .unwrap_or_default();
+        let now = (since_epoch.as_secs() % u64::from(#DAY)) as u32;
+
+        if #is_open {
+          return None;
+        }
+
+        let until_start = (#start + #DAY - now) % #DAY;
+        Some(::std::time::Duration::from_secs(u64::from(until_start)))
+      }
+    }
+  }
+}
@@ -0,0 +1,2059 @@
+//! Parsing of the arguments passed to the `#[throttle(...)]` and `#[debounce(...)]`
+//! attributes.
+
+use crate::active::Active;
+use crate::config;
+use crate::duration::{parse_duration, parse_window};
+use crate::priorities::Priorities;
+use proc_macro2::Span;
+use quote::ToTokens;
+use std::cell::Cell;
+use std::fmt::Display;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{
+  parenthesized, token, Expr, ExprLit, ExprPath, ExprUnary, ExprYield, Ident, Lit,
+  LitInt, LitStr, Token, Type, TypePath, UnOp,
+};
+
+/// What happens to a call that arrives while the quota is exhausted.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Mode {
+  /// The call is skipped and the wrapper returns `None` (the default).
+  Skip,
+
+  /// The call is queued and executed later by a background thread once capacity frees
+  /// up. The wrapper returns immediately.
+  Defer,
+
+  /// Like [Mode::Defer], but at most one call is kept pending: a new call replaces the
+  /// pending one, so only a single trailing execution with the latest arguments happens
+  /// once capacity frees up.
+  Coalesce,
+}
+
+/// What happens to a deferred call when the pending queue is full.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Overflow {
+  /// The new call is dropped (the default).
+  DropNewest,
+
+  /// The oldest pending call is dropped to make room for the new one.
+  DropOldest,
+
+  /// The caller blocks until there is room in the queue.
+  Block,
+}
+
+/// How a call blocked on a full quota waits for a free slot (`wait = ...`).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Wait {
+  /// The thread sleeps until the slot is free (the default).
+  Sleep,
+
+  /// The thread busy-waits until the slot is free.
+  Spin,
+
+  /// The thread yields to the scheduler until the slot is free.
+  Yield,
+
+  /// The thread sleeps until shortly before the slot is free, then yields.
+  Adaptive,
+}
+
+/// On which edge of a window a function executes, lodash-style.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Edge {
+  /// On the first call of the window.
+  Leading,
+
+  /// At the end of the window, with the arguments of the latest call.
+  Trailing,
+
+  /// Both on the first call and at the end of the window.
+  Both,
+}
+
+/// Parse the value of the `edge` option.
+fn edge(name: &Ident, value: &Expr) -> syn::Result<Edge> {
+  Ok(match choice(name, value, &["leading", "trailing", "both"])? {
+    "leading" => Edge::Leading,
+    "trailing" => Edge::Trailing,
+    _ => Edge::Both,
+  })
+}
+
+/// A limit applying to each key of a function separately, on top of the limit of the
+/// function as a whole (`per_key = (10, "1s"), key = client_ip`).
+#[derive(Clone)]
+pub(crate) struct PerKey {
+  /// Number of calls with the same key allowed over `duration`.
+  pub(crate) times: Expr,
+
+  /// The [std::time::Duration] over which `times` calls with the same key are allowed.
+  pub(crate) duration: Expr,
+
+  /// The arguments of the function making up the key.
+  pub(crate) key: Vec<Ident>,
+
+  /// Whether a key can only use its share of the limit of the function as a whole, split
+  /// evenly between the keys with calls in its window (`fair = true`).
+  pub(crate) fair: bool,
+
+  /// The `BuildHasher` of the map of the calls per key (`hasher = FxBuildHasher`), the
+  /// one of `HashMap` if not provided.
+  pub(crate) hasher: Option<Type>,
+
+  /// The function normalizing keys before they are looked up
+  /// (`normalize_key = normalize`), given the key and returning it. The key is the
+  /// argument itself with a single one, and a tuple of them otherwise.
+  pub(crate) normalize_key: Option<Expr>,
+
+  /// The number of keys kept in the map of the calls per key (`max_keys = 10_000`), the
+  /// least recently let through being forgotten to make room for a new one.
+  pub(crate) max_keys: Option<Expr>,
+
+  /// The name of the option, for error messages.
+  pub(crate) name: Ident,
+}
+
+/// A provider of the limit of a function, called at runtime
+/// (`rate_from = path::to::provider`).
+#[derive(Clone)]
+pub(crate) struct RateFrom {
+  /// The path of the provider, a function returning a `(usize, Duration)` pair.
+  pub(crate) provider: Expr,
+
+  /// The [std::time::Duration] for which the limit returned by the provider is reused
+  /// before calling it again.
+  pub(crate) refresh: Expr,
+
+  /// The name of the option, for error messages.
+  pub(crate) name: Ident,
+}
+
+/// A single argument to the attribute: positional (`10`), named (`mode = defer`) or a
+/// named list (`variants(try, wait)`).
+enum Arg {
+  Positional(Expr),
+  Named(Ident, Expr),
+  List(Ident, Punctuated<Ident, Token![,]>),
+}
+
+/// The options taking a list, which could otherwise be mistaken for function calls.
+const LIST_OPTIONS: &[&str] = &["variants"];
+
+impl Parse for Arg {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let fork = input.fork();
+    let is_list =
+      fork.parse::<Ident>().is_ok_and(|name| LIST_OPTIONS.contains(&&*name.to_string()))
+        && fork.peek(token::Paren);
+
+    if is_list {
+      let name: Ident = input.parse()?;
+      let content;
+      let _ = parenthesized!(content in input);
+      // List items may be keywords, e.g. `try`.
+      let items = content.parse_terminated(Ident::parse_any)?;
+      Ok(Arg::List(name, items))
+    } else if input.peek(Ident) && input.peek2(Token![=]) {
+      let name: Ident = input.parse()?;
+      let _: Token![=] = input.parse()?;
+      let value: Expr = input.parse()?;
+      Ok(Arg::Named(name, value))
+    } else {
+      Ok(Arg::Positional(input.parse()?))
+    }
+  }
+}
+
+/// Parse the comma-separated arguments of an attribute, pointing at the second
+/// occurrence of an option given more than once.
+fn parse_args(input: ParseStream) -> syn::Result<Punctuated<Arg, Token![,]>> {
+  let args = Punctuated::<Arg, Token![,]>::parse_terminated(input)?;
+
+  let mut names: Vec<&Ident> = Vec::new();
+  for arg in &args {
+    let name = match arg {
+      Arg::Positional(_) => continue,
+      Arg::Named(name, _) | Arg::List(name, _) => name,
+    };
+    if names.contains(&name) {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!("option `{}` is given more than once", name),
+      ));
+    }
+    names.push(name);
+  }
+
+  Ok(args)
+}
+
+/// The parsed arguments of the attribute.
+#[derive(Clone)]
+pub(crate) struct Args {
+  /// Number of times the function should be limited to running over `duration`.
+  pub(crate) times: Expr,
+
+  /// The [std::time::Duration] over which the function should be allowed to run `times`
+  /// times.
+  pub(crate) duration: Expr,
+
+  /// The absolute path of the configuration file `times` and `duration` were read from
+  /// (`config = "throttles.toml", name = "NAME"`), if any.
+  pub(crate) config_path: Option<String>,
+
+  /// See [RateFrom]. `times` and `duration` are then read from the provider.
+  pub(crate) rate_from: Option<RateFrom>,
+
+  /// The length in seconds of the calendar period calls are charged against
+  /// (`per = "hour", align = calendar`), if any, and the name of the option. Every call
+  /// is then charged at the start of its period, for all calls of a period to fall out of
+  /// the window at its end.
+  pub(crate) calendar: Option<(u64, Ident)>,
+
+  /// See [Mode].
+  pub(crate) mode: Mode,
+
+  /// The name of the `mode` option with `mode = wait_with_deadline`, in which calls are
+  /// passed a deadline to wait for a free slot until, and are otherwise skipping. The
+  /// [Mode] is [Mode::Skip] then.
+  pub(crate) deadline: Option<Ident>,
+
+  /// The candidate limit of the `shadow = (times, duration)` option, which every call is
+  /// also charged against for the statistics to tell how many calls it would have
+  /// skipped. Only used in skipping mode.
+  pub(crate) shadow: Option<(Expr, Expr)>,
+
+  /// The name of the `mode` option with `mode = warn` or `mode = observe`, in which calls
+  /// over the limit are executed all the same. The [Mode] is [Mode::Skip] then.
+  pub(crate) warn: Option<Ident>,
+
+  /// Whether calls over the limit are only recorded in the statistics of the function
+  /// (`mode = observe`), rather than reported as over budget (`mode = warn`).
+  pub(crate) observe: bool,
+
+  /// The handler called with the name of the function and the location of the call right
+  /// before a call over the limit is executed in warning mode (`on_over_budget =
+  /// handler`), which that mode requires.
+  pub(crate) on_over_budget: Option<Expr>,
+
+  /// See [Active]. Calls outside of the window are skipped or deferred.
+  pub(crate) active: Option<Active>,
+
+  /// See [PerKey].
+  pub(crate) per_key: Option<PerKey>,
+
+  /// Maximum number of pending calls in deferred mode, unbounded if not provided.
+  pub(crate) queue: Option<LitInt>,
+
+  /// See [Overflow].
+  pub(crate) overflow: Overflow,
+
+  /// See [Wait]. Only used by deferred calls and the `wait` variant.
+  pub(crate) wait: Wait,
+
+  /// Maximum number of executions in flight at the same time, unbounded if not provided.
+  /// `reentrant = deny` is the same as a concurrency of 1.
+  pub(crate) concurrency: Option<Expr>,
+
+  /// The name of the `on_busy = wait` option, with which calls of async functions wait
+  /// for a concurrency permit instead of being skipped.
+  pub(crate) on_busy_wait: Option<Ident>,
+
+  /// The value to return when a call is skipped, keeping the function's return type
+  /// instead of wrapping it in an [std::option::Option]. Only used in skipping mode.
+  pub(crate) skipped: Option<Expr>,
+
+  /// Whether the outer function returns a `ThrottleResult<T>` telling when to retry
+  /// skipped calls (`output = detailed`), rather than an [std::option::Option] (the
+  /// default). Only used in skipping mode.
+  pub(crate) detailed: bool,
+
+  /// Whether the outer function returns a `Result<T, (A, B, ...)>`, giving the arguments
+  /// of skipped calls back to the caller (`output = result_with_args`). Only used in
+  /// skipping mode.
+  pub(crate) result_with_args: bool,
+
+  /// Whether skipped calls of a function returning an `Option<T>` return `None`, merging
+  /// them with the function's own `None` instead of returning an `Option<Option<T>>`
+  /// (`flatten = true`). Only used in skipping mode.
+  pub(crate) flatten: bool,
+
+  /// Whether functions to query the state of the throttle without calling the function
+  /// are generated alongside it (`queries = true`), e.g. `NAME_retry_after()`.
+  pub(crate) queries: Option<Ident>,
+
+  /// Whether the calls to the function are counted, and the counts reported by a
+  /// function generated alongside it (`stats = true`), i.e. `NAME_stats()`.
+  pub(crate) stats: Option<Ident>,
+
+  /// The handler called with the name of the function and the number of calls in the
+  /// window right before a call is executed (`on_execute = handler`), and the name of the
+  /// option. Only used in skipping mode.
+  pub(crate) on_execute: Option<(Expr, Ident)>,
+
+  /// The name of the `sharded = true` option, which splits the quota across a calls
+  /// deque per core. Only used in skipping mode.
+  pub(crate) sharded: Option<Ident>,
+
+  /// Whether a function reserving a slot in the quota ahead of calling the function is
+  /// generated alongside it (`permits = true`), i.e. `NAME_acquire()`.
+  pub(crate) permits: Option<Ident>,
+
+  /// Whether a function returning a tower layer charging the quota of the function is
+  /// generated alongside it (`layer = true`), i.e. `NAME_throttle_layer()`.
+  pub(crate) layer: Option<Ident>,
+
+  /// The name under which the unthrottled user-provided function is also available
+  /// (`expose_inner = "NAME_raw"`), if any.
+  pub(crate) expose_inner: Option<(Ident, Ident)>,
+
+  /// Whether the limit is also available as constants next to the function
+  /// (`consts = true`), i.e. `NAME_THROTTLE_TIMES` and `NAME_THROTTLE_PERIOD`.
+  pub(crate) consts: Option<Ident>,
+
+  /// Whether a `NAME_wait` variant of the function, which blocks until the call can be
+  /// executed instead of skipping it, is generated alongside it
+  /// (`variants(try, wait)`). Both variants share the same quota.
+  pub(crate) wait_variant: Option<Ident>,
+
+  /// Whether a `NAME_async` variant of the function, which waits for the call to be
+  /// executable on the tokio timer and then runs it on the blocking thread pool, is
+  /// generated alongside it (`variants(try, async)`). All variants share the same quota.
+  pub(crate) async_variant: Option<Ident>,
+
+  /// Whether the limiter of the function is exposed as the `NAME_throttle::STATE` static
+  /// next to it (`expose = true`), for other code paths to charge calls against its
+  /// quota.
+  pub(crate) expose: Option<Ident>,
+
+  /// Whether the `NAME_throttle` module of [Args::expose] also holds functions reporting
+  /// on and resetting the limiter (`emit = module`), for tooling to find at predictable
+  /// paths.
+  pub(crate) emit_module: bool,
+
+  /// The number of calls let through in the first window after the function is first
+  /// called (`cold_start_cap = 10`), and the name of the option. Only used in skipping
+  /// mode.
+  pub(crate) cold_start_cap: Option<(Expr, Ident)>,
+
+  /// The name of the `with_override = true` option, which adds an `override_` parameter
+  /// to the function for each call to tell how it is throttled. Only used in skipping
+  /// mode.
+  pub(crate) with_override: Option<Ident>,
+
+  /// Whether a `NAME_throttle_drain(timeout)` function waiting for the deferred calls to
+  /// be executed is generated next to the function (`drain = true`). Only used in
+  /// deferred and coalescing modes.
+  pub(crate) drain: Option<Ident>,
+
+  /// Whether throttling the function can be switched off at runtime with the generated
+  /// `NAME_throttle_enabled(bool)` function (`toggle = true`).
+  pub(crate) toggle: Option<Ident>,
+
+  /// The [std::time::Duration] without any calls after which the calls in the window are
+  /// forgotten (`reset_after_idle = "5m"`), and the name of the option.
+  pub(crate) reset_after_idle: Option<(Expr, Ident)>,
+
+  /// The [std::time::Duration] of the slots the calls in the window are counted in
+  /// (`resolution = "1s"`) instead of keeping the time of each, and the name of the
+  /// option.
+  pub(crate) resolution: Option<(Expr, Ident)>,
+
+  /// The name of the `charge = end` option, which charges calls when they finish rather
+  /// than when they start. Only used in skipping mode.
+  pub(crate) charge_at_end: Option<Ident>,
+
+  /// The name of the `refund = on_cancel` option, which gives the slot of a call back
+  /// when its future is dropped before it completes. Only used in skipping mode.
+  pub(crate) refund_on_cancel: Option<Ident>,
+
+  /// The `AdmissionPolicy` deciding whether calls are let through instead of the limit
+  /// (`policy = MY_POLICY`), and the name of the option. Only used in skipping mode.
+  pub(crate) policy: Option<(Expr, Ident)>,
+
+  /// The classes of calls sharing the limit by their weights
+  /// (`priorities = (high = 70, low = 30), priority = class`). Only used in skipping
+  /// mode.
+  pub(crate) priorities: Option<Priorities>,
+
+  /// Whether the methods of an impl block share a single quota (the default), or each
+  /// get their own.
+  pub(crate) share: Option<(Ident, bool)>,
+
+  /// Whether functions returning `impl Future<Output = T>` are throttled once their
+  /// future is first polled (`future = poll`), rather than when they are called (the
+  /// default).
+  pub(crate) poll_future: bool,
+
+  /// Whether each instantiation of a generic function gets its own quota
+  /// (`scope = per_type`), rather than all of them sharing one (`scope = global`, the
+  /// default).
+  pub(crate) per_type: Option<Ident>,
+
+  /// The name of the group of functions sharing a quota across crates
+  /// (`group = "github"`), and the name of the option.
+  pub(crate) group: Option<(LitStr, Ident)>,
+
+  /// Whether the calls the function makes to itself go straight to the user-provided
+  /// function without being charged (`recursion = bypass`), rather than through the
+  /// throttle (`recursion = charge`, the default).
+  pub(crate) bypass_recursion: Option<Ident>,
+
+  /// Whether calls are never executed immediately, but only at the end of the window
+  /// started by the first of them (`edge = trailing`). Only used in coalescing mode.
+  pub(crate) trailing_only: bool,
+}
+
+/// Extract a bare identifier from an option's value, e.g. `defer` in `mode = defer`, and
+/// make sure it is one of `choices`.
+fn choice<'a>(name: &Ident, value: &Expr, choices: &[&'a str]) -> syn::Result<&'a str> {
+  let ident = match value {
+    Expr::Path(path) => path.path.get_ident().map(Ident::to_string),
+    // `yield` is a keyword, which parses as a yield expression.
+    Expr::Yield(ExprYield { expr: None, .. }) => Some(String::from("yield")),
+    _ => None,
+  };
+
+  match choices.iter().find(|&&choice| Some(choice) == ident.as_deref()) {
+    Some(choice) => Ok(choice),
+    None => {
+      let choices = choices.iter().map(|choice| format!("`{} = {}`", name, choice));
+      let choices = choices.collect::<Vec<_>>().join(", ");
+      Err(syn::Error::new_spanned(value, format!("expecting one of: {}", choices)))
+    }
+  }
+}
+
+/// Validate the number of calls of a limit, when it is written as a literal. Other
+/// expressions are left to be checked by the compiler, and at runtime.
+fn parse_times(times: Expr) -> syn::Result<Expr> {
+  const TIMES_ERR_MSG: &str = "expecting a positive integer number of calls";
+
+  match &times {
+    Expr::Lit(ExprLit { lit: Lit::Int(lit), .. })
+      if lit.base10_parse::<usize>()? == 0 =>
+    {
+      return Err(syn::Error::new_spanned(
+        lit,
+        format!("a limit of 0 calls makes the function unreachable, {}", TIMES_ERR_MSG),
+      ));
+    }
+    Expr::Lit(ExprLit { lit: Lit::Int(_), .. }) => {}
+    Expr::Lit(ExprLit { lit, .. }) => {
+      return Err(syn::Error::new_spanned(lit, TIMES_ERR_MSG));
+    }
+    Expr::Unary(ExprUnary { op: UnOp::Neg(_), expr, .. })
+      if matches!(**expr, Expr::Lit(_)) =>
+    {
+      return Err(syn::Error::new_spanned(&times, TIMES_ERR_MSG));
+    }
+    _ => {}
+  }
+
+  Ok(times)
+}
+
+thread_local! {
+  /// How the macro whose arguments are being parsed is used, see [with_usage].
+  static USAGE: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+/// Parse the arguments of a macro with `parse`, appending `usage` to the messages of the
+/// errors about missing or unexpected positional arguments. Other errors, such as an
+/// unknown option or a malformed value, are precise enough on their own.
+pub(crate) fn with_usage<T>(
+  usage: &'static str,
+  parse: impl FnOnce() -> syn::Result<T>,
+) -> syn::Result<T> {
+  let previous = USAGE.with(|current| current.replace(Some(usage)));
+  let result = parse();
+  USAGE.with(|current| current.set(previous));
+  result
+}
+
+/// The message of an error about the positional arguments, followed by the usage of the
+/// macro when it is known, see [with_usage].
+fn positional(message: impl Display) -> String {
+  match USAGE.with(Cell::get) {
+    Some(usage) => format!("{}, {}", message, usage),
+    None => message.to_string(),
+  }
+}
+
+/// The error for missing positional arguments, about the whole attribute.
+pub(crate) fn missing_arguments() -> syn::Error {
+  syn::Error::new(Span::call_site(), positional("missing arguments"))
+}
+
+/// The error for a missing positional argument, about the whole attribute.
+fn missing_argument(what: &str) -> syn::Error {
+  syn::Error::new(Span::call_site(), positional(format!("missing the {} argument", what)))
+}
+
+/// The error for a positional argument that is not expected, pointing at it.
+fn unexpected_positional(extra: impl ToTokens, message: impl Display) -> syn::Error {
+  syn::Error::new_spanned(extra, positional(message))
+}
+
+/// Make sure there are no positional arguments left over in `rest`, pointing at the
+/// first extra one otherwise.
+fn reject_extra(mut rest: impl Iterator<Item = Expr>) -> syn::Result<()> {
+  match rest.next() {
+    Some(extra) => Err(unexpected_positional(extra, "unexpected positional argument")),
+    None => Ok(()),
+  }
+}
+
+/// The error for an option that the attribute doesn't know about.
+fn unknown_option(name: &Ident) -> syn::Error {
+  syn::Error::new_spanned(name, format!("unknown option `{}`", name))
+}
+
+/// Extract a boolean from an option's value, e.g. `true` in `share = true`.
+fn flag(name: &Ident, value: &Expr) -> syn::Result<bool> {
+  match value {
+    Expr::Lit(ExprLit { lit: Lit::Bool(lit), .. }) => Ok(lit.value),
+    _ => Err(syn::Error::new_spanned(
+      value,
+      format!("expecting one of: `{} = true`, `{} = false`", name, name),
+    )),
+  }
+}
+
+/// Extract a function name from an option's value, e.g. `send_raw` in
+/// `expose_inner = "send_raw"`.
+fn function_name(name: &Ident, value: &Expr) -> syn::Result<Ident> {
+  match value {
+    Expr::Lit(ExprLit { lit: Lit::Str(lit), .. }) => lit.parse(),
+    _ => Err(syn::Error::new_spanned(
+      value,
+      format!("expecting a function name: `{} = \"NAME\"`", name),
+    )),
+  }
+}
+
+/// Extract the number of calls and the duration of a limit from an option's value, e.g.
+/// `(10, "1s")` in `per_key = (10, "1s")`.
+fn limit(name: &Ident, value: Expr) -> syn::Result<(Expr, Expr)> {
+  let (times, duration) = match value {
+    Expr::Tuple(tuple) if tuple.elems.len() == 2 => {
+      (tuple.elems[0].clone(), tuple.elems[1].clone())
+    }
+    value => {
+      return Err(syn::Error::new_spanned(
+        value,
+        format!("expecting a limit: `{} = (<number-of-calls>, <duration>)`", name),
+      ))
+    }
+  };
+
+  let times = parse_times(times)?;
+  let duration = parse_window(duration, &times)?;
+  Ok((times, duration))
+}
+
+/// The interval between executions of a rate in executions per second, e.g. `30.0` in
+/// `rate = 30.0`. Literal rates are turned into a number of nanoseconds at expansion
+/// time, any other expression is divided at runtime.
+fn rate_interval(name: &Ident, value: Expr) -> syn::Result<Expr> {
+  let rate = match &value {
+    Expr::Lit(ExprLit { lit: Lit::Float(lit), .. }) => lit.base10_parse::<f64>()?,
+    Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }) => lit.base10_parse::<f64>()?,
+    Expr::Lit(_) | Expr::Unary(ExprUnary { op: UnOp::Neg(_), .. }) => f64::NAN,
+    _ => {
+      return Ok(syn::parse_quote! {
+        ::std::time::Duration::from_secs_f64(1.0 / f64::from(#value))
+      })
+    }
+  };
+
+  let nanos = (1e9 / rate).round();
+  if !(rate > 0.0 && rate.is_finite()) {
+    return Err(syn::Error::new_spanned(
+      value,
+      format!("expecting a positive number of executions per second: `{} = 30.0`", name),
+    ));
+  } else if nanos < 1.0 {
+    return Err(syn::Error::new_spanned(
+      value,
+      "a rate of more than 1 execution per nanosecond is finer than the clock can \
+       measure",
+    ));
+  } else if nanos > u64::MAX as f64 {
+    return Err(syn::Error::new_spanned(value, "the rate is too slow to be represented"));
+  }
+
+  let nanos = nanos as u64;
+  Ok(syn::parse_quote! { ::std::time::Duration::from_nanos(#nanos) })
+}
+
+/// Extract the names of the arguments making up a key from an option's value, e.g.
+/// `client_ip` in `key = client_ip` or `user, region` in `key = (user, region)`.
+fn key_params(name: &Ident, value: &Expr) -> syn::Result<Vec<Ident>> {
+  let elems: Vec<&Expr> = match value {
+    Expr::Tuple(tuple) => tuple.elems.iter().collect(),
+    Expr::Paren(paren) => vec![&*paren.expr],
+    value => vec![value],
+  };
+
+  elems
+    .into_iter()
+    .map(|elem| match elem {
+      Expr::Path(path) if path.path.get_ident().is_some() => {
+        Ok(path.path.segments[0].ident.clone())
+      }
+      _ => Err(syn::Error::new_spanned(
+        elem,
+        format!("expecting argument names: `{0} = NAME` or `{0} = (NAME, ...)`", name),
+      )),
+    })
+    .collect()
+}
+
+/// Extract the type of a `BuildHasher` from an option's value, e.g. `FxBuildHasher` in
+/// `hasher = FxBuildHasher`. Generic types are written as paths with turbofishes, e.g.
+/// `BuildHasherDefault::<FxHasher>`.
+fn hasher_type(name: &Ident, value: &Expr) -> syn::Result<Type> {
+  match value {
+    Expr::Path(ExprPath { qself: None, path, .. }) => {
+      Ok(Type::Path(TypePath { qself: None, path: path.clone() }))
+    }
+    _ => Err(syn::Error::new_spanned(
+      value,
+      format!("expecting the path of a type: `{} = FxBuildHasher`", name),
+    )),
+  }
+}
+
+/// The length in seconds of a named period, e.g. `per = "hour"`.
+fn period(name: &Ident, value: &Expr) -> syn::Result<u64> {
+  match string(name, value)?.value().as_str() {
+    "second" => Ok(1),
+    "minute" => Ok(60),
+    "hour" => Ok(60 * 60),
+    "day" => Ok(24 * 60 * 60),
+    _ => Err(syn::Error::new_spanned(
+      value,
+      format!(
+        "expecting one of: \"second\", \"minute\", \"hour\", \"day\" for `{}`",
+        name
+      ),
+    )),
+  }
+}
+
+/// Extract the size of a bounded queue from an option's value, a positive integer
+/// literal, e.g. `128` in `queue = 128`. A queue with no room drops or blocks every call.
+fn queue_size(name: &Ident, value: &Expr) -> syn::Result<LitInt> {
+  let usage = format!("expecting a positive integer: `{} = <n>`", name);
+  match value {
+    Expr::Lit(ExprLit { lit: Lit::Int(lit), .. })
+      if lit.base10_parse::<usize>()? == 0 =>
+    {
+      Err(syn::Error::new_spanned(
+        lit,
+        format!("a queue of 0 calls has no room for deferred calls, {}", usage),
+      ))
+    }
+    Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }) => Ok(lit.clone()),
+    _ => Err(syn::Error::new_spanned(value, usage)),
+  }
+}
+
+/// Extract a string literal from an option's value, e.g. `"throttles.toml"` in
+/// `config = "throttles.toml"`.
+fn string(name: &Ident, value: &Expr) -> syn::Result<LitStr> {
+  match value {
+    Expr::Lit(ExprLit { lit: Lit::Str(lit), .. }) => Ok(lit.clone()),
+    _ => Err(syn::Error::new_spanned(
+      value,
+      format!("expecting a string literal: `{} = \"...\"`", name),
+    )),
+  }
+}
+
+impl Parse for Args {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let args = parse_args(input)?;
+    Args::from_args(args)
+  }
+}
+
+impl Args {
+  /// Build the arguments from the already-parsed list of `args`.
+  fn from_args(args: impl IntoIterator<Item = Arg>) -> syn::Result<Self> {
+    let mut positional = Vec::new();
+    let mut mode = Mode::Skip;
+    let mut deadline = None;
+    let mut warn = None;
+    let mut observe = false;
+    let mut shadow = None;
+    let mut on_over_budget = None;
+    let mut queue = None;
+    let mut overflow = Overflow::DropNewest;
+    let mut wait_strategy = None;
+    let mut edge_option = None;
+    let mut concurrency = None;
+    let mut on_busy_wait = None;
+    let mut reentrant = None;
+    let mut skipped = None;
+    let mut share = None;
+    let mut future = None;
+    let mut recursion = None;
+    let mut per_type = None;
+    let mut group = None;
+    let mut global = None;
+    let mut config = None;
+    let mut config_name = None;
+    let mut rate_from = None;
+    let mut rate_refresh = None;
+    let mut per = None;
+    let mut every = None;
+    let mut rate = None;
+    let mut align = None;
+    let mut per_key = None;
+    let mut key = None;
+    let mut detailed = None;
+    let mut result_with_args = None;
+    let mut queries = None;
+    let mut permits = None;
+    let mut layer = None;
+    let mut stats = None;
+    let mut on_execute = None;
+    let mut sharded = None;
+    let mut fair = None;
+    let mut hasher = None;
+    let mut normalize_key = None;
+    let mut max_keys = None;
+    let mut active = None;
+    let mut expose_inner = None;
+    let mut consts = None;
+    let mut expose = None;
+    let mut emit_module = None;
+    let mut toggle = None;
+    let mut drain = None;
+    let mut with_override = None;
+    let mut cold_start_cap = None;
+    let mut reset_after_idle = None;
+    let mut charge_at_end = None;
+    let mut refund_on_cancel = None;
+    let mut policy = None;
+    let mut priorities = None;
+    let mut priority = None;
+    let mut resolution = None;
+    let mut flatten = None;
+    let mut variants = None;
+
+    // Options that only make sense in deferred mode.
+    let mut defer_only = Vec::new();
+
+    for arg in args {
+      match arg {
+        Arg::Positional(expr) => positional.push(expr),
+        Arg::List(name, items) => variants = Some((name, items)),
+        Arg::Named(name, value) => match name.to_string().as_str() {
+          "mode" => {
+            let choices =
+              ["skip", "defer", "coalesce", "wait_with_deadline", "warn", "observe"];
+            let choice = choice(&name, &value, &choices)?;
+            deadline = (choice == "wait_with_deadline").then(|| name.clone());
+            warn = matches!(choice, "warn" | "observe").then(|| name.clone());
+            observe = choice == "observe";
+            mode = match choice {
+              "defer" => Mode::Defer,
+              "coalesce" => Mode::Coalesce,
+              _ => Mode::Skip,
+            }
+          }
+          "queue" => {
+            queue = Some(queue_size(&name, &value)?);
+            defer_only.push(name);
+          }
+          "overflow" => {
+            overflow =
+              match choice(&name, &value, &["drop_newest", "drop_oldest", "block"])? {
+                "drop_oldest" => Overflow::DropOldest,
+                "block" => Overflow::Block,
+                _ => Overflow::DropNewest,
+              };
+            defer_only.push(name);
+          }
+          "wait" => {
+            let wait =
+              match choice(&name, &value, &["sleep", "spin", "yield", "adaptive"])? {
+                "spin" => Wait::Spin,
+                "yield" => Wait::Yield,
+                "adaptive" => Wait::Adaptive,
+                _ => Wait::Sleep,
+              };
+            wait_strategy = Some((wait, name));
+          }
+          "edge" => edge_option = Some((edge(&name, &value)?, name)),
+          "concurrency" => concurrency = Some((value, name)),
+          "on_busy" => {
+            if choice(&name, &value, &["skip", "wait"])? == "wait" {
+              on_busy_wait = Some(name);
+            }
+          }
+          "reentrant" => {
+            if choice(&name, &value, &["allow", "deny"])? == "deny" {
+              reentrant = Some(name);
+            }
+          }
+          // A value converted into the return type is still a skipped value.
+          "skipped" | "skip_into" => {
+            if let Some((_, other)) = &skipped {
+              return Err(syn::Error::new_spanned(
+                &name,
+                format!("option `{}` cannot be used with option `{}`", name, other),
+              ));
+            }
+
+            let value = match name == "skip_into" {
+              true => syn::parse_quote! { ::std::convert::Into::into(#value) },
+              false => value,
+            };
+            skipped = Some((value, name));
+          }
+          "share" => share = Some((name.clone(), flag(&name, &value)?)),
+          "queries" => {
+            if flag(&name, &value)? {
+              queries = Some(name);
+            }
+          }
+          "stats" => {
+            if flag(&name, &value)? {
+              stats = Some(name);
+            }
+          }
+          "on_execute" => on_execute = Some((value, name)),
+          "on_over_budget" => on_over_budget = Some((value, name)),
+          "fair" => {
+            if flag(&name, &value)? {
+              fair = Some(name);
+            }
+          }
+          "hasher" => hasher = Some((hasher_type(&name, &value)?, name)),
+          "normalize_key" => normalize_key = Some((value, name)),
+          "max_keys" => max_keys = Some((value, name)),
+          "sharded" => {
+            if flag(&name, &value)? {
+              sharded = Some(name);
+            }
+          }
+          "active" => {
+            let window = string(&name, &value)?;
+            active = Some(Active::parse(name, &window)?);
+          }
+          "permits" => {
+            if flag(&name, &value)? {
+              permits = Some(name);
+            }
+          }
+          "layer" => {
+            if flag(&name, &value)? {
+              layer = Some(name);
+            }
+          }
+          "flatten" => {
+            if flag(&name, &value)? {
+              flatten = Some(name);
+            }
+          }
+          "consts" => {
+            if flag(&name, &value)? {
+              consts = Some(name);
+            }
+          }
+          "expose" => {
+            if flag(&name, &value)? {
+              expose = Some(name);
+            }
+          }
+          "emit" => {
+            if choice(&name, &value, &["inline", "module"])? == "module" {
+              emit_module = Some(name);
+            }
+          }
+          "toggle" => {
+            if flag(&name, &value)? {
+              toggle = Some(name);
+            }
+          }
+          "cold_start_cap" => cold_start_cap = Some((value, name)),
+          "with_override" => {
+            if flag(&name, &value)? {
+              with_override = Some(name);
+            }
+          }
+          "drain" => {
+            if flag(&name, &value)? {
+              drain = Some(name);
+            }
+          }
+          "charge" => {
+            if choice(&name, &value, &["start", "end"])? == "end" {
+              charge_at_end = Some(name);
+            }
+          }
+          "resolution" => resolution = Some((parse_duration(value)?, name)),
+          "policy" => policy = Some((value, name)),
+          "priorities" => priorities = Some((value, name)),
+          "priority" => priority = Some((value, name)),
+          "refund" => {
+            if choice(&name, &value, &["never", "on_cancel"])? == "on_cancel" {
+              refund_on_cancel = Some(name);
+            }
+          }
+          "reset_after_idle" => {
+            reset_after_idle = Some((parse_duration(value)?, name));
+          }
+          "expose_inner" => expose_inner = Some((function_name(&name, &value)?, name)),
+          "output" => {
+            let output =
+              choice(&name, &value, &["option", "detailed", "result_with_args"])?;
+            detailed = (output == "detailed").then(|| name.clone());
+            result_with_args = (output == "result_with_args").then(|| name.clone());
+          }
+          "future" => {
+            if choice(&name, &value, &["call", "poll"])? == "poll" {
+              future = Some(name);
+            }
+          }
+          "global" => global = Some((limit(&name, value)?, name)),
+          "every" => every = Some((parse_duration(value)?, name)),
+          "rate" => rate = Some((rate_interval(&name, value)?, name)),
+          "config" => config = Some((string(&name, &value)?, name)),
+          "name" => config_name = Some((string(&name, &value)?, name)),
+          "rate_from" => rate_from = Some((value, name)),
+          "rate_refresh" => rate_refresh = Some((parse_duration(value)?, name)),
+          "per" => per = Some((period(&name, &value)?, name)),
+          "align" => {
+            align = Some((choice(&name, &value, &["rolling", "calendar"])?, name))
+          }
+          "per_key" => per_key = Some((limit(&name, value)?, name)),
+          "shadow" => shadow = Some((limit(&name, value)?, name)),
+          "key" => key = Some((key_params(&name, &value)?, name)),
+          "group" => group = Some((string(&name, &value)?, name)),
+          "scope" => {
+            if choice(&name, &value, &["global", "per_type"])? == "per_type" {
+              per_type = Some(name);
+            }
+          }
+          "recursion" => {
+            if choice(&name, &value, &["charge", "bypass"])? == "bypass" {
+              recursion = Some(name);
+            }
+          }
+          _ => return Err(unknown_option(&name)),
+        },
+      }
+    }
+
+    // A trailing edge means keeping the latest call pending until the end of the window,
+    // which is what coalescing does.
+    let mut trailing_only = false;
+    if let Some((edge, name)) = edge_option {
+      match (mode, edge) {
+        (Mode::Skip, Edge::Leading) => {}
+        (Mode::Skip | Mode::Coalesce, Edge::Both) => mode = Mode::Coalesce,
+        (Mode::Skip | Mode::Coalesce, Edge::Trailing) => {
+          mode = Mode::Coalesce;
+          trailing_only = true;
+        }
+        (Mode::Coalesce, Edge::Leading) => {
+          return Err(syn::Error::new_spanned(
+            name,
+            "`mode = coalesce` always has a trailing edge, use `edge = both` or \
+             `edge = trailing`",
+          ))
+        }
+        (Mode::Defer, _) => {
+          return Err(syn::Error::new_spanned(
+            name,
+            "option `edge` cannot be used with `mode = defer`",
+          ))
+        }
+      }
+    }
+
+    if mode != Mode::Defer {
+      if let Some(name) = defer_only.first() {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("option `{}` requires `mode = defer`", name),
+        ));
+      }
+    }
+
+    // The function itself is the `try` variant, which is the only one supported by the
+    // other modes.
+    let mut wait_variant = None;
+    let mut async_variant = None;
+    if let Some((name, items)) = variants {
+      let mut has_try = false;
+      for item in items {
+        match item.to_string().as_str() {
+          "try" => has_try = true,
+          "wait" => wait_variant = Some(item),
+          "async" => async_variant = Some(item),
+          _ => {
+            return Err(syn::Error::new_spanned(
+              &item,
+              format!("expecting one of: `try`, `wait`, `async` in `{}(...)`", name),
+            ))
+          }
+        }
+      }
+
+      if !has_try {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("`{}(...)` must include `try`, which is the function itself", name),
+        ));
+      }
+    }
+
+    // Only deferred calls are executed by the worker, which can be waited for.
+    if let (Some(name), Mode::Skip) = (&drain, mode) {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!("option `{}` requires `mode = defer` or `mode = coalesce`", name),
+      ));
+    }
+
+    // Only deferred calls, calls with a deadline and calls to the wait variant wait for a
+    // free slot.
+    if let Some((_, name)) = &wait_strategy {
+      if mode == Mode::Skip && deadline.is_none() && wait_variant.is_none() {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!(
+            "option `{}` requires `mode = defer`, `mode = wait_with_deadline` or \
+             `variants(wait)`",
+            name
+          ),
+        ));
+      }
+    }
+
+    // Calls over the limit are executed all the same in warning and observing modes, so
+    // there is no skipped value to return, nor a concurrency permit to hold for them.
+    // Observing is only useful with statistics to look at.
+    if let Some(name) = &warn {
+      let mode = if observe { "observe" } else { "warn" };
+      let conflict = skipped.as_ref().map(|(_, name)| name);
+      let conflict = conflict.or(detailed.as_ref()).or(result_with_args.as_ref());
+      let conflict = conflict.or(flatten.as_ref()).or(reentrant.as_ref());
+      let conflict = conflict.or(concurrency.as_ref().map(|(_, name)| name));
+      let conflict = conflict.or(active.as_ref().map(|active: &Active| &active.name));
+      if let Some(conflict) = conflict {
+        return Err(syn::Error::new_spanned(
+          conflict,
+          format!("option `{}` cannot be used with `{} = {}`", conflict, name, mode),
+        ));
+      }
+
+      if observe && stats.is_none() {
+        stats = Some(name.clone());
+      }
+    }
+
+    // The module of the limiter is the one exposing it.
+    if let (Some(name), None) = (&emit_module, &expose) {
+      expose = Some(name.clone());
+    }
+
+    // The candidate limit is only compared against the calls charged right away, and is
+    // reported by the statistics.
+    let shadow = match shadow {
+      Some((_, name)) if mode != Mode::Skip => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` requires `mode = skip`", name),
+        ))
+      }
+      Some((limit, name)) => {
+        stats = stats.or(Some(name));
+        Some(limit)
+      }
+      None => None,
+    };
+
+    let on_over_budget = match on_over_budget {
+      Some((_, name)) if warn.is_none() || observe => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` requires `mode = warn`", name),
+        ))
+      }
+      Some((value, _)) => Some(value),
+      None => None,
+    };
+
+    // Calls over budget are reported to nothing but the handler, which warning mode
+    // exists for: recording them silently is what observing mode does.
+    if let (Some(name), false, None) = (&warn, observe, &on_over_budget) {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!(
+          "`{} = warn` requires option `on_over_budget`, use `{} = observe` to only \
+           record calls over budget in the statistics",
+          name, name
+        ),
+      ));
+    }
+
+    // Denying overlapping executions is limiting them to one at a time.
+    if let Some(name) = reentrant {
+      if let Some((_, concurrency)) = &concurrency {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("`{} = deny` cannot be used with option `{}`", name, concurrency),
+        ));
+      }
+
+      concurrency = Some((syn::parse_quote! { 1 }, name));
+    }
+
+    // Reserved slots, requests through a layer and waiting calls are run right away,
+    // without waiting in a queue or for a concurrency permit.
+    let permits_option = permits.iter().chain(&layer);
+    let permits_option = permits_option.map(|name| (name, format!("option `{}`", name)));
+    let variant_options = wait_variant.iter().chain(&async_variant);
+    let variant_options =
+      variant_options.map(|name| (name, format!("`variants({})`", name)));
+    for (name, what) in permits_option.into_iter().chain(variant_options) {
+      if mode != Mode::Skip {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("{} requires `mode = skip`", what),
+        ));
+      }
+
+      if let Some((_, concurrency)) = &concurrency {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("{} cannot be used with option `{}`", what, concurrency),
+        ));
+      }
+    }
+
+    // Only calls waiting for a concurrency permit can wait for one.
+    if let (Some(name), None) = (&on_busy_wait, &concurrency) {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!("`{} = wait` requires option `concurrency`", name),
+      ));
+    }
+
+    // Concurrency permits are only acquired on the immediate execution path.
+    let concurrency = match concurrency {
+      Some((_, name)) if mode != Mode::Skip => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` requires `mode = skip`", name),
+        ))
+      }
+      Some((value, _)) => Some(value),
+      None => None,
+    };
+
+    // Calls that aren't executed immediately are deferred rather than skipped.
+    let skipped = match skipped {
+      Some((_, name)) if mode != Mode::Skip => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` requires `mode = skip`", name),
+        ))
+      }
+      Some((value, _)) => Some(value),
+      None => None,
+    };
+
+    // Deferred calls always return a handle, and a skipped value replaces the Option.
+    let outputs = detailed.iter().map(|name| (name, "detailed"));
+    let outputs =
+      outputs.chain(result_with_args.iter().map(|name| (name, "result_with_args")));
+    for (name, output) in outputs {
+      if mode != Mode::Skip {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = {}` requires `mode = skip`", name, output),
+        ));
+      }
+
+      if skipped.is_some() {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = {}` cannot be used with option `skipped`", name, output),
+        ));
+      }
+    }
+
+    // Flattening is the same as returning `None` as the skipped value.
+    if let Some(name) = &flatten {
+      if mode != Mode::Skip {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = true` requires `mode = skip`", name),
+        ));
+      }
+
+      if skipped.is_some() || detailed.is_some() || result_with_args.is_some() {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = true` cannot be used with option `skipped` or `output`", name),
+        ));
+      }
+    }
+
+    // Deferred calls are queued for a single background thread, whatever the
+    // instantiation of the function they call.
+    if let Some(name) = &per_type {
+      if mode != Mode::Skip {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = per_type` requires `mode = skip`", name),
+        ));
+      }
+    }
+
+    // Only calls that are let through or skipped right away are counted.
+    if let Some(name) = &stats {
+      if mode != Mode::Skip {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = true` requires `mode = skip`", name),
+        ));
+      }
+    }
+
+    // Deferred calls are executed later by a background thread, from a window that has
+    // changed by then.
+    if let Some((_, name)) = &on_execute {
+      if mode != Mode::Skip {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("option `{}` requires `mode = skip`", name),
+        ));
+      }
+    }
+
+    // Deferred calls wait for a slot of the whole window, rather than of a shard.
+    if let Some(name) = &sharded {
+      if mode != Mode::Skip {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = true` requires `mode = skip`", name),
+        ));
+      }
+    }
+
+    // The shards only know about their own calls, and not when the function as a whole
+    // was last called.
+    if let (Some(name), Some((_, reset))) = (&sharded, &reset_after_idle) {
+      return Err(syn::Error::new_spanned(
+        reset,
+        format!("option `{}` cannot be used with option `{}`", reset, name),
+      ));
+    }
+
+    // Deferred calls are executed by a background thread, which cannot poll futures.
+    if let Some(name) = &future {
+      if mode != Mode::Skip {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = poll` requires `mode = skip`", name),
+        ));
+      }
+    }
+
+    // A call with a key must fit in both the limit of its key and the limit of the
+    // function, which the key would not take part in otherwise.
+    let per_key = match (per_key, key) {
+      (Some(((times, duration), name)), Some((key, _))) => {
+        if mode != Mode::Skip {
+          return Err(syn::Error::new_spanned(
+            &name,
+            format!("option `{}` requires `mode = skip`", name),
+          ));
+        }
+
+        if let Some(detailed) = &detailed {
+          return Err(syn::Error::new_spanned(
+            &name,
+            format!("option `{}` cannot be used with `{} = detailed`", name, detailed),
+          ));
+        }
+
+        Some(PerKey {
+          times,
+          duration,
+          key,
+          name,
+          fair: fair.take().is_some(),
+          hasher: hasher.take().map(|(hasher, _)| hasher),
+          normalize_key: normalize_key.take().map(|(normalize_key, _)| normalize_key),
+          max_keys: max_keys.take().map(|(max_keys, _)| max_keys),
+        })
+      }
+      (Some((_, name)), None) => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` requires option `key`", name),
+        ))
+      }
+      (None, Some((_, name))) => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` requires option `per_key`", name),
+        ))
+      }
+      (None, None) => None,
+    };
+
+    // Only keys have a share of the limit of the function, and a map to be looked up in.
+    let key_options = fair.iter().chain(hasher.as_ref().map(|(_, name)| name));
+    let key_options = key_options.chain(normalize_key.as_ref().map(|(_, name)| name));
+    let key_options = key_options.chain(max_keys.as_ref().map(|(_, name)| name));
+    if let Some(name) = key_options.into_iter().next() {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!("option `{}` requires option `per_key`", name),
+      ));
+    }
+
+    // A minimum interval between executions is a limit of a single call per interval, and
+    // so is a rate in executions per second, spacing them evenly.
+    let every = match (every, rate) {
+      (Some((_, every)), Some((_, name))) => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` cannot be used with option `{}`", name, every),
+        ))
+      }
+      (every, rate) => every.or(rate),
+    };
+    let global = match (global, every) {
+      (Some((_, global)), Some((_, name))) => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` cannot be used with option `{}`", name, global),
+        ))
+      }
+      (None, Some((interval, name))) => {
+        let times = syn::parse_quote! { 1 };
+        let duration = parse_window(interval, &times)?;
+        Some(((times, duration), name))
+      }
+      (global, None) => global,
+    };
+
+    // The limit of the function as a whole can be read from a configuration file instead
+    // of being written down in the attribute.
+    let mut config_path = None;
+    let global = match (global, config, config_name) {
+      (Some((_, global)), Some((_, name)), _) => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` cannot be used with option `{}`", name, global),
+        ))
+      }
+      (global, None, None) => global,
+      (None, Some((config, name)), Some((config_name, _))) => {
+        let limit = config::read_limit(&config, &config_name)?;
+        config_path = Some(limit.path);
+
+        let times = parse_times(limit.times)?;
+        let duration = parse_window(limit.duration, &times)?;
+        Some(((times, duration), name))
+      }
+      (_, Some((_, name)), None) => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` requires option `name`", name),
+        ))
+      }
+      (_, None, Some((_, name))) => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` requires option `config`", name),
+        ))
+      }
+    };
+
+    // The limit can also be read from a provider at runtime, which is called through a
+    // function generated in the outer function's body, see [RateFrom].
+    let (global, rate_from) = match (global, rate_from, rate_refresh) {
+      (Some((_, other)), Some((_, name)), _) => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` cannot be used with option `{}`", name, other),
+        ))
+      }
+      (global, None, None) => (global, None),
+      (None, Some((provider, name)), refresh) => {
+        let refresh = match refresh {
+          Some((refresh, _)) => refresh,
+          None => syn::parse_quote! { ::std::time::Duration::from_secs(1) },
+        };
+
+        let times = syn::parse_quote! { __throttle_rate().0 };
+        let duration = syn::parse_quote! { __throttle_rate().1 };
+        let rate_from = RateFrom { provider, refresh, name: name.clone() };
+        (Some(((times, duration), name)), Some(rate_from))
+      }
+      (_, None, Some((_, name))) => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` requires option `rate_from`", name),
+        ))
+      }
+    };
+
+    // The limit of the function as a whole is either given positionally, or as
+    // `global = (...)` alongside a limit per key, or read from a configuration file or a
+    // provider. With `per = "..."`, only the number of calls is given positionally.
+    let (times, duration) = match (global, &per) {
+      (Some((_, name)), Some((_, per))) => {
+        return Err(syn::Error::new_spanned(
+          per,
+          format!("option `{}` cannot be used with option `{}`", per, name),
+        ))
+      }
+      (Some(((times, duration), name)), None) => {
+        if let Some(extra) = positional.first() {
+          return Err(unexpected_positional(
+            extra,
+            format!("unexpected positional argument alongside option `{}`", name),
+          ));
+        }
+        (times, duration)
+      }
+      (None, Some((secs, name))) => {
+        let mut positional = positional.into_iter();
+
+        let times = match positional.next() {
+          Some(times) => times,
+          None => return Err(missing_argument("number of calls")),
+        };
+        if let Some(extra) = positional.next() {
+          return Err(unexpected_positional(
+            extra,
+            format!("unexpected positional argument alongside option `{}`", name),
+          ));
+        }
+
+        let times = parse_times(times)?;
+        let duration = syn::parse_quote! { ::std::time::Duration::from_secs(#secs) };
+        (times, duration)
+      }
+      (None, None) => {
+        let mut positional = positional.into_iter();
+
+        let (times, duration) = match (positional.next(), positional.next()) {
+          (Some(times), Some(duration)) => (times, duration),
+          (Some(_), None) => return Err(missing_argument("duration")),
+          (None, _) => return Err(missing_arguments()),
+        };
+        reject_extra(positional)?;
+
+        let times = parse_times(times)?;
+        let duration = parse_window(duration, &times)?;
+        (times, duration)
+      }
+    };
+
+    // Calendar periods start at fixed times, so only the named periods can be aligned to
+    // them.
+    let calendar = match (align, per) {
+      (Some(("calendar", name)), None) => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("`{} = calendar` requires option `per`", name),
+        ))
+      }
+      (Some(("calendar", name)), Some((secs, _))) => Some((secs, name)),
+      _ => None,
+    };
+
+    // Calls charged when they finish move their charge in the calls deque, which calls
+    // charged at the start of their period or in shards don't have, and which deferred
+    // calls are charged against by a background thread.
+    if let Some(name) = &charge_at_end {
+      if mode != Mode::Skip {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = end` requires `mode = skip`", name),
+        ));
+      }
+
+      let conflict = sharded
+        .as_ref()
+        .or(per_type.as_ref())
+        .or(calendar.as_ref().map(|(_, align)| align));
+      if let Some(conflict) = conflict {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = end` cannot be used with option `{}`", name, conflict),
+        ));
+      }
+    }
+
+    // Refunded calls are taken out of the calls deque, but not out of the deques of their
+    // keys, and would be moved there once finished anyway with `charge = end`.
+    if let Some(name) = &refund_on_cancel {
+      if mode != Mode::Skip {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = on_cancel` requires `mode = skip`", name),
+        ));
+      }
+
+      let conflict = sharded
+        .as_ref()
+        .or(per_type.as_ref())
+        .or(per_key.as_ref().map(|per_key| &per_key.name))
+        .or(charge_at_end.as_ref());
+      if let Some(conflict) = conflict {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = on_cancel` cannot be used with option `{}`", name, conflict),
+        ));
+      }
+    }
+
+    // The policy only decides about the calls charged right away, which nothing else
+    // looks into the calls deque for with the limit in mind.
+    if let Some((_, name)) = &policy {
+      if mode != Mode::Skip || deadline.is_some() {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("option `{}` requires `mode = skip`", name),
+        ));
+      }
+
+      let conflict = sharded
+        .as_ref()
+        .or(resolution.as_ref().map(|(_, name)| name))
+        .or(detailed.as_ref())
+        .or(queries.as_ref())
+        .or(permits.as_ref())
+        .or(layer.as_ref())
+        .or(wait_variant.as_ref())
+        .or(async_variant.as_ref())
+        .or(expose.as_ref());
+      if let Some(conflict) = conflict {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("option `{}` cannot be used with option `{}`", name, conflict),
+        ));
+      }
+    }
+
+    // The classes only share the calls charged right away, in skipping mode or with a
+    // deadline, which nothing else looks into the calls deque for with the limit in mind.
+    let priorities = match (priorities, priority) {
+      (Some((value, name)), Some((priority, _))) => {
+        Some(Priorities::parse(name, &value, priority)?)
+      }
+      (Some((_, name)), None) => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` requires option `priority`", name),
+        ))
+      }
+      (None, Some((_, name))) => {
+        return Err(syn::Error::new_spanned(
+          &name,
+          format!("option `{}` requires option `priorities`", name),
+        ))
+      }
+      (None, None) => None,
+    };
+
+    if let Some(Priorities { name, .. }) = &priorities {
+      if mode != Mode::Skip {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("option `{}` requires `mode = skip`", name),
+        ));
+      }
+
+      let conflict = sharded
+        .as_ref()
+        .or(resolution.as_ref().map(|(_, name)| name))
+        .or(policy.as_ref().map(|(_, name)| name))
+        .or(per_key.as_ref().map(|per_key| &per_key.name))
+        .or(queries.as_ref())
+        .or(permits.as_ref())
+        .or(layer.as_ref())
+        .or(wait_variant.as_ref())
+        .or(async_variant.as_ref())
+        .or(expose.as_ref())
+        .or(charge_at_end.as_ref())
+        .or(refund_on_cancel.as_ref());
+      if let Some(conflict) = conflict {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("option `{}` cannot be used with option `{}`", name, conflict),
+        ));
+      }
+    }
+
+    // The cap is only checked against the calls charged right away into the calls deque.
+    if let Some((_, name)) = &cold_start_cap {
+      if mode != Mode::Skip || deadline.is_some() {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("option `{}` requires `mode = skip`", name),
+        ));
+      }
+
+      let conflict = sharded
+        .as_ref()
+        .or(resolution.as_ref().map(|(_, name)| name))
+        .or(policy.as_ref().map(|(_, name)| name));
+      if let Some(conflict) = conflict {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("option `{}` cannot be used with option `{}`", name, conflict),
+        ));
+      }
+    }
+
+    // Calls taking more than a slot are only skipped, and only charged right away into
+    // the calls deque. The variants don't take the parameter.
+    if let Some(name) = &with_override {
+      if mode != Mode::Skip || deadline.is_some() {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("option `{}` requires `mode = skip`", name),
+        ));
+      }
+
+      let conflict = sharded
+        .as_ref()
+        .or(resolution.as_ref().map(|(_, name)| name))
+        .or(policy.as_ref().map(|(_, name)| name))
+        .or(priorities.as_ref().map(|priorities| &priorities.name))
+        .or(charge_at_end.as_ref())
+        .or(refund_on_cancel.as_ref())
+        .or(wait_variant.as_ref())
+        .or(async_variant.as_ref());
+      if let Some(conflict) = conflict {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("option `{}` cannot be used with option `{}`", name, conflict),
+        ));
+      }
+    }
+
+    Ok(Args {
+      times,
+      duration,
+      config_path,
+      rate_from,
+      calendar,
+      mode,
+      deadline,
+      shadow,
+      warn,
+      observe,
+      on_over_budget,
+      active,
+      per_key,
+      queue,
+      overflow,
+      wait: wait_strategy.map_or(Wait::Sleep, |(wait, _)| wait),
+      concurrency,
+      on_busy_wait,
+      skipped,
+      detailed: detailed.is_some(),
+      result_with_args: result_with_args.is_some(),
+      flatten: flatten.is_some(),
+      queries,
+      permits,
+      layer,
+      stats,
+      on_execute,
+      sharded,
+      expose_inner,
+      consts,
+      wait_variant,
+      async_variant,
+      expose,
+      emit_module: emit_module.is_some(),
+      toggle,
+      drain,
+      with_override,
+      cold_start_cap,
+      reset_after_idle,
+      charge_at_end,
+      refund_on_cancel,
+      policy,
+      priorities,
+      resolution,
+      share,
+      poll_future: future.is_some(),
+      per_type,
+      group,
+      bypass_recursion: recursion,
+      trailing_only,
+    })
+  }
+}
+
+/// The parsed arguments of the `#[debounce(...)]` attribute.
+pub(crate) struct DebounceArgs {
+  /// The [std::time::Duration] of quiet, without any further calls, after which the
+  /// function executes.
+  pub(crate) duration: Expr,
+
+  /// See [Edge], defaults to [Edge::Trailing].
+  pub(crate) edge: Edge,
+}
+
+impl Parse for DebounceArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let args = parse_args(input)?;
+
+    let mut positional = Vec::new();
+    let mut edge_option = Edge::Trailing;
+
+    for arg in args {
+      match arg {
+        Arg::Positional(expr) => positional.push(expr),
+        Arg::List(name, _) => return Err(unknown_option(&name)),
+        Arg::Named(name, value) => match name.to_string().as_str() {
+          "edge" => edge_option = edge(&name, &value)?,
+          _ => return Err(unknown_option(&name)),
+        },
+      }
+    }
+
+    let mut positional = positional.into_iter();
+
+    let duration = match positional.next() {
+      Some(duration) => parse_duration(duration)?,
+      None => return Err(missing_arguments()),
+    };
+    reject_extra(positional)?;
+
+    Ok(DebounceArgs { duration, edge: edge_option })
+  }
+}
+
+/// The parsed arguments of the `#[throttle_group(...)]` attribute on a struct.
+pub(crate) struct GroupArgs {
+  /// Number of calls to the methods of an instance allowed over `duration`.
+  pub(crate) times: Expr,
+
+  /// The [std::time::Duration] over which `times` calls are allowed.
+  pub(crate) duration: Expr,
+}
+
+impl Parse for GroupArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let args = parse_args(input)?;
+
+    let mut positional = Vec::new();
+    for arg in args {
+      match arg {
+        Arg::Positional(expr) => positional.push(expr),
+        Arg::Named(name, _) | Arg::List(name, _) => return Err(unknown_option(&name)),
+      }
+    }
+
+    let mut positional = positional.into_iter();
+    let (times, duration) = match (positional.next(), positional.next()) {
+      (Some(times), Some(duration)) => (parse_times(times)?, parse_duration(duration)?),
+      _ => return Err(missing_arguments()),
+    };
+    reject_extra(positional)?;
+
+    Ok(GroupArgs { times, duration })
+  }
+}
+
+/// The parsed arguments of the attributes taking a single positional argument along
+/// with the `skipped` option: `#[cooldown(...)]`, `#[sample(...)]`, `#[once(...)]` and
+/// `#[max_concurrent(...)]`.
+pub(crate) struct SkippableArgs {
+  /// The positional argument, if provided.
+  pub(crate) value: Option<Expr>,
+
+  /// The value to return when a call is skipped, see [Args::skipped].
+  pub(crate) skipped: Option<Expr>,
+}
+
+impl Parse for SkippableArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let args = parse_args(input)?;
+
+    let mut value = None;
+    let mut skipped = None;
+
+    for arg in args {
+      match arg {
+        Arg::Positional(expr) if value.is_none() => value = Some(expr),
+        Arg::Positional(expr) => {
+          return Err(unexpected_positional(expr, "unexpected positional argument"))
+        }
+        Arg::List(name, _) => return Err(unknown_option(&name)),
+        Arg::Named(name, value) => match name.to_string().as_str() {
+          "skipped" => skipped = Some(value),
+          _ => return Err(unknown_option(&name)),
+        },
+      }
+    }
+
+    Ok(SkippableArgs { value, skipped })
+  }
+}
+
+impl SkippableArgs {
+  /// The positional argument, or an error if it was not provided.
+  pub(crate) fn value(&self) -> syn::Result<Expr> {
+    match &self.value {
+      Some(value) => Ok(value.clone()),
+      None => Err(missing_arguments()),
+    }
+  }
+}
+
+/// The parsed arguments of the `#[circuit_breaker(...)]` attribute.
+pub(crate) struct CircuitBreakerArgs {
+  /// Number of consecutive failures after which the circuit opens.
+  pub(crate) failures: Expr,
+
+  /// The [std::time::Duration] after which an open circuit lets a probing call through.
+  pub(crate) reset: Expr,
+
+  /// The value to return when a call is skipped, see [Args::skipped].
+  pub(crate) skipped: Option<Expr>,
+}
+
+impl Parse for CircuitBreakerArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let args = parse_args(input)?;
+
+    let mut failures = None;
+    let mut reset = None;
+    let mut skipped = None;
+
+    for arg in args {
+      match arg {
+        Arg::Positional(expr) => {
+          return Err(unexpected_positional(expr, "unexpected positional argument"))
+        }
+        Arg::List(name, _) => return Err(unknown_option(&name)),
+        Arg::Named(name, value) => match name.to_string().as_str() {
+          "failures" => failures = Some(value),
+          "reset" => reset = Some(parse_duration(value)?),
+          "skipped" => skipped = Some(value),
+          _ => return Err(unknown_option(&name)),
+        },
+      }
+    }
+
+    match (failures, reset) {
+      (Some(failures), Some(reset)) => {
+        Ok(CircuitBreakerArgs { failures, reset, skipped })
+      }
+      _ => Err(missing_arguments()),
+    }
+  }
+}
+
+/// The parsed arguments of the `#[retry(...)]` attribute.
+pub(crate) struct RetryArgs {
+  /// Maximum number of retries after the first failed call.
+  pub(crate) retries: Expr,
+
+  /// The [std::time::Duration] to wait before the first retry, doubling for every later
+  /// retry. No waiting if not provided.
+  pub(crate) backoff: Option<Expr>,
+}
+
+impl Parse for RetryArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let args = parse_args(input)?;
+
+    let mut positional = Vec::new();
+    let mut backoff = None;
+
+    for arg in args {
+      match arg {
+        Arg::Positional(expr) => positional.push(expr),
+        Arg::List(name, _) => return Err(unknown_option(&name)),
+        Arg::Named(name, value) => match name.to_string().as_str() {
+          "backoff" => backoff = Some(parse_duration(value)?),
+          _ => return Err(unknown_option(&name)),
+        },
+      }
+    }
+
+    let mut positional = positional.into_iter();
+
+    let retries = match positional.next() {
+      Some(retries) => retries,
+      None => return Err(missing_arguments()),
+    };
+    reject_extra(positional)?;
+
+    Ok(RetryArgs { retries, backoff })
+  }
+}
+
+/// The parsed arguments of the `#[memoize(...)]` attribute.
+pub(crate) struct MemoizeArgs {
+  /// The [std::time::Duration] for which a returned value is reused.
+  pub(crate) ttl: Expr,
+
+  /// Maximum number of distinct sets of arguments whose values are cached at the same
+  /// time, unbounded if not provided.
+  pub(crate) max_keys: Option<Expr>,
+}
+
+impl Parse for MemoizeArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let args = parse_args(input)?;
+
+    let mut ttl = None;
+    let mut max_keys = None;
+
+    for arg in args {
+      match arg {
+        Arg::Positional(expr) => {
+          return Err(unexpected_positional(expr, "unexpected positional argument"))
+        }
+        Arg::List(name, _) => return Err(unknown_option(&name)),
+        Arg::Named(name, value) => match name.to_string().as_str() {
+          "ttl" => ttl = Some(parse_duration(value)?),
+          "max_keys" => max_keys = Some(value),
+          _ => return Err(unknown_option(&name)),
+        },
+      }
+    }
+
+    match ttl {
+      Some(ttl) => Ok(MemoizeArgs { ttl, max_keys }),
+      None => Err(missing_arguments()),
+    }
+  }
+}
+
+/// The parsed arguments of the `#[throttled(...)]` attribute on the fields of a
+/// `#[derive(Throttled)]` struct.
+pub(crate) struct ThrottledArgs {
+  /// Number of calls the field lets through over `duration`.
+  pub(crate) times: Expr,
+
+  /// The [std::time::Duration] over which the field lets `times` calls through.
+  pub(crate) duration: Expr,
+}
+
+impl Parse for ThrottledArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let args = parse_args(input)?;
+
+    let mut positional = Vec::new();
+
+    for arg in args {
+      match arg {
+        Arg::Positional(expr) => positional.push(expr),
+        Arg::Named(name, _) | Arg::List(name, _) => return Err(unknown_option(&name)),
+      }
+    }
+
+    let mut positional = positional.into_iter();
+
+    let (times, duration) = match (positional.next(), positional.next()) {
+      (Some(times), Some(duration)) => (times, duration),
+      (Some(_), None) => return Err(missing_argument("duration")),
+      (None, _) => return Err(missing_arguments()),
+    };
+    reject_extra(positional)?;
+
+    let times = parse_times(times)?;
+    let duration = parse_window(duration, &times)?;
+    Ok(ThrottledArgs { times, duration })
+  }
+}
+
+/// The parsed arguments of the `throttle_block!(...)` and `throttle_fn!(...)` macros: the
+/// same arguments as the `#[throttle(...)]` attribute, followed by the throttled block or
+/// closure.
+pub(crate) struct InlineArgs {
+  /// The arguments of the throttle, see [Args].
+  pub(crate) args: Args,
+
+  /// The throttled block (or any other expression), or closure.
+  pub(crate) body: Expr,
+}
+
+impl Parse for InlineArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let mut args = parse_args(input)?.into_iter();
+
+    let body = match args.next_back() {
+      Some(Arg::Positional(body)) => body,
+      Some(Arg::Named(name, _) | Arg::List(name, _)) => {
+        return Err(unexpected_positional(
+          name,
+          "expecting a block or closure as the last argument",
+        ))
+      }
+      None => return Err(missing_arguments()),
+    };
+
+    let args = Args::from_args(args)?;
+
+    let rate_from = args.rate_from.as_ref().map(|rate_from| &rate_from.name);
+    let on_execute = args.on_execute.as_ref().map(|(_, name)| name);
+    let active = args.active.as_ref().map(|active| &active.name);
+    let calendar = args.calendar.as_ref().map(|(_, name)| name);
+    let sharded = args.sharded.as_ref();
+    let toggle = args.toggle.as_ref();
+    let reset_after_idle = args.reset_after_idle.as_ref().map(|(_, name)| name);
+    let unsupported = rate_from
+      .or(on_execute)
+      .or(active)
+      .or(calendar)
+      .or(sharded)
+      .or(toggle)
+      .or(args.drain.as_ref())
+      .or(args.with_override.as_ref())
+      .or(args.cold_start_cap.as_ref().map(|(_, name)| name))
+      .or(reset_after_idle)
+      .or(args.charge_at_end.as_ref())
+      .or(args.refund_on_cancel.as_ref())
+      .or(args.policy.as_ref().map(|(_, name)| name))
+      .or(args.priorities.as_ref().map(|priorities| &priorities.name))
+      .or(args.resolution.as_ref().map(|(_, name)| name))
+      .or(args.group.as_ref().map(|(_, name)| name))
+      .or(args.on_busy_wait.as_ref());
+    if let Some(name) = unsupported {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!("option `{}` is not supported by inline throttles", name),
+      ));
+    }
+
+    if args.mode != Mode::Skip || args.deadline.is_some() || args.warn.is_some() {
+      return Err(syn::Error::new(
+        Span::call_site(),
+        "inline throttles require `mode = skip`",
+      ));
+    }
+
+    Ok(InlineArgs { args, body })
+  }
+}
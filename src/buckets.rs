@@ -0,0 +1,36 @@
+//! Generation of the bucketed book-keeping of throttles with a `resolution`, see the
+//! `resolution` option of [crate::throttle].
+//!
+//! The window is a `throttle_my_fn_runtime::BucketWindow`, which counts the calls in
+//! slots of `resolution` each rather than keeping the time of every call in the window.
+//! The memory taken by the window is then proportional to `duration / resolution`
+//! instead of to `times`, and calls stay in the window for up to `resolution` longer
+//! than they would otherwise.
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::Expr;
+
+/// Generate the `__THROTTLE_BUCKETS` static.
+pub(crate) fn buckets_item() -> TokenStream2 {
+  quote_mixed! {
+    static __THROTTLE_BUCKETS: ::std::sync::LazyLock<
+      ::throttle_my_fn_runtime::BucketWindow,
+    > = ::std::sync::LazyLock::new(::throttle_my_fn_runtime::BucketWindow::new);
+  }
+}
+
+/// Generate the book-keeping charging a call made at `current_time` to a slot of
+/// [buckets_item], or returning `skipped` if the quota is full over `duration`.
+pub(crate) fn charge(
+  times: &Expr,
+  duration: &Expr,
+  resolution: &Expr,
+  skipped: &TokenStream2,
+) -> TokenStream2 {
+  quote_mixed! {
+    // Return None (or the skipped value) if our quota is full for the duration.
+    if !__THROTTLE_BUCKETS.try_charge(current_time, #times, #duration, #resolution) {
+      return #skipped;
+    }
+  }
+}
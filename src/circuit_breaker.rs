@@ -0,0 +1,115 @@
+//! Expansion of the `#[circuit_breaker(...)]` attribute.
+
+use crate::args::{with_usage, CircuitBreakerArgs};
+use crate::clock;
+use crate::split::Split;
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, ItemFn};
+
+/// See [crate::circuit_breaker].
+pub(crate) fn circuit_breaker(args: TokenStream, func: TokenStream) -> TokenStream {
+  // The circuit is a small state machine kept in a static alongside the function. While
+  // closed, calls go through and consecutive errors are counted. Once there are too many
+  // of them, the circuit opens and calls are skipped until the reset period elapses. Then
+  // a single probing call is let through (half-open): if it succeeds the circuit closes
+  // again, otherwise it re-opens for another reset period.
+
+  const ARGS_ERR_MSG: &str = "expecting failures and reset options: \
+                              #[circuit_breaker(failures = <n>, reset = <duration>)]";
+
+  let args_parsed =
+    match with_usage(ARGS_ERR_MSG, || syn::parse::<CircuitBreakerArgs>(args.clone())) {
+      Ok(args) => args,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let failures = &args_parsed.failures;
+  let reset = &args_parsed.reset;
+
+  let func_parsed = parse_macro_input!(func as ItemFn);
+  let mut split = match Split::new(func_parsed) {
+    Ok(split) => split,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  let skipped = args_parsed.skipped.as_ref();
+  let (skipped, wrap) =
+    match split.set_skippable_output(skipped, "`#[circuit_breaker(...)]`") {
+      Ok(skippable) => skippable,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let Split { attrs, vis, impl_fn, outer_sig, call, .. } = &split;
+
+  let gen = quote_mixed! {
+    #(#attrs)* #vis #outer_sig {
+      #impl_fn
+
+      enum __ThrottleCircuit {
+        Closed { consecutive_failures: usize },
+        Open { since: ::throttle_my_fn_runtime::Instant },
+        HalfOpen,
+      }
+
+      static __THROTTLE_CIRCUIT: ::throttle_my_fn_runtime::parking_lot::Mutex<
+        __ThrottleCircuit,
+      > = ::throttle_my_fn_runtime::parking_lot::const_mutex(
+        __ThrottleCircuit::Closed { consecutive_failures: 0 },
+      );
+
+      // Re-opens the circuit if the probing call panics, so that the circuit doesn't get
+      // stuck in the half-open state.
+      struct __ThrottleProbe;
+
+      impl ::std::ops::Drop for __ThrottleProbe {
+        fn drop(&mut self) {
+          if ::std::thread::panicking() {
+            let since = ::throttle_my_fn_runtime::system_now();
+            *__THROTTLE_CIRCUIT.lock() = __ThrottleCircuit::Open { since };
+          }
+        }
+      }
+
+      let current_time = ::throttle_my_fn_runtime::system_now();
+
+      // The lock is scoped so that other threads can call us even while the inner impl
+      // function is running (and so that the futures of async functions don't hold it).
+      let probe = {
+        let mut circuit = __THROTTLE_CIRCUIT.lock();
+        match *circuit {
+          __ThrottleCircuit::Closed { .. } => None,
+          __ThrottleCircuit::Open { since }
+            if current_time.duration_since(since) >= #reset =>
+          {
+            *circuit = __ThrottleCircuit::HalfOpen;
+            Some(__ThrottleProbe)
+          }
+          // Return None (or the skipped value) if the circuit is open, or if a probing
+          // call is already in flight.
+          __ThrottleCircuit::Open { .. } | __ThrottleCircuit::HalfOpen => return #skipped,
+        }
+      };
+
+      let result = #call;
+
+      let mut circuit = __THROTTLE_CIRCUIT.lock();
+      if Result::is_ok(&result) {
+        *circuit = __ThrottleCircuit::Closed { consecutive_failures: 0 };
+      } else if probe.is_some() {
+        let since = ::throttle_my_fn_runtime::system_now();
+        *circuit = __ThrottleCircuit::Open { since };
+      } else if let __ThrottleCircuit::Closed { consecutive_failures } = &mut *circuit {
+        *consecutive_failures += 1;
+        if *consecutive_failures >= #failures {
+          let since = ::throttle_my_fn_runtime::system_now();
+          *circuit = __ThrottleCircuit::Open { since };
+        }
+      }
+      drop(circuit);
+
+      #wrap(result)
+    }
+  };
+
+  TokenStream::from(clock::virtualize(gen))
+}
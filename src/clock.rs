@@ -0,0 +1,46 @@
+//! Reading of the time by the generated code, from the virtual clock of
+//! `throttle_my_fn_runtime::testing` with the `testing` feature.
+
+use proc_macro2::{Group, TokenStream as TokenStream2, TokenTree};
+
+/// The path the generated code reads the time from, which is hidden from the docs of the
+/// runtime crate so that only the generated code calls it.
+const SYSTEM_NOW: [&str; 5] = [":", ":", "throttle_my_fn_runtime", ":", ":"];
+
+/// Make the generated `tokens` read the time from the virtual clock rather than from the
+/// monotonic clock of the system, with the `testing` feature: every path to
+/// `::throttle_my_fn_runtime::system_now` is replaced by
+/// `::throttle_my_fn_runtime::testing::now`. The bodies of the throttled functions keep
+/// reading the clock they read, `Instant::now()` included.
+pub(crate) fn virtualize(tokens: TokenStream2) -> TokenStream2 {
+  if !cfg!(feature = "testing") {
+    return tokens;
+  }
+
+  let mut trees: Vec<TokenTree> = tokens.into_iter().collect();
+  let mut index = 0;
+  while index < trees.len() {
+    let end = index + SYSTEM_NOW.len();
+    let is_now = trees.get(end).is_some_and(|tree| tree.to_string() == "system_now")
+      && trees[index..end]
+        .iter()
+        .zip(SYSTEM_NOW)
+        .all(|(tree, text)| tree.to_string() == text);
+
+    if is_now {
+      let span = trees[end].span();
+      let now = quote::quote_spanned! { span=> ::throttle_my_fn_runtime::testing::now };
+      let _ = trees.splice(index..=end, now);
+      continue;
+    }
+
+    if let TokenTree::Group(group) = &trees[index] {
+      let mut virtualized = Group::new(group.delimiter(), virtualize(group.stream()));
+      virtualized.set_span(group.span());
+      trees[index] = TokenTree::Group(virtualized);
+    }
+    index += 1;
+  }
+
+  trees.into_iter().collect()
+}
@@ -0,0 +1,177 @@
+//! Uses of `#[throttle(...)]` that must not compile, checked as `compile_fail` doctests.
+//! Each one is given along with the error it fails with, which the doctests cannot check
+//! but the unit tests in `tests.rs` do, and the UI tests in `tests/ui` from the outside.
+//!
+//! A limit without a duration (`missing the duration argument`):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2)]
+//! fn ping() {}
+//! ```
+//!
+//! An unknown option (``unknown option `bogus` ``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1s", bogus = true)]
+//! fn ping() {}
+//! ```
+//!
+//! A duration in an unknown unit (`expecting a duration like "500ms"`):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1 fortnight")]
+//! fn ping() {}
+//! ```
+//!
+//! A limit of no calls at all (`a limit of 0 calls makes the function unreachable`):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(0, "1s")]
+//! fn ping() {}
+//! ```
+//!
+//! An unknown mode (``expecting one of: `mode = skip`, ...``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1s", mode = sometimes)]
+//! fn ping() {}
+//! ```
+//!
+//! A limit per key without a key (``option `per_key` requires option `key` ``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(global = (2, "1s"), per_key = (1, "1s"))]
+//! fn fetch(host: u32) {}
+//! ```
+//!
+//! A key that isn't an argument (`` `tenant` is not an argument of the function ``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(global = (2, "1s"), per_key = (1, "1s"), key = tenant)]
+//! fn fetch(host: u32) {}
+//! ```
+//!
+//! A bounded queue without deferred calls (``option `queue` requires `mode = defer` ``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1s", queue = 4)]
+//! fn ping() {}
+//! ```
+//!
+//! Statistics for a method (``option `stats` is not supported for methods``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! struct Client;
+//!
+//! impl Client {
+//!   #[throttle(2, "1s", stats = true)]
+//!   fn ping(&self) {}
+//! }
+//! ```
+//!
+//! Blocking on a deadline in an async function
+//! (`` `mode = wait_with_deadline` is not supported for async functions``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1s", mode = wait_with_deadline)]
+//! async fn ping() {}
+//! ```
+//!
+//! Draining calls that are never deferred
+//! (``option `drain` requires `mode = defer` or `mode = coalesce` ``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1s", drain = true)]
+//! fn ping() {}
+//! ```
+//!
+//! A skipped value for calls that are never skipped
+//! (``option `skipped` cannot be used with `mode = warn` ``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1s", mode = warn, skipped = 0)]
+//! fn ping() -> u8 {
+//!   1
+//! }
+//! ```
+//!
+//! Calls over budget reported to nobody
+//! (``` `mode = warn` requires option `on_over_budget` ```):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1s", mode = warn)]
+//! fn ping() {}
+//! ```
+//!
+//! A single priority class (`expecting weighted classes`):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1s", priorities = (high = 70), priority = class)]
+//! fn fetch(class: &str) {}
+//! ```
+//!
+//! Refunds for calls that cannot be cancelled
+//! (`` `refund = on_cancel` requires an async function or `future = poll` ``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1s", refund = on_cancel)]
+//! fn ping() {}
+//! ```
+//!
+//! Overrides of deferred calls (``option `with_override` requires `mode = skip` ``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1s", with_override = true, mode = defer)]
+//! fn ping() {}
+//! ```
+//!
+//! Conflicting options
+//! (``option `cold_start_cap` cannot be used with option `sharded` ``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1s", cold_start_cap = 1, sharded = true)]
+//! fn ping() {}
+//! ```
+//!
+//! Anything but a function (``expected `fn` ``):
+//!
+//! ```compile_fail
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(2, "1s")]
+//! struct Client;
+//! ```
@@ -0,0 +1,202 @@
+//! Reading of limits from a configuration file at expansion time, see the `config`
+//! option of [crate::throttle].
+//!
+//! Only the subset of TOML needed to write down limits is supported: `[table]` headers
+//! (with dotted names), `key = value` pairs of integers or strings without escapes, and
+//! comments.
+
+use std::path::PathBuf;
+use syn::{Expr, ExprLit, Lit, LitInt, LitStr};
+
+/// A limit read from a configuration file.
+pub(crate) struct ConfigLimit {
+  /// The `times` of the limit, as an integer literal.
+  pub(crate) times: Expr,
+
+  /// The `duration` of the limit, as a string literal such as `"1s"`.
+  pub(crate) duration: Expr,
+
+  /// The absolute path of the configuration file, for the generated code to depend on
+  /// it.
+  pub(crate) path: String,
+}
+
+/// A value in a configuration file.
+enum Value {
+  /// The digits of an integer, without underscores.
+  Int(String),
+
+  /// The contents of a string.
+  Str(String),
+}
+
+/// Strip the comment from a `line`, if any. Strings cannot contain `#` then, which
+/// durations don't.
+fn strip_comment(line: &str) -> &str {
+  match line.find('#') {
+    Some(start) => &line[..start],
+    None => line,
+  }
+}
+
+/// Parse the name in a `[table]` header, e.g. `github.fetch` in `[github.fetch]` or in
+/// `[github."fetch"]`.
+fn table_name(header: &str) -> Option<String> {
+  let parts = header.strip_prefix('[')?.strip_suffix(']')?.split('.');
+  let parts = parts.map(|part| {
+    let part = part.trim();
+    let part = match part.strip_prefix('"').and_then(|part| part.strip_suffix('"')) {
+      Some(quoted) => quoted,
+      None => part,
+    };
+
+    match part.is_empty() {
+      true => None,
+      false => Some(part),
+    }
+  });
+
+  Some(parts.collect::<Option<Vec<_>>>()?.join("."))
+}
+
+/// Parse a `key = value` pair.
+fn pair(line: &str) -> Option<(&str, Value)> {
+  let (key, value) = line.split_once('=')?;
+  let key = key.trim();
+  let value = value.trim();
+
+  let bare = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+  if key.is_empty() || !key.chars().all(bare) {
+    return None;
+  }
+
+  let digit = |c: char| c.is_ascii_digit() || c == '_';
+  let value = match value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) {
+    Some(value) if !value.contains(['"', '\\']) => Value::Str(String::from(value)),
+    Some(_) => return None,
+    None if !value.is_empty() && value.chars().all(digit) => {
+      Value::Int(value.replace('_', ""))
+    }
+    None => return None,
+  };
+
+  Some((key, value))
+}
+
+/// The `key = value` pairs of the table named `table` in `source`, or `None` if there is
+/// no such table.
+///
+/// # Returns
+///
+/// The pairs, or the number of the first line that couldn't be parsed.
+fn table(source: &str, table: &str) -> Result<Option<Vec<(String, Value)>>, usize> {
+  let mut pairs = None;
+  let mut in_table = false;
+
+  for (number, line) in source.lines().enumerate() {
+    let line = strip_comment(line).trim();
+    if line.is_empty() {
+      continue;
+    }
+
+    if line.starts_with('[') {
+      in_table = table_name(line).ok_or(number + 1)? == table;
+      if in_table {
+        pairs = Some(Vec::new());
+      }
+      continue;
+    }
+
+    let (key, value) = pair(line).ok_or(number + 1)?;
+    if let (true, Some(pairs)) = (in_table, pairs.as_mut()) {
+      pairs.push((String::from(key), value));
+    }
+  }
+
+  Ok(pairs)
+}
+
+/// Read the limit named `name` from the configuration file at `config`, relative to the
+/// directory of the manifest of the crate being compiled.
+///
+/// The limit is a table with a `times` integer and a `duration` string:
+///
+/// ```toml
+/// [github.fetch]
+/// times = 5000
+/// duration = "1h"
+/// ```
+pub(crate) fn read_limit(config: &LitStr, name: &LitStr) -> syn::Result<ConfigLimit> {
+  let dir = std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from);
+  let path = dir.unwrap_or_default().join(config.value());
+  let file = config.value();
+
+  let source = std::fs::read_to_string(&path).map_err(|e| {
+    syn::Error::new_spanned(config, format!("cannot read `{}`: {}", path.display(), e))
+  })?;
+
+  let pairs = match table(&source, &name.value()) {
+    Ok(Some(pairs)) => pairs,
+    Ok(None) => {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!("no `[{}]` table in `{}`", name.value(), file),
+      ))
+    }
+    Err(line) => {
+      return Err(syn::Error::new_spanned(
+        config,
+        format!(
+          "unsupported syntax in `{}` at line {}, expecting `[table]` headers and \
+           `key = value` pairs of integers or strings",
+          file, line
+        ),
+      ))
+    }
+  };
+
+  // The literals point at the name of the limit, for errors about their values.
+  let mut times = None;
+  let mut duration = None;
+  for (key, value) in pairs {
+    match (key.as_str(), value) {
+      ("times", Value::Int(digits)) => {
+        times = Some(Lit::Int(LitInt::new(&digits, name.span())));
+      }
+      ("duration", Value::Str(text)) => {
+        duration = Some(Lit::Str(LitStr::new(&text, name.span())));
+      }
+      (key @ ("times" | "duration"), _) => {
+        let expecting = if key == "times" { "an integer" } else { "a string" };
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("expecting {} for `{}` in `[{}]`", expecting, key, name.value()),
+        ));
+      }
+      (key, _) => {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!(
+            "unknown key `{}` in `[{}]`, expecting `times` and `duration`",
+            key,
+            name.value()
+          ),
+        ))
+      }
+    }
+  }
+
+  let missing = |key: &str| {
+    syn::Error::new_spanned(
+      name,
+      format!("missing `{}` in `[{}]` of `{}`", key, name.value(), file),
+    )
+  };
+  let lit = |lit| Expr::Lit(ExprLit { attrs: Vec::new(), lit });
+
+  Ok(ConfigLimit {
+    times: lit(times.ok_or_else(|| missing("times"))?),
+    duration: lit(duration.ok_or_else(|| missing("duration"))?),
+    path: path.display().to_string(),
+  })
+}
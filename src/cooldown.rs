@@ -0,0 +1,72 @@
+//! Expansion of the `#[cooldown(...)]` attribute.
+
+use crate::args::{with_usage, SkippableArgs};
+use crate::clock;
+use crate::duration::parse_duration;
+use crate::split::Split;
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, ItemFn};
+
+/// See [crate::cooldown].
+pub(crate) fn cooldown(args: TokenStream, func: TokenStream) -> TokenStream {
+  // A cooldown is a throttle of a single call per duration, so instead of a deque of call
+  // timestamps, only the timestamp of the last executed call needs to be kept around.
+
+  const ARGS_ERR_MSG: &str = "expecting a duration expression: #[cooldown(<duration>)]";
+
+  let args_parsed =
+    match with_usage(ARGS_ERR_MSG, || syn::parse::<SkippableArgs>(args.clone())) {
+      Ok(args) => args,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let duration =
+    match with_usage(ARGS_ERR_MSG, || args_parsed.value()).and_then(parse_duration) {
+      Ok(duration) => duration,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let func_parsed = parse_macro_input!(func as ItemFn);
+  let mut split = match Split::new(func_parsed) {
+    Ok(split) => split,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  let skipped = args_parsed.skipped.as_ref();
+  let (skipped, wrap) = match split.set_skippable_output(skipped, "`#[cooldown(...)]`") {
+    Ok(skippable) => skippable,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  let Split { attrs, vis, impl_fn, outer_sig, call, .. } = &split;
+
+  let gen = quote_mixed! {
+    #(#attrs)* #vis #outer_sig {
+      #impl_fn
+
+      static __THROTTLE_LAST_RUN: ::throttle_my_fn_runtime::parking_lot::Mutex<
+        Option<::throttle_my_fn_runtime::Instant>,
+      > = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+
+      let current_time = ::throttle_my_fn_runtime::system_now();
+
+      // The lock is scoped so that other threads can call us even while the inner impl
+      // function is running (and so that the futures of async functions don't hold it).
+      {
+        let mut last_run = __THROTTLE_LAST_RUN.lock();
+        if let Some(last_run_time) = *last_run {
+          // Return None (or the skipped value) if we're still cooling down from the last
+          // execution.
+          if current_time.duration_since(last_run_time) < #duration {
+            return #skipped;
+          }
+        }
+        *last_run = Some(current_time);
+      }
+
+      #wrap(#call)
+    }
+  };
+
+  TokenStream::from(clock::virtualize(gen))
+}
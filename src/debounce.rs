@@ -0,0 +1,171 @@
+//! Expansion of the `#[debounce(...)]` attribute.
+
+use crate::args::{with_usage, DebounceArgs, Edge};
+use crate::clock;
+use crate::split::Split;
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, ItemFn};
+
+/// See [crate::debounce].
+pub(crate) fn debounce(args: TokenStream, func: TokenStream) -> TokenStream {
+  // Debouncing works by wrapping the user-provided function just like throttling does,
+  // except that by default (on the trailing edge) the outer function never calls the
+  // inner impl function directly. Instead, it replaces the pending call with itself and
+  // lets a background worker thread execute the pending call once no other calls have
+  // come in for the debounce duration.
+
+  const ARGS_ERR_MSG: &str = "expecting a duration expression: #[debounce(<duration>)]";
+
+  let args_parsed =
+    match with_usage(ARGS_ERR_MSG, || syn::parse::<DebounceArgs>(args.clone())) {
+      Ok(args) => args,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let duration = &args_parsed.duration;
+
+  let func_parsed = parse_macro_input!(func as ItemFn);
+  let mut split = match Split::new(func_parsed) {
+    Ok(split) => split,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  // Debounced calls are executed by a background thread, which cannot await them.
+  if let Err(e) = split.reject_async("`#[debounce(...)]`") {
+    return e.into_compile_error().into();
+  }
+
+  // The outer function returns a ThrottleHandle<T>, which resolves to the return value
+  // once the call has been executed, or to None if it has been replaced by a later call.
+  let ret_ty = &split.ret_ty;
+  let output = quote_mixed! { ::throttle_my_fn_runtime::ThrottleHandle<#ret_ty> };
+  if let Err(e) = split.set_output(output, "`#[debounce(...)]`") {
+    return e.into_compile_error().into();
+  }
+
+  let Split { attrs, vis, impl_fn, outer_sig, call, .. } = &split;
+  let thread_name = format!("debounce-{}", outer_sig.ident);
+
+  // On the trailing edge, the latest call is kept pending until a background worker
+  // thread executes it once no other calls have come in for the debounce duration. In
+  // the browser, where there are no threads to spawn, it is executed from a timer.
+  let trailing_items = quote_mixed! {
+    // The latest call, along with the time at which it was made.
+    type __ThrottleCall =
+      (::throttle_my_fn_runtime::Instant, Box<dyn ::throttle_my_fn_runtime::Deferred>);
+    static __THROTTLE_PENDING: ::throttle_my_fn_runtime::parking_lot::Mutex<
+      Option<__ThrottleCall>,
+    > = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+    static __THROTTLE_PENDING_CONDVAR: ::throttle_my_fn_runtime::parking_lot::Condvar =
+      ::throttle_my_fn_runtime::parking_lot::Condvar::new();
+    static __THROTTLE_WORKER: ::throttle_my_fn_runtime::Worker =
+      ::throttle_my_fn_runtime::Worker::new();
+
+    // A step of the background worker: waits for a pending call, then keeps waiting
+    // until the debounce duration has passed since the latest call before executing it.
+    // A step that panics cancels its call, and the worker carries on with the next one.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn __throttle_work() {
+      let mut pending = __THROTTLE_PENDING.lock();
+      let job = loop {
+        match pending.as_ref().map(|(call_time, _)| *call_time + #duration) {
+          None => __THROTTLE_PENDING_CONDVAR.wait(&mut pending),
+          Some(quiet_time) if ::throttle_my_fn_runtime::system_now() >= quiet_time => {
+            // The pending call is some, so this unwrap cannot fail.
+            break pending.take().unwrap().1;
+          }
+          Some(quiet_time) => {
+            let _ = __THROTTLE_PENDING_CONDVAR.wait_until(&mut pending, quiet_time);
+          }
+        }
+      };
+      drop(pending);
+      job.run();
+    }
+
+    // In the browser, the pending call is executed from a timer polling it once the
+    // debounce duration has passed since the latest call, and again whenever a call came
+    // in since.
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    fn __throttle_poll() -> Option<::std::time::Duration> {
+      loop {
+        let mut pending = __THROTTLE_PENDING.lock();
+        let quiet_time = pending.as_ref()?.0 + #duration;
+        let current_time = ::throttle_my_fn_runtime::system_now();
+        if current_time < quiet_time {
+          return Some(quiet_time.saturating_duration_since(current_time));
+        }
+
+        let job = pending.take()?.1;
+        drop(pending);
+        job.run();
+      }
+    }
+  };
+
+  let trailing_body = quote_mixed! {
+    let (call, handle) = ::throttle_my_fn_runtime::DeferredCall::new(move || #call);
+
+    // Replace the pending call, if any, which cancels it. Drop it outside of the lock.
+    let replaced = __THROTTLE_PENDING.lock().replace((current_time, Box::new(call)));
+    drop(replaced);
+
+    __THROTTLE_PENDING_CONDVAR.notify_one();
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    __THROTTLE_WORKER.ensure_running(#thread_name, __throttle_work);
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    __THROTTLE_WORKER.ensure_polling(__throttle_poll);
+
+    handle
+  };
+
+  // On the leading edge, a call executes immediately if the debounce duration has passed
+  // since the previous call, whether the previous call executed or not.
+  let leading_items = quote_mixed! {
+    static __THROTTLE_LAST_CALL: ::throttle_my_fn_runtime::parking_lot::Mutex<
+      Option<::throttle_my_fn_runtime::Instant>,
+    > = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+  };
+
+  let leading_check = quote_mixed! {
+    let mut last_call = __THROTTLE_LAST_CALL.lock();
+    let quiet = last_call.map_or(true, |call_time| {
+      current_time.duration_since(call_time) >= #duration
+    });
+    *last_call = Some(current_time);
+    drop(last_call);
+
+    if quiet {
+      return ::throttle_my_fn_runtime::ThrottleHandle::ready(#call);
+    }
+  };
+
+  let (items, body) = match args_parsed.edge {
+    Edge::Trailing => (trailing_items, trailing_body),
+    Edge::Leading => (
+      leading_items,
+      quote_mixed! {
+        #leading_check
+        ::throttle_my_fn_runtime::ThrottleHandle::cancelled()
+      },
+    ),
+    Edge::Both => (
+      quote_mixed! { #leading_items #trailing_items },
+      quote_mixed! { #leading_check #trailing_body },
+    ),
+  };
+
+  let gen = quote_mixed! {
+    #(#attrs)* #vis #outer_sig {
+      #impl_fn
+
+      #items
+
+      let current_time = ::throttle_my_fn_runtime::system_now();
+
+      #body
+    }
+  };
+
+  TokenStream::from(clock::virtualize(gen))
+}
@@ -0,0 +1,101 @@
+//! Parsing of human-readable duration literals such as `"1s"` or `"1h30m"`.
+
+use syn::{Expr, ExprCall, ExprLit, Lit};
+use throttle_my_fn_runtime::parse_nanos;
+
+const FORMAT_ERR_MSG: &str =
+  "expecting a duration like \"500ms\", \"30s\" or \"1h30m\", \
+                              with units ns, us, ms, s, m (or min), h and d";
+
+/// Turn a duration argument into a [std::time::Duration] expression.
+///
+/// String literals such as `"1s"` are parsed at expansion time into a
+/// `Duration::from_nanos(...)` expression, any other expression is left untouched.
+pub(crate) fn parse_duration(expr: Expr) -> syn::Result<Expr> {
+  let lit = match &expr {
+    Expr::Lit(ExprLit { lit: Lit::Str(lit), .. }) => lit,
+    _ => return Ok(expr),
+  };
+
+  let nanos = match parse_nanos(&lit.value()) {
+    Some(nanos) if nanos <= u64::MAX as u128 => nanos as u64,
+    _ => return Err(syn::Error::new_spanned(lit, FORMAT_ERR_MSG)),
+  };
+
+  Ok(syn::parse_quote! { ::std::time::Duration::from_nanos(#nanos) })
+}
+
+/// The length in nanoseconds of a duration written as a literal, i.e. a `Duration`
+/// constructor such as `Duration::from_secs(1)` taking an integer literal, or a string
+/// literal such as `"1s"`. Any other expression isn't known until runtime.
+pub(crate) fn literal_nanos(expr: &Expr) -> Option<u128> {
+  if let Expr::Lit(ExprLit { lit: Lit::Str(lit), .. }) = expr {
+    return parse_nanos(&lit.value());
+  }
+
+  let (func, args) = match expr {
+    Expr::Call(ExprCall { func, args, .. }) if args.len() == 1 => (func, args),
+    _ => return None,
+  };
+
+  let path = match &**func {
+    Expr::Path(path) => &path.path,
+    _ => return None,
+  };
+
+  let mut segments = path.segments.iter().rev();
+  let (ctor, ty) = (segments.next()?, segments.next()?);
+  if ty.ident != "Duration" {
+    return None;
+  }
+
+  let nanos: u128 = match ctor.ident.to_string().as_str() {
+    "from_nanos" => 1,
+    "from_micros" => 1_000,
+    "from_millis" => 1_000_000,
+    "from_secs" => 1_000_000_000,
+    _ => return None,
+  };
+
+  match &args[0] {
+    Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }) => {
+      lit.base10_parse::<u128>().ok()?.checked_mul(nanos)
+    }
+    _ => None,
+  }
+}
+
+/// Turn the duration argument of a sliding window into a [std::time::Duration]
+/// expression, see [parse_duration].
+///
+/// Windows written as literals are validated: a zero-length window cannot hold any
+/// calls, and a window shorter than a nanosecond per call (`times`, when it is a literal)
+/// is beyond what the clock can tell apart.
+pub(crate) fn parse_window(expr: Expr, times: &Expr) -> syn::Result<Expr> {
+  let nanos = match literal_nanos(&expr) {
+    Some(nanos) => nanos,
+    None => return parse_duration(expr),
+  };
+
+  if nanos == 0 {
+    return Err(syn::Error::new_spanned(
+      &expr,
+      "a zero-length window cannot hold any calls, expecting a positive duration",
+    ));
+  }
+
+  if let Expr::Lit(ExprLit { lit: Lit::Int(times), .. }) = times {
+    if times.base10_parse::<u128>()? > nanos {
+      return Err(syn::Error::new_spanned(
+        &expr,
+        format!(
+          "a limit of {} calls over {}ns is finer than the clock can measure, expecting \
+           at most 1 call per nanosecond",
+          times, nanos
+        ),
+      ));
+    }
+  }
+
+  parse_duration(expr)
+}
@@ -0,0 +1,148 @@
+//! Generation of the module exposing the limiter of a throttled function, see the
+//! `expose` option of [crate::throttle].
+
+use crate::args::Args;
+use crate::queries;
+use crate::split::Split;
+use crate::window;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::format_ident;
+
+/// Generate the functions of the `NAME_throttle` module of `split` with `emit = module`,
+/// reporting on and resetting the limiter through the `STATE` static.
+fn module_fns(args: &Args, split: &Split) -> TokenStream2 {
+  let ident = &split.outer_sig.ident;
+
+  let stats_fn = match args.stats {
+    Some(_) => {
+      let stats_ident = format_ident!("{}_stats", ident);
+      let stats_doc = format!("Statistics about the calls made to [`{}`].", ident);
+      quote_mixed! {
+        #[doc = #stats_doc]
+        pub fn stats() -> ::throttle_my_fn_runtime::ThrottleStats {
+          super::#stats_ident()
+        }
+      }
+    }
+    None => quote_mixed! {},
+  };
+
+  quote_mixed! {
+    /// How long until there is a free slot in the window, or `None` if there is one
+    /// already.
+    pub fn retry_after() -> Option<::std::time::Duration> {
+      STATE.retry_after()
+    }
+
+    /// The number of calls in the window.
+    pub fn in_window() -> usize {
+      STATE.in_window()
+    }
+
+    /// Forget about the calls in the window, letting the next calls through right away.
+    pub fn reset() {
+      STATE.reset()
+    }
+
+    #stats_fn
+  }
+}
+
+/// Generate the `NAME_throttle` module of `split`, holding the `STATE` static through
+/// which other code paths can charge calls against the quota of the function. The
+/// calls deque is the one of [queries::calls_item].
+pub(crate) fn state_module(args: &Args, split: &Split) -> TokenStream2 {
+  let Args { times, duration, .. } = args;
+  let vis = &split.vis;
+  let ident = &split.outer_sig.ident;
+  let calls_fn = queries::calls_fn(split);
+  let module = format_ident!("{}_throttle", ident);
+
+  let module_doc = format!("The limiter of [`{}`](super::{}).", ident, ident);
+  let state_doc = format!(
+    "The state of the limiter of [`{}`](super::{}), which shares its quota.",
+    ident, ident
+  );
+
+  let cleanup_fn = window::cleanup_fn(times, duration);
+  let charged_at = window::charged_at(&args.calendar);
+  let retry_after_fn = window::retry_after_fn(times, duration);
+  let module_fns = match args.emit_module {
+    true => module_fns(args, split),
+    false => quote_mixed! {},
+  };
+
+  quote_mixed! {
+    #[doc = #module_doc]
+    #vis mod #module {
+      // The limit is written in the scope of the function.
+      #[allow(unused_imports)]
+      use super::*;
+
+      #[doc = #state_doc]
+      pub struct State {
+        _private: (),
+      }
+
+      #[doc = #state_doc]
+      pub static STATE: State = State { _private: () };
+
+      impl State {
+        /// Charge a call against the quota if there is a free slot in the window, just
+        /// like a call to the function would be.
+        ///
+        /// # Returns
+        ///
+        /// Whether there was a free slot.
+        pub fn try_acquire(&self) -> bool {
+
+          #cleanup_fn
+
+          let current_time = ::throttle_my_fn_runtime::system_now();
+          let mut calls_guard = #calls_fn().lock();
+          let calls = calls_guard
+            .get_or_insert_with(|| ::std::collections::VecDeque::with_capacity(#times));
+          __throttle_cleanup(calls, current_time);
+
+          if calls.len() >= #times {
+            return false;
+          }
+
+          calls.push_back(#charged_at);
+          true
+        }
+
+        /// How long until there is a free slot in the window, or `None` if there is one
+        /// already.
+        pub fn retry_after(&self) -> Option<::std::time::Duration> {
+
+          #retry_after_fn
+
+          let calls_guard = #calls_fn().lock();
+          let current_time = ::throttle_my_fn_runtime::system_now();
+          __throttle_retry_after(calls_guard.as_ref()?, current_time)
+        }
+
+        /// The number of calls in the window.
+        pub fn in_window(&self) -> usize {
+          let current_time = ::throttle_my_fn_runtime::system_now();
+          let calls_guard = #calls_fn().lock();
+          let in_window = |call_time: &&::throttle_my_fn_runtime::Instant| {
+            current_time.duration_since(**call_time) <= #duration
+          };
+          calls_guard.iter().flatten().filter(in_window).count()
+        }
+
+        /// Forget about the calls in the window, letting the next calls through right
+        /// away.
+        pub fn reset(&self) {
+          if let Some(calls) = #calls_fn().lock().as_mut() {
+            calls.clear();
+          }
+        }
+      }
+
+      #module_fns
+    }
+  }
+}
@@ -0,0 +1,76 @@
+//! Generation of the lock-free book-keeping of throttles that only skip calls, which is
+//! used instead of the locked calls deque when nothing else needs to look into it.
+//!
+//! The window is a `throttle_my_fn_runtime::SlotWindow`, which keeps the times at which
+//! the last `times` calls were let through in a ring of slots, and is model checked
+//! along with the rest of the synchronization of the runtime crate.
+
+use crate::args::{Args, Mode};
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{Expr, Ident};
+
+/// Whether the calls of a function throttled with `args` can be charged without a calls
+/// deque, i.e. when no other generated code looks into the deque and calls are only ever
+/// skipped. `shared` is the static holding a quota shared with other functions, if any.
+///
+/// The window has as many slots as the limit, so a limit scaled at runtime (the `scale`
+/// feature) needs the deque as well.
+pub(crate) fn applies(args: &Args, shared: Option<&Ident>) -> bool {
+  !cfg!(feature = "scale") && args.resolution.is_none() && only_skips(args, shared)
+}
+
+/// Whether the calls of a function throttled with `args` are only ever skipped, without
+/// any other generated code looking into the calls deque, see [applies]. The calls can
+/// then be counted in slots as well (`resolution = "1s"`).
+pub(crate) fn only_skips(args: &Args, shared: Option<&Ident>) -> bool {
+  let needs_deque = shared.is_some()
+    || args.queries.is_some()
+    || args.permits.is_some()
+    || args.layer.is_some()
+    || args.stats.is_some()
+    || args.wait_variant.is_some()
+    || args.async_variant.is_some()
+    || args.expose.is_some()
+    || args.per_type.is_some()
+    || args.group.is_some()
+    || args.per_key.is_some()
+    || args.on_execute.is_some()
+    || args.sharded.is_some()
+    || args.deadline.is_some()
+    || args.calendar.is_some()
+    || args.rate_from.is_some()
+    || args.reset_after_idle.is_some()
+    || args.charge_at_end.is_some()
+    || args.refund_on_cancel.is_some()
+    || args.policy.is_some()
+    || args.priorities.is_some()
+    || args.with_override.is_some()
+    || args.cold_start_cap.is_some()
+    || args.concurrency.is_some()
+    || args.detailed;
+
+  args.mode == Mode::Skip && !needs_deque
+}
+
+/// Generate the `__THROTTLE_WINDOW` static of `times` slots, see the module docs.
+pub(crate) fn window_item(times: &Expr) -> TokenStream2 {
+  quote_mixed! {
+    static __THROTTLE_WINDOW: ::std::sync::LazyLock<
+      ::throttle_my_fn_runtime::SlotWindow,
+    > = ::std::sync::LazyLock::new(|| {
+      let epoch = ::throttle_my_fn_runtime::system_now();
+      ::throttle_my_fn_runtime::SlotWindow::new(#times, epoch)
+    });
+  }
+}
+
+/// Generate the book-keeping charging a call made at `current_time` against the window
+/// of [window_item], or returning `skipped` if the quota is full over `duration`.
+pub(crate) fn charge(duration: &Expr, skipped: &TokenStream2) -> TokenStream2 {
+  quote_mixed! {
+    // Return None (or the skipped value) if our quota is full for the duration.
+    if !__THROTTLE_WINDOW.try_charge(current_time, #duration) {
+      return #skipped;
+    }
+  }
+}
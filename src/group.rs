@@ -0,0 +1,174 @@
+//! Expansion of the `#[throttle(...)]` attribute on impl blocks and modules.
+
+use crate::args::Args;
+use crate::expand_throttle;
+use crate::split::Split;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, ToTokens};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use syn::{Attribute, FnArg, Ident, ImplItem, Item, ItemFn, ItemImpl, ItemMod};
+
+/// Whether `attrs` contain a `#[throttle(...)]` attribute.
+fn has_throttle(attrs: &[Attribute]) -> bool {
+  attrs
+    .iter()
+    .any(|attr| attr.path.segments.last().is_some_and(|seg| seg.ident == "throttle"))
+}
+
+/// Make sure the arguments only name functions that can be named after a single
+/// function, which a group of functions cannot share.
+fn reject_names(args: &Args) -> syn::Result<()> {
+  match &args.expose_inner {
+    Some((_, name)) => Err(syn::Error::new_spanned(
+      name,
+      format!("option `{}` cannot be used on a group of functions", name),
+    )),
+    None => Ok(()),
+  }
+}
+
+/// The static holding the calls deque shared by a group of functions, if they share it.
+/// Functions of a named group (`group = "..."`) share the deque of the group instead.
+///
+/// The static needs a name that doesn't clash with the statics of other groups in the
+/// same module, so it is named after a hash of the group's `tokens`.
+fn shared_static(args: &Args, tokens: impl ToTokens) -> syn::Result<Option<Ident>> {
+  if args.group.is_some() || !args.share.as_ref().is_none_or(|(_, share)| *share) {
+    return Ok(None);
+  }
+
+  if let Some(concurrency) = &args.concurrency {
+    return Err(syn::Error::new_spanned(
+      concurrency,
+      "option `concurrency` cannot be shared by a group of functions, use \
+       `share = false`",
+    ));
+  }
+
+  let mut hasher = DefaultHasher::new();
+  tokens.to_token_stream().to_string().hash(&mut hasher);
+  Ok(Some(format_ident!("__THROTTLE_CALLS_{:X}", hasher.finish())))
+}
+
+/// The definition of the `shared` static, if any.
+fn shared_item(shared: &Option<Ident>) -> TokenStream2 {
+  match shared {
+    Some(shared) => quote_mixed! {
+      static #shared: ::throttle_my_fn_runtime::parking_lot::Mutex<
+        Option<::std::collections::VecDeque<::throttle_my_fn_runtime::Instant>>,
+      > = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+    },
+    _ => quote_mixed! {},
+  }
+}
+
+/// Throttle every method of an impl block, see [crate::throttle].
+pub(crate) fn throttle_impl(
+  args: &Args,
+  mut item: ItemImpl,
+) -> syn::Result<TokenStream2> {
+  // Every method taking `self` is throttled as if it carried the attribute itself, except
+  // for those that already carry their own. Associated functions without a receiver (e.g.
+  // constructors) are left alone. With a shared quota, the calls deque lives in a static
+  // next to the impl block instead of in each method.
+
+  // The methods of a trait impl must keep the return types of the trait's methods.
+  if let Some((_, path, _)) = &item.trait_ {
+    if args.skipped.is_none() {
+      return Err(syn::Error::new_spanned(
+        path,
+        "throttling the methods of a trait impl requires the `skipped` option, to keep \
+         their return types",
+      ));
+    }
+
+    // Nor can they have associated items the trait doesn't declare.
+    if let Some(name) = &args.consts {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!("option `{}` cannot be used on a trait impl", name),
+      ));
+    }
+  }
+
+  reject_names(args)?;
+  let shared = shared_static(args, &item)?;
+
+  let mut throttled = false;
+
+  for impl_item in &mut item.items {
+    let method = match impl_item {
+      ImplItem::Method(method) => method,
+      _ => continue,
+    };
+
+    let has_receiver = matches!(method.sig.inputs.first(), Some(FnArg::Receiver(_)));
+    if !has_receiver || has_throttle(&method.attrs) {
+      continue;
+    }
+
+    let func = ItemFn {
+      attrs: method.attrs.clone(),
+      vis: method.vis.clone(),
+      sig: method.sig.clone(),
+      block: Box::new(method.block.clone()),
+    };
+
+    let defaultness = &method.defaultness;
+    let gen = expand_throttle(args, Split::new(func)?, shared.as_ref())?;
+    *impl_item = ImplItem::Verbatim(quote_mixed! { #defaultness #gen });
+    throttled = true;
+  }
+
+  // Don't define the static if no method was throttled, it would be unused.
+  let shared_item = if throttled {
+    shared_item(&shared)
+  } else {
+    quote_mixed! {}
+  };
+
+  Ok(quote_mixed! {
+    #shared_item
+    #item
+  })
+}
+
+/// Throttle every function of a module, see [crate::throttle].
+pub(crate) fn throttle_mod(args: &Args, mut item: ItemMod) -> syn::Result<TokenStream2> {
+  // Every function directly in the module is throttled as if it carried the attribute
+  // itself, except for those that already carry their own. With a shared quota, the calls
+  // deque lives in a static in the module instead of in each function.
+
+  reject_names(args)?;
+  let shared = shared_static(args, &item)?;
+
+  let content = match &mut item.content {
+    Some((_, content)) => content,
+    None => {
+      return Err(syn::Error::new_spanned(
+        &item,
+        "only modules with their content inline can be throttled",
+      ))
+    }
+  };
+
+  let mut throttled = false;
+
+  for mod_item in content.iter_mut() {
+    let func = match mod_item {
+      Item::Fn(func) if !has_throttle(&func.attrs) => func.clone(),
+      _ => continue,
+    };
+
+    let gen = expand_throttle(args, Split::new(func)?, shared.as_ref())?;
+    *mod_item = Item::Verbatim(gen);
+    throttled = true;
+  }
+
+  if throttled {
+    content.push(Item::Verbatim(shared_item(&shared)));
+  }
+
+  Ok(quote_mixed! { #item })
+}
@@ -0,0 +1,87 @@
+//! Keys for state kept per distinct set of arguments.
+
+use crate::split::Split;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{FnArg, Ident, Pat, Type};
+
+/// The key identifying a call by its arguments.
+pub(crate) struct Key {
+  /// The type of the key, a tuple of the owned argument types.
+  pub(crate) ty: TokenStream2,
+
+  /// The expression building the key from the outer function's arguments.
+  pub(crate) expr: TokenStream2,
+}
+
+impl Key {
+  /// Build the key of `split`'s arguments.
+  ///
+  /// Keys outlive calls, so they are made of owned values: reference arguments (e.g.
+  /// `&str`) are converted with [std::borrow::ToOwned] and other arguments are cloned.
+  pub(crate) fn new(split: &Split) -> Self {
+    let mut types = Vec::new();
+    let mut values = Vec::new();
+
+    let typed = split.impl_sig.inputs.iter().filter_map(|input| match input {
+      FnArg::Typed(t) => Some(t),
+      FnArg::Receiver(_) => None,
+    });
+
+    for (input, param) in typed.zip(&split.call_params) {
+      let (ty, value) = owned(&input.ty, param);
+      types.push(ty);
+      values.push(value);
+    }
+
+    Key { ty: quote_mixed! { (#(#types,)*) }, expr: quote_mixed! { (#(#values,)*) } }
+  }
+
+  /// Build the key made of the arguments of `split` named `params`, in that order, like
+  /// [Key::new]. The arguments are looked up in the outer function's signature, so this
+  /// works for methods as well.
+  pub(crate) fn of(split: &Split, params: &[Ident]) -> syn::Result<Self> {
+    let mut types = Vec::new();
+    let mut values = Vec::new();
+
+    for param in params {
+      let input = split.outer_sig.inputs.iter().find_map(|input| match input {
+        FnArg::Typed(t) => match &*t.pat {
+          Pat::Ident(p) if p.ident == *param => Some(t),
+          _ => None,
+        },
+        FnArg::Receiver(_) => None,
+      });
+
+      let input = match input {
+        Some(input) => input,
+        None => {
+          return Err(syn::Error::new_spanned(
+            param,
+            format!("`{}` is not an argument of the function", param),
+          ))
+        }
+      };
+
+      let (ty, value) = owned(&input.ty, param);
+      types.push(ty);
+      values.push(value);
+    }
+
+    Ok(Key { ty: quote_mixed! { (#(#types,)*) }, expr: quote_mixed! { (#(#values,)*) } })
+  }
+}
+
+/// The owned type of an argument of type `ty`, and the expression converting the
+/// argument `param` to it, see [Key::new].
+fn owned(ty: &Type, param: &Ident) -> (TokenStream2, TokenStream2) {
+  match ty {
+    Type::Reference(r) => {
+      let elem = &r.elem;
+      (
+        quote_mixed! { <#elem as ::std::borrow::ToOwned>::Owned },
+        quote_mixed! { ::std::borrow::ToOwned::to_owned(#param) },
+      )
+    }
+    ty => (quote_mixed! { #ty }, quote_mixed! { ::std::clone::Clone::clone(&#param) }),
+  }
+}
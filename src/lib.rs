@@ -19,16 +19,30 @@
 //! ```toml
 //! [dependencies]
 //! throttle_my_fn = "0.2"
-//! parking_lot = "0.11"
+//! throttle_my_fn_runtime = "0.2"
 //! ```
 //!
 //! Or, using `cargo add`:
 //!
 //! ```sh
 //! $ cargo add throttle_my_fn
-//! $ cargo add parking_lot
+//! $ cargo add throttle_my_fn_runtime
 //! ```
 //!
+//! The generated code calls into the `throttle_my_fn_runtime` crate for the book-keeping
+//! of the sliding window, which is documented there along with the other types the
+//! options refer to. The `parking_lot` locks the generated code keeps the quota behind
+//! are re-exported by the runtime crate as well, so they don't need a dependency of their
+//! own. Both crates are to be kept at the same version.
+//!
+//! The quota of a throttled function is kept behind `parking_lot` locks, which cannot be
+//! poisoned: a call that panics, whether in the function itself or in a handler given to
+//! one of its options, doesn't keep the calls after it from being throttled as usual.
+//! There is no option choosing what to do with a poisoned lock (such as an
+//! `on_lock_error` policy), since there never is one. The background threads executing
+//! deferred and debounced calls keep running as well, after a panic of a call or while
+//! waiting for its slot, and are spawned again should they ever stop.
+//!
 //! Include the macro:
 //!
 //! ```ignore
@@ -63,43 +77,3325 @@
 //! ```
 //!
 //! The `Option<T>` returned signifies whether the function executed or not.
+//!
+//! Durations can also be written as string literals, which are parsed at compile time:
+//! `"500ms"`, `"30s"`, `"1h30m"`, with the units `ns`, `us`, `ms`, `s`, `m` (or `min`),
+//! `h` and `d`.
+//!
+//! ```ignore
+//! #[throttle(10, "1s")]
+//! pub(crate) fn run_10_times_per_second(arg: &str) -> String {
+//!   ...
+//! }
+//! ```
+//!
+//! Parameters can be written with any pattern, e.g. `(x, y): (i32, i32)`, or be left
+//! unused with `_: Token` or `_guard: MutexGuard<'_, T>`. The arguments are passed to the
+//! function unchanged, so a guard passed to a skipped call is dropped right away, while
+//! one passed to an executed call is held for as long as the call runs.
+//!
+//! The number of calls and the duration can be any expressions, e.g.
+//! `Duration::from_secs(CONFIG.window_secs())`. Expressions other than literals and plain
+//! paths to constants are evaluated once, on the first call to the function, rather than
+//! on every call.
+//!
+//! Throttles that only skip calls, without any of the options looking into their past
+//! calls (such as `stats`, `queries`, `permits` or `per_key`), keep the times of their
+//! calls in a ring of atomics rather than in a locked queue: a call with plenty of quota
+//! costs a few atomic loads, one compare-exchange and two stores. `cargo bench` compares
+//! the overhead of both to calling a function directly.
+//!
+//! ## Options
+//!
+//! Options are passed to the attribute after the number of calls and the duration, as
+//! `name = value` pairs.
+//!
+//! ### `config` and `name`
+//!
+//! Instead of being written down in the attribute, the limit of a function can be read
+//! from a configuration file when the function is compiled, so that the limits of many
+//! functions can be reviewed in one place. `config` is the path of the file, relative to
+//! the crate's `Cargo.toml`, and `name` is the name of the table holding the limit. A
+//! missing file, table or key is a compile error, and the function is rebuilt whenever
+//! the file changes.
+//!
+//! ```toml
+//! [github.fetch]
+//! times = 5000
+//! duration = "1h"
+//! ```
+//!
+//! ```ignore
+//! #[throttle(config = "throttles.toml", name = "github.fetch")]
+//! pub(crate) fn fetch(path: &str) -> Response {
+//!   ...
+//! }
+//! ```
+//!
+//! Only a subset of TOML is supported: `[table]` headers, `key = value` pairs of integers
+//! or strings without escapes, and comments.
+//!
+//! ### `rate_from` and `rate_refresh`
+//!
+//! For limits that change while the program runs, e.g. when they come from a live
+//! configuration system or a feature-flag service, `rate_from` is the path of a function
+//! returning the limit as a `(usize, Duration)` pair. The function is called by the
+//! wrapper, and the limit it returns is reused for `rate_refresh` (1 second by default)
+//! before it is called again.
+//!
+//! ```ignore
+//! fn github_limit() -> (usize, Duration) {
+//!   (flags::get("github.rate"), Duration::from_secs(3600))
+//! }
+//!
+//! #[throttle(rate_from = github_limit, rate_refresh = "30s")]
+//! pub(crate) fn fetch(path: &str) -> Response {
+//!   ...
+//! }
+//! ```
+//!
+//! The provider is called while a lock is held, so it should return quickly rather than
+//! make a network request of its own. Calls already in the window are kept when the limit
+//! changes. Since the limit is only known at runtime, `rate_from` cannot be used with
+//! `queries`, `stats`, `permits`, `variants` or `consts`, nor with inline throttles.
+//!
+//! ### `every` and `rate`
+//!
+//! States a minimum interval between two executions of a function, instead of a number of
+//! calls per duration. `every = Duration::from_millis(250)` (or `every = "250ms"`) is the
+//! same limit as `#[throttle(1, "250ms")]`, and no other limit is given positionally.
+//!
+//! ```ignore
+//! #[throttle(every = Duration::from_millis(250))]
+//! pub(crate) fn poll_sensor(sensor: SensorId) -> Reading {
+//!   ...
+//! }
+//! ```
+//!
+//! Frame-rate and telemetry limits are more naturally given in executions per second:
+//! `rate = 30.0` spaces executions evenly, at most one every 1/30th of a second, and
+//! fractional rates such as `rate = 0.5` are allowed. Literal rates are checked when the
+//! function is compiled, any other expression is converted with `f64::from` on the first
+//! call.
+//!
+//! ```ignore
+//! #[throttle(rate = 30.0)]
+//! pub(crate) fn render_frame(frame: &Frame) {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `per` and `align`
+//!
+//! Instead of a duration, the limit can be given for a named period: `per = "second"`,
+//! `"minute"`, `"hour"` or `"day"`. Only the number of calls is then given positionally.
+//! By default, the window rolls, so a call frees up its slot a period after it was made.
+//! With `align = calendar`, the quota instead resets at the start of every period of the
+//! clock (in UTC), which is how many vendors reset their quotas.
+//!
+//! ```ignore
+//! // 500 calls per clock hour, resetting at minute 0.
+//! #[throttle(500, per = "hour", align = calendar)]
+//! pub(crate) fn query_vendor(query: &str) -> Response {
+//!   ...
+//! }
+//! ```
+//!
+//! Every call is charged at the start of its period, so `queries`, `output = detailed`
+//! and waiting calls tell the time until the next period starts. `align = calendar`
+//! cannot be used with inline throttles.
+//!
+//! ### `mode`
+//!
+//! Controls what happens to a call that arrives while the quota is exhausted:
+//!
+//! * `mode = skip` (the default): the call is skipped and `None` is returned.
+//!
+//! * `mode = defer`: the call is queued and executed later by a background thread
+//!   once capacity frees up, spreading the work out over time instead of dropping it.
+//!   The wrapper returns immediately with a `ThrottleHandle<T>` (from the
+//!   `throttle_my_fn_runtime` crate) instead of an `Option<T>`. The handle can be used
+//!   to block on the result with `wait()`, `.await` it, or `cancel()` the call while it
+//!   is still pending. The arguments and return value of a deferred call are moved
+//!   across threads, so they must be `Send + 'static`. A deferred call that panics is
+//!   cancelled, and the background thread carries on with the calls after it.
+//!
+//! * `mode = coalesce`: like `mode = defer`, but at most one call is kept pending. A
+//!   call that arrives while another one is pending replaces it (and the replaced call's
+//!   handle resolves to `None`), so only a single trailing execution with the latest
+//!   arguments happens once capacity frees up. This is useful for "redraw", "save
+//!   settings" or "sync state" functions where intermediate calls are redundant.
+//!
+//! * `mode = wait_with_deadline`: a `deadline: Instant` parameter is added to the end of
+//!   the function's parameters, and the call blocks the calling thread until a slot frees
+//!   up, unless that would be past the deadline, in which case the call is skipped right
+//!   away. This suits request handlers with end-to-end deadlines. The wrapper returns an
+//!   `Option<T>` as with `mode = skip`, and the function cannot be async.
+//!
+//! * `mode = warn`: the call is executed all the same, but reported as over budget to the
+//!   required `on_over_budget = handler` option: a path to a function or a closure taking
+//!   the `&'static str` name of the function and the `&'static Location<'static>` of the
+//!   call. The throttle prints nothing itself, the handler decides where reports go, e.g.
+//!   to a logger. This is the safe first step when introducing limits to an existing
+//!   codebase, to observe violations before enforcing them. The wrapper returns an
+//!   `Option<T>` as with `mode = skip`, which is always `Some`, so that switching to
+//!   `mode = skip` later doesn't change callers. Calls over budget are counted as skipped
+//!   by [`stats`](#stats), and aren't charged against the quota, as with `mode = skip`.
+//!   Cannot be used with `skipped`, `output`, `flatten`, `concurrency`, `reentrant` or
+//!   `active`.
+//!
+//! * `mode = observe`: like `mode = warn`, but calls over the limit aren't reported, only
+//!   recorded in the statistics of the function, whose `NAME_stats()` function is
+//!   generated as with [`stats`](#stats): the calls that would have been skipped are
+//!   counted as skipped, and `max_wait` is the longest any of them would have had to wait
+//!   for a slot. This validates proposed limits against production traffic before
+//!   enforcing them. Only supported for free functions.
+//!
+//! ```ignore
+//! #[throttle(10, Duration::from_secs(1), mode = defer)]
+//! pub(crate) fn flush_analytics(events: Vec<Event>) -> usize {
+//!   ...
+//! }
+//!
+//! let handle: ThrottleHandle<usize> = flush_analytics(events);
+//! let flushed: Option<usize> = handle.wait();
+//! ```
+//!
+//! ```ignore
+//! #[throttle(100, "1s", mode = wait_with_deadline)]
+//! pub(crate) fn query_backend(query: &Query) -> Response {
+//!   ...
+//! }
+//!
+//! let response: Option<Response> = query_backend(&query, request.deadline);
+//! ```
+//!
+//! ```ignore
+//! use std::panic::Location;
+//!
+//! fn over_budget(function: &'static str, caller: &'static Location<'static>) {
+//!   log::warn!("{} called over budget at {}", function, caller);
+//! }
+//!
+//! #[throttle(50, "1s", mode = warn, on_over_budget = over_budget)]
+//! pub(crate) fn charge_card(card: &Card, amount: Amount) -> Receipt {
+//!   ...
+//! }
+//! ```
+//!
+//! Functions throttled with `mode = warn` get the `#[track_caller]` attribute, for the
+//! location to be that of the call rather than of the function, unless they are async or
+//! `extern` functions, which cannot track their caller. A `#[track_caller]` attribute
+//! written on the function is kept as it is.
+//!
+//! Deferred, coalescing and debounced calls are executed on a background thread. On
+//! `wasm32-unknown-unknown`, where browsers have no threads to spawn, they are executed
+//! from `setTimeout` timers instead, scheduled for when the next call gets a free slot or
+//! is no longer debounced, e.g. to throttle the event handlers of a web page. Throttles
+//! read the time from `performance.now()` there, through the
+//! `throttle_my_fn_runtime::Instant` type, which is `std::time::Instant` on every other
+//! target. The timers cannot block on anything else, so `active`, `drain` and `overflow =
+//! block` fail to compile with deferred calls for the browser. So does the `tokio`
+//! feature of `throttle_my_fn_runtime`, since the timer of tokio cannot read the time in
+//! the browser.
+//!
+//! ### `queue` and `overflow`
+//!
+//! In deferred mode, the number of pending calls can be bounded with `queue = <n>`, where
+//! `<n>` is a positive integer literal. The `overflow` option then controls what happens
+//! to a call that arrives while the queue is full:
+//!
+//! * `overflow = drop_newest` (the default): the new call is dropped.
+//!
+//! * `overflow = drop_oldest`: the oldest pending call is dropped to make room for the
+//!   new one.
+//!
+//! * `overflow = block`: the caller blocks until there is room in the queue.
+//!
+//! Dropped calls are cancelled, so waiting on their handle returns `None`.
+//!
+//! ```ignore
+//! #[throttle(10, Duration::from_secs(1), mode = defer, queue = 128, overflow = block)]
+//! pub(crate) fn flush_analytics(events: Vec<Event>) -> usize {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `drain`
+//!
+//! With `drain = true`, in deferred and coalescing modes, a
+//! `NAME_throttle_drain(timeout: Duration) -> bool` function is generated next to the
+//! function, with the same visibility. It blocks until every deferred call has been
+//! executed (or cancelled), or until the timeout expires, and returns whether the calls
+//! were drained, e.g. to shut down cleanly without losing the calls still in the queue.
+//! Calls deferred while draining are waited for as well. Only supported for free
+//! functions.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", mode = defer, drain = true)]
+//! pub(crate) fn flush_analytics(events: Vec<Event>) -> usize {
+//!   ...
+//! }
+//!
+//! // On shutdown.
+//! if !flush_analytics_throttle_drain(Duration::from_secs(5)) {
+//!   log::warn!("some analytics were not flushed");
+//! }
+//! ```
+//!
+//! ### `wait`
+//!
+//! Controls how a call waits for a slot to free up, in deferred mode, with
+//! `mode = wait_with_deadline` and in the `wait` variant (see `variants`):
+//!
+//! * `wait = sleep` (the default): the thread sleeps until the slot is free, which suits
+//!   long windows.
+//!
+//! * `wait = spin`: the thread busy-waits, for short windows on dedicated threads where
+//!   waking up from a sleep would be too late.
+//!
+//! * `wait = yield`: the thread yields to the scheduler until the slot is free.
+//!
+//! * `wait = adaptive`: the thread sleeps until a millisecond before the slot is free,
+//!   then yields for the rest of the wait.
+//!
+//! ```ignore
+//! #[throttle(1, "1ms", variants(try, wait), wait = spin)]
+//! pub(crate) fn step_motor() {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `edge`
+//!
+//! Controls on which edge of the window the function executes, lodash-style:
+//!
+//! * `edge = leading` (the default): calls execute immediately as long as there is quota
+//!   left in the window.
+//!
+//! * `edge = trailing`: calls never execute immediately. The first call of a burst starts
+//!   a window, and once the window ends, the latest call made during it is executed with
+//!   its arguments.
+//!
+//! * `edge = both`: calls execute immediately as long as there is quota left, and the
+//!   latest call made while the quota is exhausted is executed once capacity frees up.
+//!   This is the same as `mode = coalesce`.
+//!
+//! With a trailing edge, calls are executed on a background thread and the function
+//! returns a `ThrottleHandle<T>` like in coalescing mode.
+//!
+//! ### `active`
+//!
+//! Restricts a function to a window of the day, e.g. to respect the maintenance windows
+//! of an upstream service. Outside of the window, every call is skipped, or deferred
+//! until the window opens again with `mode = defer` or `mode = coalesce`. Inside of it,
+//! calls are throttled as usual. With `output = detailed`, calls skipped outside of the
+//! window are told how long until it opens.
+//!
+//! ```ignore
+//! #[throttle(100, "1m", active = "22:00-06:00 UTC", mode = defer)]
+//! pub(crate) fn run_batch_job(job: Job) -> Report {
+//!   ...
+//! }
+//! ```
+//!
+//! The window is given as `HH:MM-HH:MM UTC`, and may span midnight. Only UTC windows are
+//! supported, and `active` cannot be used with `permits` or `variants`, nor with inline
+//! throttles.
+//!
+//! ### `policy`
+//!
+//! With `policy = MY_POLICY`, a value implementing the `AdmissionPolicy` trait of the
+//! `throttle_my_fn_runtime` crate decides whether calls are let through instead of the
+//! limit, for schemes the other options don't cover, e.g. a budget depending on the time
+//! of day. Its `decide(now, history, cost)` method is given the time of the call, the
+//! times at which the calls in the window were let through, the oldest first, and the
+//! number of slots the call takes. The limit still tells how long calls are kept in the
+//! window for. `SlidingWindow` is the policy the limit stands for otherwise.
+//!
+//! Only supported with `mode = skip` (or `warn` and `observe`), and without
+//! `sharded = true`, `resolution`, `output = detailed` or functions generated alongside
+//! the throttled function other than the statistics.
+//!
+//! ```ignore
+//! struct Nightly;
+//!
+//! impl AdmissionPolicy for Nightly {
+//!   fn decide(&self, _: Instant, history: &VecDeque<Instant>, cost: usize) -> Decision {
+//!     let budget = if is_night() { 2000 } else { 1000 };
+//!     match history.len() + cost <= budget {
+//!       true => Decision::Admit,
+//!       false => Decision::Skip,
+//!     }
+//!   }
+//! }
+//!
+//! #[throttle(1000, "1h", policy = Nightly)]
+//! fn backup(file: &Path) -> Uploaded {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `priorities` and `priority`
+//!
+//! Splits the limit of a function between classes of calls by their weights, so that
+//! low-priority calls still go through while high-priority ones keep the limit busy.
+//! `priority` gives the class of each call as a `&str`, usually from an argument, and
+//! calls of classes not listed count in the last one. Each class with calls in the
+//! window, whether they were let through or not, can only use its share of the limit: the
+//! limit split between those classes by their weights, rounded up. A class alone in the
+//! window can use all of it.
+//!
+//! ```ignore
+//! #[throttle(100, "1s", priorities = (high = 70, low = 30), priority = class)]
+//! pub(crate) fn request(class: &str, url: &str) -> Response {
+//!   ...
+//! }
+//! ```
+//!
+//! Calls over the share of their class are skipped like calls over the limit. This is a
+//! cap per class rather than a queue of waiting calls: the shares are not reserved, and a
+//! class can go over its share once the others stop.
+//!
+//! With `mode = wait_with_deadline`, calls are queued by class instead, and the free
+//! slots are handed out by deficit round-robin between the classes with waiting calls:
+//! the class whose turn it is takes as many slots in a row as its weight, e.g. 7 slots
+//! for `high` then 3 for `low`, before the turn moves on. A class alone in waiting takes
+//! every free slot, and calls that aren't let through by their deadline are skipped.
+//!
+//! Only supported with `mode = skip` (or `wait_with_deadline`), and without `per_key`,
+//! `sharded = true`, `resolution`, `policy`, `charge = end`, `refund = on_cancel` or
+//! functions generated alongside the throttled function other than the statistics.
+//!
+//! ### `per_key` and `key`
+//!
+//! On top of the limit of the function as a whole, calls can be limited per key, where
+//! the key is made of some of the function's arguments, e.g. a client's IP address. A
+//! call must fit in both the limit of the function and the limit of its key, and is only
+//! charged against both once it does: a call skipped because its key is over budget is
+//! not charged against the limit of the function. The limit of the function can then be
+//! given as `global = (...)` for clarity.
+//!
+//! ```ignore
+//! #[throttle(global = (1000, "1s"), per_key = (10, "1s"), key = client_ip)]
+//! pub(crate) fn handle_request(client_ip: IpAddr, request: Request) -> Response {
+//!   ...
+//! }
+//! ```
+//!
+//! The key is either a single argument or a tuple of them, e.g. `key = (user, region)`.
+//! Their values are kept between calls, so they need to implement `Hash`, `Eq` and
+//! `Clone`, like with `#[memoize(...)]`. Keys are forgotten once all their calls have
+//! fallen out of the window, so that the state doesn't grow with keys that aren't used
+//! anymore. Only supported with `mode = skip`, and without `output = detailed` or
+//! functions generated alongside the throttled function, e.g. with `queries = true`.
+//!
+//! With `max_keys = N`, at most N keys are kept at once, e.g. against clients cycling
+//! through addresses: making room for a new key forgets about the one let through the
+//! least recently, whose calls then stop counting against its limit.
+//!
+//! With `fair = true`, a single noisy key cannot starve the others of the limit of the
+//! function, even while it is under its own limit: a key can only use its share of the
+//! limit of the function, which is split evenly between the keys called in its window,
+//! whether their calls were let through or not, rounded down but at least one call. A key
+//! alone in the window can use all of it.
+//!
+//! ```ignore
+//! #[throttle(global = (100, "1s"), per_key = (50, "1s"), key = tenant, fair = true)]
+//! pub(crate) fn query(tenant: TenantId, sql: &str) -> Rows {
+//!   ...
+//! }
+//! ```
+//!
+//! With `normalize_key = normalize`, keys are passed through a function or closure before
+//! they are looked up, e.g. to lowercase a hostname or to mask an IP address to its
+//! network, rather than at every call site. It takes the key and returns it, the owned
+//! argument itself for a single one and a tuple of them otherwise. With
+//! `hasher = FxBuildHasher`, the keys are hashed with the given `BuildHasher` rather than
+//! the default one of `HashMap`, which needs to implement `Default`. Generic hashers are
+//! written with turbofishes, e.g. `hasher = BuildHasherDefault::<FxHasher>`.
+//!
+//! ```ignore
+//! #[throttle(
+//!   global = (1000, "1s"),
+//!   per_key = (10, "1s"),
+//!   key = host,
+//!   normalize_key = |host: String| host.to_lowercase(),
+//!   hasher = FxBuildHasher
+//! )]
+//! pub(crate) fn fetch(host: &str, path: &str) -> Response {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `sharded`
+//!
+//! Splits the quota of a function called from many threads at once across a calls queue
+//! per core, each behind a lock of its own, rather than a single queue that the threads
+//! contend for. Threads mostly charge their calls to a shard of their own, and borrow
+//! from the other shards once theirs is full. Shards that are busy at the moment are not
+//! borrowed from, so a call may be skipped while some quota is left: a little accuracy is
+//! traded for much less contention. Only supported with `mode = skip`, and not with
+//! options looking into the calls of the function, such as `stats` or
+//! `output = detailed`.
+//!
+//! ```ignore
+//! #[throttle(1000, "1m", sharded = true)]
+//! pub(crate) fn send_metric(value: u64) {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `resolution`
+//!
+//! With `resolution = "1s"`, the calls in the window are counted in slots of the given
+//! duration rather than each keeping the time it was made at, so that the memory taken by
+//! the throttle is proportional to the number of slots in the window instead of to the
+//! limit, e.g. for limits of tens of thousands of calls. A call is only known to have
+//! been made within its slot, so it is counted as if it had been made at the end of it:
+//! calls stay in the window for up to the resolution longer, and the limit is never
+//! exceeded. The duration is written like the one of the limit.
+//!
+//! Only supported with `mode = skip` (or `warn` and `observe`), and without a shared
+//! quota or other options looking into the calls of the function, e.g. `queries = true`
+//! or `per_key`.
+//!
+//! ```ignore
+//! #[throttle(50_000, "1m", resolution = "1s")]
+//! fn ingest(event: &Event) -> Ingested {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `charge`
+//!
+//! Calls are charged against the quota when they start by default (`charge = start`).
+//! With `charge = end`, they are charged when they finish instead, including when they
+//! panic, so that the limit applies to completions rather than to starts, as some
+//! upstream quotas do for long-running requests. A call still takes its slot while it
+//! runs, so the limit is never exceeded by calls started in the meantime.
+//!
+//! Only supported with `mode = skip`, and without `sharded = true`, `scope = per_type` or
+//! `align = calendar`. Calls made through [`permits`](#permits) and
+//! [`variants`](#variants) are charged when they start.
+//!
+//! ```ignore
+//! #[throttle(10, "1m", charge = end)]
+//! fn export(report: &Report) -> Exported {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `refund`
+//!
+//! The calls of async functions are charged against the quota once their future is first
+//! polled, whether they complete or not (`refund = never`). With `refund = on_cancel`, a
+//! call whose future is dropped before it completes gives its slot back, e.g. when it
+//! loses a `select!` or times out, or when it panics. The slot is only given back while
+//! the call is still in the window.
+//!
+//! Only supported for async functions (or with `future = poll`) with `mode = skip`, and
+//! without `sharded = true`, `scope = per_type`, `per_key` or `charge = end`.
+//!
+//! ```ignore
+//! #[throttle(10, "1m", refund = on_cancel)]
+//! async fn search(query: &str) -> Results {
+//!   ...
+//! }
+//!
+//! tokio::select! {
+//!   results = search("rust") => show(results),
+//!   _ = cancelled() => {} // The search doesn't count against the quota.
+//! }
+//! ```
+//!
+//! ### `concurrency`
+//!
+//! Additionally bounds how many executions can be in flight at the same time, like
+//! `#[max_concurrent(...)]`: a call must both fit in the rate window and acquire a
+//! concurrency permit. The permit is acquired first, so a call skipped for lack of a
+//! permit is not charged against the quota, and it is released when the function returns,
+//! even if it panics. Only supported with `mode = skip`.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", concurrency = 2)]
+//! pub(crate) fn fetch_page(url: &str) -> String {
+//!   ...
+//! }
+//! ```
+//!
+//! With `on_busy = wait`, the calls of async functions wait for a permit instead of being
+//! skipped, suspending their task on a semaphore of tokio. A call whose future is dropped
+//! while waiting gives up its place, and the permit of a call is released when its future
+//! is dropped as well. This requires the `tokio` feature of the `throttle_my_fn_runtime`
+//! crate, and works with `reentrant = deny` as well.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", concurrency = 2, on_busy = wait)]
+//! pub(crate) async fn fetch_page(url: &str) -> String {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `reentrant`
+//!
+//! With `reentrant = deny`, a call is skipped while the function is already executing,
+//! whether on another thread or further up the same thread's stack, so that executions
+//! never overlap. Skipped calls are not charged against the quota. This is the same as
+//! `concurrency = 1`, and is only supported with `mode = skip`. The default is
+//! `reentrant = allow`.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", reentrant = deny)]
+//! pub(crate) fn rebuild_index() {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `skipped`
+//!
+//! Keeps the function's return type instead of wrapping it in an `Option`, and returns
+//! the given expression when a call is skipped. This is useful when the caller cannot
+//! handle an `Option`, e.g. for web handlers that answer throttled requests with a
+//! `429 Too Many Requests` response. Only supported with `mode = skip`.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", skipped = StatusCode::TOO_MANY_REQUESTS.into_response())]
+//! pub(crate) async fn create_item(Json(item): Json<Item>) -> Response {
+//!   ...
+//! }
+//! ```
+//!
+//! With `skip_into = expr`, the expression is converted into the function's return type
+//! with `Into` instead, so that route handlers can keep their return type and answer
+//! throttled requests with a rejection of their own, whatever response type they return:
+//!
+//! ```ignore
+//! impl From<RateLimited> for ApiResponse {
+//!   ...
+//! }
+//!
+//! #[throttle(10, "1s", skip_into = RateLimited)]
+//! pub(crate) async fn create_item(Json(item): Json<Item>) -> ApiResponse {
+//!   ...
+//! }
+//! ```
+//!
+//! The other attributes that skip calls support the `skipped` option as well:
+//! `#[cooldown(...)]`, `#[sample(...)]`, `#[once]` (without `cache_last`),
+//! `#[max_concurrent(...)]` and `#[circuit_breaker(...)]`.
+//!
+//! ```ignore
+//! #[cooldown("5s", skipped = false)]
+//! pub(crate) fn refresh_cache() -> bool {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `flatten`
+//!
+//! A function that already returns an `Option<T>` would return an `Option<Option<T>>`
+//! once throttled, which every caller then has to flatten. With `flatten = true`, skipped
+//! calls return `None` instead, merging them with the function's own `None` so that it
+//! keeps returning an `Option<T>`. This is the same as `skipped = None`, but only allowed
+//! for functions returning an `Option<T>`. Only supported with `mode = skip`, and cannot
+//! be combined with the `skipped` or `output` options.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", flatten = true)]
+//! pub(crate) fn lookup(id: u32) -> Option<User> {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `output`
+//!
+//! With `output = detailed`, the function returns a `ThrottleResult<T>` from the
+//! `throttle_my_fn_runtime` crate instead of an `Option<T>`. Its `Executed(T)` variant
+//! holds the returned value of executed calls, while its
+//! `Skipped { retry_after, window_remaining }` variant tells skipped calls how long until
+//! a slot frees up and how many free slots are left in the window, for callers
+//! implementing their own backoff. Only supported with `mode = skip`, and cannot be
+//! combined with the `skipped` option.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", output = detailed)]
+//! pub(crate) fn send_request(request: Request) -> Response {
+//!   ...
+//! }
+//!
+//! match send_request(request) {
+//!   ThrottleResult::Executed(response) => handle(response),
+//!   ThrottleResult::Skipped { retry_after, .. } => thread::sleep(retry_after),
+//! }
+//! ```
+//!
+//! With `output = result_with_args`, the function returns a `Result<T, (A, B, ...)>`
+//! instead, where skipped calls get the tuple of their arguments back in the `Err`
+//! variant (a tuple of one for a single argument), rather than having them dropped. This
+//! lets callers retry later with arguments that are expensive to build or cannot be
+//! cloned. Receivers are not part of the tuple, and the parameters of methods must be
+//! plain identifiers. Only supported with `mode = skip`, and cannot be combined with the
+//! `skipped` option.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", output = result_with_args)]
+//! pub(crate) fn upload(file: File, name: String) -> Receipt {
+//!   ...
+//! }
+//!
+//! if let Err((file, name)) = upload(file, name) {
+//!   retry_queue.push((file, name));
+//! }
+//! ```
+//!
+//! ### `queries`
+//!
+//! With `queries = true`, functions to query the state of the throttle without calling
+//! the function are generated next to it, with the same visibility:
+//!
+//! * `NAME_retry_after() -> Option<Duration>`: how long until a call would no longer be
+//!   throttled, or `None` if it would execute right away. Schedulers can sleep exactly as
+//!   long as needed instead of polling the function.
+//!
+//! * `NAME_next_slot() -> Option<Instant>`: the same as an `Instant` rather than a
+//!   `Duration`, for executors scheduling their own wake-ups at an exact time.
+//!
+//! * `NAME_is_available() -> bool`: whether a call would execute right away. Probing
+//!   doesn't charge the call against the quota, unlike calling the function.
+//!
+//! Only the quota is queried: the `concurrency` option and pending deferred calls are
+//! not taken into account.
+//!
+//! Only supported for free functions (not methods or other associated functions), and
+//! within modules only with `share = false`.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", queries = true)]
+//! pub(crate) fn sync_inbox() -> usize {
+//!   ...
+//! }
+//!
+//! if let Some(delay) = sync_inbox_retry_after() {
+//!   thread::sleep(delay);
+//! }
+//! ```
+//!
+//! ### `expose`
+//!
+//! With `expose = true`, the limiter of the function is exposed as the `STATE` static of
+//! a `NAME_throttle` module next to it, with the same visibility, for other code paths to
+//! draw from the same quota without going through the function:
+//!
+//! * `STATE.try_acquire() -> bool`: charges a call against the quota if there is a free
+//!   slot in the window, like a call to the function would.
+//!
+//! * `STATE.retry_after() -> Option<Duration>`: how long until there is a free slot.
+//!
+//! * `STATE.in_window() -> usize`: the number of calls in the window.
+//!
+//! * `STATE.reset()`: forgets about the calls in the window.
+//!
+//! Only supported for free functions, and within modules only with `share = false`.
+//!
+//! ```ignore
+//! #[throttle(100, "1m", expose = true)]
+//! pub(crate) fn send_sms(to: &str, text: &str) -> Status {
+//!   ...
+//! }
+//!
+//! // A bulk sender charges the same quota for each message of a batch.
+//! if send_sms_throttle::STATE.try_acquire() {
+//!   gateway.send_batch_item(item);
+//! }
+//! ```
+//!
+//! With `emit = module` (rather than the default `emit = inline`), the limiter is exposed
+//! as with `expose = true`, and the `NAME_throttle` module also holds `retry_after()`,
+//! `in_window()` and `reset()` functions, along with `stats()` with `stats = true`, so
+//! that tooling can find every throttle at predictable paths, e.g.
+//! `send_sms_throttle::reset`.
+//!
+//! ### `toggle`
+//!
+//! With `toggle = true`, a `NAME_throttle_enabled(set: bool)` function is generated next
+//! to the throttled function, with the same visibility, to switch throttling off and on
+//! at runtime, e.g. in tests or behind a feature switch. While throttling is off, every
+//! call is let through without being charged against the quota, but still counts as
+//! executed in the [`stats`](#stats) of the function. Throttling is on to begin with.
+//!
+//! Only supported for free functions.
+//!
+//! ```ignore
+//! #[throttle(10, "1m", toggle = true)]
+//! fn notify(user: &User) -> Sent {
+//!   ...
+//! }
+//!
+//! // Maintenance scripts notify everyone at once.
+//! notify_throttle_enabled(false);
+//! ```
+//!
+//! ### `with_override`
+//!
+//! With `with_override = true`, an `override_: ThrottleOverride` parameter is added to
+//! the end of the function's parameters, for call sites to control how each call is
+//! throttled without a separate function:
+//!
+//! * `ThrottleOverride::Default`: the call is throttled like any other.
+//!
+//! * `ThrottleOverride::Bypass`: the call is executed right away without being charged
+//!   against the quota, e.g. from an admin console or replay tooling. It is counted as
+//!   executed by the [`stats`](#stats) all the same.
+//!
+//! * `ThrottleOverride::CostMultiplier(n)`: the call takes `n` slots of the quota rather
+//!   than one, and is skipped unless they are all free. A multiplier of zero bypasses the
+//!   throttle, as with `ThrottleOverride::Bypass`.
+//!
+//! The `ThrottleOverride` type comes from the `throttle_my_fn_runtime` crate. Only
+//! supported with `mode = skip` (or `warn` and `observe`), and without `sharded = true`,
+//! `resolution`, `policy`, `priorities`, `charge = end`, `refund = on_cancel` or
+//! `variants`. With `per_key`, calls are charged a single slot of the limit of their key.
+//!
+//! ```ignore
+//! #[throttle(100, "1m", with_override = true)]
+//! pub(crate) fn export(report: &Report) -> Exported {
+//!   ...
+//! }
+//!
+//! let exported = export(&report, ThrottleOverride::CostMultiplier(10));
+//! ```
+//!
+//! ### `reset_after_idle`
+//!
+//! With `reset_after_idle = "5m"`, the calls in the window are forgotten once the
+//! function hasn't been called for the given duration, skipped calls included, so that
+//! the next call starts from an empty window rather than waiting for older calls to fall
+//! out of it. With [`per_key`](#per_key-and-key), the calls of every key are forgotten as
+//! well. The duration is written like the one of the limit, and only makes a difference
+//! when it is shorter than it.
+//!
+//! Not supported with [`sharded`](#sharded).
+//!
+//! ```ignore
+//! // A burst after a quiet hour starts over, rather than counting against the day.
+//! #[throttle(100, "1d", reset_after_idle = "1h")]
+//! fn sync(account: &Account) -> Synced {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `cold_start_cap`
+//!
+//! With `cold_start_cap = N`, at most `N` calls are let through in the first window after
+//! the function is first called, whatever its limit, e.g. to protect an upstream from a
+//! storm of reconnections when a whole fleet restarts at once. The limit applies as usual
+//! from the second window on, unlike a warm-up that would ramp it up over several
+//! windows. Only supported with `mode = skip` (or `warn` and `observe`), and without
+//! `sharded = true`, `resolution` or `policy`.
+//!
+//! ```ignore
+//! #[throttle(1000, "1s", cold_start_cap = 50)]
+//! pub(crate) fn connect(peer: &Peer) -> Connection {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `stats`
+//!
+//! With `stats = true`, the calls to the function are counted with atomics, and a
+//! `NAME_stats() -> ThrottleStats` function reporting the counts is generated next to it,
+//! with the same visibility. The statistics are the number of executed and skipped calls,
+//! the longest a call has waited for a free slot through the `NAME_wait` variant or with
+//! `mode = wait_with_deadline`, the median (`p50_wait`) and 95th percentile (`p95_wait`)
+//! of those waits, and the number of calls in the current window. The percentiles are
+//! read from a histogram of a few buckets per power of two, and are at most a quarter
+//! over the actual waits. Calls reserving a slot with `permits = true` are counted as
+//! executed when the slot is reserved.
+//!
+//! The `ThrottleStats` type comes from the `throttle_my_fn_runtime` crate. Only supported
+//! with `mode = skip`, for free functions, and within modules only with `share = false`.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", stats = true)]
+//! pub(crate) fn send_heartbeat() {
+//!   ...
+//! }
+//!
+//! let ThrottleStats { executed, skipped, .. } = send_heartbeat_stats();
+//! ```
+//!
+//! ### `shadow`
+//!
+//! With `shadow = (times, duration)`, every call is also charged against a window of the
+//! candidate limit, which never skips anything but counts the calls it would have skipped
+//! in the `shadow_skipped` field of the [`stats`](#stats) of the function, whose
+//! `NAME_stats()` function is generated as with `stats = true`. This compares a proposed
+//! change of the limit against the actual traffic, rather than by analyzing logs offline.
+//! Only supported with `mode = skip` (including `mode = observe`), for free functions.
+//!
+//! ```ignore
+//! #[throttle(50, "1s", shadow = (20, "1s"))]
+//! pub(crate) fn fetch_quote(symbol: &str) -> Quote {
+//!   ...
+//! }
+//!
+//! let stats = fetch_quote_stats();
+//! let calls = stats.executed + stats.skipped;
+//! let would_skip = stats.shadow_skipped as f64 / calls as f64;
+//! ```
+//!
+//! ### `on_execute`
+//!
+//! Calls the given handler right before every call that is let through is executed, with
+//! the name of the function and the number of calls in the window, including this one.
+//! This is useful e.g. for stamping audit logs for the admitted calls of sensitive
+//! functions. The handler is called once the lock on the quota is released, and can be a
+//! path to a function or a closure taking a `&'static str` and a `usize`.
+//!
+//! ```ignore
+//! fn audit(function: &'static str, occupancy: usize) {
+//!   log::info!("executing {} ({} calls in the window)", function, occupancy);
+//! }
+//!
+//! #[throttle(10, "1m", on_execute = audit)]
+//! pub(crate) fn delete_account(id: AccountId) {
+//!   ...
+//! }
+//! ```
+//!
+//! Only supported with `mode = skip`, and not together with `permits` or `variants`,
+//! whose calls would go unreported.
+//!
+//! ### `permits`
+//!
+//! With `permits = true`, a `NAME_acquire()` function is generated next to the function,
+//! with the same visibility. It reserves a slot in the quota and returns a permit, or
+//! `None` if the quota is exhausted. Running the permit with `permit.run(args)` calls the
+//! function without charging it against the quota again, while dropping the permit
+//! without running it returns the slot to the quota. Callers can reserve capacity early,
+//! do some validation, and only then commit.
+//!
+//! The permit type is generated next to the function as well, named after it in
+//! `CamelCase`, e.g. `SendRequestPermit` for `send_request`. Only supported with
+//! `mode = skip` and without the `concurrency` option, for free functions (not methods or
+//! other associated functions), and within modules only with `share = false`.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", permits = true)]
+//! pub(crate) fn send_request(request: Request) -> Response {
+//!   ...
+//! }
+//!
+//! let permit = send_request_acquire()?;
+//! let request = build_request()?; // Dropping the permit returns its slot.
+//! let response = permit.run(request);
+//! ```
+//!
+//! A `NAME_acquire_n(n)` function is generated as well, reserving `n` slots at once for
+//! a caller about to make `n` calls, and returning a permit for each of them. Either all
+//! of the slots are reserved, or none of them if there are fewer left, so that callers
+//! racing for the same slots cannot end up with some of them each.
+//!
+//! ```ignore
+//! let permits = send_request_acquire_n(requests.len())?;
+//! for (permit, request) in permits.into_iter().zip(requests) {
+//!   responses.push(permit.run(request));
+//! }
+//! ```
+//!
+//! ### `expose_inner`
+//!
+//! With `expose_inner = "NAME"`, the unthrottled function is also available under the
+//! given name, with the same signature and visibility. Benchmarks, tests and trusted
+//! internal callers can then call the real function directly. Other attributes on the
+//! function (e.g. `#[inline]`) are kept on the copy, except for its docs and
+//! `#[no_mangle]`/`#[export_name]`. Cannot be used on impl blocks or modules.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", expose_inner = "send_request_raw")]
+//! pub(crate) fn send_request(request: Request) -> Response {
+//!   ...
+//! }
+//!
+//! let response = send_request_raw(request);
+//! ```
+//!
+//! ### `variants`
+//!
+//! With `variants(try, wait)`, a `NAME_wait` variant of the function is generated next to
+//! it, with the same signature and visibility. While the function itself (the `try`
+//! variant) skips calls when the quota is exhausted, the `wait` variant blocks the
+//! calling thread until a slot frees up and returns the value directly. Both variants
+//! share the same quota, for call sites that legitimately want different behaviors. Only
+//! supported with `mode = skip` and without the `concurrency` option, for free functions
+//! that aren't async, and within modules only with `share = false`.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", variants(try, wait))]
+//! pub(crate) fn send_email(to: &str) -> Status {
+//!   ...
+//! }
+//!
+//! let status: Option<Status> = send_email("admin@example.com");
+//! let status: Status = send_email_wait("user@example.com");
+//! ```
+//!
+//! With `variants(try, async)`, a `NAME_async` variant is generated as well, for calling
+//! a blocking function from async code. It waits for a slot on the timer of the tokio
+//! runtime rather than blocking the executor, and then runs the function on the blocking
+//! thread pool with `spawn_blocking`, so its arguments and return value must be
+//! `Send + 'static`. It requires the `tokio` feature of the `throttle_my_fn_runtime`
+//! crate, and a function that isn't async itself.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", variants(try, async))]
+//! pub(crate) fn compress(data: Vec<u8>) -> Vec<u8> {
+//!   ...
+//! }
+//!
+//! let compressed: Vec<u8> = compress_async(data).await;
+//! ```
+//!
+//! ### `consts`
+//!
+//! With `consts = true`, the limit is also available as constants next to the function,
+//! with the same visibility: `NAME_THROTTLE_TIMES: usize` and
+//! `NAME_THROTTLE_PERIOD: Duration`, e.g. to display the limit or to size batches without
+//! duplicating its numbers. For methods, they are associated constants of the impl block,
+//! so they cannot be used on trait impls. Requires the number of calls and the duration
+//! to be constant expressions.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", consts = true)]
+//! pub(crate) fn upload(batch: &[Item]) {
+//!   ...
+//! }
+//!
+//! for batch in items.chunks(items.len().div_ceil(UPLOAD_THROTTLE_TIMES)) {
+//!   upload(batch);
+//! }
+//! ```
+//!
+//! ### `future`
+//!
+//! For functions returning `impl Future<Output = T>` instead of being `async fn`, selects
+//! when a call is charged: when the function is called (`future = call`, the default), or
+//! when the returned future is first polled (`future = poll`). With the latter, futures
+//! that are never awaited, e.g. the losing branches of a select, are not charged, and the
+//! function returns an `impl Future<Output = Option<T>>` instead of an
+//! `Option<impl Future<Output = T>>`. Only supported with `mode = skip`.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", future = poll)]
+//! pub(crate) fn fetch(url: &str) -> impl Future<Output = String> + '_ {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `scope`
+//!
+//! All the instantiations of a generic function share a single quota by default
+//! (`scope = global`). With `scope = per_type`, each instantiation gets its own instead,
+//! i.e. each combination of the function's type parameters. The instantiations are told
+//! apart by the names of their type parameters, so arguments of `impl Trait` types, which
+//! have unnamed type parameters, are not. Only supported with `mode = skip`, and without
+//! a shared quota or functions generated alongside the throttled function, e.g. with
+//! `queries = true`.
+//!
+//! ```ignore
+//! #[throttle(1, "1s", scope = per_type)]
+//! pub(crate) fn log<M: Debug>(message: M) {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `group`
+//!
+//! With `group = "name"`, every function throttled with the same group name shares a
+//! single quota, whichever crate it is in, e.g. the crates of a workspace calling the
+//! same upstream service. The calls are kept in the `throttle_my_fn_runtime` crate, which
+//! needs to be a dependency of each of them, and all of them need to link the same
+//! version of it. Each function still charges its calls against its own limit, which
+//! should then be the same for the whole group.
+//!
+//! Can be put on impl blocks and modules as well. Not supported with `scope = per_type`,
+//! `sharded = true` or functions generated alongside the throttled function, e.g. with
+//! `queries = true`.
+//!
+//! ```ignore
+//! // In the crate of the accounts service.
+//! #[throttle(5000, "1h", group = "github")]
+//! pub fn fetch_repos(user: &str) -> Repos {
+//!   ...
+//! }
+//!
+//! // In the crate of the billing service.
+//! #[throttle(5000, "1h", group = "github")]
+//! pub fn fetch_sponsors(user: &str) -> Sponsors {
+//!   ...
+//! }
+//! ```
+//!
+//! ### `recursion`
+//!
+//! By default, the calls a throttled function makes to itself go through the throttle
+//! like any other call: each of them is charged against the quota and returns an
+//! `Option<T>` (`recursion = charge`). With `recursion = bypass`, they go straight to the
+//! user-provided function instead, so that only the outermost call is charged and
+//! recursive calls return a `T`. Only direct calls by name, e.g. `factorial(n - 1)`, are
+//! redirected, and methods are not supported.
+//!
+//! ```ignore
+//! #[throttle(10, "1s", recursion = bypass)]
+//! pub(crate) fn factorial(n: u64) -> u64 {
+//!   match n {
+//!     0 => 1,
+//!     n => n * factorial(n - 1),
+//!   }
+//! }
+//! ```
+//!
+//! ## Methods, impl blocks and modules
+//!
+//! Methods can be throttled like free functions. Each method has a single quota shared by
+//! all instances of its type, rather than one per instance.
+//!
+//! The attribute can also be put on an impl block, to throttle all of its methods taking
+//! `self` at once, which is easier than keeping the attributes of many methods in sync.
+//! By default, the methods share a single quota, while with `share = false` each of them
+//! gets its own. Methods that carry their own `#[throttle(...)]` attribute keep their own
+//! quota, and associated functions without a receiver, like constructors, are not
+//! throttled.
+//!
+//! ```ignore
+//! use throttle_my_fn::throttle;
+//!
+//! #[throttle(100, "1s")]
+//! impl Client {
+//!   pub(crate) fn new() -> Self {
+//!     ...
+//!   }
+//!
+//!   pub(crate) fn get(&self, path: &str) -> Response {
+//!     ...
+//!   }
+//!
+//!   pub(crate) fn post(&self, path: &str, body: &str) -> Response {
+//!     ...
+//!   }
+//! }
+//! ```
+//!
+//! A shared quota cannot be combined with the `concurrency` option.
+//!
+//! For a quota per instance instead, e.g. one client object for one upstream connection
+//! with its own quota, put `#[throttle_group(...)]` on the struct and `#[throttle_group]`
+//! on the methods drawing from its quota. The struct gets a `throttle_group` field
+//! holding the quota of each instance, which is initialized with the generated
+//! `new_throttle_group()` function.
+//!
+//! ```ignore
+//! use throttle_my_fn::throttle_group;
+//!
+//! #[throttle_group(100, "1s")]
+//! pub(crate) struct Client {
+//!   url: String,
+//! }
+//!
+//! impl Client {
+//!   pub(crate) fn new(url: String) -> Self {
+//!     Client { url, throttle_group: Client::new_throttle_group() }
+//!   }
+//!
+//!   #[throttle_group]
+//!   pub(crate) fn get(&self, path: &str) -> Response {
+//!     ...
+//!   }
+//!
+//!   #[throttle_group]
+//!   pub(crate) fn post(&self, path: &str, body: &str) -> Response {
+//!     ...
+//!   }
+//! }
+//! ```
+//!
+//! The attribute can be put on an inline module as well, to throttle all of the functions
+//! directly in it, e.g. everything in an `outbound::github` module. The `share` option
+//! works the same way as for impl blocks.
+//!
+//! ```ignore
+//! #[throttle(5000, "1h")]
+//! mod github {
+//!   pub(crate) fn repos(user: &str) -> Vec<Repo> {
+//!     ...
+//!   }
+//!
+//!   pub(crate) fn issues(repo: &str) -> Vec<Issue> {
+//!     ...
+//!   }
+//! }
+//! ```
+//!
+//! Trait methods can be throttled as well, both default methods in trait definitions and
+//! methods in trait impls, as long as they keep the trait's return types with the
+//! `skipped` option. The methods of a trait impl each have a quota per impl, while a
+//! default method has a single quota shared by all the implementors that don't override
+//! it. On a trait impl, the attribute's `skipped` value applies to all methods, so
+//! methods with other return types need an attribute of their own.
+//!
+//! ```ignore
+//! trait Notifier {
+//!   #[throttle(10, "1s", skipped = false)]
+//!   fn notify(&self, message: &str) -> bool {
+//!     ...
+//!   }
+//! }
+//! ```
+//!
+//! ## Return types
+//!
+//! Functions returning `impl Trait` types, or references borrowed from their arguments,
+//! keep their signatures and only get their return types wrapped, e.g. in an `Option`.
+//! Lifetimes can be elided or explicit, e.g. `fn head<'a>(text: &'a str) -> &'a str`,
+//! including those of the type of the methods of an impl block.
+//! The exceptions are `#[memoize(...)]`, `#[once(cache_last)]` and the `skipped` option,
+//! which do not support `impl Trait` return types, since they need to name the return
+//! type outside of the function's signature.
+//!
+//! ```ignore
+//! #[throttle(10, "1s")]
+//! pub(crate) fn words(text: &str) -> impl Iterator<Item = &str> {
+//!   text.split_whitespace()
+//! }
+//! ```
+//!
+//! ## Attributes of the function
+//!
+//! The attributes written on a throttled function are kept, and most of them go on the
+//! wrapper, such as doc comments, `#[must_use]` or `#[deprecated]`. Hints about the code
+//! generated for the body of the function, `#[inline]` and `#[cold]`, go on the function
+//! the wrapper calls instead, and lint levels such as `#[allow(...)]` go on both, except
+//! for methods, whose body is evaluated in the wrapper. A `#[cfg(...)]` attribute gates
+//! the functions and constants generated next to the wrapper as well, such as the
+//! `NAME_stats()` function. Test functions cannot be throttled, since the test harness
+//! calls them directly, so `#[test]` and `#[bench]` are rejected.
+//!
+//! ```ignore
+//! #[throttle(10, "1s")]
+//! #[cfg(feature = "telemetry")]
+//! #[inline(always)]
+//! pub(crate) fn report(event: &Event) {
+//!   ...
+//! }
+//! ```
+//!
+//! ## Foreign functions
+//!
+//! `extern "C"` functions (along with `#[no_mangle]` or `#[export_name = ...]`) can be
+//! throttled as well. They keep their ABI and attributes, and since foreign callers
+//! cannot handle an `Option`, they require the `skipped` option to keep their return
+//! types, e.g. to return a sentinel value when a call is skipped. The same goes for the
+//! other attributes supporting the `skipped` option, while attributes that always change
+//! the return type, like `#[timeout(...)]`, are not supported for `extern` functions.
+//!
+//! ```ignore
+//! #[no_mangle]
+//! #[throttle(10, "1s", skipped = -1)]
+//! pub extern "C" fn process(value: i32) -> i32 {
+//!   ...
+//! }
+//! ```
+//!
+//! ## Async functions
+//!
+//! All attributes support async functions, except for `#[debounce(...)]`,
+//! `#[once(cache_last)]`, `#[retry(...)]` and the deferred modes of `#[throttle(...)]`.
+//! A call is charged when its future is first polled, and no locks are held while the
+//! function's own future is awaited, so the returned futures are `Send` whenever the
+//! function's are. Generic functions and `impl Trait` return types are supported as
+//! well.
+//!
+//! ## Debouncing
+//!
+//! The `#[debounce(...)]` attribute only executes the function once calls to it have
+//! stopped for a period of time: every call replaces the previous pending call and
+//! restarts the quiet period, and once the period has passed the latest call is executed
+//! on a background thread with its arguments.
+//!
+//! ```ignore
+//! use throttle_my_fn::debounce;
+//!
+//! #[debounce(Duration::from_millis(300))]
+//! pub(crate) fn search(query: String) -> Vec<String> {
+//!   ...
+//! }
+//! ```
+//!
+//! Like in deferred mode, the function returns a `ThrottleHandle<T>` which resolves to
+//! the return value once the call has executed, or to `None` if it was replaced by a
+//! later call.
+//!
+//! The `edge` option is also supported: `edge = trailing` is the default described above,
+//! `edge = leading` executes a call immediately if the quiet period has passed since the
+//! previous call (and skips it otherwise, resolving its handle to `None`), and
+//! `edge = both` does both.
+//!
+//! ```ignore
+//! #[debounce(Duration::from_millis(300), edge = leading)]
+//! pub(crate) fn on_click(button: Button) {
+//!   ...
+//! }
+//! ```
+//!
+//! ## Cooldowns
+//!
+//! The `#[cooldown(...)]` attribute is a shorthand for `#[throttle(1, ...)]`: it skips
+//! all calls made within the given duration of the last executed call. It reads better
+//! and only needs to keep track of a single timestamp instead of a list of them.
+//!
+//! ```ignore
+//! use throttle_my_fn::cooldown;
+//!
+//! #[cooldown(Duration::from_secs(5))]
+//! pub(crate) fn refresh_cache() {
+//!   ...
+//! }
+//! ```
+//!
+//! Like with `#[throttle(...)]`, the function's return value is wrapped in an `Option`.
+//!
+//! ## Sampling
+//!
+//! The `#[sample(...)]` attribute executes the function only once every N calls: on the
+//! first call and then on every Nth call after it. Unlike throttling, sampling is
+//! count-based rather than time-based, which is the right model for high-volume logging
+//! or telemetry call sites.
+//!
+//! ```ignore
+//! use throttle_my_fn::sample;
+//!
+//! #[sample(100)]
+//! pub(crate) fn log_request(request: &Request) {
+//!   ...
+//! }
+//! ```
+//!
+//! Like with `#[throttle(...)]`, the function's return value is wrapped in an `Option`.
+//!
+//! ## Running once
+//!
+//! The `#[once]` attribute executes the function at most once per process, thread-safely,
+//! and skips all later calls. With `#[once(cache_last)]`, later calls return a clone of
+//! the result of the first call instead of `None` (callers that come in while the first
+//! call is executing wait for its result).
+//!
+//! ```ignore
+//! use throttle_my_fn::once;
+//!
+//! #[once]
+//! pub(crate) fn print_banner() {
+//!   ...
+//! }
+//!
+//! #[once(cache_last)]
+//! pub(crate) fn load_config(path: &str) -> Config {
+//!   ...
+//! }
+//! ```
+//!
+//! Like with `#[throttle(...)]`, the function's return value is wrapped in an `Option`.
+//!
+//! ## Limiting concurrency
+//!
+//! The `#[max_concurrent(...)]` attribute bounds how many executions of the function can
+//! be in flight at the same time, independently of the call rate. A call made while the
+//! limit is reached (e.g. by other threads) is skipped. The slot is released when the
+//! function returns, even if it panics.
+//!
+//! ```ignore
+//! use throttle_my_fn::max_concurrent;
+//!
+//! #[max_concurrent(4)]
+//! pub(crate) fn resize_image(image: &Image) -> Image {
+//!   ...
+//! }
+//! ```
+//!
+//! Like with `#[throttle(...)]`, the function's return value is wrapped in an `Option`.
+//!
+//! ## Circuit breaking
+//!
+//! The `#[circuit_breaker(...)]` attribute is meant for functions returning a `Result`.
+//! After `failures` consecutive calls have returned an error, the circuit opens and all
+//! calls are skipped until `reset` has elapsed. Then a single probing call is let through
+//! (half-open): if it succeeds the circuit closes again, otherwise it stays open for
+//! another `reset` period.
+//!
+//! ```ignore
+//! use throttle_my_fn::circuit_breaker;
+//!
+//! #[circuit_breaker(failures = 5, reset = "30s")]
+//! pub(crate) fn fetch(url: &str) -> Result<String, Error> {
+//!   ...
+//! }
+//! ```
+//!
+//! Like with `#[throttle(...)]`, the function's return value is wrapped in an `Option`,
+//! which is `None` when the call was skipped because the circuit is open.
+//!
+//! ## Retrying
+//!
+//! The `#[retry(...)]` attribute is meant for functions returning a `Result`. A call that
+//! returns an error is retried up to the given number of times, optionally waiting
+//! `backoff` before the first retry and twice as long before every retry after it. The
+//! function's arguments are cloned for every attempt, so they need to implement `Clone`.
+//!
+//! ```ignore
+//! use throttle_my_fn::{retry, throttle};
+//!
+//! #[retry(3, backoff = "100ms")]
+//! #[throttle(10, "1s")]
+//! pub(crate) fn fetch(url: &str) -> Result<String, Error> {
+//!   ...
+//! }
+//! ```
+//!
+//! When stacked on top of `#[throttle(...)]`, every attempt goes through the rate limit.
+//! An attempt that gets throttled doesn't use up a retry: the call waits until a slot
+//! frees up instead of bypassing the limit, and the function keeps returning a plain
+//! `Result`. The throttle is given `queries = true` for the call to sleep until its next
+//! free slot. Throttles that cannot have query functions, e.g. with `per_key`, and calls
+//! throttled with free slots, e.g. outside of an `active` window, are tried again after
+//! the backoff (or a millisecond) instead.
+//!
+//! ## Memoizing
+//!
+//! The `#[memoize(...)]` attribute caches the function's return values, keyed by its
+//! arguments, for a time-to-live: a call with the same arguments as an executed call made
+//! less than `ttl` ago returns a clone of that call's value without executing the
+//! function. The arguments need to implement `Hash`, `Eq` and `Clone` (reference
+//! arguments like `&str` are stored as their owned counterpart) and the return value
+//! needs to implement `Clone`.
+//!
+//! ```ignore
+//! use throttle_my_fn::{memoize, throttle};
+//!
+//! #[memoize(ttl = "10s")]
+//! #[throttle(1, "1s")]
+//! pub(crate) fn exchange_rate(currency: &str) -> f64 {
+//!   ...
+//! }
+//! ```
+//!
+//! When stacked on top of `#[throttle(...)]`, calls are only throttled when they miss the
+//! cache, so that the function executes at most this often and reuses its last answer in
+//! the meantime. Its return value is then wrapped in an `Option`, which is `None` when a
+//! call missed the cache and was throttled.
+//!
+//! Expired values are evicted whenever a new value is cached. When the arguments are
+//! user-controlled, e.g. IP addresses or session ids, the cache can still grow without
+//! bound within the time-to-live. It can be bounded with `max_keys`, in which case the
+//! oldest value is evicted to make room for a new one when the cache is full.
+//!
+//! ```ignore
+//! #[memoize(ttl = "1m", max_keys = 10_000)]
+//! pub(crate) fn geolocate(ip: IpAddr) -> Option<Country> {
+//!   ...
+//! }
+//! ```
+//!
+//! ## Timeouts
+//!
+//! The `#[timeout(...)]` attribute gives up on calls that take longer than the given
+//! duration. Every call is executed on a watchdog thread of its own while the caller
+//! waits for it: once the duration has passed, the caller gets `None` and the call is
+//! abandoned, running to completion in the background with its result discarded. The
+//! function's arguments and return value therefore need to be `Send + 'static`. Every
+//! call costs spawning a thread, which lives for as long as the call runs, abandoned
+//! calls included: timeouts are meant for calls slow enough for that not to matter, e.g.
+//! over the network.
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use throttle_my_fn::{throttle, timeout};
+//!
+//! #[timeout(Duration::from_secs(2))]
+//! #[throttle(10, "1s")]
+//! pub(crate) fn query(sql: String) -> Vec<Row> {
+//!   ...
+//! }
+//! ```
+//!
+//! Like with `#[throttle(...)]`, the function's return value is wrapped in an `Option`.
+//! Stacked together as above, the function returns an `Option<Option<Vec<Row>>>`, which
+//! is `None` when the call was throttled and `Some(None)` when it timed out.
+//!
+//! The future of an async function is awaited on the timer of the tokio runtime instead,
+//! without any thread of its own, and is dropped once the duration has passed, which
+//! cancels the call. This requires the `tokio` feature of the `throttle_my_fn_runtime`
+//! crate.
+//!
+//! ## Throttling blocks
+//!
+//! Not everything worth throttling is a function of its own. The `throttle_block!(...)`
+//! macro takes the same arguments and options as `#[throttle(...)]`, followed by a block,
+//! and throttles that block in place. It evaluates to an `Option` of the block's value,
+//! and every call site has its own quota.
+//!
+//! ```ignore
+//! use throttle_my_fn::throttle_block;
+//!
+//! for event in events {
+//!   throttle_block!(1, "1s", {
+//!     eprintln!("Processing events, currently at {}", event.id);
+//!   });
+//!   ...
+//! }
+//! ```
+//!
+//! Since the block is not moved into a function of its own, `return`, `?` and `break`
+//! inside of it apply to the surrounding code. Only skipping throttled calls is
+//! supported: the `mode` option and trailing edges cannot be used.
+//!
+//! ## Throttling closures
+//!
+//! The attribute cannot be applied to closures, so the `throttle_fn!(...)` macro creates
+//! a throttled closure out of a closure instead. It takes the same arguments as
+//! `#[throttle(...)]`, followed by the closure. The throttled closure takes the same
+//! arguments and returns an `Option` of the closure's return value.
+//!
+//! ```ignore
+//! use throttle_my_fn::throttle_fn;
+//!
+//! let double = throttle_fn!(10, "1s", |x: u32| x * 2);
+//! assert_eq!(double(21), Some(42));
+//! ```
+//!
+//! Every closure created by the macro owns its quota, unlike throttled functions and
+//! blocks which share theirs through statics. Only skipping throttled calls is supported,
+//! and the `concurrency` option cannot be used.
+//!
+//! ## Throttling iterators
+//!
+//! Pacing a loop is the same problem as throttling a function. The
+//! `throttle_my_fn_runtime` crate provides a `ThrottleExt` extension trait with a
+//! `throttle(times, duration)` adapter for all iterators, which blocks when pulling an
+//! item would exceed `times` items per `duration`.
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use throttle_my_fn_runtime::ThrottleExt;
+//!
+//! for job in jobs.into_iter().throttle(10, Duration::from_secs(1)) {
+//!   job.run();
+//! }
+//! ```
+//!
+//! With the `futures` feature of `throttle_my_fn_runtime`, a `ThrottleStreamExt`
+//! extension trait provides the same pacing to async pipelines as a
+//! `throttle_items(times, duration)` adapter for all streams, which sleeps on the timer
+//! of the tokio runtime rather than blocking a thread:
+//!
+//! ```toml
+//! [dependencies]
+//! throttle_my_fn_runtime = { version = "0.2", features = ["futures"] }
+//! ```
+//!
+//! ```ignore
+//! use futures::StreamExt;
+//! use std::time::Duration;
+//! use throttle_my_fn_runtime::ThrottleStreamExt;
+//!
+//! let mut events = events.throttle_items(10, Duration::from_secs(1));
+//! while let Some(event) = events.next().await {
+//!   publish(event).await;
+//! }
+//! ```
+//!
+//! The book-keeping behind both adapters is available on its own as `Pacer`, which tells
+//! how long to wait before the next item can go through without doing any waiting itself,
+//! for pipelines on other runtimes to wait on their own timers.
+//!
+//! ## Throttlers as values
+//!
+//! Sometimes a throttle needs to be a value, e.g. stored in a struct for per-connection
+//! limits, rather than a static behind a function. The `throttle_my_fn_runtime` crate
+//! provides a `Throttler` for that, with the same sliding window as `#[throttle(...)]`.
+//! It can be shared between threads, and provides `try_acquire()`, `acquire_blocking()`
+//! and `retry_after()`, `stats()` about executed and skipped calls, as well as
+//! `set_times()`, `set_duration()` and `reset()` to reconfigure it.
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use throttle_my_fn_runtime::Throttler;
+//!
+//! struct Connection {
+//!   throttler: Throttler,
+//! }
+//!
+//! impl Connection {
+//!   fn send(&self, message: &str) -> Option<()> {
+//!     self.throttler.try_acquire().then(|| self.write(message))
+//!   }
+//! }
+//! ```
+//!
+//! With the `serde` feature of `throttle_my_fn_runtime`, the statistics returned by
+//! `stats()` implement `Serialize`, e.g. for a service to report them from a JSON health
+//! endpoint:
+//!
+//! ```toml
+//! [dependencies]
+//! throttle_my_fn_runtime = { version = "0.2", features = ["serde"] }
+//! ```
+//!
+//! For structs holding throttlers, `#[derive(Throttled)]` generates the boilerplate.
+//! Every field carrying a `#[throttled(times, duration)]` attribute gets a
+//! `FIELD_throttler()` associated function creating a throttler with the configured limit
+//! for the struct's constructor, and a `try_FIELD(f)` method calling the closure `f` only
+//! if the field's throttler lets the call through.
+//!
+//! ```ignore
+//! use throttle_my_fn::Throttled;
+//! use throttle_my_fn_runtime::Throttler;
+//!
+//! #[derive(Throttled)]
+//! struct Session {
+//!   #[throttled(10, "1s")]
+//!   requests: Throttler,
+//! }
+//!
+//! let session = Session { requests: Session::requests_throttler() };
+//! let response: Option<Response> = session.try_requests(|| handle(request));
+//! ```
+//!
+//! ## Throttling tower services
+//!
+//! With the `tower` feature of `throttle_my_fn_runtime`, a `ThrottleLayer` throttles the
+//! requests going through a tower service stack. Requests over the limit are turned down
+//! with a `Throttled` error rather than passed on to the inner service, whose errors are
+//! boxed along with it. The limit is kept in a `Throttler`, which `ThrottleLayer::shared`
+//! takes for code calling the same upstream directly to be throttled together with the
+//! service stack:
+//!
+//! ```toml
+//! [dependencies]
+//! throttle_my_fn_runtime = { version = "0.2", features = ["tower"] }
+//! ```
+//!
+//! ```ignore
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use throttle_my_fn_runtime::{ThrottleLayer, Throttler};
+//!
+//! let upstream = Arc::new(Throttler::new(100, Duration::from_secs(1)));
+//! let service = ServiceBuilder::new()
+//!   .layer(ThrottleLayer::shared(Arc::clone(&upstream)))
+//!   .service(client);
+//! ```
+//!//!
+//! With `layer = true`, a `NAME_throttle_layer()` function is generated next to a
+//! `#[throttle(...)]` function, with the same visibility, returning a `ThrottleLayer`
+//! whose requests are charged against the quota of the function like its calls. One
+//! declaration then throttles both direct calls and a service stack, which requires the
+//! `tower` feature of `throttle_my_fn_runtime`. Only supported with `mode = skip` and
+//! without the `concurrency` option, for free functions (not methods or other associated
+//! functions), and within modules only with `share = false`.
+//!
+//! ```ignore
+//! #[throttle(100, "1s", layer = true)]
+//! pub(crate) fn call_upstream(request: Request) -> Response {
+//!   ...
+//! }
+//!
+//! let service = ServiceBuilder::new()
+//!   .layer(call_upstream_throttle_layer())
+//!   .service(client);
+//! ```
+//!
+//! ## Scaling every limit at once
+//!
+//! `throttle_my_fn_runtime::set_scale(f32)` scales the number of calls every throttle
+//! lets through per window, e.g. to clamp all outbound traffic at once during an incident
+//! rather than changing the limits of dozens of functions: `0.5` lets half as many calls
+//! through, `0.0` lets none through at all, and `1.0` goes back to the limits as they
+//! were written. Calls already in a window are kept and count against the scaled limit.
+//!
+//! The scale applies to every `Throttler`, and to the throttles generated by the macros
+//! when the `scale` feature of `throttle_my_fn` is enabled, since they then read the
+//! scale from the runtime crate on every call. Calls waiting for a slot, and deferred
+//! calls, wait for the scale to change while it leaves no slots at all, and calls with a
+//! deadline are skipped right away:
+//!
+//! ```toml
+//! [dependencies]
+//! throttle_my_fn = { version = "0.2", features = ["scale"] }
+//! throttle_my_fn_runtime = "0.2"
+//! ```
+//!
+//! ```ignore
+//! fn on_incident() {
+//!   throttle_my_fn_runtime::set_scale(0.25);
+//! }
+//! ```
+//!
+//! ## Testing throttled functions
+//!
+//! The `throttle_my_fn_runtime::testing` module helps test throttled code without
+//! sleeping. Throttles read the time from a virtual clock, which `testing::advance(by)`
+//! moves forward for the calls in the windows to fall out of them right away. Every
+//! `Throttler` reads it, and so do the functions generated by `#[throttle(...)]`,
+//! `#[debounce(...)]`, `#[cooldown(...)]`, `#[circuit_breaker(...)]` and
+//! `#[memoize(...)]` when the `testing` feature of `throttle_my_fn` is enabled:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! throttle_my_fn = { version = "0.2", features = ["testing"] }
+//! throttle_my_fn_runtime = "0.2"
+//! ```
+//!
+//! `assert_throttled!(call, within = "1s", allows = 10)` asserts that the first 10 calls
+//! are executed, that the next one is skipped, and that a call is executed again once the
+//! virtual clock moved forward by a second. `testing::drive(times, every, call)` calls a
+//! function a number of times, moving the clock forward in between, and tells which of
+//! the calls were executed.
+//!
+//! ```ignore
+//! #[test]
+//! fn fetch_is_throttled() {
+//!   assert_throttled!(fetch(1), within = "1s", allows = 10);
+//!
+//!   let executed = drive(3, Duration::from_secs(30), notify);
+//!   assert_eq!(executed, [true, false, true]);
+//! }
+//! ```
+//!
+//! The virtual clock is shared by the whole process, like the quotas of the throttled
+//! functions, so tests of the same functions shouldn't run in parallel. Only the time
+//! read by the throttles moves forward: code waiting for a slot still sleeps, and the
+//! bodies of the throttled functions keep reading the clock of the system.
+//!
+
+/// Like [quote::quote], but with the generated local variables, labels and `macro_rules`
+/// definitions resolved at the definition site, so that they can neither shadow nor be
+/// shadowed by the user's. Items and paths in the generated code still resolve at the
+/// call site, which is why generated items are prefixed with `__throttle` and paths are
+/// fully qualified.
+macro_rules! quote_mixed {
+  ($($tt:tt)*) => {
+    ::quote::quote_spanned! { ::proc_macro2::Span::mixed_site()=> $($tt)* }
+  };
+}
+
+mod active;
+mod args;
+mod buckets;
+mod circuit_breaker;
+mod clock;
+#[cfg(doctest)]
+mod compile_errors;
+mod config;
+mod cooldown;
+mod debounce;
+mod duration;
+mod expose;
+mod fast;
+mod group;
+mod keyed;
+mod max_concurrent;
+mod memoize;
+mod once;
+mod permits;
+mod priorities;
+mod queries;
+mod retry;
+mod sample;
+mod shards;
+mod split;
+mod stats;
+#[cfg(test)]
+mod tests;
+mod throttle_block;
+mod throttle_fn;
+mod throttle_group;
+mod throttled;
+mod timeout;
+mod variants;
+mod window;
+
+use args::{with_usage, Args, Mode, Overflow, PerKey, RateFrom};
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, ToTokens};
+use split::Split;
+use std::fmt::Display;
+use syn::parse_macro_input;
+use syn::{Ident, Item, ItemFn};
+
+/// Shorthand for creating `syn::Error`s that type-check with [proc_macro::TokenStream].
+///
+/// Creates fancy-looking error messages on stable Rust that can be returned from
+/// functions that return a [proc_macro::TokenStream].
+///
+/// # Arguments
+///
+/// * `tokens` - The [proc_macro::TokenStream] to be spanned in the error message.
+///
+/// * `message` - Custom user-provided error message.
+///
+/// # Returns
+///
+/// A [proc_macro::TokenStream], but effectively returns nothing and stops execution. It
+/// uses [syn::parse::Error::into_compile_error] which is akin to `compile_error!()`.
+fn err<T, U>(tokens: T, message: U) -> TokenStream
+where
+  T: ToTokens,
+  U: Display,
+{
+  syn::Error::new_spanned(tokens, message).into_compile_error().into()
+}
+
+/// A copy of the user-provided function `func` named `ident`, for calling it without
+/// being throttled.
+fn raw_fn(func: &ItemFn, ident: &Ident) -> ItemFn {
+  let mut raw = func.clone();
+  raw.sig.ident = ident.clone();
+
+  // The copy gets its own docs, and must not be exported under the same symbol.
+  raw.attrs.retain(|attr| {
+    !["doc", "no_mangle", "export_name"].iter().any(|name| attr.path.is_ident(name))
+  });
+  let doc = format!("The unthrottled implementation of [`{}`].", func.sig.ident);
+  raw.attrs.insert(0, syn::parse_quote! { #[doc = #doc] });
+
+  raw
+}
+
+/// How `#[throttle(...)]` is used, appended to the errors about its positional
+/// arguments.
+const THROTTLE_ARGS_ERR_MSG: &str = "expecting a comma-separated pair of expressions: \
+                                     #[throttle(<number-of-calls>, <duration>)]";
+
+/// Throttle a function's execution count over a period of time.
+///
+/// Slow down how many times a function can be executed over a duration: 100 times per
+/// [std::time::Duration::from_secs]`(1)` for 100 times per second.
+///
+/// **NOTE:** The function being decorated with this macro will have its return value
+/// changed to be wrapped in an [std::option::Option] indicating whether the function
+/// executed or not.
+///
+/// # Arguments
+///
+/// * `times` - Number of times the function should be limited to running over `duration`.
+///
+/// * `duration` - The [std::time::Duration] over which the function should be allowed to
+///   run `times` times, or a string literal such as `"1s"` or `"500ms"`.
+///
+/// Alongside a limit per key (`per_key = (times, duration), key = ARG`), `times` and
+/// `duration` can also be given as `global = (times, duration)`.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[throttle(10, Duration::from_secs(1))]
+/// pub(crate) fn run_10_times_per_second(arg: &str) -> String {
+///   ...
+/// }
+///
+/// #[throttle(1, Duration::from_millis(100))]
+/// pub(crate) fn run_once_per_100_milliseconds(arg: &str) -> String {
+///   ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn throttle(args: TokenStream, func: TokenStream) -> TokenStream {
+  // How this macro works is by wrapping the user-provided function (called the impl here)
+  // in an outer function with a similar signature. The return type T of the impl function
+  // is changed to an Option<T> on the outer function to indicate whether the function
+  // executed or not.
+  //
+  // The outer function then initializes the necessary statics, does the book-keeping,
+  // then decides whether the execute the impl or not.
+
+  let args_parsed =
+    match with_usage(THROTTLE_ARGS_ERR_MSG, || syn::parse::<Args>(args.clone())) {
+      Ok(args) => args,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  // On an impl block or a module, every method or function is throttled.
+  let group = match syn::parse::<Item>(func.clone()) {
+    Ok(Item::Impl(item)) => Some(group::throttle_impl(&args_parsed, item)),
+    Ok(Item::Mod(item)) => Some(group::throttle_mod(&args_parsed, item)),
+    _ => None,
+  };
+
+  match group {
+    Some(Ok(gen)) => return TokenStream::from(clock::virtualize(gen)),
+    Some(Err(e)) => return e.into_compile_error().into(),
+    None => {}
+  }
+
+  if let Some((name, _)) = &args_parsed.share {
+    return err(name, format!("option `{}` requires an impl block or a module", name));
+  }
+
+  let func_parsed = parse_macro_input!(func as ItemFn);
+  let raw_fn =
+    args_parsed.expose_inner.as_ref().map(|(ident, _)| raw_fn(&func_parsed, ident));
+
+  match Split::new(func_parsed)
+    .and_then(|split| expand_throttle(&args_parsed, split, None))
+  {
+    Ok(gen) => TokenStream::from(clock::virtualize(quote_mixed! { #gen #raw_fn })),
+    Err(e) => e.into_compile_error().into(),
+  }
+}
+
+/// Generate a throttled function.
+///
+/// # Arguments
+///
+/// * `args_parsed` - The arguments of the attribute.
+///
+/// * `split` - The user-provided function.
+///
+/// * `shared` - The static holding the calls deque, when shared with other functions.
+///   Otherwise, the function gets its own.
+///
+/// # Returns
+///
+/// The outer function, or an error if the arguments don't apply to the function.
+pub(crate) fn expand_throttle(
+  args_parsed: &Args,
+  mut split: Split,
+  shared: Option<&Ident>,
+) -> syn::Result<TokenStream2> {
+  // Recursive calls are redirected before the inner impl function is copied into any of
+  // the companion items.
+  if let Some(name) = &args_parsed.bypass_recursion {
+    split.reject_method(&format!("`{} = bypass`", name))?;
+    split.bypass_recursion();
+  }
+
+  // Without a shared calls deque, the function gets its own. With query functions,
+  // permits, a layer, statistics, variants or an exposed limiter, it lives in a function
+  // of its own next to the outer function, for them to get to it as well. They are
+  // generated before the outer function's signature changes.
+  let companion = args_parsed
+    .queries
+    .as_ref()
+    .or(args_parsed.permits.as_ref())
+    .or(args_parsed.layer.as_ref())
+    .or(args_parsed.stats.as_ref())
+    .or(args_parsed.wait_variant.as_ref())
+    .or(args_parsed.async_variant.as_ref())
+    .or(args_parsed.expose.as_ref());
+
+  // The limit is evaluated once, with the statics holding it next to the calls deque.
+  // Constants are initialized with the limit as it was written.
+  let (const_times, const_duration) = (&args_parsed.times, &args_parsed.duration);
+  let mut hoisted_args = args_parsed.clone();
+  let limit_items = window::hoist_limit(&mut hoisted_args, &split, companion.is_some());
+  if cfg!(feature = "scale") {
+    window::scale_limit(&mut hoisted_args);
+  }
+  let (limit_body_items, limit_module_items) = match companion {
+    Some(_) => (quote_mixed! {}, limit_items),
+    None => (limit_items, quote_mixed! {}),
+  };
+  let args_parsed = &hoisted_args;
+  let times = &args_parsed.times;
+  let duration = &args_parsed.duration;
+
+  // With a limit read from a provider, the limit is only known inside the outer function.
+  if let Some(RateFrom { name, .. }) = &args_parsed.rate_from {
+    let conflict = companion.or(args_parsed.consts.as_ref());
+    if let Some(conflict) = conflict {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!("option `{}` cannot be used with option `{}`", name, conflict),
+      ));
+    }
+  }
+
+  // Calls made through permits, a layer or the other variants aren't reported to the
+  // handler, nor kept outside of the active window.
+  let on_execute = args_parsed.on_execute.as_ref().map(|(_, name)| name);
+  let active = args_parsed.active.as_ref().map(|active| &active.name);
+  for name in on_execute.into_iter().chain(active) {
+    let conflict = args_parsed
+      .permits
+      .as_ref()
+      .or(args_parsed.layer.as_ref())
+      .or(args_parsed.wait_variant.as_ref())
+      .or(args_parsed.async_variant.as_ref());
+    if let Some(conflict) = conflict {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!("option `{}` cannot be used with option `{}`", name, conflict),
+      ));
+    }
+  }
+
+  // Calls counted in slots have no deque which anything else could look into.
+  if let Some((_, name)) = &args_parsed.resolution {
+    if !fast::only_skips(args_parsed, shared) {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!(
+          "option `{}` requires `mode = skip`, and cannot be used with a shared quota or \
+           options looking into the calls of the function, e.g. `queries = true`",
+          name
+        ),
+      ));
+    }
+  }
+
+  // Sharded calls are spread across deques of their own, which nothing else looks into.
+  if let Some(name) = &args_parsed.sharded {
+    let option = companion
+      .or(args_parsed.per_type.as_ref())
+      .or(args_parsed.per_key.as_ref().map(|per_key| &per_key.name))
+      .or(args_parsed.on_execute.as_ref().map(|(_, name)| name));
+    let conflict = match (shared, option) {
+      (Some(_), _) => Some(String::from("a shared quota, use `share = false`")),
+      (None, Some(option)) => Some(format!("option `{}`", option)),
+      (None, None) if args_parsed.detailed => Some(String::from("`output = detailed`")),
+      (None, None) => None,
+    };
+    if let Some(conflict) = conflict {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!("`{} = true` cannot be used with {}", name, conflict),
+      ));
+    }
+  }
+
+  // With `scope = per_type`, the calls deque is looked up for the instantiation of the
+  // function being called, so it cannot be shared with other functions.
+  let per_type_calls = match &args_parsed.per_type {
+    Some(name) => {
+      let conflict = match (shared, companion) {
+        (Some(_), _) => Some(String::from("a shared quota, use `share = false`")),
+        (None, Some(companion)) => Some(format!("option `{}`", companion)),
+        (None, None) => None,
+      };
+      if let Some(conflict) = conflict {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = per_type` cannot be used with {}", name, conflict),
+        ));
+      }
+
+      let mut type_params = split.outer_sig.generics.type_params().peekable();
+      if type_params.peek().is_none() {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("`{} = per_type` requires a function with type parameters", name),
+        ));
+      }
+
+      Some(window::per_type_calls(type_params.map(|param| &param.ident)))
+    }
+    None => None,
+  };
+
+  // With `group = "name"`, the calls deque is looked up by name in the runtime crate,
+  // for the functions of other crates to share it. Nothing else can look into it then.
+  let scoped_calls = match &args_parsed.group {
+    Some((group, name)) => {
+      let option =
+        companion.or(args_parsed.per_type.as_ref()).or(args_parsed.sharded.as_ref());
+      if let Some(option) = option {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("option `{}` cannot be used with option `{}`", name, option),
+        ));
+      }
+
+      Some((window::group_calls(group), quote_mixed! {}))
+    }
+    None => {
+      per_type_calls.map(|calls_item| (quote_mixed! { __throttle_calls }, calls_item))
+    }
+  };
+
+  let (calls_static, calls_item, companion_items) =
+    match (shared, companion, scoped_calls) {
+      (Some(_), Some(name), _) => {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!(
+            "option `{}` cannot be used with a shared quota, use `share = false`",
+            name
+          ),
+        ))
+      }
+      (Some(shared), None, _) => {
+        (quote_mixed! { #shared }, quote_mixed! {}, quote_mixed! {})
+      }
+      (None, Some(name), _) => {
+        split.reject_method(&format!("option `{}`", name))?;
+        let calls_fn = queries::calls_fn(&split);
+        let calls_fn_item = queries::calls_item(&split);
+
+        let query_items = match args_parsed.queries {
+          Some(_) => queries::query_items(args_parsed, &split),
+          None => quote_mixed! {},
+        };
+
+        let permit_items = match args_parsed.permits {
+          Some(_) => permits::permit_items(args_parsed, &split),
+          None => quote_mixed! {},
+        };
+
+        let layer_item = match args_parsed.layer {
+          Some(_) => permits::layer_item(args_parsed, &split),
+          None => quote_mixed! {},
+        };
+
+        let stats_items = match args_parsed.stats {
+          Some(_) => stats::stats_items(args_parsed, &split),
+          None => quote_mixed! {},
+        };
+
+        let wait_variant = match &args_parsed.wait_variant {
+          Some(wait) => {
+            // The wait variant blocks the calling thread, which async functions must not.
+            split.reject_async(&format!("`variants({})`", wait))?;
+            variants::wait_variant(args_parsed, &split)
+          }
+          None => quote_mixed! {},
+        };
+
+        let state_module = match args_parsed.expose {
+          Some(_) => expose::state_module(args_parsed, &split),
+          None => quote_mixed! {},
+        };
+
+        let async_variant = match &args_parsed.async_variant {
+          Some(name) => {
+            // The async variant runs the function on a blocking thread, which an async
+            // function would not need.
+            split.reject_async(&format!("`variants({})`", name))?;
+            variants::async_variant(args_parsed, &split)
+          }
+          None => quote_mixed! {},
+        };
+
+        (
+          quote_mixed! { #calls_fn() },
+          quote_mixed! {},
+          quote_mixed! {
+            #calls_fn_item #query_items #permit_items #layer_item #stats_items
+            #wait_variant #async_variant #state_module
+          },
+        )
+      }
+      (None, None, Some((calls, calls_item))) => (calls, calls_item, quote_mixed! {}),
+      (None, None, None) if args_parsed.sharded.is_some() => (
+        quote_mixed! { __THROTTLE_SHARDS },
+        shards::shards_item(&args_parsed.times),
+        quote_mixed! {},
+      ),
+      (None, None, None) if args_parsed.resolution.is_some() => {
+        (quote_mixed! { __THROTTLE_BUCKETS }, buckets::buckets_item(), quote_mixed! {})
+      }
+      // Without anything else looking into the calls, they are charged lock-free.
+      (None, None, None) if fast::applies(args_parsed, shared) => (
+        quote_mixed! { __THROTTLE_WINDOW },
+        fast::window_item(&args_parsed.times),
+        quote_mixed! {},
+      ),
+      (None, None, None) => (
+        quote_mixed! { __THROTTLE_CALLS },
+        quote_mixed! {
+          static __THROTTLE_CALLS: ::throttle_my_fn_runtime::parking_lot::Mutex<
+            Option<::std::collections::VecDeque<::throttle_my_fn_runtime::Instant>>,
+          > = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+        },
+        quote_mixed! {},
+      ),
+    };
+
+  // Skipped calls give their arguments back, but not the deadline added below.
+  let args_tuple = match args_parsed.result_with_args {
+    true => Some(split.args_tuple("`output = result_with_args`")?),
+    false => None,
+  };
+
+  // Calls with a deadline block the calling thread, which async functions must not, for
+  // a slot of the function's own calls deque. Foreign callers wouldn't pass the deadline.
+  if let Some(name) = &args_parsed.deadline {
+    let conflict = args_parsed
+      .sharded
+      .as_ref()
+      .or(args_parsed.per_key.as_ref().map(|per_key| &per_key.name));
+    if let Some(conflict) = conflict {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!(
+          "`{} = wait_with_deadline` cannot be used with option `{}`",
+          name, conflict
+        ),
+      ));
+    }
+
+    let what = format!("`{} = wait_with_deadline`", name);
+    if args_parsed.poll_future {
+      return Err(syn::Error::new_spanned(
+        name,
+        format!("{} cannot be used with `future = poll`", what),
+      ));
+    }
+    split.reject_async(&what)?;
+    split.reject_abi(&what)?;
+    split.add_deadline();
+  }
+
+  if let Some(name) = &args_parsed.with_override {
+    split.reject_abi(&format!("option `{}`", name))?;
+    split.add_override();
+  }
+
+  // Functions returning futures that are throttled once their future is first polled
+  // keep returning a future, and its output type is changed below instead.
+  if args_parsed.poll_future {
+    split.poll_future("`future = poll`")?;
+  }
+
+  // Change the outer function's return type to an Option<T>. With a skipped value, or
+  // when flattening an Option<T> return type, the outer function keeps the return type of
+  // the impl function.
+  //
+  // With `output = detailed`, the outer function returns a ThrottleResult<T> instead,
+  // which tells skipped calls when to retry.
+  //
+  // In deferred and coalescing modes, the outer function returns a ThrottleHandle<T>
+  // instead, which resolves to the return value once the call has been executed.
+  let (skipped, wrap) = match args_parsed.mode {
+    Mode::Skip if args_parsed.detailed => {
+      let ret_ty = &split.ret_ty;
+      split.set_output(
+        quote_mixed! { ::throttle_my_fn_runtime::ThrottleResult<#ret_ty> },
+        "`output = detailed`",
+      )?;
+      (
+        quote_mixed! { __throttle_skipped(#calls_static.lock().as_ref(), current_time) },
+        quote_mixed! { ::throttle_my_fn_runtime::ThrottleResult::Executed },
+      )
+    }
+    Mode::Skip if args_parsed.result_with_args => {
+      // The arguments haven't been moved into the inner impl function when the call is
+      // skipped, so they can be given back.
+      let (args_ty, args) = args_tuple.unwrap_or_default();
+      let ret_ty = &split.ret_ty;
+      split.set_output(
+        quote_mixed! { ::std::result::Result<#ret_ty, #args_ty> },
+        "`output = result_with_args`",
+      )?;
+      (quote_mixed! { Err(#args) }, quote_mixed! { Ok })
+    }
+    // In warning mode, the book-keeping tells whether the call is over budget, see the
+    // body below.
+    Mode::Skip if args_parsed.warn.is_some() => {
+      split.set_skippable_output(None, "`#[throttle(...)]`")?;
+      (quote_mixed! { true }, quote_mixed! { Some })
+    }
+    Mode::Skip if args_parsed.flatten => {
+      split.require_option("`flatten = true`")?;
+      split
+        .set_skippable_output(Some(&syn::parse_quote! { None }), "`#[throttle(...)]`")?
+    }
+    Mode::Skip => {
+      let skipped = args_parsed.skipped.as_ref();
+      split.set_skippable_output(skipped, "`#[throttle(...)]`")?
+    }
+    Mode::Defer | Mode::Coalesce => {
+      // Deferred calls are executed by a background thread, which cannot await them.
+      split.reject_async("`mode = defer` or `mode = coalesce`")?;
+
+      let ret_ty = &split.ret_ty;
+      split.set_output(
+        quote_mixed! { ::throttle_my_fn_runtime::ThrottleHandle<#ret_ty> },
+        "`mode = defer` or `mode = coalesce`",
+      )?;
+      (quote_mixed! { None }, quote_mixed! { Some })
+    }
+  };
+
+  let Split { attrs, vis, impl_fn, outer_sig, call, .. } = &split;
+
+  // What to do with a call that arrives while the quota is exhausted. In the default mode
+  // the call is simply skipped. In deferred mode the call (along with its arguments) is
+  // moved into a queue that is drained by a background worker thread, which waits for a
+  // free slot before running each queued call. Coalescing mode is the same, except only
+  // the latest call is kept in the queue.
+  let admitted = call;
+
+  // What an executed call returns in skipping mode. With `refund = on_cancel`, the refund
+  // of the call is forgotten once it completed.
+  let skip_mode_admitted = match args_parsed.refund_on_cancel {
+    Some(_) => quote_mixed! {{
+      let output = #admitted;
+      ::std::mem::forget(refund);
+      #wrap(output)
+    }},
+    None => quote_mixed! { #wrap(#admitted) },
+  };
+
+  // With `output = detailed`, skipped calls are told how long until a slot frees up. The
+  // calls deque is already locked when the quota is found to be full.
+  let (detailed_items, skipped_exhausted) = if args_parsed.detailed {
+    let retry_after_fn = window::retry_after_fn(times, duration);
+    (
+      quote_mixed! {
+        #retry_after_fn
+
+        // The details of a call skipped at `current_time`, given the calls deque.
+        fn __throttle_skipped<T>(
+          calls: Option<&::std::collections::VecDeque<::throttle_my_fn_runtime::Instant>>,
+          current_time: ::throttle_my_fn_runtime::Instant,
+        ) -> ::throttle_my_fn_runtime::ThrottleResult<T> {
+          let in_window = calls.into_iter().flatten().filter(|&&call_time| {
+            current_time.duration_since(call_time) <= #duration
+          });
+
+          ::throttle_my_fn_runtime::ThrottleResult::Skipped {
+            retry_after: calls
+              .and_then(|calls| __throttle_retry_after(calls, current_time))
+              .unwrap_or_default(),
+            window_remaining: usize::saturating_sub(#times, in_window.count()),
+          }
+        }
+      },
+      quote_mixed! { __throttle_skipped(Some(&*calls), current_time) },
+    )
+  } else {
+    (quote_mixed! {}, skipped.clone())
+  };
+
+  // With `stats = true`, skipped calls are counted whichever way they are skipped, and
+  // executed ones once they are charged against the quota.
+  let record_skipped =
+    stats::record(args_parsed, &split, quote_mixed! { record_skipped() });
+  let record_executed =
+    stats::record(args_parsed, &split, quote_mixed! { record_executed() });
+  let skipped = quote_mixed! {{ #record_skipped #skipped }};
+  let skipped_exhausted = quote_mixed! {{ #record_skipped #skipped_exhausted }};
+
+  // With a limit per key, a call must also fit in the budget of its key, and is only
+  // charged against both once it does. The functions generated alongside the outer
+  // function only know about the limit of the function as a whole. The calls of every
+  // key are kept in a `throttle_my_fn_runtime::KeyedWindow`, which forgets about the keys
+  // that aren't used anymore, and with `max_keys = N`, about the least recently let
+  // through key to make room for a new one.
+  let (per_key_items, key_active, per_key_exhausted) = match &args_parsed.per_key {
+    Some(PerKey {
+      times: key_times,
+      duration: key_duration,
+      key,
+      name,
+      fair,
+      hasher,
+      normalize_key,
+      max_keys,
+    }) => {
+      if let Some(companion) = companion {
+        return Err(syn::Error::new_spanned(
+          name,
+          format!("option `{}` cannot be used with option `{}`", name, companion),
+        ));
+      }
+
+      let keyed::Key { ty: key_ty, expr: key_expr } = keyed::Key::of(&split, key)?;
+
+      // With `normalize_key = normalize`, keys are normalized before they are looked up.
+      // A key of a single argument is normalized as the argument rather than as a tuple.
+      let key_expr = match (normalize_key, key.len()) {
+        (Some(normalize_key), 1) => quote_mixed! {{
+          let (key,) = #key_expr;
+          ((#normalize_key)(key),)
+        }},
+        (Some(normalize_key), _) => quote_mixed! { (#normalize_key)(#key_expr) },
+        (None, _) => key_expr,
+      };
+      let hasher = match hasher {
+        Some(hasher) => quote_mixed! { , #hasher },
+        None => quote_mixed! {},
+      };
+
+      // With `fair = true`, a key can only use its share of the limit of the function,
+      // and is marked as active before the call is checked against that limit, for the
+      // keys whose calls are all skipped by it to get their share all the same.
+      let (key_active, key_item, fair) = match fair {
+        true => (
+          quote_mixed! {
+            let key: __ThrottleKey = #key_expr;
+            __THROTTLE_CALLS_PER_KEY.mark_active(&key, current_time);
+          },
+          quote_mixed! {},
+          quote_mixed! { Some((#times, #duration)) },
+        ),
+        false => (
+          quote_mixed! {},
+          quote_mixed! { let key: __ThrottleKey = #key_expr; },
+          quote_mixed! { None },
+        ),
+      };
+      let max_keys = match max_keys {
+        Some(max_keys) => quote_mixed! { Some(#max_keys) },
+        None => quote_mixed! { None },
+      };
+
+      (
+        quote_mixed! {
+          type __ThrottleKey = #key_ty;
+
+          static __THROTTLE_CALLS_PER_KEY: ::std::sync::LazyLock<
+            ::throttle_my_fn_runtime::KeyedWindow<__ThrottleKey #hasher>,
+          > = ::std::sync::LazyLock::new(|| {
+            ::throttle_my_fn_runtime::KeyedWindow::new(#max_keys)
+          });
+        },
+        key_active,
+        quote_mixed! {
+          // Return None (or the skipped value) if the quota of the key is full as well.
+          #key_item
+          let key_charged = __THROTTLE_CALLS_PER_KEY.try_charge(
+            key,
+            current_time,
+            #key_times,
+            #key_duration,
+            #fair,
+          );
+          if !key_charged {
+            return #skipped_exhausted;
+          }
+        },
+      )
+    }
+    None => (quote_mixed! {}, quote_mixed! {}, quote_mixed! {}),
+  };
+
+  // With a concurrency limit, a concurrency permit is acquired before charging the call
+  // against the quota, so that a call skipped for lack of a permit isn't charged. If the
+  // quota turns out to be full, the permit is released when returning. Otherwise it is
+  // held until the inner impl function returns (or panics).
+  //
+  // With `on_busy = wait`, calls of async functions wait for a permit of a semaphore of
+  // tokio instead, which is never closed.
+  let (permit_items, acquire_permit) = match &args_parsed.concurrency {
+    Some(concurrency) if args_parsed.on_busy_wait.is_some() => {
+      split.require_async("`on_busy = wait`")?;
+      (
+        max_concurrent::semaphore_item(concurrency),
+        quote_mixed! {
+          let _permit = match __THROTTLE_SEMAPHORE.acquire().await {
+            Ok(permit) => permit,
+            Err(_) => return #skipped,
+          };
+        },
+      )
+    }
+    Some(concurrency) => (
+      max_concurrent::permit_items(concurrency),
+      quote_mixed! {
+        let _permit = match __ThrottlePermit::try_acquire() {
+          Some(permit) => permit,
+          None => return #skipped,
+        };
+      },
+    ),
+    None => (quote_mixed! {}, quote_mixed! {}),
+  };
+
+  let charged_at = window::charged_at(&args_parsed.calendar);
+
+  // With `mode = observe`, calls over the limit are recorded as having waited for as long
+  // as they would have had to for a slot to free up. Without any slots at all, there is
+  // nothing that could be waited for.
+  let record_would_wait = match args_parsed.observe {
+    true => {
+      let record_wait =
+        stats::record(args_parsed, &split, quote_mixed! { record_wait(wait) });
+      quote_mixed! {
+        if let Some(&oldest) = calls.get(calls.len() - #times) {
+          let wait = (oldest + #duration).saturating_duration_since(current_time);
+          #record_wait
+        }
+      }
+    }
+    false => quote_mixed! {},
+  };
+  // The queue of the deferred calls, bounded or not depending on the `queue` option, and
+  // with what happens to a call deferred while it is full depending on `overflow`.
+  // Dropping a deferred call cancels it, so its handle resolves to None.
+  //
+  // Coalescing is deferring with a queue of a single call, where a new call replaces the
+  // pending one.
+  let (queue, overflow) = match args_parsed.mode {
+    Mode::Coalesce => (Some(syn::parse_quote! { 1 }), Overflow::DropOldest),
+    _ => (args_parsed.queue.clone(), args_parsed.overflow),
+  };
+  let bound = match &queue {
+    Some(queue) => quote_mixed! { Some(#queue) },
+    None => quote_mixed! { None },
+  };
+  let overflow_variant = match overflow {
+    Overflow::DropNewest => quote_mixed! { DropNewest },
+    Overflow::DropOldest => quote_mixed! { DropOldest },
+    Overflow::Block => quote_mixed! { Block },
+  };
+  let queue_item = |queue_static: &Ident| {
+    quote_mixed! {
+      static #queue_static: ::throttle_my_fn_runtime::DeferredQueue =
+        ::throttle_my_fn_runtime::DeferredQueue::new(
+          #bound,
+          ::throttle_my_fn_runtime::Overflow::#overflow_variant,
+        );
+    }
+  };
+
+  // With `drain = true`, the queue is declared next to the outer function rather than in
+  // it, for the drain function to wait on it as well.
+  let (queue_static, queue_item, drain_items) = match &args_parsed.drain {
+    Some(name) => {
+      split.reject_method(&format!("option `{}`", name))?;
+
+      let ident = &outer_sig.ident;
+      let queue_static = format_ident!(
+        "__THROTTLE_QUEUE_{}",
+        ident.to_string().trim_start_matches('_').to_uppercase()
+      );
+      let drain_fn = format_ident!("{}_throttle_drain", ident);
+      let drain_doc = format!(
+        "Wait until the deferred calls to [`{}`] have been executed, for up to \
+         `timeout`. Returns whether they all were, rather than the timeout expiring.",
+        ident
+      );
+      let queue_item = queue_item(&queue_static);
+
+      (
+        queue_static.clone(),
+        quote_mixed! {},
+        quote_mixed! {
+          #queue_item
+
+          #[doc = #drain_doc]
+          #vis fn #drain_fn(timeout: ::std::time::Duration) -> bool {
+            #queue_static.drain(::throttle_my_fn_runtime::system_now, timeout)
+          }
+        },
+      )
+    }
+    None => {
+      let queue_static = format_ident!("__THROTTLE_PENDING");
+      let queue_item = queue_item(&queue_static);
+      (queue_static, queue_item, quote_mixed! {})
+    }
+  };
+
+  let (mode_items, on_exhausted, admitted) = match args_parsed.mode {
+    Mode::Skip if args_parsed.deadline.is_some() => {
+      let wait = window::wait_until(args_parsed.wait, quote_mixed! { free_at });
+      (
+        quote_mixed! {},
+        quote_mixed! {
+          // The quota is full: wait until enough calls fell out of the window for one to
+          // free up, or return None (or the skipped value) if that's past the deadline.
+          // Without any slots at all, there is nothing to wait for.
+          let times = #times;
+          let free_at = ::throttle_my_fn_runtime::window::free_at(
+            calls,
+            current_time,
+            times,
+            #duration,
+          );
+          if let Some(free_at) = free_at {
+            if times == 0 || free_at > deadline {
+              return #skipped_exhausted;
+            }
+
+            drop(calls_guard);
+            #wait
+            current_time = ::throttle_my_fn_runtime::system_now();
+            continue;
+          }
+        },
+        skip_mode_admitted,
+      )
+    }
+    // With `policy = MY_POLICY`, the policy decides instead, given the calls deque.
+    Mode::Skip if args_parsed.policy.is_some() => {
+      let policy = args_parsed.policy.as_ref().map(|(policy, _)| policy);
+      (
+        quote_mixed! {},
+        quote_mixed! {
+          // Return None (or the skipped value) if the policy turns the call down.
+          let decision = ::throttle_my_fn_runtime::AdmissionPolicy::decide(
+            &#policy,
+            current_time,
+            calls,
+            1,
+          );
+          if decision == ::throttle_my_fn_runtime::Decision::Skip {
+            return #skipped_exhausted;
+          }
+        },
+        skip_mode_admitted,
+      )
+    }
+    Mode::Skip => (
+      quote_mixed! {},
+      quote_mixed! {
+        // Return None (or the skipped value) if our quota is full for the duration.
+        if calls.len() >= #times {
+          #record_would_wait
+          return #skipped_exhausted;
+        }
+      },
+      skip_mode_admitted,
+    ),
+    Mode::Defer | Mode::Coalesce => {
+      let thread_name = format!("throttle-{}", outer_sig.ident);
+      let take = quote_mixed! {
+        match #queue_static.take() {
+          Some(job) => job,
+          None => return,
+        }
+      };
+      let wait_for_slot = window::wait_for_slot(
+        &calls_static,
+        times,
+        duration,
+        &charged_at,
+        args_parsed.wait,
+        Some(&take),
+      );
+
+      // With only a trailing edge, calls are never executed immediately. Instead, the
+      // first call of a burst starts a window, and the worker waits for the end of that
+      // window before taking the latest call off the queue. In the browser, the end of
+      // the window is kept across the polls of the queue, until a call is taken off it.
+      let (should_defer, window, poll_window, window_polled) =
+        if args_parsed.trailing_only {
+          (
+            quote_mixed! { true },
+            quote_mixed! { Some(#duration) },
+            quote_mixed! {
+              static __THROTTLE_WINDOW_END: ::throttle_my_fn_runtime::parking_lot::Mutex<
+                Option<::throttle_my_fn_runtime::Instant>,
+              > = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+
+              let window_end = *__THROTTLE_WINDOW_END
+                .lock()
+                .get_or_insert(current_time + #duration);
+              if current_time < window_end {
+                return Some(window_end.saturating_duration_since(current_time));
+              }
+            },
+            quote_mixed! { *__THROTTLE_WINDOW_END.lock() = None; },
+          )
+        } else {
+          (
+            quote_mixed! { calls.len() >= #times || !pending.is_empty() },
+            quote_mixed! { None },
+            quote_mixed! {},
+            quote_mixed! {},
+          )
+        };
+
+      // Outside of the active window, calls are deferred until it opens again, and the
+      // worker waits for it to open before waiting for a free slot.
+      let (should_defer, await_active) = match &args_parsed.active {
+        Some(_) => (
+          quote_mixed! { #should_defer || __throttle_inactive_for().is_some() },
+          quote_mixed! {
+            while let Some(inactive_for) = __throttle_inactive_for() {
+              ::std::thread::sleep(inactive_for);
+            }
+          },
+        ),
+        None => (should_defer, quote_mixed! {}),
+      };
+
+      // In the browser, there are no threads to spawn the worker on, so the queue is
+      // polled from timers instead, which cannot block on anything but a free slot.
+      let blocking = match () {
+        _ if args_parsed.active.is_some() => Some("option `active`"),
+        _ if args_parsed.drain.is_some() => Some("option `drain`"),
+        _ if queue.is_some() && overflow == Overflow::Block => Some("`overflow = block`"),
+        _ => None,
+      };
+      let browser_error = blocking.map(|option| {
+        let message = format!(
+          "{} cannot be used with deferred calls on `wasm32-unknown-unknown`, where they \
+           are executed from the timers of the browser",
+          option
+        );
+        quote_mixed! {
+          #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+          ::std::compile_error!(#message);
+        }
+      });
+
+      (
+        quote_mixed! {
+          #browser_error
+
+          #queue_item
+
+          // A step of the background worker: waits for a pending call, then for a free
+          // slot in the calls deque, and only then takes the next pending call off the
+          // queue and executes it. A step that panics cancels its call, and the worker
+          // carries on with the next one.
+          #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+          fn __throttle_work() {
+            #queue_static.wait_for_call(::throttle_my_fn_runtime::system_now, #window);
+
+            #await_active
+            let job = #wait_for_slot;
+            job.run();
+          }
+
+          // In the browser, the pending calls are executed from timers, by polling the
+          // queue whenever the next call gets a free slot in the calls deque.
+          #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+          fn __throttle_poll() -> Option<::std::time::Duration> {
+            loop {
+              let current_time = ::throttle_my_fn_runtime::system_now();
+              if #queue_static.all_cancelled() {
+                #window_polled
+                return None;
+              }
+              #poll_window
+
+              let times = #times;
+              let mut calls_guard = #calls_static.lock();
+              let calls = calls_guard.get_or_insert_with(|| {
+                ::std::collections::VecDeque::with_capacity(times)
+              });
+              __throttle_cleanup(calls, current_time);
+
+              let free_at = ::throttle_my_fn_runtime::window::free_at(
+                calls,
+                current_time,
+                times,
+                #duration,
+              );
+              if let Some(free_at) = free_at {
+                return Some(free_at.saturating_duration_since(current_time));
+              }
+
+              #window_polled
+              let job = #queue_static.take()?;
+              calls.push_back(#charged_at);
+              drop(calls_guard);
+              job.run();
+            }
+          }
+        },
+        quote_mixed! {
+          // Defer the call if our quota is full for the duration, or if there are other
+          // deferred calls waiting to be executed before us.
+          let pending = #queue_static.lock();
+          if #should_defer {
+            drop(calls_guard);
+            let (call, handle) =
+              ::throttle_my_fn_runtime::DeferredCall::new(move || #admitted);
+            #queue_static.push(pending, Box::new(call));
+
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            #queue_static.ensure_running(#thread_name, __throttle_work);
+            #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+            #queue_static.ensure_polling(__throttle_poll);
+
+            return handle;
+          }
+          drop(pending);
+        },
+        quote_mixed! { ::throttle_my_fn_runtime::ThrottleHandle::ready(#admitted) },
+      )
+    }
+  };
+
+  let cleanup_fn = window::cleanup_fn(times, duration);
+
+  // With an active window, calls outside of it are skipped before acquiring a
+  // concurrency permit or being charged, and told when the window opens again with
+  // `output = detailed`.
+  let (inactive_for_fn, inactive_check) = match (&args_parsed.active, args_parsed.mode) {
+    (Some(active), Mode::Skip) if args_parsed.detailed => (
+      active.inactive_for_fn(),
+      quote_mixed! {
+        if let Some(retry_after) = __throttle_inactive_for() {
+          #record_skipped
+          return ::throttle_my_fn_runtime::ThrottleResult::Skipped {
+            retry_after,
+            window_remaining: 0,
+          };
+        }
+      },
+    ),
+    (Some(active), Mode::Skip) => (
+      active.inactive_for_fn(),
+      quote_mixed! {
+        if __throttle_inactive_for().is_some() {
+          return #skipped;
+        }
+      },
+    ),
+    (Some(active), Mode::Defer | Mode::Coalesce) => {
+      (active.inactive_for_fn(), quote_mixed! {})
+    }
+    (None, _) => (quote_mixed! {}, quote_mixed! {}),
+  };
+
+  // With `on_execute = handler`, the handler is told about every call that is let
+  // through, once the lock on the calls deque is released.
+  let (occupancy, on_execute) = match &args_parsed.on_execute {
+    Some((handler, _)) => {
+      let name = outer_sig.ident.to_string();
+      (
+        quote_mixed! {
+          // The deque is only cleaned up once it is full, so the calls that fell out of
+          // the window are not counted.
+          let occupancy = calls
+            .iter()
+            .filter(|&&call_time| current_time.duration_since(call_time) <= #duration)
+            .count();
+        },
+        quote_mixed! { (#handler)(#name, occupancy); },
+      )
+    }
+    None => (quote_mixed! {}, quote_mixed! {}),
+  };
+
+  // With `reset_after_idle = duration`, every call stores the time it was made at, and
+  // the calls in the window (and those of every key) are forgotten once the function
+  // wasn't called for the duration, skipped calls included.
+  let (idle_check, reset_idle) = match &args_parsed.reset_after_idle {
+    Some((idle_duration, _)) => {
+      let clear_keys = match args_parsed.per_key {
+        Some(_) => quote_mixed! {
+          __THROTTLE_CALLS_PER_KEY.clear();
+        },
+        None => quote_mixed! {},
+      };
+
+      (
+        quote_mixed! {
+          static __THROTTLE_LAST_CALL: ::throttle_my_fn_runtime::parking_lot::Mutex<
+            Option<::throttle_my_fn_runtime::Instant>,
+          > = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+
+          let last_call = __THROTTLE_LAST_CALL.lock().replace(current_time);
+          let idle = last_call.map_or(false, |last_call| {
+            current_time.saturating_duration_since(last_call) > #idle_duration
+          });
+        },
+        quote_mixed! {
+          // Forget about the calls made before the function went idle.
+          if idle {
+            calls.clear();
+            #clear_keys
+          }
+        },
+      )
+    }
+    None => (quote_mixed! {}, quote_mixed! {}),
+  };
+
+  // With `priorities = (...)`, every call marks its class as active before it is checked
+  // against the limit, skipped calls included, and is then charged against the share of
+  // its class as well. Calls with a deadline are counted as waiting in their class
+  // instead, before they go around waiting for a free slot, and only take the slot they
+  // find in the turn of their class.
+  let (class_active, class_waiting, class_exhausted) = match &args_parsed.priorities {
+    Some(priorities) => {
+      let deadline = args_parsed.deadline.is_some();
+      let charge = priorities.charge(times, duration, deadline, &skipped_exhausted);
+      match deadline {
+        true => (quote_mixed! {}, priorities.enter(), charge),
+        false => (priorities.mark_active(), quote_mixed! {}, charge),
+      }
+    }
+    None => (quote_mixed! {}, quote_mixed! {}, quote_mixed! {}),
+  };
+
+  // With `with_override = true`, a call with a cost multiplier must fit in the quota
+  // with all of its slots, which are all charged at the same time.
+  let (cost_exhausted, charge_cost) = match args_parsed.with_override {
+    Some(_) => (
+      quote_mixed! {
+        // The deque is only cleaned up once it is full, so the calls that fell out of
+        // the window are not counted.
+        let cost = override_.cost();
+        let in_window = calls
+          .iter()
+          .filter(|&&call_time| current_time.duration_since(call_time) <= #duration)
+          .count();
+        if in_window + cost > #times {
+          return #skipped_exhausted;
+        }
+      },
+      quote_mixed! {
+        for _ in 1..cost {
+          calls.push_back(#charged_at);
+        }
+      },
+    ),
+    None => (quote_mixed! {}, quote_mixed! {}),
+  };
+
+  // With `cold_start_cap = N`, at most N calls are let through in the first window after
+  // the function is first called, which are all the calls in the window then.
+  let cold_start_exhausted = match &args_parsed.cold_start_cap {
+    Some((cap, _)) => quote_mixed! {
+      static __THROTTLE_STARTED: ::std::sync::OnceLock<
+        ::throttle_my_fn_runtime::Instant,
+      > = ::std::sync::OnceLock::new();
+
+      let started = *__THROTTLE_STARTED.get_or_init(|| current_time);
+      let cold = current_time.saturating_duration_since(started) <= #duration;
+      if cold && calls.len() >= #cap {
+        return #skipped_exhausted;
+      }
+    },
+    None => quote_mixed! {},
+  };
+
+  // With `charge = end`, the calls that are let through are charged when they start all
+  // the same, for calls made in the meantime not to take their slot, and the charge is
+  // moved to the time they finish at (or panic) by a guard held until then.
+  //
+  // With `refund = on_cancel`, the charge of a call is taken back by a guard instead,
+  // unless the call completes. The future of an async function is dropped before it
+  // completes when it is cancelled, e.g. by losing a `select!`, or when the call panics.
+  let charge_options = (&args_parsed.charge_at_end, &args_parsed.refund_on_cancel);
+  let (charge_items, record_charged, charge_guard) = match charge_options {
+    (_, Some(name)) => {
+      split.require_async(&format!("`{} = on_cancel`", name))?;
+      (
+        quote_mixed! {
+          struct __ThrottleRefund(::throttle_my_fn_runtime::Instant);
+
+          impl ::std::ops::Drop for __ThrottleRefund {
+            fn drop(&mut self) {
+              // Any call in the window charged at the same time frees up the same slot.
+              // If the charge already fell out of the window, there is nothing to refund.
+              if let Some(calls) = #calls_static.lock().as_mut() {
+                if let Some(index) = calls.iter().position(|&call| call == self.0) {
+                  let _ = calls.remove(index);
+                }
+              }
+            }
+          }
+
+          let mut charged = None;
+        },
+        quote_mixed! { charged = calls.back().copied(); },
+        quote_mixed! { let refund = charged.map(__ThrottleRefund); },
+      )
+    }
+    (Some(_), None) => (
+      quote_mixed! {
+        struct __ThrottleCharge(::throttle_my_fn_runtime::Instant);
+
+        impl ::std::ops::Drop for __ThrottleCharge {
+          fn drop(&mut self) {
+            let finished_at = ::throttle_my_fn_runtime::system_now();
+            if let Some(calls) = #calls_static.lock().as_mut() {
+              // The charge fell out of the window already if the call took long enough.
+              if let Some(index) = calls.iter().position(|&call| call == self.0) {
+                let _ = calls.remove(index);
+              }
+              calls.push_back(finished_at);
+            }
+          }
+        }
+
+        let mut charged = None;
+      },
+      quote_mixed! { charged = Some(current_time); },
+      quote_mixed! { let _charge = charged.map(__ThrottleCharge); },
+    ),
+    (None, None) => (quote_mixed! {}, quote_mixed! {}, quote_mixed! {}),
+  };
+
+  // Only the calls deque is cleaned up, which the worker of deferred calls relies on as
+  // well.
+  let lock_free = fast::applies(args_parsed, shared);
+  let (cleanup_fn, book_keeping) = match (lock_free, &args_parsed.resolution) {
+    _ if args_parsed.sharded.is_some() => {
+      (quote_mixed! {}, shards::charge(times, duration, &charged_at, &skipped_exhausted))
+    }
+    (_, Some((resolution, _))) => {
+      (quote_mixed! {}, buckets::charge(times, duration, resolution, &skipped_exhausted))
+    }
+    (true, None) => (quote_mixed! {}, fast::charge(duration, &skipped_exhausted)),
+    (false, None) => (
+      cleanup_fn,
+      quote_mixed! {
+        {
+          #idle_check
+          #class_active
+
+          // Lock access to the calls deque.
+          let mut calls_guard = #calls_static.lock();
+
+          // If we're the first caller, we'll initialize the deque.
+          if calls_guard.is_none() {
+            *calls_guard = Some(::std::collections::VecDeque::with_capacity(#times));
+          }
+
+          // We've ensured the deque is initialized, so this unwrap cannot fail.
+          let mut calls = calls_guard.as_mut().unwrap();
+          #reset_idle
+
+          // Cleanup the calls deque.
+          __throttle_cleanup(&mut calls, current_time);
+
+          #key_active
+          #cost_exhausted
+          #cold_start_exhausted
+          #on_exhausted
+          #per_key_exhausted
+          #class_exhausted
+
+          calls.push_back(#charged_at);
+          #charge_cost
+          #record_charged
+          #record_executed
+          #occupancy
+
+          // Drop the lock here so that other threads can call us even while the inner
+          // impl function is running. The book-keeping is scoped so that the futures of
+          // async functions don't hold the lock across awaiting the inner impl function
+          // either.
+          drop(calls_guard);
+          #on_execute
+        }
+      },
+    ),
+  };
+
+  // Calls with a deadline go around again once they have waited for a slot, and with
+  // `stats = true`, the time they waited for is recorded once they are let through.
+  let book_keeping = match args_parsed.deadline {
+    Some(_) => {
+      let record_wait = stats::record(
+        args_parsed,
+        &split,
+        quote_mixed! { record_wait(current_time.saturating_duration_since(called_at)) },
+      );
+      quote_mixed! {
+        let called_at = current_time;
+        let mut current_time = current_time;
+        #class_waiting
+        loop {
+          #book_keeping
+          break;
+        }
+        #record_wait
+      }
+    }
+    None => book_keeping,
+  };
+
+  // With `toggle = true`, throttling can be switched off at runtime by a function next
+  // to the outer function, letting every call through right away without charging it.
+  // Switched off calls are still counted as executed by the statistics.
+  let (toggle_items, inactive_check, book_keeping) = match &args_parsed.toggle {
+    Some(name) => {
+      split.reject_method(&format!("option `{}`", name))?;
+
+      let ident = &outer_sig.ident;
+      let enabled_static = format_ident!(
+        "__THROTTLE_ENABLED_{}",
+        ident.to_string().trim_start_matches('_').to_uppercase()
+      );
+      let enabled_fn = format_ident!("{}_throttle_enabled", ident);
+      let enabled_doc = format!(
+        "Switch throttling [`{}`] on or off. While off, every call is executed right \
+         away without being charged against the quota.",
+        ident
+      );
+
+      (
+        quote_mixed! {
+          static #enabled_static: ::std::sync::atomic::AtomicBool =
+            ::std::sync::atomic::AtomicBool::new(true);
+
+          #[doc = #enabled_doc]
+          #vis fn #enabled_fn(set: bool) {
+            #enabled_static.store(set, ::std::sync::atomic::Ordering::Relaxed);
+          }
+        },
+        quote_mixed! {
+          let throttled = #enabled_static.load(::std::sync::atomic::Ordering::Relaxed);
+          if throttled {
+            #inactive_check
+          }
+        },
+        quote_mixed! {
+          if throttled {
+            #book_keeping
+          } else {
+            #record_executed
+          }
+        },
+      )
+    }
+    None => (quote_mixed! {}, inactive_check, book_keeping),
+  };
+
+  // With `with_override = true`, bypassing calls are let through like when throttling is
+  // switched off, and counted as executed all the same.
+  let (inactive_check, book_keeping) = match args_parsed.with_override {
+    Some(_) => (
+      quote_mixed! {
+        let bypassed = override_.is_bypass();
+        if !bypassed {
+          #inactive_check
+        }
+      },
+      quote_mixed! {
+        if !bypassed {
+          #book_keeping
+        } else {
+          #record_executed
+        }
+      },
+    ),
+    None => (inactive_check, book_keeping),
+  };
+
+  // With `shadow = (times, duration)`, every call is also charged against a window of the
+  // candidate limit, which only counts the calls it would have skipped.
+  let charge_shadow = match &args_parsed.shadow {
+    Some((shadow_times, shadow_duration)) => {
+      let record_shadow_skipped =
+        stats::record(args_parsed, &split, quote_mixed! { record_shadow_skipped() });
+      quote_mixed! {
+        {
+          static __THROTTLE_SHADOW: ::throttle_my_fn_runtime::parking_lot::Mutex<
+            ::std::collections::VecDeque<::throttle_my_fn_runtime::Instant>,
+          > = ::throttle_my_fn_runtime::parking_lot::const_mutex(
+            ::std::collections::VecDeque::new(),
+          );
+
+          let mut shadow_calls = __THROTTLE_SHADOW.lock();
+          ::throttle_my_fn_runtime::window::cleanup(
+            &mut shadow_calls,
+            current_time,
+            #shadow_times,
+            #shadow_duration,
+          );
+
+          if shadow_calls.len() < #shadow_times {
+            shadow_calls.push_back(current_time);
+          } else {
+            #record_shadow_skipped
+          }
+        }
+      }
+    }
+    None => quote_mixed! {},
+  };
+
+  // In warning and observing modes, the book-keeping is run in a closure returning
+  // whether the call is over budget, rather than skipping it. In warning mode, the call
+  // is reported before it is executed all the same.
+  let charge = match &args_parsed.warn {
+    Some(_) => {
+      let over_budget = quote_mixed! {
+        (|| {
+          #inactive_check
+          #book_keeping
+          false
+        })()
+      };
+
+      let name = outer_sig.ident.to_string();
+      match &args_parsed.on_over_budget {
+        Some(handler) => quote_mixed! {
+          if #over_budget {
+            (#handler)(#name, caller);
+          }
+        },
+        None => quote_mixed! { let _ = #over_budget; },
+      }
+    }
+    None => quote_mixed! {
+      #inactive_check
+      #acquire_permit
+      #book_keeping
+    },
+  };
+
+  // Finally generate our code. With `future = poll`, the body is put in an async block
+  // for the book-keeping to happen once the returned future is first polled.
+  let body = split.body(quote_mixed! {
+    #cleanup_fn
+    #detailed_items
+    #inactive_for_fn
+
+    let current_time = ::throttle_my_fn_runtime::system_now();
+
+    #calls_item
+    #per_key_items
+
+    #mode_items
+    #permit_items
+
+    #charge_shadow
+    #charge_items
+    #charge
+    #charge_guard
+
+    #admitted
+  });
+
+  // With `consts = true`, the limit is available to callers as constants next to the
+  // outer function, which are associated constants for methods. Their docs don't link to
+  // the function, since methods would have to be linked through `Self`.
+  let const_items = match &args_parsed.consts {
+    Some(_) => {
+      let name = outer_sig.ident.to_string().to_uppercase();
+      let times_ident = format_ident!("{}_THROTTLE_TIMES", name);
+      let period_ident = format_ident!("{}_THROTTLE_PERIOD", name);
+      let times_doc = format!("The number of calls to `{}` per period.", outer_sig.ident);
+      let period_doc = format!("The period of the limit on `{}`.", outer_sig.ident);
+
+      quote_mixed! {
+        #[doc = #times_doc]
+        #vis const #times_ident: usize = #const_times;
+
+        #[doc = #period_doc]
+        #vis const #period_ident: ::std::time::Duration = #const_duration;
+      }
+    }
+    None => quote_mixed! {},
+  };
+
+  // With a limit read from a configuration file, the function is rebuilt whenever the
+  // file changes.
+  let config_dependency = match &args_parsed.config_path {
+    Some(path) => quote_mixed! { const _: &[u8] = ::std::include_bytes!(#path); },
+    None => quote_mixed! {},
+  };
+
+  // With a limit read from a provider, the provider is called again once the limit it
+  // last returned is older than `rate_refresh`. The limit is read whenever `times` or
+  // `duration` are, so that the functions nested in the outer function see it as well.
+  let rate_fn = match &args_parsed.rate_from {
+    Some(RateFrom { provider, refresh, .. }) => quote_mixed! {
+      fn __throttle_rate() -> (usize, ::std::time::Duration) {
+        type __ThrottleRate = (usize, ::std::time::Duration);
+        static __THROTTLE_RATE: ::throttle_my_fn_runtime::parking_lot::Mutex<
+          Option<(::throttle_my_fn_runtime::Instant, __ThrottleRate)>,
+        > = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+
+        let current_time = ::throttle_my_fn_runtime::system_now();
+        let mut cached = __THROTTLE_RATE.lock();
+        match *cached {
+          Some((read_at, rate)) if current_time.duration_since(read_at) < #refresh => {
+            rate
+          }
+          _ => {
+            let rate: __ThrottleRate = #provider();
+            *cached = Some((current_time, rate));
+            rate
+          }
+        }
+      }
+    },
+    None => quote_mixed! {},
+  };
+
+  // With `mode = warn`, the outer function tracks its caller, for calls over budget to be
+  // reported with the location they are made at. The location is read before the body is
+  // put in an async block with `future = poll`. Async functions cannot track their
+  // caller, and neither can functions with an explicit ABI.
+  let reports_caller = args_parsed.warn.is_some() && !args_parsed.observe;
+  let is_track_caller = |attr: &syn::Attribute| attr.path.is_ident("track_caller");
+  let track_caller = match (&outer_sig.asyncness, &outer_sig.abi) {
+    _ if !reports_caller || attrs.iter().any(is_track_caller) => quote_mixed! {},
+    (None, None) => quote_mixed! { #[track_caller] },
+    _ => quote_mixed! {},
+  };
+  let caller = match reports_caller {
+    true => quote_mixed! { let caller = ::std::panic::Location::caller(); },
+    false => quote_mixed! {},
+  };
+
+  let module_items = split.gate_items(quote_mixed! {
+    #limit_module_items
+    #companion_items
+    #const_items
+    #toggle_items
+    #drain_items
+  })?;
+
+  let gen = quote_mixed! {
+    // The outer function with an Option<T> return type.
+    #(#attrs)* #track_caller #vis #outer_sig {
+      #config_dependency
+      #rate_fn
+      #limit_body_items
+
+      // The inner impl function. Pretty much the user provided one without any visibility
+      // modifiers.
+      #impl_fn
+
+      #caller
+      #body
+    }
 
-use proc_macro::TokenStream;
-use proc_macro2::TokenStream as TokenStream2;
-use quote::{quote, ToTokens};
-use std::fmt::Display;
-use syn::parse::Parser;
-use syn::parse_macro_input;
-use syn::punctuated::Punctuated;
-use syn::{Expr, Ident, ItemFn, ReturnType, Token};
+    #module_items
+  };
 
-/// Shorthand for creating `syn::Error`s that type-check with [proc_macro::TokenStream].
+  Ok(gen)
+}
+
+/// Debounce a function's execution until calls to it have stopped for a period of time.
 ///
-/// Creates fancy-looking error messages on stable Rust that can be returned from
-/// functions that return a [proc_macro::TokenStream].
+/// Every call to the function replaces the previous pending call and restarts the quiet
+/// period. Once `duration` has passed without any further calls, the latest call is
+/// executed by a background thread with its arguments.
 ///
-/// # Arguments
+/// **NOTE:** The function being decorated with this macro will have its return value
+/// changed to a `throttle_my_fn_runtime::ThrottleHandle`, which resolves to the return
+/// value once the call has executed, or to [std::option::Option::None] if the call was
+/// replaced by a later one.
 ///
-/// * `tokens` - The [proc_macro::TokenStream] to be spanned in the error message.
+/// # Arguments
 ///
-/// * `message` - Custom user-provided error message.
+/// * `duration` - The [std::time::Duration] of quiet after which the function executes.
 ///
-/// # Returns
+/// # Examples
 ///
-/// A [proc_macro::TokenStream], but effectively returns nothing and stops execution. It
-/// uses [syn::parse::Error::into_compile_error] which is akin to `compile_error!()`.
-fn err<T, U>(tokens: T, message: U) -> TokenStream
-where
-  T: ToTokens,
-  U: Display,
-{
-  syn::Error::new_spanned(tokens, message).into_compile_error().into()
+/// ```ignore
+/// #[debounce(Duration::from_millis(300))]
+/// pub(crate) fn search(query: String) -> Vec<String> {
+///   ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn debounce(args: TokenStream, func: TokenStream) -> TokenStream {
+  debounce::debounce(args, func)
 }
 
-/// Throttle a function's execution count over a period of time.
+/// Limit a function to executing at most once per period of time.
 ///
-/// Slow down how many times a function can be executed over a duration: 100 times per
-/// [std::time::Duration::from_secs]`(1)` for 100 times per second.
+/// This is a shorthand for `#[throttle(1, duration)]` which skips all calls made within
+/// `duration` of the last executed call, but only needs to keep track of a single
+/// timestamp.
 ///
 /// **NOTE:** The function being decorated with this macro will have its return value
 /// changed to be wrapped in an [std::option::Option] indicating whether the function
@@ -107,161 +3403,399 @@ where
 ///
 /// # Arguments
 ///
-/// * `times` - Number of times the function should be limited to running over `duration`.
+/// * `duration` - The [std::time::Duration] to wait after an execution before the
+///   function can execute again.
 ///
-/// * `duration` - The [std::time::Duration] over which the function should be allowed to
-/// run `times` times.
+/// * `skipped` - Optional. The value to return when a call is skipped, keeping the
+///   function's return type instead of wrapping it in an [std::option::Option].
 ///
 /// # Examples
 ///
 /// ```ignore
-/// #[throttle(10, Duration::from_secs(1))]
-/// pub(crate) fn run_10_times_per_second(arg: &str) -> String {
+/// #[cooldown(Duration::from_secs(5))]
+/// pub(crate) fn refresh_cache() {
 ///   ...
 /// }
+/// ```
+#[proc_macro_attribute]
+pub fn cooldown(args: TokenStream, func: TokenStream) -> TokenStream {
+  cooldown::cooldown(args, func)
+}
+
+/// Execute a function only once every N calls.
 ///
-/// #[throttle(1, Duration::from_millis(100))]
-/// pub(crate) fn run_once_per_100_milliseconds(arg: &str) -> String {
+/// Sampling is count-based rather than time-based: the function executes on the first
+/// call and then on every `n`th call after it, regardless of how much time passes between
+/// calls. This is useful for high-volume logging or telemetry call sites.
+///
+/// **NOTE:** The function being decorated with this macro will have its return value
+/// changed to be wrapped in an [std::option::Option] indicating whether the function
+/// executed or not.
+///
+/// # Arguments
+///
+/// * `n` - The function executes once every `n` calls, must not be zero.
+///
+/// * `skipped` - Optional. The value to return when a call is skipped, keeping the
+///   function's return type instead of wrapping it in an [std::option::Option].
+///
+/// # Examples
+///
+/// ```ignore
+/// #[sample(100)]
+/// pub(crate) fn log_request(request: &Request) {
 ///   ...
 /// }
 /// ```
 #[proc_macro_attribute]
-pub fn throttle(args: TokenStream, func: TokenStream) -> TokenStream {
-  // How this macro works is by wrapping the user-provided function (called the impl here)
-  // in an outer function with a similar signature. The return type T of the impl function
-  // is changed to an Option<T> on the outer function to indicate whether the function
-  // executed or not.
-  //
-  // The outer function then initializes the necessary statics, does the book-keeping,
-  // then decides whether the execute the impl or not.
-
-  const ARGS_ERR_MSG: &str = "expecting a comma-separated pair of expressions: \
-                              #[throttle(<number-of-calls>, <duration>)]";
-
-  let args_parser = Punctuated::<Expr, Token![,]>::parse_separated_nonempty;
-  let args_parsed = match args_parser.parse(args.clone()) {
-    Ok(args) => args,
-    Err(e) => return err(TokenStream2::from(args), &format!("{}, {}", e, ARGS_ERR_MSG)),
-  };
-
-  let mut args_iter = args_parsed.iter();
-
-  let times = match args_iter.next() {
-    Some(times) => times,
-    None => return err(TokenStream2::from(args), ARGS_ERR_MSG),
-  };
-
-  let duration = match args_iter.next() {
-    Some(duration) => duration,
-    None => return err(TokenStream2::from(args), ARGS_ERR_MSG),
-  };
-
-  // Clone func and operate on the clone so we can move it later for creating error
-  // messages with spans.
-  let func_clone = func.clone();
-  let func_parsed = parse_macro_input!(func_clone as ItemFn);
-
-  let attrs = &func_parsed.attrs;
-  let vis = &func_parsed.vis;
-  let impl_block = &func_parsed.block;
-
-  // Rename the impl function's name from FUNC_NAME to __throttle_impl_FUNC_NAME. Not
-  // really necessary, and could have just been renamed to inner_impl or something like
-  // that, since impl is an inner function inside of the outer function.
-  let mut impl_sig = func_parsed.sig.clone();
-  let impl_ident_name = &format!("__throttle_impl_{}", impl_sig.ident);
-  let impl_ident = Ident::new(impl_ident_name, impl_sig.ident.span());
-  impl_sig.ident = impl_ident.clone();
-
-  // Change the outer function's signature to be the same as the inner impl function's
-  // signature, except for its return type: change that to return an Option<T>.
-  let mut outer_sig = func_parsed.sig.clone();
-  let outer_sig_ret = TokenStream::from(match outer_sig.output {
-    ReturnType::Default => quote! { -> Option<()>},
-    ReturnType::Type(_, t) => quote! { -> Option<#t>},
-  });
-  outer_sig.output = parse_macro_input!(outer_sig_ret);
-
-  // Create the list of arguments for passing the outer function's arguments to the inner
-  // impl function.
-  let mut call_params = Punctuated::<Expr, Token![,]>::new();
-  for input in &impl_sig.inputs {
-    match input {
-      syn::FnArg::Receiver(_) => {
-        return err(TokenStream2::from(func), "Methods are not supported")
-      }
-      syn::FnArg::Typed(t) => {
-        let pat = &t.pat;
-        let param = TokenStream::from(quote! {#pat});
-        let param = parse_macro_input!(param as Expr);
-        call_params.push(param);
-      }
-    }
-  }
-  let call_params = call_params.iter();
-
-  // Finally generate our code.
-  let gen = quote! {
-    // The outer function with an Option<T> return type.
-    #(#attrs)* #vis #outer_sig {
-      // The inner impl function. Pretty much the user provided one without any visibility
-      // modifiers.
-      #impl_sig #impl_block
-
-      use parking_lot::{Mutex, const_mutex};
-      use std::collections::VecDeque;
-      use std::time::Instant;
-
-      // We maintain a list of timestamps at which calls to the function have happened in
-      // the `calls` deque. This function cleans the deque up by removing all calls that
-      // happened before `current_time` - `duration`. The deque should never grow larger
-      // than `times`.
-      fn cleanup(calls: &mut VecDeque<Instant>, current_time: Instant) {
-        if calls.len() < #times {
-          return;
-        }
-
-        while let Some(call_time) = calls.front().copied() {
-          if current_time.duration_since(call_time) > #duration {
-            let _ = calls.pop_front();
-          } else {
-            break;
-          }
-        }
-      }
-
-      let current_time = Instant::now();
+pub fn sample(args: TokenStream, func: TokenStream) -> TokenStream {
+  sample::sample(args, func)
+}
 
-      static CALLS: Mutex<Option<VecDeque<Instant>>> = const_mutex(None);
+/// Execute a function at most once per process.
+///
+/// The first call executes the function, all later calls are skipped. This is
+/// thread-safe: if several threads call the function at the same time, only one of them
+/// executes it.
+///
+/// **NOTE:** The function being decorated with this macro will have its return value
+/// changed to be wrapped in an [std::option::Option] indicating whether the function
+/// executed or not.
+///
+/// # Arguments
+///
+/// * `cache_last` - Optional. Instead of returning [std::option::Option::None] on later
+///   calls, return a clone of the result of the first call. Callers that come in while
+///   the first call is executing wait for its result. The return type must be `Clone`,
+///   `Send` and `Sync`.
+///
+/// * `skipped` - Optional. The value to return when a call is skipped, keeping the
+///   function's return type instead of wrapping it in an [std::option::Option]. Cannot
+///   be combined with `cache_last`.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[once]
+/// pub(crate) fn print_banner() {
+///   ...
+/// }
+///
+/// #[once(cache_last)]
+/// pub(crate) fn load_config(path: &str) -> Config {
+///   ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn once(args: TokenStream, func: TokenStream) -> TokenStream {
+  once::once(args, func)
+}
 
-      // Lock access to the calls deque.
-      let mut calls_guard = CALLS.lock();
+/// Limit how many executions of a function can be in flight at the same time.
+///
+/// A call made while `n` executions are already in flight (e.g. on other threads) is
+/// skipped. Unlike [macro@throttle], this limits concurrency rather than the call rate.
+///
+/// **NOTE:** The function being decorated with this macro will have its return value
+/// changed to be wrapped in an [std::option::Option] indicating whether the function
+/// executed or not.
+///
+/// # Arguments
+///
+/// * `n` - The maximum number of executions in flight at the same time.
+///
+/// * `skipped` - Optional. The value to return when a call is skipped, keeping the
+///   function's return type instead of wrapping it in an [std::option::Option].
+///
+/// # Examples
+///
+/// ```ignore
+/// #[max_concurrent(4)]
+/// pub(crate) fn resize_image(image: &Image) -> Image {
+///   ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn max_concurrent(args: TokenStream, func: TokenStream) -> TokenStream {
+  max_concurrent::max_concurrent(args, func)
+}
 
-      // If we're the first caller, we'll initialize the deque.
-      if calls_guard.is_none() {
-        *calls_guard = Some(VecDeque::with_capacity(#times));
-      }
+/// Stop calling a failing function for a while after too many consecutive errors.
+///
+/// Meant for functions returning a [std::result::Result]. After `failures` consecutive
+/// calls have returned an error, the circuit opens and all calls are skipped until
+/// `reset` has elapsed. Then a single probing call is let through: if it succeeds the
+/// circuit closes again, otherwise it stays open for another `reset` period.
+///
+/// **NOTE:** The function being decorated with this macro will have its return value
+/// changed to be wrapped in an [std::option::Option] indicating whether the function
+/// executed or not.
+///
+/// # Arguments
+///
+/// * `failures` - Number of consecutive errors after which the circuit opens.
+///
+/// * `reset` - The [std::time::Duration] (or a string literal such as `"30s"`) after
+///   which an open circuit lets a probing call through.
+///
+/// * `skipped` - Optional. The value to return when a call is skipped, keeping the
+///   function's return type instead of wrapping it in an [std::option::Option].
+///
+/// # Examples
+///
+/// ```ignore
+/// #[circuit_breaker(failures = 5, reset = "30s")]
+/// pub(crate) fn fetch(url: &str) -> Result<String, Error> {
+///   ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn circuit_breaker(args: TokenStream, func: TokenStream) -> TokenStream {
+  circuit_breaker::circuit_breaker(args, func)
+}
 
-      // We've ensured the deque is initialized, so this unwrap cannot fail.
-      let mut calls = calls_guard.as_mut().unwrap();
+/// Retry a failing function, with an optional exponential backoff.
+///
+/// Meant for functions returning a [std::result::Result]. If a call returns an error, it
+/// is retried up to `retries` times, waiting `backoff` before the first retry and twice
+/// as long before every retry after it. The arguments are cloned for every attempt, so
+/// they must implement [std::clone::Clone].
+///
+/// When stacked on top of [macro@throttle], every attempt goes through the rate limit: an
+/// attempt that gets throttled doesn't use up a retry, the function waits and tries again
+/// until it gets a slot. The function's return type then stays a [std::result::Result]
+/// instead of being wrapped in an [std::option::Option].
+///
+/// # Arguments
+///
+/// * `retries` - Maximum number of retries after the first failed call.
+///
+/// * `backoff` - Optional. The [std::time::Duration] (or a string literal such as
+///   `"100ms"`) to wait before the first retry.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[retry(3, backoff = "100ms")]
+/// #[throttle(10, "1s")]
+/// pub(crate) fn fetch(url: &str) -> Result<String, Error> {
+///   ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn retry(args: TokenStream, func: TokenStream) -> TokenStream {
+  retry::retry(args, func)
+}
 
-      // Cleanup the calls deque.
-      cleanup(&mut calls, current_time);
+/// Cache a function's return values for a period of time.
+///
+/// Calls are keyed by their arguments: a call made within `ttl` of an executed call with
+/// the same arguments returns a clone of the value that call returned, without executing
+/// the function. The arguments must implement [std::hash::Hash], [std::cmp::Eq] and
+/// [std::clone::Clone] (for reference arguments, their owned counterpart is used, e.g.
+/// [std::string::String] for `&str`), and the return value must implement
+/// [std::clone::Clone].
+///
+/// When stacked on top of [macro@throttle], cache hits don't count against the rate limit
+/// and only calls that miss the cache are throttled. Like with [macro@throttle], the
+/// function's return value is then wrapped in an [std::option::Option], which is `None`
+/// when a call missed the cache and was throttled.
+///
+/// # Arguments
+///
+/// * `ttl` - The [std::time::Duration] (or a string literal such as `"10s"`) for which a
+///   returned value is reused.
+/// * `max_keys` - Optional, the number of distinct sets of arguments whose values are
+///   cached at the same time. When the cache is full, the oldest value is evicted to
+///   make room for a new one. Unbounded by default, which is only safe when the
+///   arguments cannot be chosen by an attacker.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[memoize(ttl = "10s")]
+/// pub(crate) fn lookup(host: &str) -> Option<IpAddr> {
+///   ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn memoize(args: TokenStream, func: TokenStream) -> TokenStream {
+  memoize::memoize(args, func)
+}
 
-      // Return None if our quota is full for the duration.
-      if calls.len() >= #times {
-        return None;
-      }
+/// Abandon a function's execution if it takes too long.
+///
+/// Every call is executed on a thread of its own while the caller waits for its result.
+/// If the call has not returned within the given duration, the caller stops waiting and
+/// gets `None`, while the call keeps running to completion in the background and its
+/// result is discarded. Panics are propagated to the caller.
+///
+/// Since calls are moved onto another thread, the function's arguments and return value
+/// must be `Send + 'static`.
+///
+/// The future of an async function is awaited on the tokio timer instead, and dropped
+/// once the duration has passed. This requires the `tokio` feature of the
+/// `throttle_my_fn_runtime` crate.
+///
+/// **NOTE:** The function's return value is wrapped in an [std::option::Option], which is
+/// `None` when the call has timed out.
+///
+/// # Arguments
+///
+/// * `duration` - The [std::time::Duration] (or a string literal such as `"2s"`) to wait
+///   for a call before giving up on it.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[timeout(Duration::from_secs(2))]
+/// pub(crate) fn query(sql: String) -> Vec<Row> {
+///   ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn timeout(args: TokenStream, func: TokenStream) -> TokenStream {
+  timeout::timeout(args, func)
+}
 
-      calls.push_back(current_time);
+/// Give each instance of a struct a quota of its own, shared by its methods.
+///
+/// On a struct, `#[throttle_group(times, duration)]` adds a `throttle_group` field
+/// holding the quota of the instance, with the visibility of the struct, and a
+/// `new_throttle_group()` associated function creating the quota to initialize it
+/// with. On a method of the struct, `#[throttle_group]` throttles it against the quota
+/// of its receiver, so that all marked methods of an instance draw from the same quota,
+/// while different instances don't affect each other.
+///
+/// **NOTE:** The methods being decorated with this macro will have their return values
+/// changed to be wrapped in an [std::option::Option] indicating whether the method
+/// executed or not.
+///
+/// # Arguments
+///
+/// * `times` - On the struct, the number of times the methods of an instance are limited
+///   to running over `duration`.
+///
+/// * `duration` - On the struct, the [std::time::Duration] over which the methods of an
+///   instance are allowed to run `times` times, or a string literal such as `"1s"`.
+///
+/// * `skipped` - Optional, on a method. The value to return when a call is skipped,
+///   keeping the method's return type instead of wrapping it in an
+///   [std::option::Option].
+///
+/// # Examples
+///
+/// ```ignore
+/// #[throttle_group(100, "1s")]
+/// pub(crate) struct Client {
+///   url: String,
+/// }
+///
+/// impl Client {
+///   pub(crate) fn new(url: String) -> Self {
+///     Client { url, throttle_group: Client::new_throttle_group() }
+///   }
+///
+///   #[throttle_group]
+///   pub(crate) fn get(&self, path: &str) -> Response {
+///     ...
+///   }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn throttle_group(args: TokenStream, item: TokenStream) -> TokenStream {
+  throttle_group::throttle_group(args, item)
+}
 
-      // Drop the lock here so that other threads can call us even while the inner impl
-      // function is running.
-      drop(calls_guard);
+/// Throttle a block of code's execution count over a period of time.
+///
+/// Like [macro@throttle], but for a block of code at a given call site rather than for a
+/// whole function. Every call site has its own quota, and the block is evaluated in
+/// place, so `return`, `?` and `break` inside of it apply to the surrounding code.
+///
+/// **NOTE:** The macro evaluates to an [std::option::Option] of the block's value,
+/// indicating whether the block executed or not.
+///
+/// # Arguments
+///
+/// * `times` - Number of times the block should be limited to running over `duration`.
+///
+/// * `duration` - The [std::time::Duration] over which the block should be allowed to run
+///   `times` times, or a string literal such as `"1s"` or `"500ms"`.
+///
+/// * The [macro@throttle] options that apply to skipped calls (e.g. `concurrency`).
+///
+/// * The block to throttle, last.
+///
+/// # Examples
+///
+/// ```ignore
+/// for event in events {
+///   throttle_block!(1, Duration::from_secs(1), {
+///     eprintln!("Processing events, currently at {}", event.id);
+///   });
+///   ...
+/// }
+/// ```
+#[proc_macro]
+pub fn throttle_block(input: TokenStream) -> TokenStream {
+  throttle_block::throttle_block(input)
+}
 
-      Some(#impl_ident(#(#call_params),*))
-    }
-  };
+/// Throttle a closure's execution count over a period of time.
+///
+/// Like [macro@throttle], but creates a throttled closure out of a closure. The resulting
+/// closure takes the same arguments, and its return value is wrapped in an
+/// [std::option::Option] indicating whether the inner closure executed or not. Every
+/// closure created by the macro has its own quota, so the same closure can be throttled
+/// separately for different purposes, e.g. once per connection.
+///
+/// # Arguments
+///
+/// * `times` - Number of times the closure should be limited to running over `duration`.
+///
+/// * `duration` - The [std::time::Duration] over which the closure should be allowed to
+///   run `times` times, or a string literal such as `"1s"` or `"500ms"`.
+///
+/// * The closure to throttle, last.
+///
+/// # Examples
+///
+/// ```ignore
+/// let double = throttle_fn!(10, "1s", |x: u32| x * 2);
+/// assert_eq!(double(21), Some(42));
+/// ```
+#[proc_macro]
+pub fn throttle_fn(input: TokenStream) -> TokenStream {
+  throttle_fn::throttle_fn(input)
+}
 
-  TokenStream::from(gen)
+/// Derive constructors and wrapper methods for the throttlers held by a struct.
+///
+/// Unlike [macro@throttle], which keeps its quota in a static, the limits of a struct's
+/// fields are per-object, e.g. per connection or per session. Every field carrying a
+/// `#[throttled(times, duration)]` attribute must be a
+/// `throttle_my_fn_runtime::Throttler`, for which the struct gets:
+///
+/// * `FIELD_throttler()` - An associated function creating a throttler with the
+///   configured limit, for initializing the field in the struct's constructor.
+///
+/// * `try_FIELD(f)` - A method calling the closure `f` if the field's throttler lets the
+///   call through, returning its value wrapped in an [std::option::Option].
+///
+/// # Examples
+///
+/// ```ignore
+/// #[derive(Throttled)]
+/// pub(crate) struct Session {
+///   #[throttled(10, "1s")]
+///   requests: Throttler,
+/// }
+///
+/// let session = Session { requests: Session::requests_throttler() };
+/// let response = session.try_requests(|| handle(request));
+/// ```
+#[proc_macro_derive(Throttled, attributes(throttled))]
+pub fn throttled(input: TokenStream) -> TokenStream {
+  throttled::throttled(input)
 }
@@ -0,0 +1,120 @@
+//! Expansion of the `#[max_concurrent(...)]` attribute.
+
+use crate::args::{with_usage, SkippableArgs};
+use crate::split::Split;
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{parse_macro_input, Expr, ItemFn};
+
+/// Generate the items needed to bound the number of in-flight executions to `max`.
+///
+/// This is a tiny semaphore: a counter of in-flight executions, along with a `Permit`
+/// guard type which releases its slot when dropped, even when the inner impl function
+/// panics. Permits are acquired with `Permit::try_acquire()`, which returns `None` if
+/// there are already `max` executions in flight.
+pub(crate) fn permit_items(max: &Expr) -> TokenStream2 {
+  quote_mixed! {
+    static __THROTTLE_IN_FLIGHT: ::std::sync::atomic::AtomicUsize =
+      ::std::sync::atomic::AtomicUsize::new(0);
+
+    struct __ThrottlePermit;
+
+    impl __ThrottlePermit {
+      fn try_acquire() -> Option<__ThrottlePermit> {
+
+        let mut in_flight =
+          __THROTTLE_IN_FLIGHT.load(::std::sync::atomic::Ordering::Relaxed);
+        loop {
+          if in_flight >= #max {
+            return None;
+          }
+
+          match __THROTTLE_IN_FLIGHT.compare_exchange_weak(
+            in_flight,
+            in_flight + 1,
+            ::std::sync::atomic::Ordering::Acquire,
+            ::std::sync::atomic::Ordering::Relaxed,
+          ) {
+            Ok(_) => return Some(__ThrottlePermit),
+            Err(current) => in_flight = current,
+          }
+        }
+      }
+    }
+
+    impl ::std::ops::Drop for __ThrottlePermit {
+      fn drop(&mut self) {
+        __THROTTLE_IN_FLIGHT.fetch_sub(1, ::std::sync::atomic::Ordering::Release);
+      }
+    }
+  }
+}
+
+/// Generate the `__THROTTLE_SEMAPHORE` static of `max` permits, which the calls of async
+/// functions wait on for a permit instead of being skipped (`on_busy = wait`).
+///
+/// The semaphore of tokio suspends the waiting tasks, and a task whose future is dropped
+/// while waiting gives up its place in the queue. The permit is released when dropped,
+/// like [permit_items] ones.
+pub(crate) fn semaphore_item(max: &Expr) -> TokenStream2 {
+  quote_mixed! {
+    static __THROTTLE_SEMAPHORE: ::std::sync::LazyLock<
+      ::throttle_my_fn_runtime::Semaphore,
+    > = ::std::sync::LazyLock::new(|| ::throttle_my_fn_runtime::Semaphore::new(#max));
+  }
+}
+
+/// See [crate::max_concurrent].
+pub(crate) fn max_concurrent(args: TokenStream, func: TokenStream) -> TokenStream {
+  // Limiting concurrency is independent of the call rate: instead of timestamps of past
+  // calls, only the number of executions currently in flight needs to be tracked.
+
+  const ARGS_ERR_MSG: &str =
+    "expecting a number-of-executions expression: #[max_concurrent(<n>)]";
+
+  let args_parsed =
+    match with_usage(ARGS_ERR_MSG, || syn::parse::<SkippableArgs>(args.clone())) {
+      Ok(args) => args,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let max = match with_usage(ARGS_ERR_MSG, || args_parsed.value()) {
+    Ok(max) => max,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  let func_parsed = parse_macro_input!(func as ItemFn);
+  let mut split = match Split::new(func_parsed) {
+    Ok(split) => split,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  let skipped = args_parsed.skipped.as_ref();
+  let (skipped, wrap) =
+    match split.set_skippable_output(skipped, "`#[max_concurrent(...)]`") {
+      Ok(skippable) => skippable,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let Split { attrs, vis, impl_fn, outer_sig, call, .. } = &split;
+  let permit_items = permit_items(&max);
+
+  let gen = quote_mixed! {
+    #(#attrs)* #vis #outer_sig {
+      #impl_fn
+
+      #permit_items
+
+      // Return None (or the skipped value) if there are already too many executions in
+      // flight. The permit is held until the inner impl function returns.
+      let _permit = match __ThrottlePermit::try_acquire() {
+        Some(permit) => permit,
+        None => return #skipped,
+      };
+
+      #wrap(#call)
+    }
+  };
+
+  TokenStream::from(gen)
+}
@@ -0,0 +1,131 @@
+//! Expansion of the `#[memoize(...)]` attribute.
+
+use crate::args::{with_usage, MemoizeArgs};
+use crate::clock;
+use crate::keyed::Key;
+use crate::split::Split;
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, ItemFn};
+
+/// See [crate::memoize].
+pub(crate) fn memoize(args: TokenStream, func: TokenStream) -> TokenStream {
+  // Returned values are kept in a map, keyed by the arguments they were returned for,
+  // along with the time at which they were returned. A call with the same arguments
+  // within the time-to-live gets a clone of the cached value without executing the
+  // function. Expired entries are evicted whenever a new value is cached, and so is the
+  // oldest entry if the cache is full.
+  //
+  // When stacked on top of #[throttle(...)], the throttle attribute is moved onto the
+  // inner impl function: cache hits don't count against the rate limit, and calls that
+  // miss the cache and get throttled return None without caching anything.
+
+  const ARGS_ERR_MSG: &str = "expecting a ttl option and an optional bound: \
+                              #[memoize(ttl = <duration>, max_keys = <n>)]";
+
+  let args_parsed =
+    match with_usage(ARGS_ERR_MSG, || syn::parse::<MemoizeArgs>(args.clone())) {
+      Ok(args) => args,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let ttl = &args_parsed.ttl;
+
+  // With a bounded cache, room is made for a new entry by evicting the one cached the
+  // longest ago, which would be the first to expire anyway. Keys aren't required to be
+  // `Clone`, so it is found by its time.
+  let evict_oldest = match &args_parsed.max_keys {
+    Some(max_keys) => quote_mixed! {
+      if cache.len() >= #max_keys && !cache.contains_key(&key) {
+        let oldest = cache.values().map(|&(cached_at, _)| cached_at).min();
+        cache.retain(|_, (cached_at, _)| Some(*cached_at) != oldest);
+      }
+    },
+    None => quote_mixed! {},
+  };
+
+  let func_parsed = parse_macro_input!(func as ItemFn);
+  let mut split = match Split::new(func_parsed) {
+    Ok(split) => split,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  // The cache is keyed by the arguments only, so it would be shared by all instances.
+  if let Err(e) = split.reject_method("`#[memoize(...)]`") {
+    return e.into_compile_error().into();
+  }
+
+  // Cached results are kept in a static, which cannot name an `impl Trait` type.
+  if let Err(e) = split.reject_impl_trait("`#[memoize(...)]`") {
+    return e.into_compile_error().into();
+  }
+
+  let throttle_attrs = split.take_throttle_attrs();
+
+  let ret_ty = split.ret_ty.clone();
+  let wrap = if throttle_attrs.is_empty() {
+    quote_mixed! {}
+  } else {
+    let output = quote_mixed! { Option<#ret_ty> };
+    if let Err(e) = split.set_output(output, "`#[memoize(...)]`") {
+      return e.into_compile_error().into();
+    }
+    quote_mixed! { Some }
+  };
+
+  let key = Key::new(&split);
+  let Key { ty: key_ty, expr: key_expr } = &key;
+
+  let Split { attrs, vis, impl_fn, outer_sig, call, .. } = &split;
+
+  let result = if throttle_attrs.is_empty() {
+    quote_mixed! { #call }
+  } else {
+    quote_mixed! { #call? }
+  };
+
+  let gen = quote_mixed! {
+    #(#attrs)* #vis #outer_sig {
+      #(#throttle_attrs)* #impl_fn
+
+      type __ThrottleKey = #key_ty;
+
+      type __ThrottleCache = ::std::collections::HashMap<
+        __ThrottleKey,
+        (::throttle_my_fn_runtime::Instant, #ret_ty),
+      >;
+      static __THROTTLE_CACHE: ::throttle_my_fn_runtime::parking_lot::Mutex<
+        Option<__ThrottleCache>,
+      > = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+
+      let ttl: ::std::time::Duration = #ttl;
+      let key: __ThrottleKey = #key_expr;
+
+      // The lock is scoped so that other threads can call us even while the inner impl
+      // function is running (and so that the futures of async functions don't hold it).
+      {
+        let cache = __THROTTLE_CACHE.lock();
+        let cached = cache.as_ref().and_then(|cache| cache.get(&key));
+        if let Some((cached_at, value)) = cached {
+          // Return the cached value if it hasn't expired yet.
+          let current_time = ::throttle_my_fn_runtime::system_now();
+          if current_time.saturating_duration_since(*cached_at) < ttl {
+            return #wrap(::std::clone::Clone::clone(value));
+          }
+        }
+      }
+
+      let value: #ret_ty = #result;
+
+      let current_time = ::throttle_my_fn_runtime::system_now();
+      let mut cache = __THROTTLE_CACHE.lock();
+      let cache = cache.get_or_insert_with(::std::collections::HashMap::new);
+      cache.retain(|_, (cached_at, _)| current_time.duration_since(*cached_at) < ttl);
+      #evict_oldest
+      cache.insert(key, (current_time, ::std::clone::Clone::clone(&value)));
+
+      #wrap(value)
+    }
+  };
+
+  TokenStream::from(clock::virtualize(gen))
+}
@@ -0,0 +1,95 @@
+//! Expansion of the `#[once]` attribute.
+
+use crate::args::{with_usage, SkippableArgs};
+use crate::err;
+use crate::split::Split;
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, Expr, ItemFn};
+
+/// See [crate::once].
+pub(crate) fn once(args: TokenStream, func: TokenStream) -> TokenStream {
+  // Running once is throttling with an infinite window and a single call: a flag records
+  // whether the function has already executed. With `cache_last`, the result of the first
+  // execution is kept around instead and handed out to every later call.
+
+  const ARGS_ERR_MSG: &str = "expecting no arguments or `cache_last`: \
+                              #[once] or #[once(cache_last)]";
+
+  let args_parsed =
+    match with_usage(ARGS_ERR_MSG, || syn::parse::<SkippableArgs>(args.clone())) {
+      Ok(args) => args,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let cache_last = match &args_parsed.value {
+    None => false,
+    Some(Expr::Path(path)) if path.path.is_ident("cache_last") => true,
+    Some(value) => return err(value, ARGS_ERR_MSG),
+  };
+
+  // With a cached result, calls are never skipped.
+  if let (true, Some(skipped)) = (cache_last, &args_parsed.skipped) {
+    return err(skipped, "option `skipped` cannot be used with `cache_last`");
+  }
+
+  let func_parsed = parse_macro_input!(func as ItemFn);
+  let mut split = match Split::new(func_parsed) {
+    Ok(split) => split,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  // The result is initialized in a closure, which cannot await the inner impl function.
+  if cache_last {
+    if let Err(e) = split.reject_async("`#[once(cache_last)]`") {
+      return e.into_compile_error().into();
+    }
+
+    // The result is kept in a static, which cannot name an `impl Trait` type.
+    if let Err(e) = split.reject_impl_trait("`#[once(cache_last)]`") {
+      return e.into_compile_error().into();
+    }
+  }
+
+  let ret_ty = split.ret_ty.clone();
+
+  let skipped = args_parsed.skipped.as_ref();
+  let (skipped, wrap) = match split.set_skippable_output(skipped, "`#[once]`") {
+    Ok(skippable) => skippable,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  let Split { attrs, vis, impl_fn, outer_sig, call, .. } = &split;
+
+  let body = if cache_last {
+    quote_mixed! {
+      // Callers that come in while the first call is executing wait for its result.
+      static __THROTTLE_RESULT: ::std::sync::OnceLock<#ret_ty> =
+        ::std::sync::OnceLock::new();
+
+      Some(__THROTTLE_RESULT.get_or_init(move || #call).clone())
+    }
+  } else {
+    quote_mixed! {
+      static __THROTTLE_EXECUTED: ::std::sync::atomic::AtomicBool =
+        ::std::sync::atomic::AtomicBool::new(false);
+
+      // Return None (or the skipped value) if the function has already executed (or is
+      // executing).
+      if __THROTTLE_EXECUTED.swap(true, ::std::sync::atomic::Ordering::AcqRel) {
+        return #skipped;
+      }
+
+      #wrap(#call)
+    }
+  };
+
+  let gen = quote_mixed! {
+    #(#attrs)* #vis #outer_sig {
+      #impl_fn
+
+      #body
+    }
+  };
+
+  TokenStream::from(gen)
+}
@@ -0,0 +1,201 @@
+//! Generation of the function reserving a slot in the quota of a throttled function ahead
+//! of calling it, see the `permits` option of [crate::throttle].
+
+use crate::args::Args;
+use crate::queries;
+use crate::split::Split;
+use crate::stats;
+use crate::window;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::format_ident;
+use syn::{parse_quote_spanned, Ident};
+
+/// The name of the permit type of `split`, e.g. `SendRequestPermit` for `send_request`.
+fn permit_ident(split: &Split) -> Ident {
+  let name: String = split
+    .outer_sig
+    .ident
+    .to_string()
+    .split('_')
+    .flat_map(|word| {
+      let mut chars = word.chars();
+      chars.next().map(|first| first.to_ascii_uppercase()).into_iter().chain(chars)
+    })
+    .collect();
+
+  format_ident!("{}Permit", name)
+}
+
+/// Generate the `NAME_acquire()` and `NAME_acquire_n(n)` functions of `split` and the
+/// permit type they return. This must be called before the outer function's signature is
+/// changed, since running a permit returns what the user-provided function returns.
+pub(crate) fn permit_items(args: &Args, split: &Split) -> TokenStream2 {
+  let Args { times, duration, .. } = args;
+  let Split { vis, impl_fn, call, .. } = split;
+  let ident = &split.outer_sig.ident;
+  let calls_fn = queries::calls_fn(split);
+  let permit = permit_ident(split);
+
+  // Running the permit calls the inner impl function directly, with the arguments of the
+  // outer function.
+  let mut run_sig = split.outer_sig.clone();
+  run_sig.ident = format_ident!("run");
+  run_sig.abi = None;
+  run_sig.inputs.insert(0, parse_quote_spanned! { Span::mixed_site()=> self });
+
+  let acquire_ident = format_ident!("{}_acquire", ident);
+  let acquire_doc = format!(
+    "Reserve a slot in the quota of [`{}`], or `None` if it is exhausted. The slot is \
+     returned to the quota if the permit is dropped without being run.",
+    ident
+  );
+  let acquire_n_ident = format_ident!("{}_acquire_n", ident);
+  let acquire_n_doc = format!(
+    "Reserve `n` slots in the quota of [`{}`] at once, or none of them and `None` if \
+     there are fewer left. Each permit returns its slot to the quota if it is dropped \
+     without being run.",
+    ident
+  );
+  let permit_doc = format!("A slot reserved in the quota of [`{}`].", ident);
+  let run_doc = format!(
+    "Call [`{}`] using the reserved slot, without charging it against the quota again.",
+    ident
+  );
+
+  let reserve = reserve(args, split);
+  let cleanup_fn = window::cleanup_fn(times, duration);
+  let charged_at = window::charged_at(&args.calendar);
+  let record_skipped = stats::record(args, split, quote_mixed! { record_skipped() });
+  let record_executed = stats::record(args, split, quote_mixed! { record_executed() });
+  let record_executed_n = match args.stats {
+    Some(_) => quote_mixed! { (0..n).for_each(|_| { #record_executed }); },
+    None => quote_mixed! {},
+  };
+
+  quote_mixed! {
+    #[doc = #permit_doc]
+    #[must_use = "dropping a permit returns its slot to the quota"]
+    #vis struct #permit {
+      reserved_at: ::throttle_my_fn_runtime::Instant,
+    }
+
+    impl #permit {
+      #[doc = #run_doc]
+      #vis #run_sig {
+        // The slot stays charged once the permit is run.
+        ::std::mem::forget(self);
+
+        #impl_fn
+
+        #call
+      }
+    }
+
+    impl ::std::ops::Drop for #permit {
+      fn drop(&mut self) {
+        // Any call in the window charged at the same time frees up the same slot. If the
+        // reservation already fell out of the window, there is nothing to return.
+        if let Some(calls) = #calls_fn().lock().as_mut() {
+          if let Some(index) = calls.iter().position(|&call| call == self.reserved_at) {
+            let _ = calls.remove(index);
+          }
+        }
+      }
+    }
+
+    #[doc = #acquire_doc]
+    #vis fn #acquire_ident() -> Option<#permit> {
+      let reserved_at = #reserve;
+      Some(#permit { reserved_at })
+    }
+
+    #[doc = #acquire_n_doc]
+    #vis fn #acquire_n_ident(n: usize) -> Option<Vec<#permit>> {
+
+      #cleanup_fn
+
+      let current_time = ::throttle_my_fn_runtime::system_now();
+      let mut calls_guard = #calls_fn().lock();
+      let calls = calls_guard
+        .get_or_insert_with(|| ::std::collections::VecDeque::with_capacity(#times));
+      __throttle_cleanup(calls, current_time);
+
+      // The slots are all reserved at once, so that callers racing for the same slots
+      // cannot end up with some of them each. The deque is only cleaned up once it is
+      // full, so the calls that fell out of the window are not counted.
+      let in_window = calls
+        .iter()
+        .filter(|&&call_time| current_time.duration_since(call_time) <= #duration)
+        .count();
+      if n > usize::saturating_sub(#times, in_window) {
+        #record_skipped
+        return None;
+      }
+
+      let reserved_at = #charged_at;
+      calls.extend(::std::iter::repeat(reserved_at).take(n));
+      #record_executed_n
+      Some((0..n).map(|_| #permit { reserved_at }).collect())
+    }
+  }
+}
+
+/// Generate the block reserving a slot in the quota of `split`, evaluating to the time
+/// the slot is charged at, or returning `None` from the enclosing function if the quota
+/// is exhausted.
+fn reserve(args: &Args, split: &Split) -> TokenStream2 {
+  let Args { times, duration, .. } = args;
+  let calls_fn = queries::calls_fn(split);
+  let cleanup_fn = window::cleanup_fn(times, duration);
+  let charged_at = window::charged_at(&args.calendar);
+  let record_skipped = stats::record(args, split, quote_mixed! { record_skipped() });
+  let record_executed = stats::record(args, split, quote_mixed! { record_executed() });
+
+  quote_mixed! {{
+
+    #cleanup_fn
+
+    let current_time = ::throttle_my_fn_runtime::system_now();
+    let mut calls_guard = #calls_fn().lock();
+    let calls = calls_guard
+      .get_or_insert_with(|| ::std::collections::VecDeque::with_capacity(#times));
+    __throttle_cleanup(calls, current_time);
+
+    if calls.len() >= #times {
+      #record_skipped
+      return None;
+    }
+
+    let reserved_at = #charged_at;
+    calls.push_back(reserved_at);
+    #record_executed
+    reserved_at
+  }}
+}
+
+/// Generate the `NAME_throttle_layer()` function of `split`, returning a tower layer
+/// whose requests are charged against the quota of the function, like its calls.
+pub(crate) fn layer_item(args: &Args, split: &Split) -> TokenStream2 {
+  let Split { vis, .. } = split;
+  let ident = &split.outer_sig.ident;
+  let reserve = reserve(args, split);
+
+  let layer_ident = format_ident!("{}_throttle_layer", ident);
+  let layer_doc = format!(
+    "A tower layer letting requests through while the quota of [`{}`] has room, \
+     charging each of them like a call. Requests over the limit are turned down with a \
+     `Throttled` error.",
+    ident
+  );
+
+  quote_mixed! {
+    #[doc = #layer_doc]
+    #vis fn #layer_ident() -> ::throttle_my_fn_runtime::ThrottleLayer {
+      fn __throttle_reserve() -> Option<::throttle_my_fn_runtime::Instant> {
+        Some(#reserve)
+      }
+
+      ::throttle_my_fn_runtime::ThrottleLayer::from_fn(|| __throttle_reserve().is_some())
+    }
+  }
+}
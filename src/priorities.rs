@@ -0,0 +1,162 @@
+//! Weighted sharing of the limit of a function between classes of calls, see the
+//! `priorities` option of [crate::throttle].
+//!
+//! Each class with calls made in the window, whether they were let through or not, is
+//! active, and can only use its share of the limit: the limit split between the active
+//! classes by their weights. A class that is the only one active can use the whole limit,
+//! while lower classes still get their share of a limit saturated by higher ones, rather
+//! than starving.
+//!
+//! Calls with a deadline are queued by class instead, and the free slots go to the
+//! classes with waiting calls in turns as long as their weights, see
+//! `throttle_my_fn_runtime::WeightedClasses`.
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{Expr, ExprAssign, ExprLit, ExprPath, Ident, Lit};
+
+/// The classes of calls of a function and their weights
+/// (`priorities = (high = 70, low = 30), priority = class`).
+#[derive(Clone)]
+pub(crate) struct Priorities {
+  /// The names of the classes along with their weights, in the order they were written.
+  classes: Vec<(String, u64)>,
+
+  /// The expression evaluating to the name of the class of a call, as a `&str`.
+  pub(crate) priority: Expr,
+
+  /// The name of the option, for error messages.
+  pub(crate) name: Ident,
+}
+
+impl Priorities {
+  /// Parse the classes of a `priorities = (high = 70, low = 30)` option named `name`,
+  /// with the class of each call given by `priority`.
+  pub(crate) fn parse(name: Ident, value: &Expr, priority: Expr) -> syn::Result<Self> {
+    let expecting = || {
+      syn::Error::new_spanned(
+        value,
+        format!("expecting weighted classes: `{} = (high = 70, low = 30)`", name),
+      )
+    };
+
+    let elems = match value {
+      Expr::Tuple(tuple) if tuple.elems.len() >= 2 => &tuple.elems,
+      _ => return Err(expecting()),
+    };
+
+    let mut classes: Vec<(String, u64)> = Vec::new();
+    for elem in elems {
+      let (class, weight) = match elem {
+        Expr::Assign(ExprAssign { left, right, .. }) => match (&**left, &**right) {
+          (
+            Expr::Path(ExprPath { path, qself: None, .. }),
+            Expr::Lit(ExprLit { lit: Lit::Int(weight), .. }),
+          ) => match path.get_ident() {
+            Some(class) => (class, weight),
+            None => return Err(expecting()),
+          },
+          _ => return Err(expecting()),
+        },
+        _ => return Err(expecting()),
+      };
+
+      let weight: u64 = weight.base10_parse()?;
+      if weight == 0 {
+        return Err(syn::Error::new_spanned(
+          elem,
+          format!("the weight of class `{}` must be more than zero", class),
+        ));
+      }
+
+      let class = class.to_string();
+      if classes.iter().any(|(other, _)| *other == class) {
+        return Err(syn::Error::new_spanned(
+          elem,
+          format!("class `{}` is given twice", class),
+        ));
+      }
+      classes.push((class, weight));
+    }
+
+    Ok(Priorities { classes, priority, name })
+  }
+
+  /// Generate the `__THROTTLE_CLASSES` static of the classes and their weights, along
+  /// with the class of a call. Classes not listed are counted in the last one.
+  fn class_items(&self) -> TokenStream2 {
+    let priority = &self.priority;
+    let weights = self.classes.iter().map(|(_, weight)| *weight as usize);
+    let last = self.classes.len() - 1;
+    let arms = self.classes.iter().enumerate().map(|(index, (class, _))| {
+      quote_mixed! { #class => #index, }
+    });
+
+    quote_mixed! {
+      static __THROTTLE_CLASSES: ::std::sync::LazyLock<
+        ::throttle_my_fn_runtime::WeightedClasses,
+      > = ::std::sync::LazyLock::new(|| {
+        ::throttle_my_fn_runtime::WeightedClasses::new(&[#(#weights),*])
+      });
+
+      let class: usize = match #priority {
+        #(#arms)*
+        _ => #last,
+      };
+    }
+  }
+
+  /// Generate the book-keeping marking the class of a call made at `current_time` as
+  /// active, before the call is checked against the limit of the function.
+  pub(crate) fn mark_active(&self) -> TokenStream2 {
+    let class_items = self.class_items();
+    quote_mixed! {
+      #class_items
+      __THROTTLE_CLASSES.mark_active(class, current_time);
+    }
+  }
+
+  /// Generate the book-keeping counting a call with a deadline as waiting for a free slot
+  /// in its class, for as long as it is in the function.
+  pub(crate) fn enter(&self) -> TokenStream2 {
+    let class_items = self.class_items();
+    quote_mixed! {
+      #class_items
+      let waiting = __THROTTLE_CLASSES.enter(class);
+    }
+  }
+
+  /// Generate the book-keeping charging a call against the share of its class of `times`
+  /// calls over `duration`, once it fits in the limit of the function, returning
+  /// `skipped` otherwise. With a `deadline`, a call which found a free slot waits for the
+  /// turn of its class instead, while the callers of other classes are waiting as well,
+  /// and returns `skipped` if the deadline passes first.
+  pub(crate) fn charge(
+    &self,
+    times: &Expr,
+    duration: &Expr,
+    deadline: bool,
+    skipped: &TokenStream2,
+  ) -> TokenStream2 {
+    match deadline {
+      true => quote_mixed! {
+        if let Err(turn) = waiting.admit() {
+          let timeout = deadline.saturating_duration_since(current_time);
+          if timeout.is_zero() {
+            return #skipped;
+          }
+
+          drop(calls_guard);
+          turn.wait(timeout);
+          current_time = ::throttle_my_fn_runtime::system_now();
+          continue;
+        }
+      },
+      false => quote_mixed! {
+        // Return None (or the skipped value) if the class used up its share.
+        if !__THROTTLE_CLASSES.try_charge(class, current_time, #times, #duration) {
+          return #skipped;
+        }
+      },
+    }
+  }
+}
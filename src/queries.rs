@@ -0,0 +1,108 @@
+//! Generation of the functions querying the state of a throttle without calling the
+//! throttled function, see the `queries` option of [crate::throttle].
+//!
+//! The calls deque of a function with such companion functions is hoisted out of its
+//! body, see [calls_item]. The `permits` option relies on it as well.
+
+use crate::args::Args;
+use crate::split::Split;
+use crate::window;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::format_ident;
+use syn::Ident;
+
+/// The name of the function holding the calls deque of `split`.
+pub(crate) fn calls_fn(split: &Split) -> Ident {
+  let name = split.outer_sig.ident.to_string();
+  format_ident!("__throttle_calls_{}", name.trim_start_matches('_'))
+}
+
+/// Whether the query functions can be generated alongside a function throttled with
+/// `args`, which takes a calls deque of its own that they can look into.
+pub(crate) fn supported(args: &Args) -> bool {
+  args.rate_from.is_none()
+    && args.per_type.is_none()
+    && args.group.is_none()
+    && args.per_key.is_none()
+    && args.sharded.is_none()
+    && args.policy.is_none()
+    && args.priorities.is_none()
+    && args.resolution.is_none()
+}
+
+/// Generate the function holding the calls deque of `split`, which cannot be a static in
+/// the outer function's body for the functions generated alongside it to get to it.
+pub(crate) fn calls_item(split: &Split) -> TokenStream2 {
+  let calls_fn = calls_fn(split);
+
+  quote_mixed! {
+    #[doc(hidden)]
+    fn #calls_fn() -> &'static ::throttle_my_fn_runtime::parking_lot::Mutex<
+      Option<::std::collections::VecDeque<::throttle_my_fn_runtime::Instant>>,
+    > {
+      static __THROTTLE_CALLS: ::throttle_my_fn_runtime::parking_lot::Mutex<
+        Option<::std::collections::VecDeque<::throttle_my_fn_runtime::Instant>>,
+      > = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+      &__THROTTLE_CALLS
+    }
+  }
+}
+
+/// Generate the query functions of `split`, see [calls_item].
+pub(crate) fn query_items(args: &Args, split: &Split) -> TokenStream2 {
+  let Args { times, duration, .. } = args;
+  let vis = &split.vis;
+  let ident = &split.outer_sig.ident;
+  let calls_fn = calls_fn(split);
+
+  let retry_after_ident = format_ident!("{}_retry_after", ident);
+  let retry_after_doc = format!(
+    "How long until a call to [`{}`] would no longer be throttled, or `None` if it \
+     would execute right away.",
+    ident
+  );
+  let retry_after_fn = window::retry_after_fn(times, duration);
+
+  let next_slot_ident = format_ident!("{}_next_slot", ident);
+  let next_slot_doc = format!(
+    "The time at which a call to [`{}`] would no longer be throttled, or `None` if it \
+     would execute right away.",
+    ident
+  );
+
+  let is_available_ident = format_ident!("{}_is_available", ident);
+  let is_available_doc = format!(
+    "Whether a call to [`{}`] would execute right away, without charging it against \
+     the quota.",
+    ident
+  );
+
+  quote_mixed! {
+    #[doc = #retry_after_doc]
+    #vis fn #retry_after_ident() -> Option<::std::time::Duration> {
+
+      #retry_after_fn
+
+      let calls_guard = #calls_fn().lock();
+      let current_time = ::throttle_my_fn_runtime::system_now();
+      __throttle_retry_after(calls_guard.as_ref()?, current_time)
+    }
+
+    #[doc = #next_slot_doc]
+    #vis fn #next_slot_ident() -> Option<::throttle_my_fn_runtime::Instant> {
+
+      #retry_after_fn
+
+      // Measured under the lock, for the time not to depend on when the caller looks.
+      let calls_guard = #calls_fn().lock();
+      let current_time = ::throttle_my_fn_runtime::system_now();
+      let retry_after = __throttle_retry_after(calls_guard.as_ref()?, current_time)?;
+      Some(current_time + retry_after)
+    }
+
+    #[doc = #is_available_doc]
+    #vis fn #is_available_ident() -> bool {
+      #retry_after_ident().is_none()
+    }
+  }
+}
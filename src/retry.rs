@@ -0,0 +1,150 @@
+//! Expansion of the `#[retry(...)]` attribute.
+
+use crate::args::{with_usage, Args, RetryArgs};
+use crate::queries;
+use crate::split::Split;
+use proc_macro::TokenStream;
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::format_ident;
+use syn::{parse_macro_input, Attribute, ItemFn};
+
+/// Add `queries = true` to the arguments of the throttle attribute `attr` unless they
+/// already have it, for the query functions of the inner impl function to tell how long
+/// a throttled attempt has to wait.
+///
+/// # Returns
+///
+/// Whether the throttle has the query functions, which it cannot have with some options.
+fn add_queries(attr: &mut Attribute) -> bool {
+  let args = match attr.parse_args::<Args>() {
+    Ok(args) if queries::supported(&args) => args,
+    // Errors are reported by the throttle attribute itself.
+    _ => return false,
+  };
+
+  if args.queries.is_none() {
+    let mut tokens: TokenStream2 = attr.parse_args().unwrap_or_default();
+    let trailing_comma = matches!(
+      tokens.clone().into_iter().last(),
+      Some(TokenTree::Punct(punct)) if punct.as_char() == ','
+    );
+    if !trailing_comma {
+      tokens.extend(quote_mixed! { , });
+    }
+    tokens.extend(quote_mixed! { queries = true });
+    attr.tokens = quote_mixed! { (#tokens) };
+  }
+
+  true
+}
+
+/// See [crate::retry].
+pub(crate) fn retry(args: TokenStream, func: TokenStream) -> TokenStream {
+  // Retrying wraps the user-provided function like the other attributes do, and calls the
+  // inner impl function in a loop until it succeeds or runs out of retries.
+  //
+  // When stacked on top of #[throttle(...)], the throttle attribute is moved onto the
+  // inner impl function so that every attempt goes through the rate limit. A throttled
+  // attempt is neither a success nor a failure: it doesn't use up a retry, the loop just
+  // waits until a slot frees up and tries again. The throttle is given query functions
+  // for the loop to know when that is, unless it cannot have them.
+
+  const ARGS_ERR_MSG: &str = "expecting a number of retries and an optional backoff: \
+                              #[retry(<retries>, backoff = <duration>)]";
+
+  let args_parsed =
+    match with_usage(ARGS_ERR_MSG, || syn::parse::<RetryArgs>(args.clone())) {
+      Ok(args) => args,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let retries = &args_parsed.retries;
+  let backoff = match &args_parsed.backoff {
+    Some(backoff) => quote_mixed! { #backoff },
+    None => quote_mixed! { ::std::time::Duration::ZERO },
+  };
+
+  let func_parsed = parse_macro_input!(func as ItemFn);
+  let mut split = match Split::new(func_parsed) {
+    Ok(split) => split,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  // Waiting in between attempts blocks the current thread.
+  if let Err(e) = split.reject_async("`#[retry(...)]`") {
+    return e.into_compile_error().into();
+  }
+
+  let mut throttle_attrs = split.take_throttle_attrs();
+
+  if !throttle_attrs.is_empty() {
+    if let Err(e) = split.reject_method("stacking `#[retry(...)]` on `#[throttle(...)]`")
+    {
+      return e.into_compile_error().into();
+    }
+  }
+
+  let Split { attrs, vis, impl_fn, outer_sig, .. } = &split;
+
+  // Every attempt gets its own copy of the arguments.
+  let call = split.call_cloned();
+
+  // A throttled attempt waits until the next free slot. An attempt can be throttled
+  // with free slots as well, e.g. outside of an active window, which is waited out a
+  // backoff at a time, like every throttled attempt of a throttle without queries. Only
+  // a single stacked throttle has its query functions next to the inner impl function.
+  let next_slot = match throttle_attrs.len() == 1 && add_queries(&mut throttle_attrs[0]) {
+    true => {
+      let next_slot = format_ident!("{}_next_slot", split.impl_sig.ident);
+      quote_mixed! { #next_slot() }
+    }
+    false => quote_mixed! { None::<::throttle_my_fn_runtime::Instant> },
+  };
+
+  let attempt = if throttle_attrs.is_empty() {
+    quote_mixed! { #call }
+  } else {
+    quote_mixed! {
+      match #call {
+        Some(result) => result,
+        None => {
+          // Throttled: wait and try again without using up a retry.
+          let wait = match #next_slot {
+            Some(next_slot) => {
+              let current_time = ::throttle_my_fn_runtime::system_now();
+              next_slot.saturating_duration_since(current_time)
+            }
+            None => throttled_delay,
+          };
+          ::std::thread::sleep(wait);
+          continue;
+        }
+      }
+    }
+  };
+
+  let gen = quote_mixed! {
+    #(#attrs)* #vis #outer_sig {
+      #(#throttle_attrs)* #impl_fn
+
+      let mut retries_left: usize = #retries;
+      let mut delay: ::std::time::Duration = #backoff;
+      #[allow(unused_variables)]
+      let throttled_delay = ::std::cmp::max(delay, ::std::time::Duration::from_millis(1));
+
+      loop {
+        let result = #attempt;
+
+        if Result::is_ok(&result) || retries_left == 0 {
+          return result;
+        }
+
+        retries_left -= 1;
+        ::std::thread::sleep(delay);
+        delay = delay.saturating_mul(2);
+      }
+    }
+  };
+
+  TokenStream::from(gen)
+}
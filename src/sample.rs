@@ -0,0 +1,58 @@
+//! Expansion of the `#[sample(...)]` attribute.
+
+use crate::args::{with_usage, SkippableArgs};
+use crate::split::Split;
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, ItemFn};
+
+/// See [crate::sample].
+pub(crate) fn sample(args: TokenStream, func: TokenStream) -> TokenStream {
+  // Sampling is count-based rather than time-based, so all that is needed is a counter of
+  // calls: the function executes on the first call and then on every Nth call after it.
+
+  const ARGS_ERR_MSG: &str = "expecting a number-of-calls expression: #[sample(<n>)]";
+
+  let args_parsed =
+    match with_usage(ARGS_ERR_MSG, || syn::parse::<SkippableArgs>(args.clone())) {
+      Ok(args) => args,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let every = match with_usage(ARGS_ERR_MSG, || args_parsed.value()) {
+    Ok(every) => every,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  let func_parsed = parse_macro_input!(func as ItemFn);
+  let mut split = match Split::new(func_parsed) {
+    Ok(split) => split,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  let skipped = args_parsed.skipped.as_ref();
+  let (skipped, wrap) = match split.set_skippable_output(skipped, "`#[sample(...)]`") {
+    Ok(skippable) => skippable,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  let Split { attrs, vis, impl_fn, outer_sig, call, .. } = &split;
+
+  let gen = quote_mixed! {
+    #(#attrs)* #vis #outer_sig {
+      #impl_fn
+
+      static __THROTTLE_CALLS: ::std::sync::atomic::AtomicUsize =
+        ::std::sync::atomic::AtomicUsize::new(0);
+
+      // Return None (or the skipped value) unless this is the first of every N calls.
+      let call = __THROTTLE_CALLS.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed);
+      if call % #every != 0 {
+        return #skipped;
+      }
+
+      #wrap(#call)
+    }
+  };
+
+  TokenStream::from(gen)
+}
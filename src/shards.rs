@@ -0,0 +1,41 @@
+//! Generation of the sharded book-keeping of throttles called from many threads at once,
+//! see the `sharded` option of [crate::throttle].
+//!
+//! The window is a `throttle_my_fn_runtime::ShardedWindow`, which splits the quota
+//! across one calls deque per available core, each behind a lock of its own on a cache
+//! line of its own. Threads are assigned to shards round-robin, so threads calling at the
+//! same time mostly lock different shards. A thread whose shard is full borrows from the
+//! other shards that aren't locked at the moment, so a call is only skipped with quota
+//! left in shards that are busy.
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::Expr;
+
+/// Generate the `__THROTTLE_SHARDS` static, with one shard per available core and at
+/// most `times` shards.
+pub(crate) fn shards_item(times: &Expr) -> TokenStream2 {
+  quote_mixed! {
+    static __THROTTLE_SHARDS: ::std::sync::LazyLock<
+      ::throttle_my_fn_runtime::ShardedWindow,
+    > = ::std::sync::LazyLock::new(|| {
+      ::throttle_my_fn_runtime::ShardedWindow::new(#times)
+    });
+  }
+}
+
+/// Generate the book-keeping charging a call made at `current_time` to a shard of
+/// [shards_item], or returning `skipped` if the quota of every shard the call could get
+/// to is full over `duration`. The call is charged at `charged_at`.
+pub(crate) fn charge(
+  times: &Expr,
+  duration: &Expr,
+  charged_at: &TokenStream2,
+  skipped: &TokenStream2,
+) -> TokenStream2 {
+  quote_mixed! {
+    // Return None (or the skipped value) if our quota is full for the duration.
+    if !__THROTTLE_SHARDS.try_charge(current_time, #charged_at, #times, #duration) {
+      return #skipped;
+    }
+  }
+}
@@ -0,0 +1,690 @@
+//! Splitting of a user-provided function into an outer wrapper and an inner impl.
+
+use proc_macro2::{Delimiter, Group, Span, TokenStream as TokenStream2, TokenTree};
+use quote::ToTokens;
+use syn::{
+  Attribute, Expr, FnArg, GenericArgument, GenericParam, Ident, ItemFn, Pat, PatIdent,
+  PathArguments, ReturnType, Signature, Type, TypeParamBound, Visibility,
+};
+
+/// A user-provided function, split into the pieces needed to generate an outer wrapper
+/// function around an inner impl function.
+///
+/// The inner impl function is pretty much the user provided one without any visibility
+/// modifiers, while the outer function has the same signature as the user provided one,
+/// except for its return type, which each attribute changes according to its needs.
+///
+/// Methods (and other functions referring to `Self`) cannot have an inner impl function,
+/// since nested functions cannot refer to `self` or `Self`. Their body is evaluated in
+/// place instead, in a closure so that `return` and `?` keep working.
+pub(crate) struct Split {
+  /// The attributes of the user-provided function, to be put on the outer function.
+  pub(crate) attrs: Vec<Attribute>,
+
+  /// The visibility of the user-provided function, to be put on the outer function.
+  pub(crate) vis: Visibility,
+
+  /// The definition of the inner impl function, to be put in the outer function's body.
+  /// Empty for methods.
+  pub(crate) impl_fn: TokenStream2,
+
+  /// The signature of the inner impl function.
+  pub(crate) impl_sig: Signature,
+
+  /// The signature of the outer function, its return type is to be changed using
+  /// [Split::set_output].
+  pub(crate) outer_sig: Signature,
+
+  /// The return type of the user-provided function, `()` if it has none.
+  pub(crate) ret_ty: TokenStream2,
+
+  /// The outer function's arguments, as passed to the inner impl function. Empty for
+  /// methods.
+  pub(crate) call_params: Vec<Ident>,
+
+  /// The path of the inner impl function, with the outer function's const generic
+  /// parameters passed along explicitly. Empty for methods.
+  impl_path: TokenStream2,
+
+  /// The expression calling the inner impl function with the outer function's arguments,
+  /// awaiting it for async functions. For methods, the expression evaluating the body.
+  pub(crate) call: TokenStream2,
+
+  /// Whether the function is a method, see [Split].
+  pub(crate) is_method: bool,
+
+  /// Whether the return type of the user-provided function is (or contains) an `impl
+  /// Trait` type, which can only be named in the outer function's return type.
+  pub(crate) returns_impl: bool,
+
+  /// The `impl Future<Output = T>` return type of the user-provided function, when the
+  /// function is throttled once its future is first polled, see [Split::poll_future].
+  future: Option<ReturnType>,
+}
+
+/// Whether `tokens` contain the identifier (or keyword) `name`, including in nested
+/// groups.
+fn mentions(tokens: TokenStream2, name: &str) -> bool {
+  tokens.into_iter().any(|token| match token {
+    TokenTree::Ident(ident) => ident == name,
+    TokenTree::Group(group) => mentions(group.stream(), name),
+    _ => false,
+  })
+}
+
+/// Replace the calls to `from` in `tokens` by calls to `to`, including in nested groups.
+/// Only plain calls are replaced, e.g. `from(...)` or `from::<T>(...)`, not method calls
+/// or calls through a path, e.g. `module::from(...)`, which may refer to something else.
+fn redirect_calls(tokens: TokenStream2, from: &Ident, to: &Ident) -> TokenStream2 {
+  let mut tokens = tokens.into_iter().peekable();
+  let mut redirected = Vec::new();
+
+  // Whether the previous token is a `.` or a `:`, i.e. part of a method call or a path.
+  let mut qualified = false;
+
+  while let Some(token) = tokens.next() {
+    let token = match token {
+      TokenTree::Ident(ident) if !qualified && &ident == from => {
+        let called = match tokens.peek() {
+          Some(TokenTree::Group(group)) => group.delimiter() == Delimiter::Parenthesis,
+          Some(TokenTree::Punct(punct)) => punct.as_char() == ':',
+          _ => false,
+        };
+
+        match called {
+          true => TokenTree::Ident(Ident::new(&to.to_string(), ident.span())),
+          false => TokenTree::Ident(ident),
+        }
+      }
+      TokenTree::Group(group) => {
+        let mut redirected =
+          Group::new(group.delimiter(), redirect_calls(group.stream(), from, to));
+        redirected.set_span(group.span());
+        TokenTree::Group(redirected)
+      }
+      token => token,
+    };
+
+    qualified =
+      matches!(&token, TokenTree::Punct(punct) if ".:".contains(punct.as_char()));
+    redirected.push(token);
+  }
+
+  redirected.into_iter().collect()
+}
+
+/// The `T` in `impl Future<Output = T>`, if `output` is such a type.
+fn future_output(output: &mut ReturnType) -> Option<&mut Type> {
+  let bounds = match output {
+    ReturnType::Type(_, ty) => match &mut **ty {
+      Type::ImplTrait(ty) => &mut ty.bounds,
+      _ => return None,
+    },
+    ReturnType::Default => return None,
+  };
+
+  bounds.iter_mut().find_map(|bound| {
+    let segment = match bound {
+      TypeParamBound::Trait(bound) => bound.path.segments.last_mut()?,
+      TypeParamBound::Lifetime(_) => return None,
+    };
+
+    if segment.ident != "Future" {
+      return None;
+    }
+
+    match &mut segment.arguments {
+      PathArguments::AngleBracketed(args) => {
+        args.args.iter_mut().find_map(|arg| match arg {
+          GenericArgument::Binding(binding) if binding.ident == "Output" => {
+            Some(&mut binding.ty)
+          }
+          _ => None,
+        })
+      }
+      _ => None,
+    }
+  })
+}
+
+/// Collect the variables bound by `pat` into `bindings`.
+fn bindings(pat: &Pat, bindings_out: &mut Vec<Ident>) {
+  match pat {
+    Pat::Ident(p) => {
+      bindings_out.push(p.ident.clone());
+      if let Some((_, sub)) = &p.subpat {
+        bindings(sub, bindings_out);
+      }
+    }
+    Pat::Box(p) => bindings(&p.pat, bindings_out),
+    Pat::Reference(p) => bindings(&p.pat, bindings_out),
+    Pat::Type(p) => bindings(&p.pat, bindings_out),
+    Pat::Tuple(p) => p.elems.iter().for_each(|elem| bindings(elem, bindings_out)),
+    Pat::TupleStruct(p) => {
+      p.pat.elems.iter().for_each(|elem| bindings(elem, bindings_out))
+    }
+    Pat::Slice(p) => p.elems.iter().for_each(|elem| bindings(elem, bindings_out)),
+    Pat::Struct(p) => {
+      p.fields.iter().for_each(|field| bindings(&field.pat, bindings_out))
+    }
+    _ => {}
+  }
+}
+
+/// The last segment of the path of `attr`, e.g. `test` in `#[tokio::test]`.
+fn attr_name(attr: &Attribute) -> Option<String> {
+  attr.path.segments.last().map(|segment| segment.ident.to_string())
+}
+
+/// Split the attributes of a user-provided function between the outer function and the
+/// inner impl function, rejecting those of functions that cannot be wrapped.
+///
+/// Hints about the code generated for the function (`#[inline]` and `#[cold]`) are
+/// about the user-provided body, so they go on the inner impl function, and lint levels
+/// go on both. Everything else goes on the outer function, whose `#[cfg(...)]` gates the
+/// inner impl function nested in it as well. Methods have no inner impl function, and
+/// keep all of their attributes.
+///
+/// # Returns
+///
+/// The attributes of the outer function and of the inner impl function.
+fn split_attrs(
+  attrs: Vec<Attribute>,
+  is_method: bool,
+) -> syn::Result<(Vec<Attribute>, Vec<Attribute>)> {
+  let mut outer = Vec::new();
+  let mut inner = Vec::new();
+
+  for attr in attrs {
+    match attr_name(&attr).as_deref() {
+      Some(name @ ("test" | "bench")) => {
+        return Err(syn::Error::new_spanned(
+          &attr,
+          format!(
+            "`#[{}]` functions cannot be throttled, since the harness calls them \
+             directly, throttle a function the {} calls instead",
+            name, name
+          ),
+        ));
+      }
+      Some("inline" | "cold") if !is_method => inner.push(attr),
+      Some("allow" | "warn" | "deny" | "forbid" | "expect") if !is_method => {
+        inner.push(attr.clone());
+        outer.push(attr);
+      }
+      _ => outer.push(attr),
+    }
+  }
+
+  Ok((outer, inner))
+}
+
+impl Split {
+  /// Split `func`.
+  ///
+  /// # Arguments
+  ///
+  /// * `func` - The user-provided function.
+  ///
+  /// # Returns
+  ///
+  /// The split function, or an error if the function cannot be wrapped.
+  pub(crate) fn new(func: ItemFn) -> syn::Result<Self> {
+    // Rename the impl function's name from FUNC_NAME to __throttle_impl_FUNC_NAME. Not
+    // really necessary, and could have just been renamed to inner_impl or something like
+    // that, since impl is an inner function inside of the outer function.
+    //
+    // Leading underscores are trimmed so that nesting attributes (e.g. #[retry] on top of
+    // #[throttle]) doesn't produce names that trigger the non_snake_case lint.
+    let mut impl_sig = func.sig.clone();
+    let impl_ident_name =
+      &format!("__throttle_impl_{}", impl_sig.ident.to_string().trim_start_matches('_'));
+    let impl_ident = Ident::new(impl_ident_name, impl_sig.ident.span());
+    impl_sig.ident = impl_ident.clone();
+
+    // The inner impl function is only called from the outer one, so it doesn't need an
+    // explicit ABI (e.g. extern "C") even if the outer function has one.
+    impl_sig.abi = None;
+
+    let ret_ty = match &func.sig.output {
+      ReturnType::Default => quote_mixed! { () },
+      ReturnType::Type(_, t) => quote_mixed! { #t },
+    };
+
+    let returns_impl = mentions(func.sig.output.to_token_stream(), "impl");
+
+    let is_method = matches!(func.sig.inputs.first(), Some(FnArg::Receiver(_)))
+      || mentions(func.sig.to_token_stream(), "Self")
+      || mentions(func.block.to_token_stream(), "Self");
+
+    if is_method {
+      // The closure borrows or moves the arguments just like the body would, and its
+      // return type is spelled out for `?` to work, unless it is an `impl Trait` type,
+      // which closures cannot be annotated with.
+      let block = &func.block;
+      let call = match (&func.sig.asyncness, returns_impl) {
+        (Some(_), _) => quote_mixed! { async #block.await },
+        (None, false) => quote_mixed! { (|| -> #ret_ty #block)() },
+        (None, true) => quote_mixed! { (|| #block)() },
+      };
+
+      let (attrs, _) = split_attrs(func.attrs, is_method)?;
+      return Ok(Split {
+        attrs,
+        vis: func.vis,
+        impl_fn: quote_mixed! {},
+        impl_sig,
+        impl_path: quote_mixed! {},
+        outer_sig: func.sig,
+        ret_ty,
+        call_params: Vec::new(),
+        call,
+        is_method,
+        returns_impl,
+        future: None,
+      });
+    }
+
+    // Create the list of arguments for passing the outer function's arguments to the
+    // inner impl function. Arguments are bound to plain identifiers in the outer
+    // function, so that they can be passed along: patterns (e.g. `(x, y): (i32, i32)` or
+    // `_: T`) are replaced by fresh identifiers and only destructured by the inner impl
+    // function, and `mut` is left to the inner impl function as well.
+    let mut outer_sig = func.sig;
+    let mut call_params = Vec::new();
+    let typed = outer_sig.inputs.iter_mut().filter_map(|input| match input {
+      FnArg::Typed(t) => Some(t),
+      FnArg::Receiver(_) => None,
+    });
+    for (i, input) in typed.enumerate() {
+      let ident = match &*input.pat {
+        Pat::Ident(PatIdent { by_ref: None, subpat: None, ident, .. }) => ident.clone(),
+        _ => Ident::new(&format!("__throttle_arg_{}", i), Span::mixed_site()),
+      };
+      *input.pat = Pat::Ident(PatIdent {
+        attrs: Vec::new(),
+        by_ref: None,
+        mutability: None,
+        ident: ident.clone(),
+        subpat: None,
+      });
+      call_params.push(ident);
+    }
+
+    // Const generic parameters cannot be inferred from the arguments, so they are passed
+    // along explicitly, letting the type parameters be inferred.
+    let impl_path = if impl_sig.generics.const_params().next().is_some() {
+      let generics = impl_sig.generics.params.iter().filter_map(|param| match param {
+        GenericParam::Type(_) => Some(quote_mixed! { _ }),
+        GenericParam::Const(param) => Some(param.ident.to_token_stream()),
+        GenericParam::Lifetime(_) => None,
+      });
+      quote_mixed! { #impl_ident::<#(#generics),*> }
+    } else {
+      quote_mixed! { #impl_ident }
+    };
+
+    // The outer function of an async function is async as well, so it can await the inner
+    // impl function's future in place.
+    let call = match outer_sig.asyncness {
+      Some(_) => quote_mixed! { #impl_path(#(#call_params),*).await },
+      None => quote_mixed! { #impl_path(#(#call_params),*) },
+    };
+
+    let impl_block = &func.block;
+    let (attrs, impl_attrs) = split_attrs(func.attrs, is_method)?;
+
+    Ok(Split {
+      attrs,
+      vis: func.vis,
+      impl_fn: quote_mixed! { #(#impl_attrs)* #impl_sig #impl_block },
+      impl_sig,
+      impl_path,
+      outer_sig,
+      ret_ty,
+      call_params,
+      call,
+      is_method,
+      returns_impl,
+      future: None,
+    })
+  }
+
+  /// The expression calling the inner impl function with copies of the outer function's
+  /// arguments, for calling it several times. Arguments are copied with
+  /// [std::clone::Clone], receivers are passed along as they are.
+  pub(crate) fn call_cloned(&self) -> TokenStream2 {
+    if self.is_method {
+      let mut names = Vec::new();
+      for input in &self.impl_sig.inputs {
+        if let FnArg::Typed(t) = input {
+          bindings(&t.pat, &mut names);
+        }
+      }
+
+      let call = &self.call;
+      return quote_mixed! {
+        {
+          #(let #names = ::std::clone::Clone::clone(&#names);)*
+          #call
+        }
+      };
+    }
+
+    let impl_path = &self.impl_path;
+    let call_params = &self.call_params;
+    quote_mixed! { #impl_path(#(::std::clone::Clone::clone(&#call_params)),*) }
+  }
+
+  /// Make sure the function is not async, for attributes that cannot support async
+  /// functions.
+  ///
+  /// # Arguments
+  ///
+  /// * `what` - What is not supported, for the error message (e.g. an attribute name).
+  pub(crate) fn reject_async(&self, what: &str) -> syn::Result<()> {
+    match &self.outer_sig.asyncness {
+      Some(asyncness) => Err(syn::Error::new_spanned(
+        asyncness,
+        format!("{} is not supported for async functions", what),
+      )),
+      None => Ok(()),
+    }
+  }
+
+  /// Make sure the function is async, or returns a future it is throttled in once first
+  /// polled, for options that await.
+  ///
+  /// # Arguments
+  ///
+  /// * `what` - What requires an async function, for the error message (e.g. an option).
+  pub(crate) fn require_async(&self, what: &str) -> syn::Result<()> {
+    match (&self.outer_sig.asyncness, &self.future) {
+      (None, None) => Err(syn::Error::new_spanned(
+        &self.outer_sig.ident,
+        format!("{} requires an async function or `future = poll`", what),
+      )),
+      _ => Ok(()),
+    }
+  }
+
+  /// Make sure the function is not a method, for attributes that need an inner impl
+  /// function.
+  ///
+  /// # Arguments
+  ///
+  /// * `what` - What is not supported, for the error message (e.g. an attribute name).
+  pub(crate) fn reject_method(&self, what: &str) -> syn::Result<()> {
+    if self.is_method {
+      return Err(syn::Error::new_spanned(
+        &self.outer_sig.ident,
+        format!("{} is not supported for methods", what),
+      ));
+    }
+    Ok(())
+  }
+
+  /// Make the calls the user-provided function makes to itself call the inner impl
+  /// function directly, rather than going through the outer function, see the
+  /// `recursion` option of [crate::throttle]. Not supported for methods, which have no
+  /// inner impl function.
+  pub(crate) fn bypass_recursion(&mut self) {
+    let impl_fn = std::mem::take(&mut self.impl_fn);
+    self.impl_fn = redirect_calls(impl_fn, &self.outer_sig.ident, &self.impl_sig.ident);
+  }
+
+  /// Make sure the function doesn't have an explicit ABI (e.g. `extern "C"`), for
+  /// attributes that change the signature of the outer function, which foreign callers
+  /// cannot handle. Changing its return type checks for it already, see
+  /// [Split::set_output].
+  ///
+  /// # Arguments
+  ///
+  /// * `what` - What is not supported, for the error message (e.g. an attribute name).
+  pub(crate) fn reject_abi(&self, what: &str) -> syn::Result<()> {
+    match &self.outer_sig.abi {
+      Some(abi) => Err(syn::Error::new_spanned(
+        abi,
+        format!("{} is not supported for `extern` functions", what),
+      )),
+      None => Ok(()),
+    }
+  }
+
+  /// Make sure the function doesn't return an `impl Trait` type, for attributes that
+  /// need to name the return type outside of the outer function's signature (e.g. in a
+  /// static).
+  ///
+  /// # Arguments
+  ///
+  /// * `what` - What is not supported, for the error message (e.g. an attribute name).
+  pub(crate) fn reject_impl_trait(&self, what: &str) -> syn::Result<()> {
+    if self.returns_impl {
+      return Err(syn::Error::new_spanned(
+        &self.outer_sig.output,
+        format!("{} is not supported for functions returning `impl Trait`", what),
+      ));
+    }
+    Ok(())
+  }
+
+  /// Put the `#[cfg(...)]` attributes of the outer function on each of `items`, which
+  /// are generated next to it (e.g. the functions of companion options, or associated
+  /// constants for methods), for them to be gated along with it.
+  pub(crate) fn gate_items(&self, items: TokenStream2) -> syn::Result<TokenStream2> {
+    let cfgs: Vec<_> =
+      self.attrs.iter().filter(|attr| attr.path.is_ident("cfg")).collect();
+    if cfgs.is_empty() {
+      return Ok(items);
+    }
+
+    let items = syn::parse2::<syn::File>(items)?.items;
+    let items = items.iter().map(|item| quote_mixed! { #(#cfgs)* #item });
+    Ok(items.collect())
+  }
+
+  /// Remove the `#[throttle(...)]` attributes (possibly with a path such as
+  /// `#[throttle_my_fn::throttle(...)]`) from the outer function's attributes.
+  ///
+  /// # Returns
+  ///
+  /// The removed attributes, for an attribute stacked on top of `#[throttle(...)]` to put
+  /// them on the inner impl function instead.
+  pub(crate) fn take_throttle_attrs(&mut self) -> Vec<Attribute> {
+    let is_throttle = |attr: &Attribute| {
+      attr.path.segments.last().is_some_and(|segment| segment.ident == "throttle")
+    };
+    let (throttle_attrs, attrs) = self.attrs.drain(..).partition(is_throttle);
+    self.attrs = attrs;
+    throttle_attrs
+  }
+
+  /// Throttle a function returning `impl Future<Output = T>` once its future is first
+  /// polled, rather than when it is called, so that futures that are never awaited are
+  /// not charged.
+  ///
+  /// The return type becomes `T`, the call awaits the returned future, the outer
+  /// function's body is to be put in an async block using [Split::body], and
+  /// [Split::set_output] changes the future's output type instead of the return type.
+  ///
+  /// # Arguments
+  ///
+  /// * `what` - What requires such a function, for the error message (e.g. an option).
+  pub(crate) fn poll_future(&mut self, what: &str) -> syn::Result<()> {
+    if let Some(asyncness) = &self.outer_sig.asyncness {
+      return Err(syn::Error::new_spanned(
+        asyncness,
+        format!(
+          "{} is not needed for async functions, which are always throttled once \
+           their future is first polled",
+          what
+        ),
+      ));
+    }
+
+    let mut future = self.outer_sig.output.clone();
+    let output = match future_output(&mut future) {
+      Some(output) => output,
+      None => {
+        return Err(syn::Error::new_spanned(
+          &self.outer_sig.output,
+          format!("{} requires a function returning `impl Future<Output = T>`", what),
+        ))
+      }
+    };
+
+    self.ret_ty = quote_mixed! { #output };
+    self.returns_impl = mentions(output.to_token_stream(), "impl");
+
+    let call = &self.call;
+    self.call = quote_mixed! { #call.await };
+
+    self.future = Some(future);
+    Ok(())
+  }
+
+  /// The outer function's `body`, in an async block for functions throttled once their
+  /// future is first polled.
+  pub(crate) fn body(&self, body: TokenStream2) -> TokenStream2 {
+    match self.future {
+      Some(_) => quote_mixed! { async move { #body } },
+      None => body,
+    }
+  }
+
+  /// Change the return type of the outer function for attributes that skip calls: to an
+  /// `Option<T>`, or to the same type if a value to return for skipped calls is given
+  /// with the `skipped` option.
+  ///
+  /// # Arguments
+  ///
+  /// * `skipped` - The value of the `skipped` option, if provided.
+  ///
+  /// * `what` - The attribute, for error messages.
+  ///
+  /// # Returns
+  ///
+  /// The expression returned by skipped calls, and the function to wrap the result of
+  /// executed calls with (nothing if the return type is kept).
+  pub(crate) fn set_skippable_output(
+    &mut self,
+    skipped: Option<&Expr>,
+    what: &str,
+  ) -> syn::Result<(TokenStream2, TokenStream2)> {
+    match skipped {
+      Some(skipped) => {
+        // A skipped value cannot have the opaque type of the function's own return value.
+        self.reject_impl_trait("option `skipped`")?;
+        Ok((quote_mixed! { #skipped }, quote_mixed! {}))
+      }
+      None => {
+        let ret_ty = &self.ret_ty;
+        let what = format!("{} without the `skipped` option", what);
+        self.set_output(quote_mixed! { Option<#ret_ty> }, &what)?;
+        Ok((quote_mixed! { None }, quote_mixed! { Some }))
+      }
+    }
+  }
+
+  /// Make sure the function returns an `Option<T>` (or a future of one), for attributes
+  /// merging skipped calls into the function's own `None`.
+  ///
+  /// # Arguments
+  ///
+  /// * `what` - What requires it, for the error message (e.g. an option).
+  pub(crate) fn require_option(&self, what: &str) -> syn::Result<()> {
+    let is_option = match syn::parse2::<Type>(self.ret_ty.clone()) {
+      Ok(Type::Path(ty)) => {
+        ty.path.segments.last().is_some_and(|seg| seg.ident == "Option")
+      }
+      _ => false,
+    };
+
+    if is_option {
+      return Ok(());
+    }
+
+    let message = format!("{} requires a function returning `Option<T>`", what);
+    Err(match &self.outer_sig.output {
+      ReturnType::Type(_, ty) => syn::Error::new_spanned(ty, message),
+      ReturnType::Default => syn::Error::new_spanned(&self.outer_sig.ident, message),
+    })
+  }
+
+  /// The type and the value of the tuple of the outer function's arguments, receivers
+  /// excepted, for giving them back to the caller.
+  ///
+  /// # Arguments
+  ///
+  /// * `what` - What needs the arguments, for the error message (e.g. an option).
+  pub(crate) fn args_tuple(
+    &self,
+    what: &str,
+  ) -> syn::Result<(TokenStream2, TokenStream2)> {
+    let mut types = Vec::new();
+    let mut names = Vec::new();
+    for input in &self.outer_sig.inputs {
+      if let FnArg::Typed(t) = input {
+        // The parameters of methods keep their patterns, which cannot be moved out of
+        // as a whole.
+        match &*t.pat {
+          Pat::Ident(PatIdent { by_ref: None, subpat: None, ident, .. }) => {
+            names.push(ident);
+            types.push(&t.ty);
+          }
+          pat => {
+            return Err(syn::Error::new_spanned(
+              pat,
+              format!("{} requires parameters that are plain identifiers", what),
+            ))
+          }
+        }
+      }
+    }
+
+    Ok((quote_mixed! { (#(#types,)*) }, quote_mixed! { (#(#names,)*) }))
+  }
+
+  /// Add the `deadline` parameter to the end of the outer function's parameters, see
+  /// `mode = wait_with_deadline` of [crate::throttle]. It isn't passed along to the inner
+  /// impl function.
+  pub(crate) fn add_deadline(&mut self) {
+    self.outer_sig.inputs.push(syn::parse_quote_spanned! { Span::mixed_site()=>
+      deadline: ::throttle_my_fn_runtime::Instant
+    });
+  }
+
+  /// Add the `override_` parameter to the end of the outer function's parameters, see
+  /// the `with_override` option of [crate::throttle]. It isn't passed along to the inner
+  /// impl function.
+  pub(crate) fn add_override(&mut self) {
+    self.outer_sig.inputs.push(syn::parse_quote_spanned! { Span::mixed_site()=>
+      override_: ::throttle_my_fn_runtime::ThrottleOverride
+    });
+  }
+
+  /// Change the return type of the outer function to `ret`, or the output type of its
+  /// future, see [Split::poll_future]. Foreign callers cannot handle a changed return
+  /// type, so functions with an explicit ABI (e.g. `extern "C"`) are rejected.
+  ///
+  /// # Arguments
+  ///
+  /// * `ret` - The new return type.
+  ///
+  /// * `what` - What changes the return type, for the error message (e.g. an attribute
+  ///   name).
+  pub(crate) fn set_output(&mut self, ret: TokenStream2, what: &str) -> syn::Result<()> {
+    self.reject_abi(what)?;
+    self.outer_sig.output = match &self.future {
+      Some(future) => {
+        let mut future = future.clone();
+        if let Some(output) = future_output(&mut future) {
+          *output = syn::parse_quote! { #ret };
+        }
+        future
+      }
+      None => syn::parse_quote! { -> #ret },
+    };
+    Ok(())
+  }
+}
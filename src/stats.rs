@@ -0,0 +1,63 @@
+//! Generation of the counters of the calls made to a throttled function, and of the
+//! function reporting them, see the `stats` option of [crate::throttle].
+
+use crate::args::Args;
+use crate::queries;
+use crate::split::Split;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::format_ident;
+use syn::Ident;
+
+/// The name of the function holding the counters of `split`.
+fn counters_fn(split: &Split) -> Ident {
+  let name = split.outer_sig.ident.to_string();
+  format_ident!("__throttle_counters_{}", name.trim_start_matches('_'))
+}
+
+/// Generate the statement recording `event` (e.g. `record_executed()`) in the counters of
+/// `split`, or nothing if it doesn't keep any.
+pub(crate) fn record(args: &Args, split: &Split, event: TokenStream2) -> TokenStream2 {
+  match args.stats {
+    Some(_) => {
+      let counters_fn = counters_fn(split);
+      quote_mixed! { #counters_fn().#event; }
+    }
+    None => quote_mixed! {},
+  }
+}
+
+/// Generate the function holding the counters of `split`, and the `NAME_stats()`
+/// function reporting them. The counters are kept next to the calls deque, see
+/// [queries::calls_item].
+pub(crate) fn stats_items(args: &Args, split: &Split) -> TokenStream2 {
+  let duration = &args.duration;
+  let vis = &split.vis;
+  let ident = &split.outer_sig.ident;
+  let calls_fn = queries::calls_fn(split);
+  let counters_fn = counters_fn(split);
+
+  let stats_ident = format_ident!("{}_stats", ident);
+  let stats_doc = format!("Statistics about the calls made to [`{}`].", ident);
+
+  quote_mixed! {
+    #[doc(hidden)]
+    fn #counters_fn() -> &'static ::throttle_my_fn_runtime::ThrottleCounters {
+      static __THROTTLE_COUNTERS: ::throttle_my_fn_runtime::ThrottleCounters =
+        ::throttle_my_fn_runtime::ThrottleCounters::new();
+      &__THROTTLE_COUNTERS
+    }
+
+    #[doc = #stats_doc]
+    #vis fn #stats_ident() -> ::throttle_my_fn_runtime::ThrottleStats {
+      let current_time = ::throttle_my_fn_runtime::system_now();
+      let in_window = #calls_fn().lock().as_ref().map_or(0, |calls| {
+        let in_window = calls.iter().filter(|&&call_time| {
+          current_time.duration_since(call_time) <= #duration
+        });
+        in_window.count()
+      });
+
+      #counters_fn().stats(in_window)
+    }
+  }
+}
@@ -0,0 +1,216 @@
+//! The errors of `#[throttle(...)]` on arguments and functions it cannot expand, the
+//! messages of the uses in `compile_errors.rs` that must not compile.
+
+use crate::args::{with_usage, Args};
+use crate::split::Split;
+use crate::{expand_throttle, group, THROTTLE_ARGS_ERR_MSG};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Item, ItemFn};
+
+/// The messages of the errors expanding `#[throttle(#args)]` on `item` fails with, the
+/// way [crate::throttle] expands it.
+fn errors(args: TokenStream2, item: TokenStream2) -> Vec<String> {
+  let expansion = with_usage(THROTTLE_ARGS_ERR_MSG, || syn::parse2::<Args>(args))
+    .and_then(|args| match syn::parse2::<Item>(item.clone()) {
+      Ok(Item::Impl(item)) => group::throttle_impl(&args, item),
+      _ => Split::new(syn::parse2::<ItemFn>(item)?)
+        .and_then(|split| expand_throttle(&args, split, None)),
+    });
+
+  match expansion {
+    Ok(_) => panic!("expecting the expansion to fail"),
+    Err(error) => error.into_iter().map(|error| error.to_string()).collect(),
+  }
+}
+
+/// The message of the one error expanding `#[throttle(#args)]` on `item` fails with, see
+/// [errors].
+fn error(args: TokenStream2, item: TokenStream2) -> String {
+  let mut errors = errors(args, item);
+  assert_eq!(errors.len(), 1, "expecting a single error, found {:?}", errors);
+  errors.remove(0)
+}
+
+#[test]
+fn missing_duration() {
+  let message = error(quote! { 2 }, quote! { fn ping() {} });
+  assert_eq!(
+    message,
+    "missing the duration argument, expecting a comma-separated pair of expressions: \
+     #[throttle(<number-of-calls>, <duration>)]",
+  );
+}
+
+#[test]
+fn extra_argument() {
+  let message = error(quote! { 2, "1s", 3 }, quote! { fn ping() {} });
+  assert_eq!(
+    message,
+    "unexpected positional argument, expecting a comma-separated pair of expressions: \
+     #[throttle(<number-of-calls>, <duration>)]",
+  );
+}
+
+#[test]
+fn unknown_option() {
+  let message = error(quote! { 2, "1s", bogus = true }, quote! { fn ping() {} });
+  assert_eq!(message, "unknown option `bogus`");
+}
+
+#[test]
+fn duplicate_option() {
+  let message =
+    error(quote! { 2, "1s", mode = skip, mode = defer }, quote! { fn ping() {} });
+  assert_eq!(message, "option `mode` is given more than once");
+}
+
+#[test]
+fn unknown_unit() {
+  let message = error(quote! { 2, "1 fortnight" }, quote! { fn ping() {} });
+  assert_eq!(
+    message,
+    "expecting a duration like \"500ms\", \"30s\" or \"1h30m\", with units ns, us, ms, \
+     s, m (or min), h and d",
+  );
+}
+
+#[test]
+fn zero_limit() {
+  let message = error(quote! { 0, "1s" }, quote! { fn ping() {} });
+  assert_eq!(
+    message,
+    "a limit of 0 calls makes the function unreachable, expecting a positive integer \
+     number of calls",
+  );
+}
+
+#[test]
+fn unknown_mode() {
+  let message = error(quote! { 2, "1s", mode = sometimes }, quote! { fn ping() {} });
+  assert_eq!(
+    message,
+    "expecting one of: `mode = skip`, `mode = defer`, `mode = coalesce`, `mode = \
+     wait_with_deadline`, `mode = warn`, `mode = observe`",
+  );
+}
+
+#[test]
+fn per_key_without_key() {
+  let args = quote! { global = (2, "1s"), per_key = (1, "1s") };
+  let message = error(args, quote! { fn fetch(host: u32) {} });
+  assert_eq!(message, "option `per_key` requires option `key`");
+}
+
+#[test]
+fn key_not_an_argument() {
+  let args = quote! { global = (2, "1s"), per_key = (1, "1s"), key = tenant };
+  let message = error(args, quote! { fn fetch(host: u32) {} });
+  assert_eq!(message, "`tenant` is not an argument of the function");
+}
+
+#[test]
+fn queue_without_defer() {
+  let message = error(quote! { 2, "1s", queue = 4 }, quote! { fn ping() {} });
+  assert_eq!(message, "option `queue` requires `mode = defer`");
+}
+
+#[test]
+fn empty_queue() {
+  let args = quote! { 2, "1s", mode = defer, queue = 0 };
+  let message = error(args, quote! { fn ping() {} });
+  assert_eq!(
+    message,
+    "a queue of 0 calls has no room for deferred calls, expecting a positive integer: \
+     `queue = <n>`",
+  );
+}
+
+#[test]
+fn queue_not_a_literal() {
+  let args = quote! { 2, "1s", mode = defer, queue = QUEUE };
+  let message = error(args, quote! { fn ping() {} });
+  assert_eq!(message, "expecting a positive integer: `queue = <n>`");
+}
+
+#[test]
+fn stats_for_method() {
+  let message = error(quote! { 2, "1s", stats = true }, quote! { fn ping(&self) {} });
+  assert_eq!(message, "option `stats` is not supported for methods");
+}
+
+#[test]
+fn deadline_in_async_function() {
+  let args = quote! { 2, "1s", mode = wait_with_deadline };
+  let message = error(args, quote! { async fn ping() {} });
+  assert_eq!(message, "`mode = wait_with_deadline` is not supported for async functions");
+}
+
+#[test]
+fn drain_without_defer() {
+  let message = error(quote! { 2, "1s", drain = true }, quote! { fn ping() {} });
+  assert_eq!(message, "option `drain` requires `mode = defer` or `mode = coalesce`");
+}
+
+#[test]
+fn skipped_value_with_warn() {
+  let args = quote! { 2, "1s", mode = warn, skipped = 0 };
+  let message = error(args, quote! { fn ping() -> u8 { 1 } });
+  assert_eq!(message, "option `skipped` cannot be used with `mode = warn`");
+}
+
+#[test]
+fn warn_without_handler() {
+  let message = error(quote! { 2, "1s", mode = warn }, quote! { fn ping() {} });
+  assert_eq!(
+    message,
+    "`mode = warn` requires option `on_over_budget`, use `mode = observe` to only \
+     record calls over budget in the statistics",
+  );
+}
+
+#[test]
+fn single_priority_class() {
+  let args = quote! { 2, "1s", priorities = (high = 70), priority = class };
+  let message = error(args, quote! { fn fetch(class: &str) {} });
+  assert_eq!(message, "expecting weighted classes: `priorities = (high = 70, low = 30)`");
+}
+
+#[test]
+fn refund_without_future() {
+  let message = error(quote! { 2, "1s", refund = on_cancel }, quote! { fn ping() {} });
+  assert_eq!(
+    message,
+    "`refund = on_cancel` requires an async function or `future = poll`",
+  );
+}
+
+#[test]
+fn override_of_deferred_calls() {
+  let args = quote! { 2, "1s", with_override = true, mode = defer };
+  let message = error(args, quote! { fn ping() {} });
+  assert_eq!(message, "option `with_override` requires `mode = skip`");
+}
+
+#[test]
+fn conflicting_options() {
+  let args = quote! { 2, "1s", cold_start_cap = 1, sharded = true };
+  let message = error(args, quote! { fn ping() {} });
+  assert_eq!(message, "option `cold_start_cap` cannot be used with option `sharded`");
+}
+
+#[test]
+fn not_a_function() {
+  let message = error(quote! { 2, "1s" }, quote! { struct Client; });
+  assert_eq!(message, "expected `fn`");
+}
+
+#[test]
+fn changed_return_type_of_foreign_function() {
+  let message = error(quote! { 2, "1s" }, quote! { extern "C" fn ping() {} });
+  assert_eq!(
+    message,
+    "`#[throttle(...)]` without the `skipped` option is not supported for `extern` \
+     functions",
+  );
+}
@@ -0,0 +1,89 @@
+//! Expansion of the `throttle_block!(...)` macro.
+
+use crate::args::{with_usage, InlineArgs};
+use crate::max_concurrent;
+use crate::window;
+use proc_macro::TokenStream;
+
+/// See [crate::throttle_block].
+pub(crate) fn throttle_block(input: TokenStream) -> TokenStream {
+  // The block is throttled in place, exactly like the body of a throttled function, with
+  // its statics declared in a block expression around it. Every expansion of the macro
+  // gets its own statics, so every call site has its own quota.
+  //
+  // Since the block's code is not moved into a function of its own, the book-keeping
+  // cannot bail out with an early return (that would return from the enclosing
+  // function). It instead decides whether the call is admitted, and only then evaluates
+  // the block.
+
+  const ARGS_ERR_MSG: &str =
+    "expecting a comma-separated pair of expressions and a block: \
+                              throttle_block!(<number-of-calls>, <duration>, { ... })";
+
+  let args_parsed =
+    match with_usage(ARGS_ERR_MSG, || syn::parse::<InlineArgs>(input.clone())) {
+      Ok(args) => args,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let InlineArgs { args, body } = &args_parsed;
+  let mut args = args.clone();
+  if cfg!(feature = "scale") {
+    window::scale_limit(&mut args);
+  }
+  let times = &args.times;
+  let duration = &args.duration;
+
+  // With a concurrency limit, the permit is acquired before charging the call and held
+  // while the block is evaluated.
+  let (permit_items, acquire_permit) = match &args.concurrency {
+    Some(concurrency) => (
+      max_concurrent::permit_items(concurrency),
+      quote_mixed! { let permit = __ThrottlePermit::try_acquire(); },
+    ),
+    None => (quote_mixed! {}, quote_mixed! { let permit = Some(()); }),
+  };
+
+  let cleanup_fn = window::cleanup_fn(times, duration);
+
+  let gen = quote_mixed! {
+    {
+
+      #cleanup_fn
+
+      static __THROTTLE_CALLS: ::throttle_my_fn_runtime::parking_lot::Mutex<
+        Option<::std::collections::VecDeque<::throttle_my_fn_runtime::Instant>>,
+      > = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+
+      #permit_items
+
+      let current_time = ::throttle_my_fn_runtime::system_now();
+
+      #acquire_permit
+
+      let admitted = permit.is_some() && {
+        let mut calls_guard = __THROTTLE_CALLS.lock();
+        let calls = calls_guard
+          .get_or_insert_with(|| ::std::collections::VecDeque::with_capacity(#times));
+        __throttle_cleanup(calls, current_time);
+
+        // Only admit the call if our quota isn't full for the duration.
+        if calls.len() < #times {
+          calls.push_back(current_time);
+          true
+        } else {
+          false
+        }
+      };
+
+      if admitted {
+        let _permit = permit;
+        Some(#body)
+      } else {
+        None
+      }
+    }
+  };
+
+  TokenStream::from(gen)
+}
@@ -0,0 +1,97 @@
+//! Expansion of the `throttle_fn!(...)` macro.
+
+use crate::args::{with_usage, InlineArgs};
+use crate::err;
+use crate::window;
+use proc_macro::TokenStream;
+use quote::format_ident;
+use syn::{Expr, Pat};
+
+/// See [crate::throttle_fn].
+pub(crate) fn throttle_fn(input: TokenStream) -> TokenStream {
+  // The user-provided closure is wrapped in an outer closure taking the same parameters
+  // and returning an Option of the user-provided closure's return value, like the outer
+  // function of a throttled function. Instead of statics, the calls deque is owned by the
+  // outer closure, so that every closure created by the macro has its own quota.
+
+  const ARGS_ERR_MSG: &str =
+    "expecting a comma-separated pair of expressions and a closure: \
+     throttle_fn!(<number-of-calls>, <duration>, |...| ...)";
+
+  let args_parsed =
+    match with_usage(ARGS_ERR_MSG, || syn::parse::<InlineArgs>(input.clone())) {
+      Ok(args) => args,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+  let InlineArgs { args, body } = &args_parsed;
+  let mut args = args.clone();
+  if cfg!(feature = "scale") {
+    window::scale_limit(&mut args);
+  }
+  let times = &args.times;
+  let duration = &args.duration;
+
+  if let Some(concurrency) = &args.concurrency {
+    return err(concurrency, "option `concurrency` is not supported by closures");
+  }
+
+  let closure = match body {
+    Expr::Closure(closure) => closure,
+    _ => return err(body, format!("expecting a closure, {}", ARGS_ERR_MSG)),
+  };
+
+  // The outer closure's parameters, with the same type annotations as the user-provided
+  // closure's parameters, if any. Patterns are left to the user-provided closure.
+  let mut params = Vec::new();
+  let mut call_params = Vec::new();
+  for (i, input) in closure.inputs.iter().enumerate() {
+    let param = format_ident!("__throttle_arg_{}", i);
+    match input {
+      Pat::Type(t) => {
+        let ty = &t.ty;
+        params.push(quote_mixed! { #param: #ty });
+      }
+      _ => params.push(quote_mixed! { #param }),
+    }
+    call_params.push(param);
+  }
+
+  let cleanup_fn = window::cleanup_fn(times, duration);
+
+  let gen = quote_mixed! {
+    {
+      let calls = ::throttle_my_fn_runtime::parking_lot::Mutex::new(
+        ::std::collections::VecDeque::new(),
+      );
+      let func = #closure;
+
+      move |#(#params),*| {
+
+        #cleanup_fn
+
+        let current_time = ::throttle_my_fn_runtime::system_now();
+
+        let mut calls_guard: ::throttle_my_fn_runtime::parking_lot::MutexGuard<
+          ::std::collections::VecDeque<::throttle_my_fn_runtime::Instant>,
+        > = calls.lock();
+        __throttle_cleanup(&mut calls_guard, current_time);
+
+        // Return None if our quota is full for the duration.
+        if calls_guard.len() >= #times {
+          return None;
+        }
+
+        calls_guard.push_back(current_time);
+
+        // Drop the lock here so that other threads can call us even while the inner
+        // closure is running.
+        drop(calls_guard);
+
+        Some(func(#(#call_params),*))
+      }
+    }
+  };
+
+  TokenStream::from(gen)
+}
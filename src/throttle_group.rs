@@ -0,0 +1,115 @@
+//! Expansion of the `#[throttle_group(...)]` attribute on structs and their methods.
+
+use crate::args::{with_usage, GroupArgs, SkippableArgs};
+use crate::split::Split;
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::Parser;
+use syn::{parse_macro_input, Field, Fields, FnArg, Item, ItemFn, ItemStruct};
+
+/// See [crate::throttle_group].
+pub(crate) fn throttle_group(args: TokenStream, item: TokenStream) -> TokenStream {
+  let gen = match parse_macro_input!(item as Item) {
+    Item::Struct(item) => group_struct(args, item),
+    Item::Fn(func) => group_method(args, func),
+    item => Err(syn::Error::new_spanned(
+      item,
+      "`#[throttle_group(...)]` can only be put on structs and their methods",
+    )),
+  };
+
+  match gen {
+    Ok(gen) => TokenStream::from(gen),
+    Err(e) => e.into_compile_error().into(),
+  }
+}
+
+/// Add the `throttle_group` field holding the quota of each instance to a struct, along
+/// with the `new_throttle_group()` function creating it.
+fn group_struct(args: TokenStream, mut item: ItemStruct) -> syn::Result<TokenStream2> {
+  const ARGS_ERR_MSG: &str = "expecting a comma-separated pair of expressions: \
+                              #[throttle_group(<number-of-calls>, <duration>)]";
+
+  let GroupArgs { times, duration } =
+    match with_usage(ARGS_ERR_MSG, || syn::parse::<GroupArgs>(args)) {
+      Ok(args) => args,
+      Err(e) => return Ok(e.into_compile_error()),
+    };
+
+  let vis = &item.vis;
+  match &mut item.fields {
+    Fields::Named(fields) => fields.named.push(Field::parse_named.parse2(quote! {
+      /// The quota shared by the methods of this instance marked with
+      /// `#[throttle_group]`.
+      #vis throttle_group: ::throttle_my_fn_runtime::Throttler
+    })?),
+    _ => {
+      return Err(syn::Error::new_spanned(
+        &item.ident,
+        "`#[throttle_group(...)]` requires a struct with named fields",
+      ))
+    }
+  }
+
+  let ident = &item.ident;
+  let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+  Ok(quote_mixed! {
+    #item
+
+    impl #impl_generics #ident #ty_generics #where_clause {
+      /// Create the quota of an instance, for initializing its `throttle_group` field.
+      #vis fn new_throttle_group() -> ::throttle_my_fn_runtime::Throttler {
+        ::throttle_my_fn_runtime::Throttler::new(#times, #duration)
+      }
+    }
+  })
+}
+
+/// Throttle a method against the quota in the `throttle_group` field of its receiver.
+fn group_method(args: TokenStream, func: ItemFn) -> syn::Result<TokenStream2> {
+  const ARGS_ERR_MSG: &str = "expecting no arguments: #[throttle_group]";
+
+  let args_parsed = match with_usage(ARGS_ERR_MSG, || syn::parse::<SkippableArgs>(args)) {
+    Ok(args) => args,
+    Err(e) => return Ok(e.into_compile_error()),
+  };
+
+  if let Some(value) = &args_parsed.value {
+    return Err(syn::Error::new_spanned(
+      value,
+      "the limit of a group is given on its struct, expecting `#[throttle_group]`",
+    ));
+  }
+
+  let mut split = Split::new(func)?;
+  if !matches!(split.outer_sig.inputs.first(), Some(FnArg::Receiver(_))) {
+    return Err(syn::Error::new_spanned(
+      &split.outer_sig.ident,
+      "`#[throttle_group]` requires a method taking `self`",
+    ));
+  }
+
+  let skipped = args_parsed.skipped.as_ref();
+  let (skipped, wrap) = split.set_skippable_output(skipped, "`#[throttle_group]`")?;
+
+  let Split { attrs, vis, impl_fn, outer_sig, call, .. } = &split;
+
+  // The receiver is the user's, so it keeps the user's span.
+  let receiver = quote! { self };
+
+  Ok(quote_mixed! {
+    #(#attrs)* #vis #outer_sig {
+      #impl_fn
+
+      // Return None (or the skipped value) if the quota of the instance is full for the
+      // duration.
+      if !#receiver.throttle_group.try_acquire() {
+        return #skipped;
+      }
+
+      #wrap(#call)
+    }
+  })
+}
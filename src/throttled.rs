@@ -0,0 +1,97 @@
+//! Expansion of the `#[derive(Throttled)]` macro.
+
+use crate::args::{with_usage, ThrottledArgs};
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::format_ident;
+use syn::{parse_macro_input, Data, DeriveInput, Fields};
+
+/// See [crate::throttled].
+pub(crate) fn throttled(input: TokenStream) -> TokenStream {
+  // Every field carrying a `#[throttled(...)]` attribute holds a Throttler, so the struct
+  // gets a constructor for each of them with the configured limit, as well as a method
+  // running a closure only if the field's throttler lets the call through.
+
+  const ARGS_ERR_MSG: &str = "expecting a comma-separated pair of expressions: \
+                              #[throttled(<number-of-calls>, <duration>)]";
+
+  let input = parse_macro_input!(input as DeriveInput);
+
+  let fields = match &input.data {
+    Data::Struct(data) => match &data.fields {
+      Fields::Named(fields) => &fields.named,
+      _ => {
+        return syn::Error::new_spanned(
+          &data.fields,
+          "`#[derive(Throttled)]` requires a struct with named fields",
+        )
+        .into_compile_error()
+        .into()
+      }
+    },
+    _ => {
+      return syn::Error::new_spanned(
+        &input.ident,
+        "`#[derive(Throttled)]` is only supported for structs",
+      )
+      .into_compile_error()
+      .into()
+    }
+  };
+
+  let mut methods = Vec::new();
+
+  for field in fields {
+    let attr = match field.attrs.iter().find(|attr| attr.path.is_ident("throttled")) {
+      Some(attr) => attr,
+      None => continue,
+    };
+
+    let args = match with_usage(ARGS_ERR_MSG, || attr.parse_args::<ThrottledArgs>()) {
+      Ok(args) => args,
+      Err(e) => return e.into_compile_error().into(),
+    };
+
+    let ThrottledArgs { times, duration } = &args;
+    let vis = &field.vis;
+    let ident = field.ident.as_ref().unwrap();
+    let ctor_ident = format_ident!("{}_throttler", ident);
+    let try_ident = format_ident!("try_{}", ident);
+
+    let ctor_doc = format!(
+      "A new throttler for the `{}` field, with the limit it was configured with.",
+      ident
+    );
+    let try_doc = format!(
+      "Call `f` if the `{}` field's throttler lets the call through, or return `None`.",
+      ident
+    );
+
+    methods.push(quote_mixed! {
+      #[doc = #ctor_doc]
+      #vis fn #ctor_ident() -> ::throttle_my_fn_runtime::Throttler {
+        ::throttle_my_fn_runtime::Throttler::new(#times, #duration)
+      }
+
+      #[doc = #try_doc]
+      #vis fn #try_ident<T>(&self, f: impl FnOnce() -> T) -> Option<T> {
+        if self.#ident.try_acquire() {
+          Some(f())
+        } else {
+          None
+        }
+      }
+    });
+  }
+
+  let name = &input.ident;
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+  let gen: TokenStream2 = quote_mixed! {
+    impl #impl_generics #name #ty_generics #where_clause {
+      #(#methods)*
+    }
+  };
+
+  TokenStream::from(gen)
+}
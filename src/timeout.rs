@@ -0,0 +1,87 @@
+//! Expansion of the `#[timeout(...)]` attribute.
+
+use crate::args::{missing_arguments, with_usage};
+use crate::duration::parse_duration;
+use crate::split::Split;
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, Expr, ItemFn};
+
+/// See [crate::timeout].
+pub(crate) fn timeout(args: TokenStream, func: TokenStream) -> TokenStream {
+  // A synchronous call cannot be interrupted, so it is executed on a watchdog thread of
+  // its own while the caller waits for its result for at most the given duration. When
+  // the call takes too long, it is abandoned: the caller returns None while the thread
+  // keeps running the call to completion in the background and its result is discarded.
+  // Every call costs a thread spawned for it, which lives for as long as the call does.
+  //
+  // The future of an async call can be dropped instead, which cancels it: it is awaited
+  // on the timer of the tokio runtime for at most the given duration.
+
+  const ARGS_ERR_MSG: &str = "expecting a duration expression: #[timeout(<duration>)]";
+
+  let value = with_usage(ARGS_ERR_MSG, || match args.is_empty() {
+    true => Err(missing_arguments()),
+    false => syn::parse::<Expr>(args.clone()),
+  });
+
+  let duration = match value.and_then(parse_duration) {
+    Ok(duration) => duration,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  let func_parsed = parse_macro_input!(func as ItemFn);
+  let mut split = match Split::new(func_parsed) {
+    Ok(split) => split,
+    Err(e) => return e.into_compile_error().into(),
+  };
+
+  let ret_ty = split.ret_ty.clone();
+  if let Err(e) = split.set_output(quote_mixed! { Option<#ret_ty> }, "`#[timeout(...)]`")
+  {
+    return e.into_compile_error().into();
+  }
+
+  let Split { attrs, vis, impl_fn, outer_sig, call, .. } = &split;
+
+  if outer_sig.asyncness.is_some() {
+    let gen = quote_mixed! {
+      #(#attrs)* #vis #outer_sig {
+        #impl_fn
+        ::throttle_my_fn_runtime::timeout(#duration, async { #call }).await
+      }
+    };
+
+    return TokenStream::from(gen);
+  }
+
+  let thread_name = format!("timeout-{}", outer_sig.ident);
+
+  let gen = quote_mixed! {
+    #(#attrs)* #vis #outer_sig {
+      #impl_fn
+
+      // The channel has room for the result so that an abandoned call doesn't block. Its
+      // type is inferred, as the return type may not be nameable here (e.g. impl Trait).
+      let (sender, receiver) = ::std::sync::mpsc::sync_channel(1);
+
+      let handle = ::std::thread::Builder::new()
+        .name(String::from(#thread_name))
+        .spawn(move || {
+          let _ = sender.send(#call);
+        })
+        .expect("failed to spawn the timeout watchdog thread");
+
+      match receiver.recv_timeout(#duration) {
+        Ok(value) => Some(value),
+        Err(::std::sync::mpsc::RecvTimeoutError::Timeout) => None,
+        // The call panicked before sending its result, propagate the panic.
+        Err(::std::sync::mpsc::RecvTimeoutError::Disconnected) => match handle.join() {
+          Err(panic) => ::std::panic::resume_unwind(panic),
+          Ok(()) => unreachable!("the call finished without sending its result"),
+        },
+      }
+    }
+  };
+
+  TokenStream::from(gen)
+}
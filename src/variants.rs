@@ -0,0 +1,120 @@
+//! Generation of the variants of a throttled function that share its quota, see the
+//! `variants` option of [crate::throttle].
+
+use crate::args::Args;
+use crate::queries;
+use crate::split::Split;
+use crate::stats;
+use crate::window;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::format_ident;
+
+/// Generate the `NAME_wait` variant of `split`, which blocks until there is a free slot
+/// in the quota instead of skipping the call. This must be called before the outer
+/// function's signature is changed, since the variant returns what the user-provided
+/// function returns.
+pub(crate) fn wait_variant(args: &Args, split: &Split) -> TokenStream2 {
+  let Args { times, duration, .. } = args;
+  let Split { vis, impl_fn, call, .. } = split;
+  let calls = {
+    let calls_fn = queries::calls_fn(split);
+    quote_mixed! { #calls_fn() }
+  };
+
+  let mut wait_sig = split.outer_sig.clone();
+  wait_sig.ident = format_ident!("{}_wait", split.outer_sig.ident);
+  wait_sig.abi = None;
+
+  let doc = format!(
+    "Like [`{}`], but blocks until the call can be executed instead of skipping it. \
+     Both share the same quota.",
+    split.outer_sig.ident
+  );
+
+  let cleanup_fn = window::cleanup_fn(times, duration);
+  let charged_at = window::charged_at(&args.calendar);
+  let wait_for_slot =
+    window::wait_for_slot(&calls, times, duration, &charged_at, args.wait, None);
+
+  // With `stats = true`, the time spent waiting for a slot is recorded as well.
+  let (wait_start, record_wait) = match args.stats {
+    Some(_) => (
+      quote_mixed! { let wait_start = ::throttle_my_fn_runtime::system_now(); },
+      stats::record(args, split, quote_mixed! { record_wait(wait_start.elapsed()) }),
+    ),
+    None => (quote_mixed! {}, quote_mixed! {}),
+  };
+  let record_executed = stats::record(args, split, quote_mixed! { record_executed() });
+
+  quote_mixed! {
+    #[doc = #doc]
+    #vis #wait_sig {
+
+      #cleanup_fn
+
+      #impl_fn
+
+      #wait_start
+      #wait_for_slot
+      #record_wait
+      #record_executed
+
+      #call
+    }
+  }
+}
+
+/// Generate the `NAME_async` variant of `split`, which waits for a free slot in the quota
+/// on the tokio timer instead of skipping the call, and then runs the user-provided
+/// function on the blocking thread pool of tokio. Like [wait_variant], this must be
+/// called before the outer function's signature is changed.
+pub(crate) fn async_variant(args: &Args, split: &Split) -> TokenStream2 {
+  let Args { times, duration, .. } = args;
+  let Split { vis, impl_fn, call, .. } = split;
+  let calls = {
+    let calls_fn = queries::calls_fn(split);
+    quote_mixed! { #calls_fn() }
+  };
+
+  let mut async_sig = split.outer_sig.clone();
+  async_sig.ident = format_ident!("{}_async", split.outer_sig.ident);
+  async_sig.asyncness = Some(Default::default());
+  async_sig.abi = None;
+
+  let doc = format!(
+    "Like [`{}`], but waits until the call can be executed instead of skipping it, and \
+     executes it on the blocking thread pool of tokio. All variants share the same \
+     quota.",
+    split.outer_sig.ident
+  );
+
+  let cleanup_fn = window::cleanup_fn(times, duration);
+  let charged_at = window::charged_at(&args.calendar);
+  let wait_for_slot = window::wait_for_slot_async(&calls, times, duration, &charged_at);
+
+  let (wait_start, record_wait) = match args.stats {
+    Some(_) => (
+      quote_mixed! { let wait_start = ::throttle_my_fn_runtime::system_now(); },
+      stats::record(args, split, quote_mixed! { record_wait(wait_start.elapsed()) }),
+    ),
+    None => (quote_mixed! {}, quote_mixed! {}),
+  };
+  let record_executed = stats::record(args, split, quote_mixed! { record_executed() });
+
+  quote_mixed! {
+    #[doc = #doc]
+    #vis #async_sig {
+
+      #cleanup_fn
+
+      #impl_fn
+
+      #wait_start
+      #wait_for_slot
+      #record_wait
+      #record_executed
+
+      ::throttle_my_fn_runtime::spawn_blocking(move || #call).await
+    }
+  }
+}
@@ -0,0 +1,323 @@
+//! Code generation shared by the sliding-window throttles.
+
+use crate::args::{Args, Wait};
+use crate::duration::literal_nanos;
+use crate::split::Split;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::format_ident;
+use syn::{Expr, Ident, LitStr};
+
+/// Generate the `__throttle_cleanup` function of a sliding window of `times` calls over
+/// `duration`.
+pub(crate) fn cleanup_fn(times: &Expr, duration: &Expr) -> TokenStream2 {
+  quote_mixed! {
+    // We maintain a list of timestamps at which calls to the function have happened in
+    // the `calls` deque. This function cleans the deque up by removing all calls that
+    // happened before `current_time` - `duration`. The deque should never grow larger
+    // than `times`.
+    fn __throttle_cleanup(
+      calls: &mut ::std::collections::VecDeque<::throttle_my_fn_runtime::Instant>,
+      current_time: ::throttle_my_fn_runtime::Instant,
+    ) {
+      ::throttle_my_fn_runtime::window::cleanup(calls, current_time, #times, #duration)
+    }
+  }
+}
+
+/// Generate the `retry_after` function of a sliding window of `times` calls over
+/// `duration`.
+pub(crate) fn retry_after_fn(times: &Expr, duration: &Expr) -> TokenStream2 {
+  quote_mixed! {
+    // How long from `current_time` until enough calls fall out of the window for a slot
+    // to free up, or None if there already is a free slot. Without any slots at all, the
+    // limit can only change, which it is read again for after a window.
+    fn __throttle_retry_after(
+      calls: &::std::collections::VecDeque<::throttle_my_fn_runtime::Instant>,
+      current_time: ::throttle_my_fn_runtime::Instant,
+    ) -> Option<::std::time::Duration> {
+      let times = #times;
+      ::throttle_my_fn_runtime::window::retry_after(calls, current_time, times, #duration)
+    }
+  }
+}
+
+/// The time a call made at `current_time` is charged at: the start of its calendar
+/// period of `calendar` seconds if any, or `current_time` itself. A call charged at the
+/// start of its period falls out of a window as long as the period at the period's end.
+pub(crate) fn charged_at(calendar: &Option<(u64, Ident)>) -> TokenStream2 {
+  match calendar {
+    Some((secs, _)) => {
+      let period_nanos = u128::from(*secs) * 1_000_000_000;
+      quote_mixed! {{
+        let since_epoch = ::std::time::SystemTime::now()
+          .duration_since(::std::time::SystemTime::UNIX_EPOCH)
+          .unwrap_or_default();
+        let into_period = (since_epoch.as_nanos() % #period_nanos) as u64;
+        let into_period = ::std::time::Duration::from_nanos(into_period);
+        current_time.checked_sub(into_period).unwrap_or(current_time)
+      }}
+    }
+    None => quote_mixed! { current_time },
+  }
+}
+
+/// Generate the statement waiting with the `wait` strategy until `free_at`, an instant
+/// after `current_time`.
+pub(crate) fn wait_until(wait: Wait, free_at: TokenStream2) -> TokenStream2 {
+  let until_free = |step| {
+    quote_mixed! {
+      while ::throttle_my_fn_runtime::system_now() < #free_at {
+        #step;
+      }
+    }
+  };
+
+  match wait {
+    Wait::Sleep => quote_mixed! {
+      ::std::thread::sleep(#free_at.saturating_duration_since(current_time));
+    },
+    Wait::Spin => until_free(quote_mixed! { ::std::hint::spin_loop() }),
+    Wait::Yield => until_free(quote_mixed! { ::std::thread::yield_now() }),
+    Wait::Adaptive => {
+      let yield_until_free = until_free(quote_mixed! { ::std::thread::yield_now() });
+      quote_mixed! {
+        // Sleeping can overshoot by about a millisecond, so the end of the wait is spent
+        // yielding instead.
+        let sleep = #free_at.saturating_duration_since(current_time);
+        ::std::thread::sleep(sleep.saturating_sub(::std::time::Duration::from_millis(1)));
+        #yield_until_free
+      }
+    }
+  }
+}
+
+/// Generate the loop waiting for a free slot in the calls deque `calls` (a locked mutex
+/// expression) of a sliding window of `times` calls over `duration`, and charging the
+/// call once there is one, at `charged_at` (see [charged_at]). The loop waits with the
+/// `wait` strategy, and parks until the scale changes with a limit of zero.
+///
+/// With `take`, an expression evaluated once there is a free slot, before it is charged,
+/// the loop evaluates to its value. The expression can return to leave the slot free.
+pub(crate) fn wait_for_slot(
+  calls: &TokenStream2,
+  times: &Expr,
+  duration: &Expr,
+  charged_at: &TokenStream2,
+  wait: Wait,
+  take: Option<&TokenStream2>,
+) -> TokenStream2 {
+  let wait_for_limit = match cfg!(feature = "scale") {
+    true => quote_mixed! { ::throttle_my_fn_runtime::wait_rescaled(retry_at); },
+    false => wait_until(wait, quote_mixed! { retry_at }),
+  };
+  let wait = wait_until(wait, quote_mixed! { free_at });
+  let charge = match take {
+    Some(take) => quote_mixed! {
+      let taken = #take;
+      calls.push_back(#charged_at);
+      break taken;
+    },
+    None => quote_mixed! {
+      calls.push_back(#charged_at);
+      break;
+    },
+  };
+
+  quote_mixed! {
+    loop {
+      let current_time = ::throttle_my_fn_runtime::system_now();
+      let times = #times;
+      let mut calls_guard = #calls.lock();
+      let calls = calls_guard
+        .get_or_insert_with(|| ::std::collections::VecDeque::with_capacity(times));
+      __throttle_cleanup(calls, current_time);
+
+      // Wait until enough calls fell out of the window for one to free up. Without any
+      // slots at all, there is nothing to wait for but the limit to change: it is read
+      // again once the scale changes, or after a window.
+      let free_at =
+        ::throttle_my_fn_runtime::window::free_at(calls, current_time, times, #duration);
+      match free_at {
+        None => {
+          #charge
+        }
+        Some(retry_at) if times == 0 => {
+          drop(calls_guard);
+          #wait_for_limit
+        }
+        Some(free_at) => {
+          drop(calls_guard);
+          #wait
+        }
+      }
+    }
+  }
+}
+
+/// Generate the loop waiting for a free slot like [wait_for_slot], asynchronously on the
+/// timer of the tokio runtime. The calls deque is not locked across awaiting.
+pub(crate) fn wait_for_slot_async(
+  calls: &TokenStream2,
+  times: &Expr,
+  duration: &Expr,
+  charged_at: &TokenStream2,
+) -> TokenStream2 {
+  let wait_for_limit = match cfg!(feature = "scale") {
+    true => {
+      quote_mixed! { ::throttle_my_fn_runtime::wait_rescaled_async(retry_at).await }
+    }
+    false => quote_mixed! { ::throttle_my_fn_runtime::sleep_until(retry_at).await },
+  };
+
+  quote_mixed! {
+    loop {
+      let (times, free_at) = {
+        let current_time = ::throttle_my_fn_runtime::system_now();
+        let times = #times;
+        let mut calls_guard = #calls.lock();
+        let calls = calls_guard
+          .get_or_insert_with(|| ::std::collections::VecDeque::with_capacity(times));
+        __throttle_cleanup(calls, current_time);
+
+        let free_at = ::throttle_my_fn_runtime::window::free_at(
+          calls,
+          current_time,
+          times,
+          #duration,
+        );
+        match free_at {
+          Some(free_at) => (times, free_at),
+          None => {
+            calls.push_back(#charged_at);
+            break;
+          }
+        }
+      };
+
+      // Sleep until enough calls fell out of the window for one to free up, or until the
+      // limit changes without any slots at all, like the blocking loop.
+      match times {
+        0 => {
+          let retry_at = free_at;
+          #wait_for_limit
+        }
+        _ => ::throttle_my_fn_runtime::sleep_until(free_at).await,
+      }
+    }
+  }
+}
+
+/// Generate the definition of the `__throttle_calls` calls deque of the instantiation of
+/// a generic function for its `type_params`, see the `scope` option of [crate::throttle].
+///
+/// Statics in generic functions are shared by all instantiations, so the deques live in
+/// a map keyed by the names of the type parameters, and are leaked for them to be
+/// borrowed for as long as a static is. There are only as many of them as instantiations.
+pub(crate) fn per_type_calls<'a>(
+  type_params: impl IntoIterator<Item = &'a Ident>,
+) -> TokenStream2 {
+  let type_params = type_params.into_iter();
+
+  quote_mixed! {
+    type __ThrottleCalls = ::throttle_my_fn_runtime::parking_lot::Mutex<
+      Option<::std::collections::VecDeque<::throttle_my_fn_runtime::Instant>>,
+    >;
+
+    let __throttle_calls: &'static __ThrottleCalls = {
+      static __THROTTLE_CALLS_PER_TYPE: ::throttle_my_fn_runtime::parking_lot::Mutex<
+        Option<::std::collections::HashMap<&'static str, &'static __ThrottleCalls>>,
+      > = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+
+      let type_name = ::std::any::type_name::<(#(#type_params,)*)>();
+      let mut calls_per_type = __THROTTLE_CALLS_PER_TYPE.lock();
+      let calls_per_type =
+        calls_per_type.get_or_insert_with(::std::collections::HashMap::new);
+      *calls_per_type.entry(type_name).or_insert_with(|| {
+        let calls = ::throttle_my_fn_runtime::parking_lot::const_mutex(None);
+        ::std::boxed::Box::leak(::std::boxed::Box::new(calls))
+      })
+    };
+  }
+}
+
+/// Generate the lookup of the calls deque of the group of functions named `group`, see
+/// the `group` option of [crate::throttle].
+///
+/// Statics are private to the crate whose expansion defines them, so the deques of the
+/// groups live in the runtime crate, looked up by name, for every crate linking it to
+/// share them.
+pub(crate) fn group_calls(group: &LitStr) -> TokenStream2 {
+  quote_mixed! { ::throttle_my_fn_runtime::group_calls(#group) }
+}
+
+/// Hoist the `times` and `duration` expressions of `args` that are neither literals nor
+/// plain paths (unless the limiter is exposed) into statics evaluated once, on the first
+/// call, rather than on every call and every iteration of the cleanup loop. The
+/// expressions of `args` are replaced by reads of those statics, which are named after
+/// the function when `module_level`, for them to be put next to it rather than in its
+/// body.
+///
+/// The expressions cannot refer to generic parameters or to `Self` anyway, since the
+/// cleanup function they are used in is an item of its own. A limit read from a provider
+/// is never hoisted.
+///
+/// # Returns
+///
+/// The definitions of the statics.
+pub(crate) fn hoist_limit(
+  args: &mut Args,
+  split: &Split,
+  module_level: bool,
+) -> TokenStream2 {
+  if args.rate_from.is_some() {
+    return quote_mixed! {};
+  }
+
+  // Paths are hoisted as well for the exposed limiter, whose module they might not
+  // resolve in.
+  let exposed = args.expose.is_some();
+  let hoistable = |expr: &Expr| match expr {
+    Expr::Lit(_) => false,
+    Expr::Path(_) => exposed,
+    _ => true,
+  };
+
+  let suffix = match module_level {
+    true => format!("_{}", split.outer_sig.ident.to_string().to_uppercase()),
+    false => String::new(),
+  };
+
+  let mut items = TokenStream2::new();
+  if hoistable(&args.times) {
+    let times = &args.times;
+    let ident = format_ident!("__THROTTLE_TIMES{}", suffix);
+    items.extend(quote_mixed! {
+      static #ident: ::std::sync::LazyLock<usize> = ::std::sync::LazyLock::new(|| #times);
+    });
+    args.times = syn::parse_quote! { (*#ident) };
+  }
+
+  if hoistable(&args.duration) && literal_nanos(&args.duration).is_none() {
+    let duration = &args.duration;
+    let ident = format_ident!("__THROTTLE_DURATION{}", suffix);
+    items.extend(quote_mixed! {
+      static #ident: ::std::sync::LazyLock<::std::time::Duration> =
+        ::std::sync::LazyLock::new(|| #duration);
+    });
+    args.duration = syn::parse_quote! { (*#ident) };
+  }
+
+  items
+}
+
+/// Scale the `times` expressions of `args` by the process-wide scale of the runtime
+/// crate (`throttle_my_fn_runtime::set_scale`), which is then read whenever they are.
+pub(crate) fn scale_limit(args: &mut Args) {
+  let times = &args.times;
+  args.times = syn::parse_quote! { ::throttle_my_fn_runtime::scaled(#times) };
+
+  if let Some(per_key) = &mut args.per_key {
+    let times = &per_key.times;
+    per_key.times = syn::parse_quote! { ::throttle_my_fn_runtime::scaled(#times) };
+  }
+}
@@ -0,0 +1,43 @@
+//! Functions throttled with `mode = warn`, reporting where calls over budget are made.
+
+use parking_lot::Mutex;
+use std::panic::Location;
+use throttle_my_fn::throttle;
+
+static REPORTS: Mutex<Vec<(&str, u32)>> = parking_lot::const_mutex(Vec::new());
+
+fn over_budget(function: &'static str, caller: &'static Location<'static>) {
+  assert_eq!(caller.file(), file!());
+  REPORTS.lock().push((function, caller.line()));
+}
+
+#[throttle(1, "1h", mode = warn, on_over_budget = over_budget)]
+fn charge(amount: u32) -> u32 {
+  amount
+}
+
+#[track_caller]
+#[throttle(1, "1h", mode = warn, on_over_budget = over_budget)]
+fn refund(amount: u32) -> u32 {
+  amount
+}
+
+#[test]
+fn over_budget_calls_report_caller() {
+  // Each call over budget is reported at the line it is made on, rather than at the
+  // function, and is executed all the same.
+  assert_eq!(charge(1), Some(1));
+  let (first, first_line) = (charge(2), line!());
+  let (second, second_line) = (charge(3), line!());
+  assert_eq!((first, second), (Some(2), Some(3)));
+
+  // A `#[track_caller]` attribute written on the function is kept as it is.
+  assert_eq!(refund(1), Some(1));
+  let (third, third_line) = (refund(2), line!());
+  assert_eq!(third, Some(2));
+
+  let reports = REPORTS.lock();
+  let expected =
+    [("charge", first_line), ("charge", second_line), ("refund", third_line)];
+  assert_eq!(*reports, expected);
+}
@@ -0,0 +1,50 @@
+//! Functions with a circuit breaker, driven by the virtual clock of the `testing`
+//! feature. The clock is shared by the whole test binary, so a single test moves it
+//! forward.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use throttle_my_fn::circuit_breaker;
+use throttle_my_fn_runtime::testing::advance;
+
+static HEALTHY: AtomicBool = AtomicBool::new(false);
+
+#[circuit_breaker(failures = 3, reset = "30s")]
+fn fetch(id: u32) -> Result<u32, u32> {
+  if HEALTHY.load(Ordering::Relaxed) {
+    Ok(id)
+  } else {
+    Err(id)
+  }
+}
+
+#[test]
+fn circuit_opens_after_failures() {
+  // The circuit opens once 3 consecutive calls failed, skipping the calls after them.
+  assert_eq!(
+    (0..3).map(fetch).collect::<Vec<_>>(),
+    [Some(Err(0)), Some(Err(1)), Some(Err(2))]
+  );
+  HEALTHY.store(true, Ordering::Relaxed);
+  assert_eq!(fetch(3), None);
+  advance(Duration::from_secs(29));
+  assert_eq!(fetch(4), None);
+
+  // Once the reset has elapsed, a failing probing call opens the circuit again for
+  // another reset.
+  HEALTHY.store(false, Ordering::Relaxed);
+  advance(Duration::from_secs(1));
+  assert_eq!(fetch(5), Some(Err(5)));
+  HEALTHY.store(true, Ordering::Relaxed);
+  assert_eq!(fetch(6), None);
+  advance(Duration::from_secs(29));
+  assert_eq!(fetch(7), None);
+
+  // A successful probing call closes the circuit, which counts failures from scratch.
+  advance(Duration::from_secs(1));
+  assert_eq!(fetch(8), Some(Ok(8)));
+  HEALTHY.store(false, Ordering::Relaxed);
+  assert!((9..11).all(|id| fetch(id) == Some(Err(id))));
+  HEALTHY.store(true, Ordering::Relaxed);
+  assert_eq!(fetch(11), Some(Ok(11)));
+}
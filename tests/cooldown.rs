@@ -0,0 +1,29 @@
+//! Functions with a cooldown, driven by the virtual clock of the `testing` feature. The
+//! clock is shared by the whole test binary, so a single test moves it forward.
+
+use std::time::Duration;
+use throttle_my_fn::cooldown;
+use throttle_my_fn_runtime::assert_throttled;
+use throttle_my_fn_runtime::testing::{advance, drive};
+
+#[cooldown("1s")]
+fn refresh() {}
+
+#[cooldown("1m", skipped = 0)]
+fn fetch(id: u32) -> u32 {
+  id
+}
+
+#[test]
+fn calls_within_cooldown_skipped() {
+  // Calls are only executed once the cooldown has passed since the last executed one,
+  // which skipped calls don't restart.
+  let executed = drive(5, Duration::from_millis(400), refresh);
+  assert_eq!(executed, [true, false, false, true, false]);
+
+  // With a skipped value, skipped calls return it instead.
+  assert_throttled!(fetch(1) != 0, within = "1m", allows = 1);
+  assert_eq!(fetch(2), 0);
+  advance(Duration::from_secs(60));
+  assert_eq!(fetch(3), 3);
+}
@@ -0,0 +1,51 @@
+//! Debounced functions, which only execute the latest call once calls have stopped for
+//! the quiet period.
+
+use parking_lot::Mutex;
+use std::thread::sleep;
+use std::time::Duration;
+use throttle_my_fn::debounce;
+use throttle_my_fn_runtime::ThrottleHandle;
+
+static SEARCHED: Mutex<Vec<u32>> = parking_lot::const_mutex(Vec::new());
+
+#[debounce("50ms")]
+fn search(query: u32) -> u32 {
+  SEARCHED.lock().push(query);
+  query
+}
+
+#[test]
+fn latest_call_debounced() {
+  let handles: Vec<ThrottleHandle<u32>> = (0..5)
+    .map(|query| {
+      let handle = search(query);
+      sleep(Duration::from_millis(10));
+      handle
+    })
+    .collect();
+  let results: Vec<Option<u32>> = handles.into_iter().map(ThrottleHandle::wait).collect();
+
+  // Every call restarts the quiet period and replaces the pending one, so only the last
+  // one is executed once the calls stopped.
+  assert_eq!(results, [None, None, None, None, Some(4)]);
+  assert_eq!(*SEARCHED.lock(), [4]);
+}
+
+#[debounce("50ms", edge = leading)]
+fn click(button: u32) -> u32 {
+  button
+}
+
+#[test]
+fn leading_call_debounced() {
+  // The first call is executed right away, and the next one is skipped for coming in
+  // within the quiet period.
+  let first = click(1);
+  assert!(first.is_finished());
+  assert_eq!(first.wait(), Some(1));
+  assert_eq!(click(2).wait(), None);
+
+  sleep(Duration::from_millis(70));
+  assert_eq!(click(3).wait(), Some(3));
+}
@@ -0,0 +1,128 @@
+//! Deferred and coalesced calls of `#[throttle(...)]` functions, which the background
+//! worker executes once they get a slot in the window.
+
+use parking_lot::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use throttle_my_fn::throttle;
+use throttle_my_fn_runtime::ThrottleHandle;
+
+static SAVED: Mutex<Vec<u32>> = parking_lot::const_mutex(Vec::new());
+
+#[throttle(1, "300ms", mode = coalesce)]
+fn save(version: u32) -> u32 {
+  SAVED.lock().push(version);
+  version
+}
+
+#[test]
+fn latest_call_coalesced() {
+  let handles: Vec<ThrottleHandle<u32>> = (0..10)
+    .map(|version| {
+      let handle = save(version);
+      sleep(Duration::from_millis(10));
+      handle
+    })
+    .collect();
+  let results: Vec<Option<u32>> = handles.into_iter().map(ThrottleHandle::wait).collect();
+
+  // The first call is executed right away, and the calls coming in while the quota is
+  // full replace one another: only the last one is executed, once, with its arguments.
+  let mut expected = vec![None; 10];
+  expected[0] = Some(0);
+  expected[9] = Some(9);
+  assert_eq!(results, expected);
+  assert_eq!(*SAVED.lock(), [0, 9]);
+}
+
+#[throttle(1, "100ms", mode = coalesce)]
+fn publish(version: u32) -> u32 {
+  version
+}
+
+#[test]
+fn coalesced_call_charged() {
+  assert_eq!(publish(0).wait(), Some(0));
+  let replaced = publish(1);
+  let coalesced = publish(2);
+  assert_eq!(replaced.wait(), None);
+
+  // The coalesced call is executed once the first one fell out of the window, and takes
+  // the slot of the next window itself.
+  assert_eq!(coalesced.wait(), Some(2));
+  let started = Instant::now();
+  let next = publish(3);
+  assert!(!next.is_finished());
+  assert_eq!(next.wait(), Some(3));
+  assert!(started.elapsed() >= Duration::from_millis(80), "{:?}", started.elapsed());
+}
+
+#[throttle(1, "100ms", mode = defer)]
+fn upload(version: u32) -> u32 {
+  version
+}
+
+#[test]
+fn waited_cancelled_call_not_charged() {
+  assert_eq!(upload(0).wait(), Some(0));
+  let pending = upload(1);
+  assert!(pending.cancel());
+  assert_eq!(pending.wait(), None);
+
+  // The worker discards the cancelled call without charging it, so that the next call
+  // after the window is executed right away.
+  sleep(Duration::from_millis(150));
+  let next = upload(2);
+  assert!(next.is_finished());
+  assert_eq!(next.wait(), Some(2));
+}
+
+#[throttle(1, "100ms", mode = defer, queue = 2)]
+fn send_newest(n: u32) -> u32 {
+  n
+}
+
+#[test]
+fn full_queue_drops_newest_call() {
+  let handles: Vec<ThrottleHandle<u32>> = (0..4).map(send_newest).collect();
+  let results: Vec<Option<u32>> = handles.into_iter().map(ThrottleHandle::wait).collect();
+
+  // The first call is executed right away and the next two are queued, leaving no room
+  // for the last one.
+  assert_eq!(results, [Some(0), Some(1), Some(2), None]);
+}
+
+#[throttle(1, "100ms", mode = defer, queue = 2, overflow = drop_oldest)]
+fn send_oldest(n: u32) -> u32 {
+  n
+}
+
+#[test]
+fn full_queue_drops_oldest_call() {
+  let handles: Vec<ThrottleHandle<u32>> = (0..4).map(send_oldest).collect();
+  let results: Vec<Option<u32>> = handles.into_iter().map(ThrottleHandle::wait).collect();
+
+  // The last call makes room for itself by cancelling the oldest pending one.
+  assert_eq!(results, [Some(0), None, Some(2), Some(3)]);
+}
+
+#[throttle(1, "100ms", mode = defer, queue = 1, overflow = block)]
+fn send_blocking(n: u32) -> u32 {
+  n
+}
+
+#[test]
+fn full_queue_blocks_caller() {
+  let first = send_blocking(0);
+  let second = send_blocking(1);
+
+  // The third call waits for the worker to take the second one off the queue, once the
+  // first one fell out of the window.
+  let started = Instant::now();
+  let third = send_blocking(2);
+  assert!(started.elapsed() >= Duration::from_millis(80), "{:?}", started.elapsed());
+
+  let results: Vec<Option<u32>> =
+    [first, second, third].into_iter().map(ThrottleHandle::wait).collect();
+  assert_eq!(results, [Some(0), Some(1), Some(2)]);
+}
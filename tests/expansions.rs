@@ -0,0 +1,203 @@
+//! Expansions of `#[throttle(...)]` for the shapes of functions and arguments it is
+//! written with, which must compile without any warning, including the stricter lints
+//! that downstream crates may deny.
+
+#![deny(warnings)]
+#![deny(
+  missing_debug_implementations,
+  rust_2018_idioms,
+  trivial_casts,
+  trivial_numeric_casts,
+  unused_qualifications,
+  unused_results
+)]
+
+use std::time::{Duration, Instant};
+use throttle_my_fn::throttle;
+use throttle_my_fn_runtime::{ThrottleOverride, ThrottleResult};
+
+mod visibility {
+  use throttle_my_fn::throttle;
+
+  #[throttle(1, "1h")]
+  pub fn public() -> u8 {
+    1
+  }
+
+  #[throttle(1, "1h")]
+  pub(crate) fn crate_visible() -> u8 {
+    2
+  }
+
+  #[throttle(1, "1h", stats = true)]
+  pub(super) fn parent_visible() -> u8 {
+    3
+  }
+
+  #[throttle(1, "1h", queries = true)]
+  pub(in crate::visibility) fn module_visible() -> u8 {
+    4
+  }
+
+  pub(crate) fn call_module_visible() -> (Option<u8>, bool) {
+    (module_visible(), module_visible_is_available())
+  }
+}
+
+#[throttle(2, Duration::from_secs(3600))]
+fn duration_expression(name: &str) -> String {
+  name.to_uppercase()
+}
+
+#[throttle(1, per = "hour")]
+fn named_period(mut count: u32) -> u32 {
+  count += 1;
+  count
+}
+
+#[throttle(every = "1h")]
+fn minimum_interval() {}
+
+#[throttle(1, "1h")]
+fn generic<T>(value: &T) -> T
+where
+  T: Clone + Default + PartialEq,
+{
+  match *value == T::default() {
+    true => T::default(),
+    false => value.clone(),
+  }
+}
+
+#[throttle(1, "1h")]
+fn borrowed<'a>(text: &'a str, separator: &str) -> &'a str {
+  text.split(separator).next().unwrap_or_default()
+}
+
+#[throttle(1, "1h")]
+fn impl_trait(text: &str) -> impl Iterator<Item = &str> {
+  text.split_whitespace()
+}
+
+#[throttle(1, "1h", output = detailed)]
+fn detailed() -> u8 {
+  1
+}
+
+#[throttle(1, "1h", skipped = 0)]
+fn skipped_value() -> u8 {
+  1
+}
+
+#[throttle(1, "1h", mode = wait_with_deadline)]
+fn with_deadline() -> u8 {
+  1
+}
+
+#[throttle(1, "1h", with_override = true)]
+fn with_override() -> u8 {
+  1
+}
+
+#[throttle(global = (2, "1h"), per_key = (1, "1h"), key = (user, region))]
+fn per_key(user: u32, region: &str) -> String {
+  format!("{}@{}", user, region)
+}
+
+#[throttle(1, "1h")]
+async fn asynchronous(value: u8) -> u8 {
+  value
+}
+
+#[derive(Debug)]
+struct Client {
+  calls: u32,
+}
+
+impl Client {
+  #[throttle(1, "1h")]
+  fn by_ref(&self) -> u32 {
+    self.calls
+  }
+
+  #[throttle(1, "1h")]
+  fn by_mut(&mut self) -> u32 {
+    self.calls += 1;
+    self.calls
+  }
+
+  #[throttle(1, "1h")]
+  fn by_value(self) -> u32 {
+    self.calls
+  }
+
+  #[throttle(1, "1h")]
+  fn associated() -> u32 {
+    0
+  }
+}
+
+#[throttle(1, "1h")]
+impl Client {
+  fn shared_first(&self) -> u32 {
+    self.calls
+  }
+
+  fn shared_second(&self) -> u32 {
+    self.calls
+  }
+}
+
+#[test]
+fn visibilities() {
+  assert_eq!(visibility::public(), Some(1));
+  assert_eq!(visibility::crate_visible(), Some(2));
+  assert_eq!(visibility::parent_visible(), Some(3));
+  assert_eq!(visibility::parent_visible_stats().executed, 1);
+  assert_eq!(visibility::call_module_visible(), (Some(4), false));
+}
+
+#[test]
+fn argument_shapes() {
+  assert_eq!(duration_expression("a").as_deref(), Some("A"));
+  assert_eq!(named_period(1), Some(2));
+  assert_eq!(named_period(1), None);
+  assert_eq!(minimum_interval(), Some(()));
+  assert_eq!(generic(&5), Some(5));
+  assert_eq!(borrowed("a-b", "-"), Some("a"));
+  assert_eq!(impl_trait("a b").map(Iterator::count), Some(2));
+  assert_eq!(per_key(1, "eu").as_deref(), Some("1@eu"));
+  assert_eq!(per_key(1, "us").as_deref(), Some("1@us"));
+  assert_eq!(per_key(1, "eu"), None);
+}
+
+#[test]
+fn outputs() {
+  assert_eq!(detailed(), ThrottleResult::Executed(1));
+  assert!(detailed().is_skipped());
+  assert_eq!(skipped_value(), 1);
+  assert_eq!(skipped_value(), 0);
+  assert_eq!(with_deadline(Instant::now()), Some(1));
+  assert_eq!(with_deadline(Instant::now()), None);
+  assert_eq!(with_override(ThrottleOverride::Default), Some(1));
+  assert_eq!(with_override(ThrottleOverride::Bypass), Some(1));
+  assert_eq!(with_override(ThrottleOverride::Default), None);
+}
+
+#[test]
+fn async_functions() {
+  let runtime = tokio::runtime::Runtime::new().unwrap();
+  assert_eq!(runtime.block_on(asynchronous(1)), Some(1));
+  assert_eq!(runtime.block_on(asynchronous(2)), None);
+}
+
+#[test]
+fn methods() {
+  let mut client = Client { calls: 0 };
+  assert_eq!(client.by_ref(), Some(0));
+  assert_eq!(client.by_mut(), Some(1));
+  assert_eq!(Client::associated(), Some(0));
+  assert_eq!(client.shared_first(), Some(1));
+  assert_eq!(client.shared_second(), None);
+  assert_eq!(client.by_value(), Some(1));
+}
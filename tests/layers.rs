@@ -0,0 +1,29 @@
+//! Tower layers generated by `#[throttle(..., layer = true)]`, whose requests share the
+//! quota of the throttled function.
+
+use std::convert::Infallible;
+use throttle_my_fn::throttle;
+use throttle_my_fn_runtime::Throttled;
+use tower::{service_fn, Layer, ServiceExt};
+
+#[throttle(2, "60s", layer = true)]
+fn fetch(n: u32) -> u32 {
+  n * 2
+}
+
+#[test]
+fn layer_shares_quota() {
+  let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+  runtime.block_on(async {
+    let echo = service_fn(|n: u32| async move { Ok::<_, Infallible>(n) });
+    let service = fetch_throttle_layer().layer(echo);
+
+    // A direct call takes one of the two slots, leaving the other to the service.
+    assert_eq!(fetch(1), Some(2));
+    assert_eq!(service.clone().oneshot(3).await.unwrap(), 3);
+
+    let error = service.oneshot(4).await.unwrap_err();
+    assert!(error.is::<Throttled>());
+    assert_eq!(fetch(5), None);
+  });
+}
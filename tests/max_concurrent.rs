@@ -0,0 +1,44 @@
+//! Functions bounding how many of their executions are in flight at the same time.
+
+use std::panic;
+use std::sync::Barrier;
+use std::thread;
+use throttle_my_fn::max_concurrent;
+
+#[max_concurrent(2)]
+fn resize(started: &Barrier, finish: &Barrier) -> usize {
+  let _ = started.wait();
+  let _ = finish.wait();
+  1
+}
+
+#[test]
+fn calls_over_limit_skipped() {
+  let (started, finish) = (Barrier::new(3), Barrier::new(3));
+
+  // While two calls are executing, any call made by another thread is skipped, and the
+  // slots are released once the calls return.
+  thread::scope(|scope| {
+    let threads: Vec<_> =
+      (0..2).map(|_| scope.spawn(|| resize(&started, &finish))).collect();
+    let _ = started.wait();
+    assert_eq!(resize(&started, &finish), None);
+    let _ = finish.wait();
+    assert!(threads.into_iter().all(|thread| thread.join().unwrap() == Some(1)));
+  });
+
+  let (started, finish) = (Barrier::new(1), Barrier::new(1));
+  assert_eq!(resize(&started, &finish), Some(1));
+}
+
+#[max_concurrent(1)]
+fn render(fail: bool) -> usize {
+  assert!(!fail, "render failed");
+  1
+}
+
+#[test]
+fn slot_released_on_panic() {
+  assert!(panic::catch_unwind(|| render(true)).is_err());
+  assert_eq!(render(false), Some(1));
+}
@@ -0,0 +1,49 @@
+//! Memoized functions, driven by the virtual clock of the `testing` feature. The clock is
+//! shared by the whole test binary, so a single test moves it forward.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use throttle_my_fn::{memoize, throttle};
+use throttle_my_fn_runtime::testing::advance;
+
+static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+
+#[memoize(ttl = "10s", max_keys = 2)]
+fn lookup(name: &str) -> usize {
+  LOOKUPS.fetch_add(1, Ordering::Relaxed) + name.len()
+}
+
+#[memoize(ttl = "10s")]
+#[throttle(1, "1m")]
+fn exchange_rate(currency: &str) -> usize {
+  currency.len()
+}
+
+#[test]
+fn values_cached_for_ttl() {
+  // Calls with the same arguments return the cached value until the ttl has elapsed.
+  assert_eq!(lookup("a"), 1);
+  assert_eq!(lookup("bb"), 3);
+  advance(Duration::from_secs(9));
+  assert_eq!(lookup("a"), 1);
+  assert_eq!(lookup("bb"), 3);
+  advance(Duration::from_secs(1));
+  assert_eq!(lookup("a"), 3);
+
+  // The cache being full, the oldest value is evicted to make room for a new one.
+  advance(Duration::from_secs(1));
+  assert_eq!(lookup("ccc"), 6);
+  advance(Duration::from_secs(1));
+  assert_eq!(lookup("dddd"), 8);
+  assert_eq!(lookup("ccc"), 6);
+  assert_eq!(lookup("a"), 6);
+  assert_eq!(LOOKUPS.load(Ordering::Relaxed), 6);
+
+  // Stacked on a throttle, only the calls missing the cache are throttled.
+  assert_eq!(exchange_rate("EUR"), Some(3));
+  assert_eq!(exchange_rate("USD"), None);
+  advance(Duration::from_secs(9));
+  assert_eq!(exchange_rate("EUR"), Some(3));
+  advance(Duration::from_secs(1));
+  assert_eq!(exchange_rate("EUR"), None);
+}
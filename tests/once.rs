@@ -0,0 +1,47 @@
+//! Functions executed at most once per process.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+use throttle_my_fn::once;
+
+static BANNERS: AtomicUsize = AtomicUsize::new(0);
+
+#[once]
+fn print_banner() -> usize {
+  BANNERS.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+#[test]
+fn later_calls_skipped() {
+  // Of the threads racing for the first call, a single one executes it.
+  let executed: Vec<Option<usize>> = thread::scope(|scope| {
+    let threads: Vec<_> = (0..8).map(|_| scope.spawn(print_banner)).collect();
+    threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+  });
+  assert_eq!(executed.iter().flatten().collect::<Vec<_>>(), [&1]);
+  assert_eq!(print_banner(), None);
+  assert_eq!(BANNERS.load(Ordering::Relaxed), 1);
+}
+
+static LOADS: AtomicUsize = AtomicUsize::new(0);
+
+#[once(cache_last)]
+fn load_config(path: &str) -> String {
+  let _ = LOADS.fetch_add(1, Ordering::Relaxed);
+  thread::sleep(Duration::from_millis(20));
+  path.to_uppercase()
+}
+
+#[test]
+fn later_calls_return_first_result() {
+  // Callers coming in while the first call is executing wait for its result, which
+  // later calls get as well, whatever their arguments.
+  let configs: Vec<Option<String>> = thread::scope(|scope| {
+    let threads: Vec<_> = (0..4).map(|_| scope.spawn(|| load_config("a.toml"))).collect();
+    threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+  });
+  assert_eq!(configs, vec![Some(String::from("A.TOML")); 4]);
+  assert_eq!(load_config("b.toml"), Some(String::from("A.TOML")));
+  assert_eq!(LOADS.load(Ordering::Relaxed), 1);
+}
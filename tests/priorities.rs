@@ -0,0 +1,56 @@
+//! Functions throttled with `priorities = (...)`, sharing their limit between classes of
+//! calls by weight.
+
+use parking_lot::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use throttle_my_fn::throttle;
+
+#[throttle(10, "60s", priorities = (high = 70, low = 30), priority = class)]
+fn skipping(class: &str) -> usize {
+  class.len()
+}
+
+#[test]
+fn skipped_calls_capped_by_share() {
+  // Low-priority calls alone can take every slot, but stop at their share once
+  // high-priority calls keep coming as well.
+  assert_eq!((0..2).filter(|_| skipping("low").is_some()).count(), 2);
+  assert_eq!(skipping("high"), Some(4));
+  assert_eq!((0..4).filter(|_| skipping("low").is_some()).count(), 1);
+  assert_eq!((0..10).filter(|_| skipping("high").is_some()).count(), 6);
+}
+
+static ADMITTED: Mutex<Vec<&str>> = parking_lot::const_mutex(Vec::new());
+
+#[throttle(
+  1,
+  "20ms",
+  mode = wait_with_deadline,
+  priorities = (high = 3, low = 1),
+  priority = class
+)]
+fn waiting(class: &'static str) {
+  ADMITTED.lock().push(class);
+}
+
+#[test]
+fn waiting_calls_admitted_by_weight() {
+  let deadline = Instant::now() + Duration::from_secs(30);
+
+  // The first call takes the slot, for the others to wait for the next ones.
+  assert_eq!(waiting("low", deadline), Some(()));
+  thread::scope(|scope| {
+    for class in ["low"; 8].into_iter().chain(["high"; 8]) {
+      let _ = scope.spawn(move || assert_eq!(waiting(class, deadline), Some(())));
+    }
+  });
+
+  // While both classes were waiting, high-priority calls took three slots for every
+  // slot taken by low-priority ones, rather than those taking them all for having
+  // waited longer.
+  let admitted = ADMITTED.lock();
+  let high = admitted[1..9].iter().filter(|&&class| class == "high").count();
+  assert!(high >= 5, "{:?}", admitted);
+  assert_eq!(admitted.len(), 17);
+}
@@ -0,0 +1,18 @@
+//! Async functions under `#[timeout(...)]`, which are awaited on the tokio timer and
+//! dropped once they run out of time.
+
+use std::time::Duration;
+use throttle_my_fn::timeout;
+
+#[timeout("50ms")]
+async fn wait(delay: u64) -> u64 {
+  tokio::time::sleep(Duration::from_millis(delay)).await;
+  delay
+}
+
+#[test]
+fn async_timeout() {
+  let runtime = tokio::runtime::Runtime::new().unwrap();
+  assert_eq!(runtime.block_on(wait(10)), Some(10));
+  assert_eq!(runtime.block_on(wait(100)), None);
+}
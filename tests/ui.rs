@@ -0,0 +1,8 @@
+//! Uses of the attributes that must not compile, along with the errors they fail with,
+//! checked against the `.stderr` files next to them in `tests/ui`. Regenerate those with
+//! `TRYBUILD=overwrite cargo test --test ui` when the messages change on purpose.
+
+#[test]
+fn compile_errors() {
+  trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,6 @@
+use throttle_my_fn::throttle;
+
+#[throttle(2, "1s", cold_start_cap = 1, sharded = true)]
+fn ping() {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use throttle_my_fn::throttle;
+
+#[throttle(2, "1s", mode = skip, mode = defer)]
+fn ping() {}
+
+fn main() {}
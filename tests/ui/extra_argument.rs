@@ -0,0 +1,6 @@
+use throttle_my_fn::throttle;
+
+#[throttle(2, "1s", 3)]
+fn ping() {}
+
+fn main() {}
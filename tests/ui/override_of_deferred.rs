@@ -0,0 +1,6 @@
+use throttle_my_fn::throttle;
+
+#[throttle(2, "1s", with_override = true, mode = defer)]
+fn ping() {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use throttle_my_fn::throttle;
+
+#[throttle(global = (2, "1s"), per_key = (1, "1s"))]
+fn fetch(host: u32) {}
+
+fn main() {}
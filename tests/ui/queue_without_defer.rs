@@ -0,0 +1,6 @@
+use throttle_my_fn::throttle;
+
+#[throttle(2, "1s", queue = 4)]
+fn ping() {}
+
+fn main() {}
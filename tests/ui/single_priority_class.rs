@@ -0,0 +1,6 @@
+use throttle_my_fn::throttle;
+
+#[throttle(2, "1s", priorities = (high = 70), priority = class)]
+fn fetch(class: &str) {}
+
+fn main() {}
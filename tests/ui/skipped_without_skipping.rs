@@ -0,0 +1,8 @@
+use throttle_my_fn::throttle;
+
+#[throttle(2, "1s", mode = warn, skipped = 0)]
+fn ping() -> u8 {
+  1
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use throttle_my_fn::throttle;
+
+struct Client;
+
+impl Client {
+  #[throttle(2, "1s", stats = true)]
+  fn ping(&self) {}
+}
+
+fn main() {}
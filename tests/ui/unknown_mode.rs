@@ -0,0 +1,6 @@
+use throttle_my_fn::throttle;
+
+#[throttle(2, "1s", mode = sometimes)]
+fn ping() {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use throttle_my_fn::throttle;
+
+#[throttle(2, "1 fortnight")]
+fn ping() {}
+
+fn main() {}
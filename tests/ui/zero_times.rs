@@ -0,0 +1,6 @@
+use throttle_my_fn::throttle;
+
+#[throttle(0, "1s")]
+fn ping() {}
+
+fn main() {}
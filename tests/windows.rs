@@ -0,0 +1,155 @@
+//! Calls falling out of the window of `#[throttle(...)]` functions, which must free up
+//! their slots however the calls are counted.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use throttle_my_fn::{retry, throttle};
+use throttle_my_fn_runtime::ThrottleOverride;
+
+#[throttle(3, "50ms", with_override = true)]
+fn export() {}
+
+#[test]
+fn cost_after_window() {
+  assert!(export(ThrottleOverride::Default).is_some());
+  assert!(export(ThrottleOverride::Default).is_some());
+  sleep(Duration::from_millis(60));
+
+  // Both calls fell out of the window, which has room for a cost of 3 again.
+  assert!(export(ThrottleOverride::CostMultiplier(2)).is_some());
+  assert!(export(ThrottleOverride::Default).is_some());
+  assert!(export(ThrottleOverride::Default).is_none());
+}
+
+static IMPORTS: AtomicUsize = AtomicUsize::new(0);
+
+#[throttle(1, "1h", with_override = true)]
+fn import() {
+  let _ = IMPORTS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[test]
+fn zero_cost_bypasses() {
+  // Calls with a multiplier of zero go ahead without taking the only slot.
+  assert!((0..3).all(|_| import(ThrottleOverride::CostMultiplier(0)).is_some()));
+  assert!(import(ThrottleOverride::Default).is_some());
+  assert!(import(ThrottleOverride::CostMultiplier(0)).is_some());
+  assert!(import(ThrottleOverride::Default).is_none());
+  assert_eq!(IMPORTS.load(Ordering::Relaxed), 5);
+}
+
+#[throttle(3, "50ms", permits = true)]
+fn send() {}
+
+#[test]
+fn permits_after_window() {
+  send_acquire().unwrap().run();
+  send_acquire().unwrap().run();
+  sleep(Duration::from_millis(60));
+
+  // Both calls fell out of the window, which has room for 3 permits again.
+  let permits = send_acquire_n(3).unwrap();
+  assert!(send_acquire().is_none());
+  permits.into_iter().for_each(SendPermit::run);
+}
+
+static OCCUPANCY: AtomicUsize = AtomicUsize::new(0);
+
+fn audit(_: &'static str, occupancy: usize) {
+  OCCUPANCY.store(occupancy, Ordering::Relaxed);
+}
+
+#[throttle(3, "50ms", on_execute = audit)]
+fn delete() {}
+
+#[test]
+fn occupancy_after_window() {
+  assert!(delete().is_some());
+  assert!(delete().is_some());
+  assert_eq!(OCCUPANCY.load(Ordering::Relaxed), 2);
+  sleep(Duration::from_millis(60));
+
+  // Both calls fell out of the window, leaving only the new one in it.
+  assert!(delete().is_some());
+  assert_eq!(OCCUPANCY.load(Ordering::Relaxed), 1);
+}
+
+#[throttle(10, "1h", per_key = (1, "1h"), key = client, max_keys = 2)]
+fn handle(client: u32) -> u32 {
+  client
+}
+
+#[test]
+fn least_recent_key_forgotten() {
+  assert_eq!(handle(1), Some(1));
+  assert_eq!(handle(2), Some(2));
+
+  // Making room for the third key forgets about the first one, which is let through
+  // again in place of the second one, while the third one is still over its limit.
+  assert_eq!(handle(3), Some(3));
+  assert_eq!(handle(1), Some(1));
+  assert_eq!(handle(3), None);
+  assert_eq!(handle(2), Some(2));
+}
+
+#[throttle(global = (4, "200ms"), per_key = (4, "200ms"), key = tenant, fair = true)]
+fn query(tenant: u32) -> u32 {
+  tenant
+}
+
+#[test]
+fn fair_share_after_window() {
+  let executed = |tenant| (0..4).filter(|_| query(tenant).is_some()).count();
+
+  // The first tenant takes the whole limit while it is alone, and the second one is
+  // skipped by the limit of the function, which still makes it active.
+  assert_eq!(executed(1), 4);
+  assert_eq!(executed(2), 0);
+  sleep(Duration::from_millis(120));
+  assert_eq!(executed(2), 0);
+
+  // Once the calls of the first tenant fell out of the window, each tenant can only use
+  // half of the limit.
+  sleep(Duration::from_millis(100));
+  assert_eq!(executed(1), 2);
+  assert_eq!(executed(2), 2);
+}
+
+static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+
+#[retry(1)]
+#[throttle(1, "50ms")]
+fn flaky() -> Result<usize, usize> {
+  match ATTEMPTS.fetch_add(1, Ordering::Relaxed) {
+    0 => Err(0),
+    attempt => Ok(attempt),
+  }
+}
+
+#[test]
+fn retry_waits_for_window() {
+  // The retry waits for the first attempt to fall out of the window, without using up
+  // another retry in the meantime.
+  let start = Instant::now();
+  assert_eq!(flaky(), Ok(1));
+  assert!(start.elapsed() >= Duration::from_millis(50));
+}
+
+// Skipping calls without any other option, which charges them lock-free.
+#[throttle(2, "50ms")]
+fn ping() {}
+
+#[test]
+fn lock_free_after_window() {
+  let executed = || {
+    let threads: Vec<_> = (0..8).map(|_| std::thread::spawn(ping)).collect();
+    threads.into_iter().filter_map(|thread| thread.join().unwrap()).count()
+  };
+
+  // However the calls race for the slots, exactly the limit is let through, and the
+  // slots free up once the calls fell out of the window.
+  assert_eq!(executed(), 2);
+  sleep(Duration::from_millis(60));
+  assert_eq!(executed(), 2);
+}